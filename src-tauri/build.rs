@@ -1,3 +1,9 @@
 fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        // 使用内置 protoc，构建环境无需额外安装
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().expect("vendored protoc not found"));
+        tonic_build::compile_protos("proto/inference.proto").expect("failed to compile gRPC protos");
+    }
     tauri_build::build()
 }
@@ -92,6 +92,54 @@ pub async fn delete_accounts(
     Ok(())
 }
 
+/// 导出邮箱关联的全部数据 (zip 写入 dest_path)
+#[tauri::command]
+pub async fn export_account_data(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    email: String,
+    dest_path: String,
+) -> Result<modules::privacy::AccountDataExport, String> {
+    let instance_lock = proxy_state.instance.read().await;
+    let token_manager = instance_lock.as_ref().map(|i| i.token_manager.as_ref());
+    let export = modules::privacy::export_account_data(&email, token_manager)?;
+    std::fs::write(&dest_path, &export.archive).map_err(|e| format!("写入导出文件失败: {}", e))?;
+    Ok(export)
+}
+
+/// 删除邮箱关联的全部数据 (不可恢复，前端需二次确认)
+#[tauri::command]
+pub async fn delete_account_data(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    email: String,
+) -> Result<modules::privacy::DeletionReport, String> {
+    let instance_lock = proxy_state.instance.read().await;
+    let token_manager = instance_lock.as_ref().map(|i| i.token_manager.as_ref());
+    let report = modules::privacy::delete_account_data(&email, token_manager).map_err(|e| {
+        modules::logger::log_error(&format!("删除账号数据失败: {}", e));
+        e
+    })?;
+
+    crate::modules::tray::update_tray_menus(&app);
+    Ok(report)
+}
+
+/// 将全部账号文件加密落盘 (密钥不存在时生成并保存到系统钥匙串)，返回迁移的文件数
+#[tauri::command]
+pub async fn migrate_accounts_to_encrypted() -> Result<usize, String> {
+    tokio::task::spawn_blocking(|| modules::account_crypto::migrate_accounts(true))
+        .await
+        .map_err(|e| format!("迁移任务失败: {}", e))?
+}
+
+/// 将全部账号文件还原为明文并删除钥匙串中的密钥 (再次加密即完成密钥轮换)
+#[tauri::command]
+pub async fn migrate_accounts_to_plaintext() -> Result<usize, String> {
+    tokio::task::spawn_blocking(|| modules::account_crypto::migrate_accounts(false))
+        .await
+        .map_err(|e| format!("迁移任务失败: {}", e))?
+}
+
 /// 重新排序账号列表
 /// 根据传入的账号ID数组顺序更新账号排列
 #[tauri::command]
@@ -289,6 +337,12 @@ pub async fn load_config() -> Result<AppConfig, String> {
     modules::load_app_config()
 }
 
+/// 校验配置文件
+#[tauri::command]
+pub async fn get_config_validation_errors() -> Result<Vec<modules::config::ConfigError>, String> {
+    Ok(modules::config::check_config_file())
+}
+
 /// 保存配置
 #[tauri::command]
 pub async fn save_config(
@@ -296,7 +350,17 @@ pub async fn save_config(
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
     config: AppConfig,
 ) -> Result<(), String> {
+    if let Some(e) = modules::config::validate_domain_timeouts(&config).into_iter().next() {
+        return Err(format!("{}: {}", e.path, e.message));
+    }
+    let old_proxy = modules::load_app_config().map(|c| c.proxy).ok();
     modules::save_app_config(&config)?;
+    if let Some(old_proxy) = old_proxy {
+        modules::audit::record_change("save_config", &old_proxy, &config.proxy);
+    }
+
+    // 更新工具结果压缩配置
+    crate::proxy::mappers::tool_result_compressor::set_drop_keys(config.tool_compressor.drop_keys.clone());
 
     // 通知托盘配置已更新
     let _ = app.emit("config://updated", ());
@@ -317,6 +381,8 @@ pub async fn save_config(
         instance.axum_server.update_zai(&config.proxy).await;
         // 更新实验性配置
         instance.axum_server.update_experimental(&config.proxy).await;
+        // 更新上游重试配置
+        instance.axum_server.update_retry(&config.proxy).await;
         tracing::debug!("已同步热更新反代服务配置");
     }
 
@@ -332,62 +398,7 @@ pub async fn start_oauth_login(app_handle: tauri::AppHandle) -> Result<Account,
     // 1. 启动 OAuth 流程获取 Token
     let token_res = modules::oauth_server::start_oauth_flow(app_handle.clone()).await?;
 
-    // 2. 检查 refresh_token
-    let refresh_token = token_res.refresh_token.ok_or_else(|| {
-        "未获取到 Refresh Token。\n\n\
-         可能原因:\n\
-         1. 您之前已授权过此应用,Google 不会再次返回 refresh_token\n\n\
-         解决方案:\n\
-         1. 访问 https://myaccount.google.com/permissions\n\
-         2. 撤销 'Antigravity Tools' 的访问权限\n\
-         3. 重新进行 OAuth 授权\n\n\
-         或者使用 'Refresh Token' 标签页手动添加账号"
-            .to_string()
-    })?;
-
-    // 3. 获取用户信息
-    let user_info = modules::oauth::get_user_info(&token_res.access_token).await?;
-    modules::logger::log_info(&format!("获取用户信息成功: {}", user_info.email));
-
-    // 4. 尝试获取项目ID
-    let project_id = crate::proxy::project_resolver::fetch_project_id(&token_res.access_token)
-        .await
-        .ok();
-
-    if let Some(ref pid) = project_id {
-        modules::logger::log_info(&format!("获取项目ID成功: {}", pid));
-    } else {
-        modules::logger::log_warn("未能获取项目ID,将在后续懒加载");
-    }
-
-    // 5. 构造 TokenData
-    let token_data = TokenData::new(
-        token_res.access_token,
-        refresh_token,
-        token_res.expires_in,
-        Some(user_info.email.clone()),
-        project_id,
-        None,
-    );
-
-    // 6. 添加或更新到账号列表
-    modules::logger::log_info("正在保存账号信息...");
-    let mut account = modules::upsert_account(
-        user_info.email.clone(),
-        user_info.get_display_name(),
-        token_data,
-    )?;
-
-    // 7. 自动触发刷新额度
-    let _ = internal_refresh_account_quota(&app_handle, &mut account).await;
-
-    // 8. If proxy is running, reload token pool so changes take effect immediately.
-    let _ = crate::commands::proxy::reload_proxy_accounts(
-        app_handle.state::<crate::commands::proxy::ProxyServiceState>(),
-    )
-    .await;
-
-    Ok(account)
+    save_oauth_account(&app_handle, token_res).await
 }
 
 /// 完成 OAuth 授权（不自动打开浏览器）
@@ -398,7 +409,15 @@ pub async fn complete_oauth_login(app_handle: tauri::AppHandle) -> Result<Accoun
     // 1. 等待回调并交换 Token（不 open browser）
     let token_res = modules::oauth_server::complete_oauth_flow(app_handle.clone()).await?;
 
-    // 2. 检查 refresh_token
+    save_oauth_account(&app_handle, token_res).await
+}
+
+/// 保存 OAuth 授权获得的 Token 为账号 (获取用户信息与项目ID、刷新额度并重载反代账号池)
+async fn save_oauth_account(
+    app_handle: &tauri::AppHandle,
+    token_res: modules::oauth::TokenResponse,
+) -> Result<Account, String> {
+    // 1. 检查 refresh_token
     let refresh_token = token_res.refresh_token.ok_or_else(|| {
         "未获取到 Refresh Token。\n\n\
          可能原因:\n\
@@ -411,11 +430,11 @@ pub async fn complete_oauth_login(app_handle: tauri::AppHandle) -> Result<Accoun
             .to_string()
     })?;
 
-    // 3. 获取用户信息
+    // 2. 获取用户信息
     let user_info = modules::oauth::get_user_info(&token_res.access_token).await?;
     modules::logger::log_info(&format!("获取用户信息成功: {}", user_info.email));
 
-    // 4. 尝试获取项目ID
+    // 3. 尝试获取项目ID
     let project_id = crate::proxy::project_resolver::fetch_project_id(&token_res.access_token)
         .await
         .ok();
@@ -426,7 +445,7 @@ pub async fn complete_oauth_login(app_handle: tauri::AppHandle) -> Result<Accoun
         modules::logger::log_warn("未能获取项目ID,将在后续懒加载");
     }
 
-    // 5. 构造 TokenData
+    // 4. 构造 TokenData
     let token_data = TokenData::new(
         token_res.access_token,
         refresh_token,
@@ -436,7 +455,7 @@ pub async fn complete_oauth_login(app_handle: tauri::AppHandle) -> Result<Accoun
         None,
     );
 
-    // 6. 添加或更新到账号列表
+    // 5. 添加或更新到账号列表
     modules::logger::log_info("正在保存账号信息...");
     let mut account = modules::upsert_account(
         user_info.email.clone(),
@@ -444,10 +463,10 @@ pub async fn complete_oauth_login(app_handle: tauri::AppHandle) -> Result<Accoun
         token_data,
     )?;
 
-    // 7. 自动触发刷新额度
-    let _ = internal_refresh_account_quota(&app_handle, &mut account).await;
+    // 6. 自动触发刷新额度
+    let _ = internal_refresh_account_quota(app_handle, &mut account).await;
 
-    // 8. If proxy is running, reload token pool so changes take effect immediately.
+    // 7. If proxy is running, reload token pool so changes take effect immediately.
     let _ = crate::commands::proxy::reload_proxy_accounts(
         app_handle.state::<crate::commands::proxy::ProxyServiceState>(),
     )
@@ -482,6 +501,161 @@ pub async fn import_v1_accounts(app: tauri::AppHandle) -> Result<Vec<Account>, S
     Ok(accounts)
 }
 
+/// 获取所有账号健康度 (按评分升序，默认统计最近 24 小时)
+#[tauri::command]
+pub async fn get_all_account_health(
+    window_hours: Option<u32>,
+) -> Result<Vec<modules::account::AccountHealth>, String> {
+    let window_hours = window_hours.unwrap_or(24);
+    tokio::task::spawn_blocking(move || modules::account::get_all_account_health(window_hours))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 获取账号横向对比报告 (最近 24 小时，按请求数降序)
+#[tauri::command]
+pub async fn get_account_comparison_report(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+) -> Result<Vec<modules::account::AccountComparison>, String> {
+    let runtime = match proxy_state.instance.read().await.as_ref() {
+        Some(instance) => instance.token_manager.snapshot().await,
+        None => Vec::new(),
+    };
+    tokio::task::spawn_blocking(move || modules::account::get_account_comparison_report(&runtime))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 获取账号配额历史 (默认最近 7 天，可按模型过滤)，用于配额趋势图
+#[tauri::command]
+pub async fn get_quota_history(
+    account_id: String,
+    model: Option<String>,
+    days: Option<u32>,
+) -> Result<Vec<modules::proxy_db::QuotaSnapshot>, String> {
+    let days = days.unwrap_or(7).min(30);
+    tokio::task::spawn_blocking(move || {
+        modules::proxy_db::get_quota_history(&account_id, model.as_deref(), days)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 为账号添加标签
+#[tauri::command]
+pub async fn tag_account(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    account_id: String,
+    tag: String,
+) -> Result<Vec<String>, String> {
+    let tags = modules::account::tag_account(&account_id, &tag)?;
+
+    // 同步到运行中的反代服务（如果已启动）
+    let instance_lock = proxy_state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        let _ = instance.token_manager.reload_account(&account_id).await;
+    }
+
+    Ok(tags)
+}
+
+/// 移除账号标签
+#[tauri::command]
+pub async fn untag_account(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    account_id: String,
+    tag: String,
+) -> Result<Vec<String>, String> {
+    let tags = modules::account::untag_account(&account_id, &tag)?;
+
+    // 同步到运行中的反代服务（如果已启动）
+    let instance_lock = proxy_state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        let _ = instance.token_manager.reload_account(&account_id).await;
+    }
+
+    Ok(tags)
+}
+
+/// 列出带有指定标签的账号
+#[tauri::command]
+pub async fn list_accounts_by_tag(tag: String) -> Result<Vec<Account>, String> {
+    modules::account::list_accounts_by_tag(&tag)
+}
+
+/// 获取单个账号健康度
+#[tauri::command]
+pub async fn get_account_health(
+    account_id: String,
+    window_hours: Option<u32>,
+) -> Result<modules::account::AccountHealth, String> {
+    let window_hours = window_hours.unwrap_or(24);
+    tokio::task::spawn_blocking(move || modules::account::compute_account_health(&account_id, window_hours))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 批量禁用账号反代 (例如网络故障期间)
+#[tauri::command]
+pub async fn batch_disable_accounts(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    account_ids: Vec<String>,
+    reason: Option<String>,
+) -> Result<modules::account::BatchResult, String> {
+    let reason = reason.unwrap_or_else(|| "用户批量禁用".to_string());
+    let result = modules::account::batch_disable_accounts(&account_ids, &reason)?;
+
+    // 如果反代服务正在运行,重新加载账号池
+    let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+    crate::modules::tray::update_tray_menus(&app);
+
+    Ok(result)
+}
+
+/// 批量启用账号反代
+#[tauri::command]
+pub async fn batch_enable_accounts(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    account_ids: Vec<String>,
+) -> Result<modules::account::BatchResult, String> {
+    let result = modules::account::batch_enable_accounts(&account_ids)?;
+
+    let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+    crate::modules::tray::update_tray_menus(&app);
+
+    Ok(result)
+}
+
+/// 从 CSV / JSON 文件批量导入账号 (format 缺省时按扩展名识别)
+#[tauri::command]
+pub async fn import_accounts(
+    app: tauri::AppHandle,
+    path: String,
+    format: Option<String>,
+) -> Result<modules::account::ImportReport, String> {
+    let path = std::path::PathBuf::from(path);
+    let report = match format.as_deref() {
+        Some(format) => {
+            let format = modules::account::ImportFormat::parse(format)?;
+            modules::account::import_accounts_with_format(&path, format).await?
+        }
+        None => modules::account::import_accounts_from_file(&path).await?,
+    };
+
+    if report.imported > 0 {
+        // 反代运行中时重新加载账号池，使新账号立即生效
+        let _ = crate::commands::proxy::reload_proxy_accounts(
+            app.state::<crate::commands::proxy::ProxyServiceState>(),
+        )
+        .await;
+        crate::modules::tray::update_tray_menus(&app);
+    }
+
+    Ok(report)
+}
+
 #[tauri::command]
 pub async fn import_from_db(app: tauri::AppHandle) -> Result<Account, String> {
     // 同步函数包装为 async
@@ -699,39 +873,9 @@ pub async fn toggle_proxy_status(
         if enable { "启用" } else { "禁用" }
     ));
 
-    // 1. 读取账号文件
-    let data_dir = modules::account::get_data_dir()?;
-    let account_path = data_dir.join("accounts").join(format!("{}.json", account_id));
-
-    if !account_path.exists() {
-        return Err(format!("账号文件不存在: {}", account_id));
-    }
-
-    let content = std::fs::read_to_string(&account_path)
-        .map_err(|e| format!("读取账号文件失败: {}", e))?;
-
-    let mut account_json: serde_json::Value = serde_json::from_str(&content)
-        .map_err(|e| format!("解析账号文件失败: {}", e))?;
-
-    // 2. 更新 proxy_disabled 字段
-    if enable {
-        // 启用反代
-        account_json["proxy_disabled"] = serde_json::Value::Bool(false);
-        account_json["proxy_disabled_reason"] = serde_json::Value::Null;
-        account_json["proxy_disabled_at"] = serde_json::Value::Null;
-    } else {
-        // 禁用反代
-        let now = chrono::Utc::now().timestamp();
-        account_json["proxy_disabled"] = serde_json::Value::Bool(true);
-        account_json["proxy_disabled_at"] = serde_json::Value::Number(now.into());
-        account_json["proxy_disabled_reason"] = serde_json::Value::String(
-            reason.unwrap_or_else(|| "用户手动禁用".to_string())
-        );
-    }
-
-    // 3. 保存到磁盘
-    std::fs::write(&account_path, serde_json::to_string_pretty(&account_json).unwrap())
-        .map_err(|e| format!("写入账号文件失败: {}", e))?;
+    // 1. 更新账号文件的 proxy_disabled 字段 (经 account_crypto 读写，兼容加密账号文件)
+    let reason = reason.unwrap_or_else(|| "用户手动禁用".to_string());
+    modules::account::set_proxy_disabled(&account_id, (!enable).then_some(reason.as_str()))?;
 
     modules::logger::log_info(&format!(
         "账号反代状态已更新: {} ({})",
@@ -739,10 +883,10 @@ pub async fn toggle_proxy_status(
         if enable { "已启用" } else { "已禁用" }
     ));
 
-    // 4. 如果反代服务正在运行,重新加载账号池
+    // 2. 如果反代服务正在运行,重新加载账号池
     let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
 
-    // 5. 更新托盘菜单
+    // 3. 更新托盘菜单
     crate::modules::tray::update_tray_menus(&app);
 
     Ok(())
@@ -777,3 +921,15 @@ pub async fn save_http_api_settings(
 ) -> Result<(), String> {
     crate::modules::http_api::save_settings(&settings)
 }
+
+/// 获取 HTTP API 访问令牌
+#[tauri::command]
+pub async fn get_http_api_token() -> Result<String, String> {
+    crate::modules::http_api::load_settings().map(|s| s.token)
+}
+
+/// 轮换 HTTP API 访问令牌，旧令牌立即失效
+#[tauri::command]
+pub async fn regenerate_http_api_token() -> Result<String, String> {
+    crate::modules::http_api::regenerate_token()
+}
@@ -16,6 +16,15 @@ pub struct ProxyStatus {
     pub active_accounts: usize,
 }
 
+/// 账号每日请求用量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountDailyUsage {
+    pub account_id: String,
+    pub email: String,
+    pub count: u32,
+    pub limit: Option<u32>,
+}
+
 /// 反代服务全局状态
 pub struct ProxyServiceState {
     pub instance: Arc<RwLock<Option<ProxyServiceInstance>>>,
@@ -27,7 +36,19 @@ pub struct ProxyServiceInstance {
     pub config: ProxyConfig,
     pub token_manager: Arc<TokenManager>,
     pub axum_server: crate::proxy::AxumServer,
+    pub model_discovery: Arc<crate::proxy::model_discovery::ModelDiscovery>,
     pub server_handle: tokio::task::JoinHandle<()>,
+    pub alert_handle: tokio::task::JoinHandle<()>,
+    pub anomaly_handle: Option<tokio::task::JoinHandle<()>>,
+    pub quota_group_handle: tokio::task::JoinHandle<()>,
+    pub upstream_health_handle: tokio::task::JoinHandle<()>,
+    pub account_watcher_handle: Option<tokio::task::JoinHandle<()>>,
+    pub session_usage_handle: tokio::task::JoinHandle<()>,
+    pub prerefresh_handle: tokio::task::JoinHandle<()>,
+    pub session_gc_handle: tokio::task::JoinHandle<()>,
+    pub daily_reset_handle: tokio::task::JoinHandle<()>,
+    pub health_probe_handle: Option<tokio::task::JoinHandle<()>>,
+    pub model_discovery_handle: tokio::task::JoinHandle<()>,
 }
 
 impl ProxyServiceState {
@@ -53,12 +74,29 @@ pub async fn start_proxy_service(
         return Err("服务已在运行中".to_string());
     }
 
+    // 校验上游 mTLS 证书配置 (文件存在且可解析)
+    if let Some(mtls) = &config.upstream_mtls {
+        mtls.load().map_err(|e| format!("上游 mTLS 配置无效: {}", e))?;
+    }
+
+    // 加载响应签名私钥
+    let response_signer = match &config.response_signing {
+        Some(signing) => Some(Arc::new(
+            crate::proxy::middleware::response_signing::ResponseSigner::load(signing)
+                .map_err(|e| format!("响应签名配置无效: {}", e))?,
+        )),
+        None => None,
+    };
+
     // Ensure monitor exists
     {
         let mut monitor_lock = state.monitor.write().await;
         if monitor_lock.is_none() {
             *monitor_lock = Some(Arc::new(ProxyMonitor::new(1000, Some(app_handle.clone()))));
         }
+        if let (Some(monitor), Ok(app_config)) = (monitor_lock.as_ref(), crate::modules::config::load_app_config()) {
+            monitor.update_settings(&app_config);
+        }
         // Sync enabled state from config
         if let Some(monitor) = monitor_lock.as_ref() {
             monitor.set_enabled(config.enable_logging);
@@ -73,23 +111,63 @@ pub async fn start_proxy_service(
     let _ = crate::modules::account::get_accounts_dir()?;
     let accounts_dir = app_data_dir.clone();
     
+    // 恢复上次停止时保存的思维签名缓存 (文件损坏时跳过)
+    if let Err(e) = crate::proxy::SignatureCache::global().load_from_disk(&app_data_dir.join("signature_cache.bin")) {
+        tracing::warn!("恢复签名缓存失败，已跳过: {}", e);
+    }
+
     let token_manager = Arc::new(TokenManager::new(accounts_dir));
+    token_manager.set_app_handle(app_handle.clone());
     token_manager.start_auto_cleanup(); // 启动限流记录自动清理后台任务
+    let prerefresh_handle = token_manager.start_token_prerefresh_task(); // 启动 token 预刷新后台任务
+    let daily_reset_handle = token_manager.start_daily_usage_reset_task(); // 启动每日请求计数重置任务
+    let session_gc_handle = token_manager.start_session_gc_task(); // 启动会话绑定回收任务
     // 同步 UI 传递的调度配置
     token_manager.update_sticky_config(config.scheduling.clone()).await;
+    token_manager.set_quota_protection_enabled(
+        crate::modules::config::load_app_config()
+            .map(|cfg| cfg.quota_protection.enabled)
+            .unwrap_or(false),
+    );
     
     // 3. 加载账号
-    let active_accounts = token_manager.load_accounts().await
-        .map_err(|e| format!("加载账号失败: {}", e))?;
+    let active_accounts = match token_manager.load_accounts().await {
+        Ok(count) => count,
+        Err(e) => {
+            prerefresh_handle.abort();
+            session_gc_handle.abort();
+            daily_reset_handle.abort();
+            return Err(format!("加载账号失败: {}", e));
+        }
+    };
+
+    // 恢复上次停止时保存的粘性会话绑定
+    if let Err(e) = token_manager.restore_sessions().await {
+        tracing::warn!("恢复会话绑定失败: {}", e);
+    }
     
     if active_accounts == 0 {
         let zai_enabled = config.zai.enabled
             && !matches!(config.zai.dispatch_mode, crate::proxy::ZaiDispatchMode::Off);
         if !zai_enabled {
+            prerefresh_handle.abort();
+            session_gc_handle.abort();
+            daily_reset_handle.abort();
             return Err("没有可用账号，请先添加账号".to_string());
         }
     }
     
+    // 启动上游模型发现 (立即拉取一次，此后每小时刷新)
+    let discovery_upstream = Arc::new(crate::proxy::upstream::client::UpstreamClient::new(
+        Some(config.upstream_proxy.clone()),
+        config.upstream_mtls.as_ref(),
+    ));
+    let model_discovery = Arc::new(crate::proxy::model_discovery::ModelDiscovery::new(
+        token_manager.clone(),
+        discovery_upstream,
+    ));
+    let model_discovery_handle = model_discovery.start_refresh_task();
+    
     // 启动 Axum 服务器
     let (axum_server, server_handle) =
         match crate::proxy::AxumServer::start(
@@ -97,24 +175,75 @@ pub async fn start_proxy_service(
             config.port,
             token_manager.clone(),
             config.custom_mapping.clone(),
+            &config.regex_mapping,
+            config.fallback_chains.clone(),
             config.request_timeout,
+            config.model_timeouts.clone(),
             config.upstream_proxy.clone(),
+            config.upstream_mtls.clone(),
             crate::proxy::ProxySecurityConfig::from_proxy_config(&config),
             config.zai.clone(),
             monitor.clone(),
             config.experimental.clone(),
-
+            model_discovery.clone(),
+            config.retry.clone(),
+            config.grpc_port,
+            config.max_request_body_mb,
+            config.stream_heartbeat_interval_secs,
+            config.response_compression,
+            &config.response_headers,
+            response_signer,
         ).await {
             Ok((server, handle)) => (server, handle),
-            Err(e) => return Err(format!("启动 Axum 服务器失败: {}", e)),
+            Err(e) => {
+                model_discovery_handle.abort();
+                prerefresh_handle.abort();
+                session_gc_handle.abort();
+                daily_reset_handle.abort();
+                return Err(format!("启动 Axum 服务器失败: {}", e));
+            }
         };
     
+    // 启动错误率告警、用量异常检测与配额共享组统计
+    let app_config = crate::modules::config::load_app_config().unwrap_or_default();
+    let alert_handle =
+        crate::proxy::alerting::AlertWatcher::new(monitor.clone(), app_config.alert.clone(), Some(app_handle.clone()))
+            .start();
+    let anomaly_handle = app_config.anomaly_detection_enabled.then(|| {
+        crate::proxy::anomaly::AnomalyWatcher::new(
+            token_manager.anomaly_detector(),
+            app_config.anomaly_z_score_threshold,
+            Some(app_handle.clone()),
+        )
+        .start()
+    });
+    let quota_group_handle =
+        crate::proxy::quota_groups::QuotaGroupWatcher::new(token_manager.clone(), app_config.quota_groups.clone()).start();
+    let upstream_health_handle =
+        crate::proxy::upstream_health::UpstreamHealthChecker::new(axum_server.upstream_health(), Some(app_handle.clone()))
+            .start();
+    let account_watcher_handle = token_manager.start_account_watcher(Some(app_handle.clone()));
+    let session_usage_handle = token_manager.start_session_usage_task(); // 会话 token 用量统计
+    let health_probe_handle = token_manager.start_health_probe_task(config.health_probe_interval_secs); // 账号健康探测
+
     // 创建服务实例
     let instance = ProxyServiceInstance {
         config: config.clone(),
         token_manager: token_manager.clone(), // Clone for ProxyServiceInstance
         axum_server,
+        model_discovery,
         server_handle,
+        alert_handle,
+        anomaly_handle,
+        quota_group_handle,
+        upstream_health_handle,
+        account_watcher_handle,
+        session_usage_handle,
+        prerefresh_handle,
+        session_gc_handle,
+        daily_reset_handle,
+        health_probe_handle,
+        model_discovery_handle,
     };
     
     *instance_lock = Some(instance);
@@ -122,8 +251,11 @@ pub async fn start_proxy_service(
 
     // 保存配置到全局 AppConfig
     let mut app_config = crate::modules::config::load_app_config().map_err(|e| e)?;
-    app_config.proxy = config.clone();
+    crate::proxy::mappers::tool_result_compressor::set_drop_keys(app_config.tool_compressor.drop_keys.clone());
+    crate::proxy::plugins::reload(&app_config.plugins);
+    let old_proxy = std::mem::replace(&mut app_config.proxy, config.clone());
     crate::modules::config::save_app_config(&app_config).map_err(|e| e)?;
+    crate::modules::audit::record_change("start_proxy_service", &old_proxy, &config);
     
     Ok(ProxyStatus {
         running: true,
@@ -146,14 +278,62 @@ pub async fn stop_proxy_service(
     
     // 停止 Axum 服务器
     if let Some(instance) = instance_lock.take() {
+        instance.alert_handle.abort();
+        if let Some(handle) = &instance.anomaly_handle {
+            handle.abort();
+        }
+        instance.quota_group_handle.abort();
+        instance.upstream_health_handle.abort();
+        if let Some(handle) = &instance.account_watcher_handle {
+            handle.abort();
+        }
+        instance.session_usage_handle.abort();
+        instance.prerefresh_handle.abort();
+        instance.session_gc_handle.abort();
+        instance.daily_reset_handle.abort();
+        if let Some(handle) = &instance.health_probe_handle {
+            handle.abort();
+        }
+        instance.model_discovery_handle.abort();
         instance.axum_server.stop();
         // 等待服务器任务完成
         instance.server_handle.await.ok();
+        // 保存粘性会话绑定，供下次启动恢复
+        if let Err(e) = instance.token_manager.save_sessions() {
+            tracing::warn!("保存会话绑定失败: {}", e);
+        }
+        // 保存限流记录，避免重启后立即重试已限流的账号
+        if let Err(e) = instance.token_manager.save_rate_limits() {
+            tracing::warn!("保存限流记录失败: {}", e);
+        }
+        // 保存思维签名缓存，避免跨重启的工具调用循环丢失 thoughtSignature
+        if let Ok(data_dir) = crate::modules::account::get_data_dir() {
+            if let Err(e) = crate::proxy::SignatureCache::global().save_to_disk(&data_dir.join("signature_cache.bin")) {
+                tracing::warn!("保存签名缓存失败: {}", e);
+            }
+        }
     }
     
     Ok(())
 }
 
+/// 清除已保存的限流记录 (反代运行中时同时清空内存中的记录)
+#[tauri::command]
+pub async fn clear_saved_rate_limits(
+    state: State<'_, ProxyServiceState>,
+) -> Result<(), String> {
+    let path = crate::modules::account::get_data_dir()?.join(crate::proxy::rate_limit::RATE_LIMITS_FILE);
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("删除限流记录文件失败: {}", e))?;
+    }
+
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.token_manager.clear_all_rate_limits();
+    }
+    Ok(())
+}
+
 /// 获取反代服务状态
 #[tauri::command]
 pub async fn get_proxy_status(
@@ -183,11 +363,23 @@ pub async fn get_proxy_stats(
     state: State<'_, ProxyServiceState>,
 ) -> Result<ProxyStats, String> {
     let monitor_lock = state.monitor.read().await;
-    if let Some(monitor) = monitor_lock.as_ref() {
-        Ok(monitor.get_stats().await)
+    let mut stats = if let Some(monitor) = monitor_lock.as_ref() {
+        monitor.get_stats().await
     } else {
-        Ok(ProxyStats::default())
-    }
+        ProxyStats::default()
+    };
+    stats.latency_by_model = crate::modules::proxy_db::get_model_latency_stats(None, 7)
+        .unwrap_or_default();
+    Ok(stats)
+}
+
+/// 获取按模型统计的延迟分位数 (p50/p95/p99)
+#[tauri::command]
+pub async fn get_proxy_latency_stats(
+    model: Option<String>,
+    days: Option<u32>,
+) -> Result<Vec<crate::modules::proxy_db::ModelLatencyStats>, String> {
+    crate::modules::proxy_db::get_model_latency_stats(model.as_deref(), days.unwrap_or(7))
 }
 
 /// 获取反代请求日志
@@ -229,6 +421,32 @@ pub async fn clear_proxy_logs(
     Ok(())
 }
 
+/// 将超过指定天数的反代日志归档为 gzip JSONL (按月分文件) 并从数据库删除
+#[tauri::command]
+pub async fn archive_proxy_logs(
+    days: i64,
+    archive_dir: Option<String>,
+) -> Result<crate::modules::proxy_db::ArchiveStats, String> {
+    if days < 0 {
+        return Err("days must be >= 0".to_string());
+    }
+    let dir = match archive_dir.filter(|d| !d.trim().is_empty()) {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => crate::modules::proxy_db::get_archive_dir()?,
+    };
+    tokio::task::spawn_blocking(move || crate::modules::proxy_db::archive_logs_older_than(days, &dir))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 手动执行日志数据库 WAL checkpoint
+#[tauri::command]
+pub async fn force_wal_checkpoint() -> Result<crate::modules::proxy_db::WalCheckpointStats, String> {
+    tokio::task::spawn_blocking(crate::modules::proxy_db::wal_checkpoint)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 /// 获取反代请求日志 (分页)
 #[tauri::command]
 pub async fn get_proxy_logs_paginated(
@@ -241,6 +459,31 @@ pub async fn get_proxy_logs_paginated(
     )
 }
 
+/// 游标分页结果
+#[derive(serde::Serialize)]
+pub struct ProxyLogsPage {
+    pub logs: Vec<ProxyRequestLog>,
+    pub next_cursor: Option<String>,
+}
+
+/// 获取反代请求日志 (游标分页，适合大量日志时向后翻页)
+#[tauri::command]
+pub async fn get_proxy_logs_page(
+    cursor: Option<String>,
+    limit: usize,
+) -> Result<ProxyLogsPage, String> {
+    let after = cursor
+        .as_deref()
+        .map(crate::modules::proxy_db::parse_cursor)
+        .transpose()?;
+    let (logs, next_cursor) = crate::modules::proxy_db::get_logs_page_after(
+        after.as_ref().map(|(_, id)| id.as_str()),
+        after.as_ref().map(|(ts, _)| *ts),
+        limit,
+    )?;
+    Ok(ProxyLogsPage { logs, next_cursor })
+}
+
 /// 获取单条日志的完整详情
 #[tauri::command]
 pub async fn get_proxy_log_detail(
@@ -249,27 +492,127 @@ pub async fn get_proxy_log_detail(
     crate::modules::proxy_db::get_log_detail(&log_id)
 }
 
+/// 按 X-Request-ID 查询日志详情
+#[tauri::command]
+pub async fn get_proxy_log_by_request_id(
+    request_id: String,
+) -> Result<ProxyRequestLog, String> {
+    crate::modules::proxy_db::get_log_by_request_id(&request_id)
+}
+
 /// 获取日志总数
 #[tauri::command]
 pub async fn get_proxy_logs_count() -> Result<u64, String> {
     crate::modules::proxy_db::get_logs_count()
 }
 
-/// 导出所有日志到指定文件
+/// 使用 SQLite 在线备份 API 备份日志数据库，返回复制的页数
+#[tauri::command]
+pub async fn backup_proxy_logs(dest_path: String) -> Result<u64, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::proxy_db::backup_proxy_db(std::path::Path::new(&dest_path))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 从备份文件恢复日志数据库 (需先停止反代服务)
+#[tauri::command]
+pub async fn restore_proxy_logs(
+    src_path: String,
+    state: State<'_, ProxyServiceState>,
+) -> Result<(), String> {
+    if state.instance.read().await.is_some() {
+        return Err("请先停止反代服务再恢复日志数据库".to_string());
+    }
+    tokio::task::spawn_blocking(move || {
+        crate::modules::proxy_db::restore_proxy_db(std::path::Path::new(&src_path))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // 清空内存中的旧日志，下次查看时从数据库重新加载
+    if let Some(monitor) = state.monitor.read().await.as_ref() {
+        monitor.logs.write().await.clear();
+    }
+    Ok(())
+}
+
+/// 获取指定时间 (毫秒) 以来的估算费用汇总
+#[tauri::command]
+pub async fn get_cost_summary(
+    since_timestamp: i64,
+    by_model: Option<bool>,
+) -> Result<crate::modules::proxy_db::CostSummary, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::proxy_db::get_cost_summary(since_timestamp, by_model.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 生成月度用量报告 (format: "html" | "pdf")，返回 base64 编码的内容
+#[tauri::command]
+pub async fn generate_usage_report(year: i32, month: u32, format: String) -> Result<String, String> {
+    use base64::Engine as _;
+    let format = crate::modules::report::ReportFormat::parse(&format)?;
+    let bytes = tokio::task::spawn_blocking(move || {
+        crate::modules::report::generate_monthly_report(year, month, format)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+/// 获取日志数据库大小与日志总数
+#[tauri::command]
+pub async fn get_proxy_db_stats() -> Result<crate::modules::proxy_db::ProxyDbStats, String> {
+    tokio::task::spawn_blocking(|| {
+        Ok::<_, String>(crate::modules::proxy_db::ProxyDbStats {
+            size_bytes: crate::modules::proxy_db::get_db_size_bytes()?,
+            log_count: crate::modules::proxy_db::get_logs_count()?,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 导出日志到指定文件
+/// format: "csv" | "jsonl" | "json"（json 为完整的格式化数组，不支持过滤）
 #[tauri::command]
 pub async fn export_proxy_logs(
-    file_path: String,
+    format: String,
+    path: String,
+    filter: Option<String>,
+    errors_only: Option<bool>,
+    include_bodies: Option<bool>,
 ) -> Result<usize, String> {
-    let logs = crate::modules::proxy_db::get_all_logs_for_export()?;
-    let count = logs.len();
-    
-    let json = serde_json::to_string_pretty(&logs)
-        .map_err(|e| format!("Failed to serialize logs: {}", e))?;
-    
-    std::fs::write(&file_path, json)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-    
-    Ok(count)
+    let filter = filter.unwrap_or_default();
+    let errors_only = errors_only.unwrap_or(false);
+    let columns: Vec<&str> = crate::modules::proxy_db::EXPORT_COLUMNS
+        .iter()
+        .copied()
+        .filter(|c| include_bodies.unwrap_or(false) || (*c != "request_body" && *c != "response_body"))
+        .collect();
+    let file_path = std::path::Path::new(&path);
+
+    match format.as_str() {
+        "csv" => crate::modules::proxy_db::export_logs_csv(file_path, &filter, errors_only, &columns),
+        "jsonl" => crate::modules::proxy_db::export_logs_jsonl(file_path, &filter, errors_only, &columns),
+        "json" => {
+            let logs = crate::modules::proxy_db::get_all_logs_for_export()?;
+            let count = logs.len();
+            
+            let json = serde_json::to_string_pretty(&logs)
+                .map_err(|e| format!("Failed to serialize logs: {}", e))?;
+            
+            std::fs::write(file_path, json)
+                .map_err(|e| format!("Failed to write file: {}", e))?;
+            
+            Ok(count)
+        }
+        other => Err(format!("Unsupported export format: {}", other)),
+    }
 }
 
 /// 导出指定的日志JSON到文件
@@ -313,10 +656,86 @@ pub async fn get_proxy_logs_filtered(
     crate::modules::proxy_db::get_logs_filtered(&filter, errors_only, limit, offset)
 }
 
+/// 获取指定模型最近的失败请求
+#[tauri::command]
+pub async fn get_proxy_errors_by_model(
+    model: String,
+    limit: Option<usize>,
+) -> Result<Vec<crate::proxy::monitor::ProxyRequestLog>, String> {
+    crate::modules::proxy_db::get_errors_by_model(&model, limit.unwrap_or(50))
+}
+
+/// 获取按小时聚合的请求统计
+#[tauri::command]
+pub async fn get_proxy_hourly_stats(
+    hours: Option<u64>,
+) -> Result<Vec<crate::modules::proxy_db::AggregateBucket>, String> {
+    crate::modules::proxy_db::get_hourly_stats(hours.unwrap_or(24))
+}
+
 /// 生成 API Key
+/// 不传 label 时仅返回新密钥 (兼容旧版单一 api_key)；
+/// 传入 label 时会将密钥哈希加入多 Key 列表并热更新，明文只在此处返回一次
 #[tauri::command]
-pub fn generate_api_key() -> String {
-    format!("sk-{}", uuid::Uuid::new_v4().simple())
+pub async fn generate_api_key(
+    label: Option<String>,
+    permissions: Option<crate::proxy::config::KeyPermissions>,
+    expires_at: Option<i64>,
+    requests_per_minute: Option<u32>,
+    state: State<'_, ProxyServiceState>,
+) -> Result<String, String> {
+    let key = format!("sk-{}", uuid::Uuid::new_v4().simple());
+    let Some(label) = label else {
+        return Ok(key);
+    };
+
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let old_keys = app_config.proxy.api_keys.clone();
+    app_config.proxy.api_keys.push(crate::proxy::config::ApiKeyEntry {
+        key_hash: crate::proxy::security::hash_api_key(&key),
+        label,
+        permissions: permissions.unwrap_or_default(),
+        expires_at,
+        requests_per_minute,
+    });
+    crate::modules::config::save_app_config(&app_config)?;
+    crate::modules::audit::record_change("generate_api_key", &old_keys, &app_config.proxy.api_keys);
+    sync_api_keys(app_config.proxy.api_keys, &state).await;
+    Ok(key)
+}
+
+/// 列出多 API Key (仅包含哈希，不含明文)
+#[tauri::command]
+pub async fn list_api_keys() -> Result<Vec<crate::proxy::config::ApiKeyEntry>, String> {
+    Ok(crate::modules::config::load_app_config()?.proxy.api_keys)
+}
+
+/// 吊销 API Key
+#[tauri::command]
+pub async fn revoke_api_key(
+    key_hash: String,
+    state: State<'_, ProxyServiceState>,
+) -> Result<(), String> {
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let old_keys = app_config.proxy.api_keys.clone();
+    app_config.proxy.api_keys.retain(|entry| entry.key_hash != key_hash);
+    if app_config.proxy.api_keys.len() == old_keys.len() {
+        return Err("API Key 不存在".to_string());
+    }
+    crate::modules::config::save_app_config(&app_config)?;
+    crate::modules::audit::record_change("revoke_api_key", &old_keys, &app_config.proxy.api_keys);
+    sync_api_keys(app_config.proxy.api_keys, &state).await;
+    Ok(())
+}
+
+async fn sync_api_keys(
+    api_keys: Vec<crate::proxy::config::ApiKeyEntry>,
+    state: &State<'_, ProxyServiceState>,
+) {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.axum_server.update_api_keys(api_keys).await;
+    }
 }
 
 /// 重新加载账号（当主应用添加/删除账号时调用）
@@ -342,6 +761,7 @@ pub async fn update_model_mapping(
     config: ProxyConfig,
     state: State<'_, ProxyServiceState>,
 ) -> Result<(), String> {
+    crate::proxy::middleware::static_headers::parse_response_headers(&config.response_headers)?;
     let instance_lock = state.instance.read().await;
     
     // 1. 如果服务正在运行，立即更新内存中的映射 (这里目前只更新了 anthropic_mapping 的 RwLock, 
@@ -353,12 +773,131 @@ pub async fn update_model_mapping(
     
     // 2. 无论是否运行，都保存到全局配置持久化
     let mut app_config = crate::modules::config::load_app_config().map_err(|e| e)?;
+    let old_proxy = app_config.proxy.clone();
     app_config.proxy.custom_mapping = config.custom_mapping;
+    app_config.proxy.regex_mapping = config.regex_mapping;
+    app_config.proxy.fallback_chains = config.fallback_chains;
+    app_config.proxy.response_headers = config.response_headers;
+    app_config.proxy.model_timeouts = config.model_timeouts;
     crate::modules::config::save_app_config(&app_config).map_err(|e| e)?;
+    crate::modules::audit::record_change("update_model_mapping", &old_proxy, &app_config.proxy);
     
     Ok(())
 }
 
+/// 更新反代安全设置 (IP 白名单 / 免认证路径，热更新并持久化；未传入的项保持不变)
+#[tauri::command]
+pub async fn update_proxy_security(
+    ip_allowlist: Option<Vec<String>>,
+    bypass_paths: Option<Vec<String>>,
+    state: State<'_, ProxyServiceState>,
+) -> Result<(), String> {
+    let normalize = |entries: Vec<String>| -> Vec<String> {
+        entries
+            .into_iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
+    };
+    let ip_allowlist = ip_allowlist.map(normalize);
+    let bypass_paths = bypass_paths.map(normalize);
+
+    for entry in ip_allowlist.iter().flatten() {
+        entry
+            .parse::<ipnetwork::IpNetwork>()
+            .map_err(|e| format!("无效的 CIDR '{}': {}", entry, e))?;
+    }
+    if let Some(path) = bypass_paths.iter().flatten().find(|p| !p.starts_with('/')) {
+        return Err(format!("无效的免认证路径 '{}': 必须以 / 开头", path));
+    }
+
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        if let Some(list) = &ip_allowlist {
+            instance.axum_server.update_ip_allowlist(list.clone()).await;
+        }
+        if let Some(paths) = &bypass_paths {
+            instance.axum_server.update_bypass_paths(paths.clone()).await;
+        }
+    }
+
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let old_proxy = app_config.proxy.clone();
+    if let Some(list) = ip_allowlist {
+        app_config.proxy.ip_allowlist = list;
+    }
+    if let Some(paths) = bypass_paths {
+        app_config.proxy.bypass_paths = paths;
+    }
+    crate::modules::config::save_app_config(&app_config)?;
+    crate::modules::audit::record_change("update_proxy_security", &old_proxy, &app_config.proxy);
+    Ok(())
+}
+
+/// 更新模型降级链 (热更新并持久化)
+#[tauri::command]
+pub async fn update_fallback_chains(
+    chains: std::collections::HashMap<String, Vec<String>>,
+    state: State<'_, ProxyServiceState>,
+) -> Result<(), String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.axum_server.update_fallback_chains(chains.clone()).await;
+    }
+
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let old_chains = std::mem::replace(&mut app_config.proxy.fallback_chains, chains);
+    crate::modules::config::save_app_config(&app_config)?;
+    crate::modules::audit::record_change("update_fallback_chains", &old_chains, &app_config.proxy.fallback_chains);
+    Ok(())
+}
+
+/// 立即从上游重新拉取可用模型列表
+#[tauri::command]
+pub async fn refresh_discovered_models(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<String>, String> {
+    let instance_lock = state.instance.read().await;
+    let instance = instance_lock.as_ref().ok_or("服务未运行")?;
+    instance.model_discovery.refresh().await
+}
+
+/// 查询模型能力 (上下文窗口、视觉/工具/思考支持)，供前端展示能力标签
+#[tauri::command]
+pub async fn get_model_capabilities(
+    model: String,
+) -> Result<crate::proxy::common::model_mapping::ModelCapability, String> {
+    let model = model.trim();
+    if model.is_empty() {
+        return Err("模型名称不能为空".to_string());
+    }
+    Ok(crate::proxy::common::model_mapping::get_model_capability(model))
+}
+
+/// 更新正则模型映射规则 (热更新并持久化)
+#[tauri::command]
+pub async fn update_regex_mapping(
+    rules: Vec<crate::proxy::config::RegexMappingConfig>,
+    state: State<'_, ProxyServiceState>,
+) -> Result<(), String> {
+    // 提前校验，避免保存无法编译的规则
+    for rule in &rules {
+        regex::Regex::new(&rule.pattern)
+            .map_err(|e| format!("无效的正则表达式 '{}': {}", rule.pattern, e))?;
+    }
+
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.axum_server.update_regex_mapping(&rules).await;
+    }
+
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let old_rules = std::mem::replace(&mut app_config.proxy.regex_mapping, rules);
+    crate::modules::config::save_app_config(&app_config)?;
+    crate::modules::audit::record_change("update_regex_mapping", &old_rules, &app_config.proxy.regex_mapping);
+    Ok(())
+}
+
 fn join_base_url(base: &str, path: &str) -> String {
     let base = base.trim_end_matches('/');
     let path = if path.starts_with('/') {
@@ -433,25 +972,20 @@ pub async fn fetch_zai_models(
 
     let url = join_base_url(&zai.base_url, "/v1/models");
 
-    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(request_timeout.max(5)));
-    if upstream_proxy.enabled && !upstream_proxy.url.is_empty() {
-        let proxy = reqwest::Proxy::all(&upstream_proxy.url)
-            .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
-        builder = builder.proxy(proxy);
-    }
-    let client = builder
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let builder = reqwest::Client::builder().timeout(Duration::from_secs(request_timeout.max(5)));
+    let client = crate::proxy::upstream_proxy::PooledClient::build(builder, &upstream_proxy)?;
 
-    let resp = client
+    let result = client
+        .client
         .get(&url)
         .header("Authorization", format!("Bearer {}", zai.api_key))
         .header("x-api-key", zai.api_key)
         .header("anthropic-version", "2023-06-01")
         .header("accept", "application/json")
         .send()
-        .await
-        .map_err(|e| format!("Upstream request failed: {}", e))?;
+        .await;
+    client.report(&result);
+    let resp = result.map_err(|e| format!("Upstream request failed: {}", e))?;
 
     let status = resp.status();
     let text = resp.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
@@ -489,13 +1023,141 @@ pub async fn update_proxy_scheduling_config(
     state: State<'_, ProxyServiceState>,
     config: crate::proxy::sticky_config::StickySessionConfig,
 ) -> Result<(), String> {
+    // 先持久化，服务未运行时下次启动生效
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let old_scheduling = std::mem::replace(&mut app_config.proxy.scheduling, config.clone());
+    crate::modules::config::save_app_config(&app_config)?;
+    crate::modules::audit::record_change("update_proxy_scheduling_config", &old_scheduling, &config);
+
     let instance_lock = state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
         instance.token_manager.update_sticky_config(config).await;
-        Ok(())
-    } else {
-        Err("服务未运行，无法更新实时配置".to_string())
     }
+    Ok(())
+}
+
+/// 比较两个配置片段是否不同 (按序列化结果比较，避免为所有配置类型派生 PartialEq)
+fn section_changed<T: serde::Serialize>(old: &T, new: &T) -> bool {
+    serde_json::to_value(old).ok() != serde_json::to_value(new).ok()
+}
+
+/// 从磁盘重新加载配置并热更新正在运行的服务，返回已应用的配置项
+/// 端口与监听地址需要重启服务，仅记录警告并保留当前值
+pub async fn reload_running_config(state: &ProxyServiceState) -> Result<Vec<String>, String> {
+    let app_config = crate::modules::config::load_app_config()?;
+    // Webhook 订阅位于 HTTP API 设置中，随配置重载一并刷新
+    if let Ok(settings) = crate::modules::http_api::load_settings() {
+        crate::modules::webhook::reload(settings.webhooks);
+    }
+    crate::proxy::mappers::tool_result_compressor::set_drop_keys(app_config.tool_compressor.drop_keys.clone());
+    crate::proxy::plugins::reload(&app_config.plugins);
+    if let Some(monitor) = state.monitor.read().await.as_ref() {
+        monitor.update_settings(&app_config);
+    }
+
+    let mut instance_lock = state.instance.write().await;
+    let Some(instance) = instance_lock.as_mut() else {
+        return Ok(Vec::new());
+    };
+
+    instance.token_manager.set_quota_protection_enabled(app_config.quota_protection.enabled);
+
+    let old = instance.config.clone();
+    let mut new = app_config.proxy;
+    let mut applied = Vec::new();
+
+    if old.port != new.port || old.allow_lan_access != new.allow_lan_access {
+        tracing::warn!(
+            "[ConfigReload] 端口/监听地址变更 ({}:{} -> {}:{}) 需重启反代服务后生效，已跳过",
+            old.get_bind_address(),
+            old.port,
+            new.get_bind_address(),
+            new.port
+        );
+        new.port = old.port;
+        new.allow_lan_access = old.allow_lan_access;
+    }
+    if old.max_request_body_mb != new.max_request_body_mb {
+        tracing::warn!(
+            "[ConfigReload] 请求体大小上限变更 ({} MB -> {} MB) 需重启反代服务后生效，已跳过",
+            old.max_request_body_mb,
+            new.max_request_body_mb
+        );
+        new.max_request_body_mb = old.max_request_body_mb;
+    }
+    if old.stream_heartbeat_interval_secs != new.stream_heartbeat_interval_secs {
+        tracing::warn!(
+            "[ConfigReload] 流式心跳间隔变更 ({}s -> {}s) 需重启反代服务后生效，已跳过",
+            old.stream_heartbeat_interval_secs,
+            new.stream_heartbeat_interval_secs
+        );
+        new.stream_heartbeat_interval_secs = old.stream_heartbeat_interval_secs;
+    }
+    if old.response_compression != new.response_compression {
+        tracing::warn!(
+            "[ConfigReload] 响应压缩开关变更 ({} -> {}) 需重启反代服务后生效，已跳过",
+            old.response_compression,
+            new.response_compression
+        );
+        new.response_compression = old.response_compression;
+    }
+    if old.upstream_mtls != new.upstream_mtls {
+        tracing::warn!("[ConfigReload] 上游 mTLS 证书配置变更需重启反代服务后生效，已跳过");
+        new.upstream_mtls = old.upstream_mtls.clone();
+    }
+    if old.response_signing != new.response_signing {
+        tracing::warn!("[ConfigReload] 响应签名配置变更需重启反代服务后生效，已跳过");
+        new.response_signing = old.response_signing.clone();
+    }
+
+    if section_changed(&old.custom_mapping, &new.custom_mapping)
+        || section_changed(&old.regex_mapping, &new.regex_mapping)
+        || section_changed(&old.fallback_chains, &new.fallback_chains)
+        || section_changed(&old.response_headers, &new.response_headers)
+        || section_changed(&old.model_timeouts, &new.model_timeouts)
+    {
+        instance.axum_server.update_mapping(&new).await;
+        applied.push("model_mapping".to_string());
+    }
+    if section_changed(&old.upstream_proxy, &new.upstream_proxy) {
+        instance.axum_server.update_proxy(new.upstream_proxy.clone()).await;
+        applied.push("upstream_proxy".to_string());
+    }
+    if section_changed(
+        &(&old.auth_mode, &old.api_key, &old.api_keys, &old.ip_allowlist, &old.bypass_paths, &old.cors_allowed_origins),
+        &(&new.auth_mode, &new.api_key, &new.api_keys, &new.ip_allowlist, &new.bypass_paths, &new.cors_allowed_origins),
+    ) {
+        instance.axum_server.update_security(&new).await;
+        applied.push("security".to_string());
+    }
+    if section_changed(&old.zai, &new.zai) {
+        instance.axum_server.update_zai(&new).await;
+        applied.push("zai".to_string());
+    }
+    if section_changed(&old.experimental, &new.experimental) {
+        instance.axum_server.update_experimental(&new).await;
+        applied.push("experimental".to_string());
+    }
+    if section_changed(&old.retry, &new.retry) {
+        instance.axum_server.update_retry(&new).await;
+        applied.push("retry".to_string());
+    }
+    if section_changed(&old.scheduling, &new.scheduling) {
+        instance.token_manager.update_sticky_config(new.scheduling.clone()).await;
+        applied.push("scheduling".to_string());
+    }
+
+    instance.config = new;
+    if !applied.is_empty() {
+        tracing::info!("[ConfigReload] 已热更新: {}", applied.join(", "));
+    }
+    Ok(applied)
+}
+
+/// 手动触发配置重载 (与文件监听效果相同)
+#[tauri::command]
+pub async fn reload_config(state: State<'_, ProxyServiceState>) -> Result<Vec<String>, String> {
+    reload_running_config(&state).await
 }
 
 /// 清除所有会话粘性绑定
@@ -506,9 +1168,285 @@ pub async fn clear_proxy_session_bindings(
     let instance_lock = state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
         instance.token_manager.clear_all_sessions();
+        instance.axum_server.clear_search_seen_urls();
         Ok(())
     } else {
         Err("服务未运行".to_string())
     }
 }
 
+
+/// 获取账号健康探测结果
+#[tauri::command]
+pub async fn get_proxy_health(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::token_manager::ProbeResult>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.token_manager.get_health_probe_results())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取账号池状态快照（调试用）
+#[tauri::command]
+pub async fn get_token_pool_snapshot(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::token_manager::AccountSnapshot>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.token_manager.snapshot().await)
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取各账号限流与熔断器状态
+#[tauri::command]
+pub async fn get_proxy_rate_limits(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::token_manager::AccountRateLimitStatus>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.token_manager.rate_limit_statuses())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 订阅限流状态变更 (Tauri Channel)：先推送全量快照，之后推送变更事件，直到前端关闭 Channel 或服务停止
+#[tauri::command]
+pub async fn subscribe_rate_limits(
+    channel: tauri::ipc::Channel<crate::proxy::token_manager::RateLimitEvent>,
+    state: State<'_, ProxyServiceState>,
+) -> Result<(), String> {
+    use crate::proxy::token_manager::RateLimitEvent;
+
+    let token_manager = {
+        let instance_lock = state.instance.read().await;
+        match instance_lock.as_ref() {
+            Some(instance) => instance.token_manager.clone(),
+            None => return Err("服务未运行".to_string()),
+        }
+    };
+    let mut rx = token_manager.rate_limit_events().subscribe();
+    channel
+        .send(RateLimitEvent::Snapshot {
+            statuses: token_manager.rate_limit_statuses(),
+        })
+        .map_err(|e| e.to_string())?;
+    drop(token_manager);
+
+    tokio::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    if channel.send(event).is_err() {
+                        break;
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Rate limit channel lagged, skipped {} events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+/// 生成响应签名密钥对 (私钥写入 path，公钥写入 <path>.pub)
+#[tauri::command]
+pub async fn generate_response_signing_keypair(path: String) -> Result<(), String> {
+    crate::proxy::middleware::response_signing::generate_keypair(std::path::Path::new(&path))
+}
+
+/// 获取最近的配置变更审计记录 (新记录在前)
+#[tauri::command]
+pub async fn get_audit_log(limit: usize) -> Result<Vec<crate::modules::audit::AuditEntry>, String> {
+    crate::modules::audit::AuditLogger::global().recent(limit)
+}
+
+/// 获取各上游代理的健康状态 (连续失败次数、冷却剩余时间)
+#[tauri::command]
+pub async fn get_upstream_proxy_status(
+) -> Result<Vec<crate::proxy::upstream_proxy::UpstreamProxyStatus>, String> {
+    let config = crate::modules::config::load_app_config()?;
+    Ok(crate::proxy::upstream_proxy::UpstreamProxyPool::global()
+        .status(&config.proxy.upstream_proxy))
+}
+
+/// 获取各账号最近请求的 P50/P95/P99 延迟
+#[tauri::command]
+pub async fn get_account_latency_stats(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::token_manager::AccountLatencyStats>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.token_manager.account_latency_stats())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取各账号最近 1 小时 / 5 分钟的上游错误率
+#[tauri::command]
+pub async fn get_error_budget_snapshot(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::error_budget::ErrorBudgetSnapshot>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.token_manager.error_budget_snapshot())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取上游可达性与延迟
+#[tauri::command]
+pub async fn get_upstream_health(
+    state: State<'_, ProxyServiceState>,
+) -> Result<crate::proxy::upstream_health::UpstreamHealthSnapshot, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.axum_server.upstream_health().read().await.snapshot())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 列出内置及插件目录中的请求转换插件
+#[tauri::command]
+pub fn list_plugins() -> Result<Vec<crate::proxy::plugins::PluginInfo>, String> {
+    let app_config = crate::modules::config::load_app_config()?;
+    Ok(crate::proxy::plugins::list_plugins(&app_config.plugins))
+}
+
+/// 启用请求转换插件 (立即生效)
+#[tauri::command]
+pub fn enable_plugin(name: String) -> Result<(), String> {
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let plugin = crate::proxy::plugins::list_plugins(&app_config.plugins)
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("未找到插件: {}", name))?;
+    if let Some(e) = plugin.error {
+        return Err(format!("插件清单无效: {}", e));
+    }
+    let old_enabled = app_config.plugins.enabled.clone();
+    if !app_config.plugins.enabled.contains(&name) {
+        app_config.plugins.enabled.push(name);
+    }
+    crate::modules::config::save_app_config(&app_config)?;
+    crate::modules::audit::record_change("enable_plugin", &old_enabled, &app_config.plugins.enabled);
+    crate::proxy::plugins::reload(&app_config.plugins);
+    Ok(())
+}
+
+/// 禁用请求转换插件 (立即生效)
+#[tauri::command]
+pub fn disable_plugin(name: String) -> Result<(), String> {
+    let mut app_config = crate::modules::config::load_app_config()?;
+    let old_enabled = app_config.plugins.enabled.clone();
+    app_config.plugins.enabled.retain(|n| n != &name);
+    crate::modules::config::save_app_config(&app_config)?;
+    crate::modules::audit::record_change("disable_plugin", &old_enabled, &app_config.plugins.enabled);
+    crate::proxy::plugins::reload(&app_config.plugins);
+    Ok(())
+}
+
+/// 获取思维签名缓存命中率统计
+#[tauri::command]
+pub fn get_signature_cache_stats() -> crate::proxy::signature_cache::SignatureCacheReport {
+    crate::proxy::SignatureCache::global().stats()
+}
+
+/// 获取 z.ai Web Reader 缓存命中统计
+#[tauri::command]
+pub async fn get_web_reader_cache_stats(
+    state: State<'_, ProxyServiceState>,
+) -> Result<crate::proxy::zai_web_tools::CacheStatsSnapshot, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.axum_server.web_reader_cache_stats())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取当前会话绑定数量
+#[tauri::command]
+pub async fn get_session_count(
+    state: State<'_, ProxyServiceState>,
+) -> Result<usize, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.token_manager.session_count())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取活跃粘性会话列表 (客户端 IP 已脱敏)
+#[tauri::command]
+pub async fn list_active_sessions(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::token_manager::SessionSnapshot>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.token_manager.list_sessions())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取单个会话的累计 token 用量
+#[tauri::command]
+pub async fn get_session_token_usage(
+    session_id: String,
+    state: State<'_, ProxyServiceState>,
+) -> Result<crate::proxy::token_manager::SessionTokenUsageSnapshot, String> {
+    let instance_lock = state.instance.read().await;
+    let instance = instance_lock.as_ref().ok_or("服务未运行")?;
+    instance
+        .token_manager
+        .session_token_usage(&session_id)
+        .ok_or_else(|| format!("未找到会话 {} 的用量记录", session_id))
+}
+
+/// 获取所有会话的累计 token 用量
+#[tauri::command]
+pub async fn list_session_token_usage(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<crate::proxy::token_manager::SessionTokenUsageSnapshot>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.token_manager.list_session_token_usage())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// 获取各账号当日请求用量
+#[tauri::command]
+pub async fn get_account_daily_usage(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Vec<AccountDailyUsage>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance
+            .token_manager
+            .get_daily_usage()
+            .into_iter()
+            .map(|(account_id, email, count, limit)| AccountDailyUsage {
+                account_id,
+                email,
+                count,
+                limit,
+            })
+            .collect())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
@@ -46,6 +46,9 @@ pub fn run() {
             // 自动启动反代服务
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
+                // 校验配置文件，错误通过事件通知前端
+                modules::config::emit_validation_errors(&handle);
+
                 // 加载配置
                 if let Ok(config) = modules::config::load_app_config() {
                     if config.proxy.auto_start {
@@ -64,13 +67,19 @@ pub fn run() {
                 }
             });
             
+            // 与可信时间源校准时钟偏移，用于配额刷新时间的精确锁定
+            proxy::time_sync::TimeSyncManager::global().start_background_sync();
+
+            // 监听配置文件变更并热更新反代服务
+            modules::config_watcher::start(app.handle().clone());
+
             // 启动智能调度器
             modules::scheduler::start_scheduler(app.handle().clone());
             
             // 启动 HTTP API 服务器（供外部程序调用，如 VS Code 插件）
             match modules::http_api::load_settings() {
                 Ok(settings) if settings.enabled => {
-                    modules::http_api::spawn_server(settings.port);
+                    modules::http_api::spawn_server(settings.port, app.handle().clone());
                     info!("HTTP API server started on port {}", settings.port);
                 }
                 Ok(_) => {
@@ -79,7 +88,7 @@ pub fn run() {
                 Err(e) => {
                     // 加载失败时使用默认端口
                     error!("Failed to load HTTP API settings: {}, using default port", e);
-                    modules::http_api::spawn_server(modules::http_api::DEFAULT_PORT);
+                    modules::http_api::spawn_server(modules::http_api::DEFAULT_PORT, app.handle().clone());
                     info!("HTTP API server started on port {}", modules::http_api::DEFAULT_PORT);
                 }
             }
@@ -104,6 +113,10 @@ pub fn run() {
             commands::add_account,
             commands::delete_account,
             commands::delete_accounts,
+            commands::export_account_data,
+            commands::delete_account_data,
+            commands::migrate_accounts_to_encrypted,
+            commands::migrate_accounts_to_plaintext,
             commands::reorder_accounts,
             commands::switch_account,
             // 设备指纹
@@ -124,12 +137,14 @@ pub fn run() {
             // 配置命令
             commands::load_config,
             commands::save_config,
+            commands::get_config_validation_errors,
             // 新增命令
             commands::prepare_oauth_url,
             commands::start_oauth_login,
             commands::complete_oauth_login,
             commands::cancel_oauth_login,
             commands::import_v1_accounts,
+            commands::import_accounts,
             commands::import_from_db,
             commands::import_custom_db,
             commands::sync_account_from_db,
@@ -147,28 +162,78 @@ pub fn run() {
             commands::should_check_updates,
             commands::update_last_check_time,
             commands::toggle_proxy_status,
+            commands::batch_disable_accounts,
+            commands::batch_enable_accounts,
+            commands::get_all_account_health,
+            commands::get_account_comparison_report,
+            commands::get_quota_history,
+            commands::get_account_health,
+            commands::tag_account,
+            commands::untag_account,
+            commands::list_accounts_by_tag,
             // 反代服务命令
             commands::proxy::start_proxy_service,
             commands::proxy::stop_proxy_service,
+            commands::proxy::clear_saved_rate_limits,
             commands::proxy::get_proxy_status,
             commands::proxy::get_proxy_stats,
             commands::proxy::get_proxy_logs,
             commands::proxy::get_proxy_logs_paginated,
+            commands::proxy::get_proxy_logs_page,
             commands::proxy::get_proxy_log_detail,
+            commands::proxy::get_proxy_log_by_request_id,
             commands::proxy::get_proxy_logs_count,
+            commands::proxy::get_proxy_db_stats,
+            commands::proxy::get_cost_summary,
+            commands::proxy::generate_usage_report,
+            commands::proxy::get_upstream_health,
+            commands::proxy::list_plugins,
+            commands::proxy::enable_plugin,
+            commands::proxy::disable_plugin,
+            commands::proxy::backup_proxy_logs,
+            commands::proxy::restore_proxy_logs,
             commands::proxy::export_proxy_logs,
             commands::proxy::export_proxy_logs_json,
             commands::proxy::get_proxy_logs_count_filtered,
             commands::proxy::get_proxy_logs_filtered,
+            commands::proxy::get_proxy_errors_by_model,
+            commands::proxy::get_proxy_hourly_stats,
+            commands::proxy::get_proxy_latency_stats,
             commands::proxy::set_proxy_monitor_enabled,
             commands::proxy::clear_proxy_logs,
             commands::proxy::generate_api_key,
+            commands::proxy::list_api_keys,
+            commands::proxy::revoke_api_key,
             commands::proxy::reload_proxy_accounts,
             commands::proxy::update_model_mapping,
+            commands::proxy::update_regex_mapping,
+            commands::proxy::update_fallback_chains,
+            commands::proxy::update_proxy_security,
+            commands::proxy::get_model_capabilities,
+            commands::proxy::refresh_discovered_models,
             commands::proxy::fetch_zai_models,
             commands::proxy::get_proxy_scheduling_config,
             commands::proxy::update_proxy_scheduling_config,
+            commands::proxy::reload_config,
+            commands::proxy::force_wal_checkpoint,
+            commands::proxy::archive_proxy_logs,
             commands::proxy::clear_proxy_session_bindings,
+            commands::proxy::get_proxy_health,
+            commands::proxy::get_account_daily_usage,
+            commands::proxy::get_token_pool_snapshot,
+            commands::proxy::list_active_sessions,
+            commands::proxy::get_session_token_usage,
+            commands::proxy::list_session_token_usage,
+            commands::proxy::get_session_count,
+            commands::proxy::get_signature_cache_stats,
+            commands::proxy::get_web_reader_cache_stats,
+            commands::proxy::get_proxy_rate_limits,
+            commands::proxy::subscribe_rate_limits,
+            commands::proxy::get_upstream_proxy_status,
+            commands::proxy::generate_response_signing_keypair,
+            commands::proxy::get_audit_log,
+            commands::proxy::get_account_latency_stats,
+            commands::proxy::get_error_budget_snapshot,
             // Autostart 命令
             commands::autostart::toggle_auto_launch,
             commands::autostart::is_auto_launch_enabled,
@@ -178,10 +243,16 @@ pub fn run() {
             // HTTP API 设置命令
             commands::get_http_api_settings,
             commands::save_http_api_settings,
+            commands::get_http_api_token,
+            commands::regenerate_http_api_token,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app_handle, event| {
+            if matches!(event, tauri::RunEvent::Exit) {
+                proxy::otel::shutdown();
+            }
+
             // Handle macOS dock icon click to reopen window
             #[cfg(target_os = "macos")]
             if let tauri::RunEvent::Reopen { .. } = event {
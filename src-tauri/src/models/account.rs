@@ -37,6 +37,9 @@ pub struct Account {
     /// 受配额保护禁用的模型列表 [NEW #621]
     #[serde(default, skip_serializing_if = "HashSet::is_empty")]
     pub protected_models: HashSet<String>,
+    /// 账号标签 (用于分组与按标签路由)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     pub created_at: i64,
     pub last_used: i64,
 }
@@ -59,6 +62,7 @@ impl Account {
             proxy_disabled_reason: None,
             proxy_disabled_at: None,
             protected_models: HashSet::new(),
+            tags: Vec::new(),
             created_at: now,
             last_used: now,
         }
@@ -114,6 +118,9 @@ pub struct DeviceProfile {
     pub mac_machine_id: String,
     pub dev_device_id: String,
     pub sqm_id: String,
+    /// dev_device_id 过期时间 (Unix 秒)；None 表示未知 (如从 storage.json 捕获)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
 }
 
 /// 指纹历史版本
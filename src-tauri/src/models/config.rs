@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use crate::proxy::ProxyConfig;
 
 /// 应用配置
@@ -21,6 +22,34 @@ pub struct AppConfig {
     pub scheduled_warmup: ScheduledWarmupConfig, // [NEW] 定时预热配置
     #[serde(default)]
     pub quota_protection: QuotaProtectionConfig, // [NEW] 配额保护配置
+    #[serde(default)]
+    pub tool_compressor: ToolCompressorConfig, // 工具结果压缩配置
+    #[serde(default)]
+    pub alert: AlertConfig, // 错误率告警配置
+    #[serde(default)]
+    pub health_weights: HealthWeights, // 账号健康度评分权重
+    #[serde(default)]
+    pub quota_groups: Vec<QuotaGroup>, // 配额共享组
+    #[serde(default = "default_model_pricing")]
+    pub model_pricing: HashMap<String, ModelPricing>, // 模型单价 (用于估算请求费用)
+    #[serde(default)]
+    pub scrub_pii: bool, // 日志写入前脱敏 PII
+    #[serde(default)]
+    pub pii_patterns: Vec<String>, // 额外的脱敏正则
+    #[serde(default)]
+    pub plugins: PluginsConfig, // 请求转换插件
+    #[serde(default)]
+    pub encrypt_accounts: bool, // 账号文件加密落盘 (密钥保存在系统钥匙串)
+    #[serde(default)]
+    pub anomaly_detection_enabled: bool, // 账号用量异常检测
+    #[serde(default)]
+    pub auto_archive_days: Option<u32>, // 反代日志超过 N 天后归档为 gzip JSONL 并从数据库删除
+    #[serde(default)]
+    pub log_retention: LogRetentionPolicy, // 按类别的反代日志保留天数
+    #[serde(default = "default_anomaly_z_score_threshold")]
+    pub anomaly_z_score_threshold: f64, // 每分钟请求数超过 均值 + N 倍标准差 时告警
+    #[serde(default)]
+    pub device_profile_refresh_enabled: bool, // 设备指纹过期前 48 小时内自动重新生成
 }
 
 /// 定时预热配置
@@ -92,6 +121,221 @@ impl Default for QuotaProtectionConfig {
     }
 }
 
+/// 工具结果压缩配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCompressorConfig {
+    /// JSON 工具结果中需要递归剔除的噪声字段
+    #[serde(default = "default_drop_keys")]
+    pub drop_keys: Vec<String>,
+}
+
+fn default_drop_keys() -> Vec<String> {
+    crate::proxy::mappers::tool_result_compressor::DEFAULT_DROP_KEYS
+        .iter()
+        .map(|k| k.to_string())
+        .collect()
+}
+
+impl Default for ToolCompressorConfig {
+    fn default() -> Self {
+        Self {
+            drop_keys: default_drop_keys(),
+        }
+    }
+}
+
+/// 错误率告警配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertConfig {
+    /// 触发告警的错误率阈值 (0.0-1.0)
+    #[serde(default = "default_error_rate_threshold")]
+    pub error_rate_threshold: f64,
+
+    /// 滚动统计窗口 (秒)
+    #[serde(default = "default_alert_window_secs")]
+    pub window_secs: u64,
+
+    /// 两次告警之间的最小间隔 (秒)
+    #[serde(default = "default_alert_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_anomaly_z_score_threshold() -> f64 {
+    3.0
+}
+
+fn default_error_rate_threshold() -> f64 {
+    0.2
+}
+
+fn default_alert_window_secs() -> u64 {
+    300
+}
+
+fn default_alert_cooldown_secs() -> u64 {
+    600
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            error_rate_threshold: default_error_rate_threshold(),
+            window_secs: default_alert_window_secs(),
+            cooldown_secs: default_alert_cooldown_secs(),
+        }
+    }
+}
+
+/// 反代日志保留策略 (天数，0 表示该类别不按时间清理)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogRetentionPolicy {
+    /// 成功请求 (2xx/3xx)
+    #[serde(default = "default_log_retention_days")]
+    pub success_days: u32,
+
+    /// 错误请求 (4xx/5xx 及未拿到响应的请求)
+    #[serde(default = "default_log_retention_days")]
+    pub error_days: u32,
+
+    /// 请求体 + 响应体超过 1 MB 的日志 (不论状态)
+    #[serde(default = "default_log_retention_days")]
+    pub large_body_days: u32,
+}
+
+fn default_log_retention_days() -> u32 {
+    30
+}
+
+impl Default for LogRetentionPolicy {
+    fn default() -> Self {
+        Self {
+            success_days: default_log_retention_days(),
+            error_days: default_log_retention_days(),
+            large_body_days: default_log_retention_days(),
+        }
+    }
+}
+
+/// 账号健康度评分权重 (按权重和归一化，无需恰好加总为 1)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthWeights {
+    #[serde(default = "default_weight_success_rate")]
+    pub success_rate: f32,
+    #[serde(default = "default_weight_latency")]
+    pub latency: f32,
+    #[serde(default = "default_weight_rate_limit")]
+    pub rate_limit: f32,
+    #[serde(default = "default_weight_quota")]
+    pub quota: f32,
+    #[serde(default = "default_weight_token_refresh")]
+    pub token_refresh: f32,
+}
+
+fn default_weight_success_rate() -> f32 {
+    0.35
+}
+
+fn default_weight_latency() -> f32 {
+    0.15
+}
+
+fn default_weight_rate_limit() -> f32 {
+    0.15
+}
+
+fn default_weight_quota() -> f32 {
+    0.25
+}
+
+fn default_weight_token_refresh() -> f32 {
+    0.1
+}
+
+impl Default for HealthWeights {
+    fn default() -> Self {
+        Self {
+            success_rate: default_weight_success_rate(),
+            latency: default_weight_latency(),
+            rate_limit: default_weight_rate_limit(),
+            quota: default_weight_quota(),
+            token_refresh: default_weight_token_refresh(),
+        }
+    }
+}
+
+/// 配额共享组：组内账号的 token 消耗按共享预算统一计算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaGroup {
+    pub name: String,
+    pub account_ids: Vec<String>,
+    /// 每个配额窗口的共享 token 预算；None 表示只统计不限制
+    #[serde(default)]
+    pub shared_budget_tokens: Option<u64>,
+}
+
+/// 请求转换插件配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PluginsConfig {
+    /// 已启用的插件名称
+    #[serde(default)]
+    pub enabled: Vec<String>,
+    /// 内置 system-prompt-injector 注入的提示词
+    #[serde(default)]
+    pub system_prompt: String,
+}
+
+/// 模型单价 (每 1000 token)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input_cost_per_1k_tokens: f64,
+    pub output_cost_per_1k_tokens: f64,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// 默认单价：Gemini / Claude 官方 API 公开价格 (美元)
+pub fn default_model_pricing() -> HashMap<String, ModelPricing> {
+    let price = |input: f64, output: f64| ModelPricing {
+        input_cost_per_1k_tokens: input,
+        output_cost_per_1k_tokens: output,
+        currency: default_currency(),
+    };
+    HashMap::from([
+        ("claude-opus-4-5".to_string(), price(0.005, 0.025)),
+        ("claude-sonnet-4-5".to_string(), price(0.003, 0.015)),
+        ("claude-haiku-4-5".to_string(), price(0.001, 0.005)),
+        ("gemini-3-pro".to_string(), price(0.002, 0.012)),
+        ("gemini-3-flash".to_string(), price(0.0005, 0.003)),
+        ("gemini-2.5-pro".to_string(), price(0.00125, 0.01)),
+        ("gemini-2.5-flash".to_string(), price(0.0003, 0.0025)),
+        ("gemini-2.5-flash-lite".to_string(), price(0.0001, 0.0004)),
+    ])
+}
+
+/// 按模型名估算费用：优先精确匹配，否则使用最长前缀匹配 (如 gemini-3-pro 匹配 gemini-3-pro-high)
+pub fn estimate_cost(
+    pricing: &HashMap<String, ModelPricing>,
+    model: &str,
+    input_tokens: u64,
+    output_tokens: u64,
+) -> Option<f64> {
+    let price = pricing.get(model).or_else(|| {
+        pricing
+            .iter()
+            .filter(|(name, _)| model.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, price)| price)
+    })?;
+    Some(
+        input_tokens as f64 / 1000.0 * price.input_cost_per_1k_tokens
+            + output_tokens as f64 / 1000.0 * price.output_cost_per_1k_tokens,
+    )
+}
+
 impl AppConfig {
     pub fn new() -> Self {
         Self {
@@ -108,6 +352,20 @@ impl AppConfig {
             auto_launch: false,
             scheduled_warmup: ScheduledWarmupConfig::default(),
             quota_protection: QuotaProtectionConfig::default(),
+            tool_compressor: ToolCompressorConfig::default(),
+            alert: AlertConfig::default(),
+            health_weights: HealthWeights::default(),
+            quota_groups: Vec::new(),
+            model_pricing: default_model_pricing(),
+            scrub_pii: false,
+            pii_patterns: Vec::new(),
+            plugins: PluginsConfig::default(),
+            encrypt_accounts: false,
+            anomaly_detection_enabled: false,
+            auto_archive_days: None,
+            log_retention: LogRetentionPolicy::default(),
+            anomaly_z_score_threshold: default_anomaly_z_score_threshold(),
+            device_profile_refresh_enabled: false,
         }
     }
 }
@@ -117,3 +375,23 @@ impl Default for AppConfig {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_cost_prefix_match() {
+        let pricing = default_model_pricing();
+        let cost = estimate_cost(&pricing, "claude-sonnet-4-5", 1000, 1000).unwrap();
+        assert!((cost - 0.018).abs() < 1e-9);
+
+        // 最长前缀优先: gemini-2.5-flash-lite 不应按 gemini-2.5-flash 计价
+        let lite = estimate_cost(&pricing, "gemini-2.5-flash-lite-preview", 10_000, 0).unwrap();
+        assert!((lite - 0.001).abs() < 1e-9);
+        let pro = estimate_cost(&pricing, "gemini-3-pro-high", 0, 1000).unwrap();
+        assert!((pro - 0.012).abs() < 1e-9);
+
+        assert!(estimate_cost(&pricing, "unknown-model", 1000, 1000).is_none());
+    }
+}
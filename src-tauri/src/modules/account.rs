@@ -99,8 +99,7 @@ pub fn load_account(account_id: &str) -> Result<Account, String> {
         return Err(format!("账号不存在: {}", account_id));
     }
     
-    let content = fs::read_to_string(&account_path)
-        .map_err(|e| format!("读取账号数据失败: {}", e))?;
+    let content = crate::modules::account_crypto::read_account_file(&account_path)?;
     
     serde_json::from_str(&content)
         .map_err(|e| format!("解析账号数据失败: {}", e))
@@ -114,7 +113,7 @@ pub fn save_account(account: &Account) -> Result<(), String> {
     let content = serde_json::to_string_pretty(account)
         .map_err(|e| format!("序列化账号数据失败: {}", e))?;
     
-    fs::write(&account_path, content)
+    crate::modules::account_crypto::write_account_file(&account_path, &content)
         .map_err(|e| format!("保存账号数据失败: {}", e))
 }
 
@@ -524,6 +523,29 @@ fn apply_profile_to_account(account: &mut Account, profile: DeviceProfile, label
     Ok(())
 }
 
+/// 设备指纹在过期前多久自动刷新
+const DEVICE_PROFILE_REFRESH_WINDOW_SECS: i64 = 48 * 3600;
+
+/// 设备指纹是否即将过期 (expires_at 未设置时不刷新)
+pub fn device_profile_expiring(expires_at: Option<i64>, now: i64) -> bool {
+    expires_at
+        .map(|at| at - now <= DEVICE_PROFILE_REFRESH_WINDOW_SECS)
+        .unwrap_or(false)
+}
+
+/// 绑定的设备指纹即将过期时重新生成并保存，返回新的指纹
+pub fn refresh_expiring_device_profile(account_id: &str) -> Result<Option<DeviceProfile>, String> {
+    let mut account = load_account(account_id)?;
+    let expires_at = account.device_profile.as_ref().and_then(|p| p.expires_at);
+    if !device_profile_expiring(expires_at, chrono::Utc::now().timestamp()) {
+        return Ok(None);
+    }
+
+    let profile = crate::modules::device::generate_profile();
+    apply_profile_to_account(&mut account, profile.clone(), Some("auto_refresh".to_string()), true)?;
+    Ok(Some(profile))
+}
+
 /// 列出指定账号的可用指纹版本（含基线）
 pub fn list_device_versions(account_id: &str) -> Result<DeviceProfiles, String> {
     get_device_profiles(account_id)
@@ -956,3 +978,660 @@ pub async fn refresh_all_quotas_logic() -> Result<RefreshStats, String> {
         details,
     })
 }
+
+/// 批量启用/禁用结果 (failed 为 (账号 ID, 错误信息))
+#[derive(Debug, Default, Serialize)]
+pub struct BatchResult {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
+/// 写入反代禁用状态字段；reason 为 None 时表示启用
+fn apply_proxy_disabled(account_json: &mut serde_json::Value, reason: Option<&str>, now: i64) {
+    match reason {
+        Some(reason) => {
+            account_json["proxy_disabled"] = serde_json::Value::Bool(true);
+            account_json["proxy_disabled_at"] = serde_json::Value::Number(now.into());
+            account_json["proxy_disabled_reason"] =
+                serde_json::Value::String(crate::proxy::token_manager::truncate_reason(reason, 800));
+        }
+        None => {
+            account_json["proxy_disabled"] = serde_json::Value::Bool(false);
+            account_json["proxy_disabled_reason"] = serde_json::Value::Null;
+            account_json["proxy_disabled_at"] = serde_json::Value::Null;
+        }
+    }
+}
+
+/// 以 read-modify-write 方式更新单个账号文件 (临时文件 + 原子重命名)
+/// 使用原始 JSON 而非 Account 结构体，避免丢失未建模的字段
+fn update_account_json<F>(account_id: &str, update: F) -> Result<serde_json::Value, String>
+where
+    F: FnOnce(&mut serde_json::Value),
+{
+    let accounts_dir = get_accounts_dir()?;
+    let account_path = accounts_dir.join(format!("{}.json", account_id));
+    if !account_path.exists() {
+        return Err(format!("账号文件不存在: {}", account_id));
+    }
+
+    let content = crate::modules::account_crypto::read_account_file(&account_path)?;
+    let mut account_json: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("解析账号文件失败: {}", e))?;
+
+    update(&mut account_json);
+
+    let content = serde_json::to_string_pretty(&account_json)
+        .map_err(|e| format!("序列化账号数据失败: {}", e))?;
+    let temp_path = accounts_dir.join(format!("{}.json.tmp", account_id));
+    crate::modules::account_crypto::write_account_file(&temp_path, &content)
+        .map_err(|e| format!("写入临时账号文件失败: {}", e))?;
+    fs::rename(&temp_path, &account_path).map_err(|e| format!("替换账号文件失败: {}", e))?;
+    Ok(account_json)
+}
+
+/// 启用/禁用单个账号的反代；reason 为 None 时表示启用
+pub fn set_proxy_disabled(account_id: &str, reason: Option<&str>) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    update_account_json(account_id, |account_json| apply_proxy_disabled(account_json, reason, now)).map(|_| ())
+}
+
+fn batch_set_proxy_disabled(account_ids: &[String], reason: Option<&str>) -> BatchResult {
+    let mut result = BatchResult::default();
+    for account_id in account_ids {
+        match set_proxy_disabled(account_id, reason) {
+            Ok(()) => result.succeeded.push(account_id.clone()),
+            Err(e) => result.failed.push((account_id.clone(), e)),
+        }
+    }
+    crate::modules::logger::log_info(&format!(
+        "批量{}反代账号: {} 成功, {} 失败",
+        if reason.is_some() { "禁用" } else { "启用" },
+        result.succeeded.len(),
+        result.failed.len()
+    ));
+    result
+}
+
+/// 批量禁用账号的反代功能 (不影响应用内使用)，单个账号失败不会中断其余账号
+pub fn batch_disable_accounts(account_ids: &[String], reason: &str) -> Result<BatchResult, String> {
+    Ok(batch_set_proxy_disabled(account_ids, Some(reason)))
+}
+
+/// 批量重新启用账号的反代功能
+pub fn batch_enable_accounts(account_ids: &[String]) -> Result<BatchResult, String> {
+    Ok(batch_set_proxy_disabled(account_ids, None))
+}
+
+/// 规范化标签 (去除首尾空白，统一小写)
+fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err("标签不能为空".to_string());
+    }
+    Ok(tag)
+}
+
+/// 读取账号 JSON 中的标签列表
+fn json_tags(account_json: &serde_json::Value) -> Vec<String> {
+    account_json
+        .get("tags")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_str()).map(|s| s.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// 为账号添加标签 (已存在时不重复添加)，返回更新后的标签列表
+pub fn tag_account(account_id: &str, tag: &str) -> Result<Vec<String>, String> {
+    let tag = normalize_tag(tag)?;
+    let updated = update_account_json(account_id, |account_json| {
+        let mut tags = json_tags(account_json);
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+        account_json["tags"] = serde_json::json!(tags);
+    })?;
+    Ok(json_tags(&updated))
+}
+
+/// 移除账号标签，返回更新后的标签列表
+pub fn untag_account(account_id: &str, tag: &str) -> Result<Vec<String>, String> {
+    let tag = normalize_tag(tag)?;
+    let updated = update_account_json(account_id, |account_json| {
+        let tags: Vec<String> = json_tags(account_json).into_iter().filter(|t| t != &tag).collect();
+        account_json["tags"] = serde_json::json!(tags);
+    })?;
+    Ok(json_tags(&updated))
+}
+
+/// 列出带有指定标签的账号
+pub fn list_accounts_by_tag(tag: &str) -> Result<Vec<Account>, String> {
+    let tag = normalize_tag(tag)?;
+    Ok(list_accounts()?
+        .into_iter()
+        .filter(|account| account.tags.contains(&tag))
+        .collect())
+}
+
+/// 平均延迟达到该值 (毫秒) 时延迟分项记为 0
+const HEALTH_LATENCY_CEILING_MS: f64 = 30_000.0;
+
+/// 账号健康度 (窗口内无请求时成功率与限流分项按满分计)
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountHealth {
+    pub account_id: String,
+    pub email: String,
+    pub window_hours: u32,
+    pub total_requests: u64,
+    pub success_rate: f32,
+    pub avg_latency_ms: Option<f64>,
+    pub rate_limit_events: u64,
+    /// 各模型中最高的剩余配额百分比 (未获取过配额时为 None)
+    pub quota_remaining: Option<i32>,
+    pub token_refresh_failures: u64,
+    pub disabled: bool,
+    pub proxy_disabled: bool,
+    /// 0.0 (最差) - 1.0 (最佳)
+    pub health_score: f32,
+}
+
+/// 按权重合成健康度评分；已禁用 (如 invalid_grant) 的账号固定为 0
+fn score_health(health: &AccountHealth, weights: &crate::models::config::HealthWeights) -> f32 {
+    if health.disabled {
+        return 0.0;
+    }
+    let weight_sum = weights.success_rate + weights.latency + weights.rate_limit + weights.quota + weights.token_refresh;
+    if weight_sum <= 0.0 {
+        return score_health(health, &crate::models::config::HealthWeights::default());
+    }
+
+    let latency = health
+        .avg_latency_ms
+        .map(|ms| (1.0 - ms / HEALTH_LATENCY_CEILING_MS).clamp(0.0, 1.0) as f32)
+        .unwrap_or(1.0);
+    let rate_limit = if health.total_requests == 0 {
+        1.0
+    } else {
+        1.0 - health.rate_limit_events as f32 / health.total_requests as f32
+    };
+    let quota = health
+        .quota_remaining
+        .map(|p| (p as f32 / 100.0).clamp(0.0, 1.0))
+        .unwrap_or(1.0);
+    let refresh = 1.0 / (1.0 + health.token_refresh_failures as f32);
+
+    let score = weights.success_rate * health.success_rate
+        + weights.latency * latency
+        + weights.rate_limit * rate_limit
+        + weights.quota * quota
+        + weights.token_refresh * refresh;
+    (score / weight_sum).clamp(0.0, 1.0)
+}
+
+fn compute_health_for(
+    account: &Account,
+    window_hours: u32,
+    weights: &crate::models::config::HealthWeights,
+) -> Result<AccountHealth, String> {
+    let since_ms = chrono::Utc::now().timestamp_millis() - window_hours as i64 * 3600 * 1000;
+    let stats = crate::modules::proxy_db::get_account_request_stats(&account.email, since_ms)?;
+
+    let quota_remaining = account.quota.as_ref().and_then(|q| {
+        if q.is_forbidden {
+            Some(0)
+        } else {
+            q.models.iter().map(|m| m.percentage).max()
+        }
+    });
+
+    let mut health = AccountHealth {
+        account_id: account.id.clone(),
+        email: account.email.clone(),
+        window_hours,
+        total_requests: stats.total,
+        success_rate: if stats.total == 0 {
+            1.0
+        } else {
+            stats.success as f32 / stats.total as f32
+        },
+        avg_latency_ms: stats.avg_latency_ms,
+        rate_limit_events: stats.rate_limited,
+        quota_remaining,
+        token_refresh_failures: stats.refresh_failures,
+        disabled: account.disabled,
+        proxy_disabled: account.proxy_disabled,
+        health_score: 0.0,
+    };
+    health.health_score = score_health(&health, weights);
+    Ok(health)
+}
+
+/// 计算单个账号在最近 window_hours 小时内的健康度 (数据来自反代请求日志库)
+pub fn compute_account_health(account_id: &str, window_hours: u32) -> Result<AccountHealth, String> {
+    let weights = crate::modules::config::load_app_config()?.health_weights;
+    compute_health_for(&load_account(account_id)?, window_hours, &weights)
+}
+
+/// 计算所有账号的健康度，按评分升序排列 (问题账号在前)
+pub fn get_all_account_health(window_hours: u32) -> Result<Vec<AccountHealth>, String> {
+    let weights = crate::modules::config::load_app_config()?.health_weights;
+    let mut all = list_accounts()?
+        .iter()
+        .map(|account| compute_health_for(account, window_hours, &weights))
+        .collect::<Result<Vec<_>, _>>()?;
+    all.sort_by(|a, b| a.health_score.total_cmp(&b.health_score));
+    Ok(all)
+}
+
+/// 账号横向对比 (最近 24 小时的请求日志 + 反代运行时状态)
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountComparison {
+    pub account_id: String,
+    pub email: String,
+    pub total_requests: u64,
+    pub error_count: u64,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<u64>,
+    pub input_tokens_used: u64,
+    pub output_tokens_used: u64,
+    pub estimated_cost: f64,
+    pub is_rate_limited: bool,
+    /// 各模型中最高的剩余配额百分比 (未获取过配额时为 None)
+    pub quota_percentage: Option<i32>,
+    pub protected_models_count: usize,
+}
+
+/// 生成账号对比报告，按请求数降序排列
+/// runtime 为反代账号池快照 (反代未运行时传空切片，配额与受限模型改从账号文件读取)
+pub fn get_account_comparison_report(
+    runtime: &[crate::proxy::token_manager::AccountSnapshot],
+) -> Result<Vec<AccountComparison>, String> {
+    let since_ms = chrono::Utc::now().timestamp_millis() - 24 * 3600 * 1000;
+    let mut usage: std::collections::HashMap<String, crate::modules::proxy_db::AccountUsageStats> =
+        crate::modules::proxy_db::get_account_usage_stats(since_ms)?
+            .into_iter()
+            .map(|s| (s.account_email.clone(), s))
+            .collect();
+
+    let mut report: Vec<AccountComparison> = list_accounts()?
+        .into_iter()
+        .map(|account| {
+            let stats = usage.remove(&account.email).unwrap_or_default();
+            let live = runtime.iter().find(|s| s.account_id == account.id);
+            let (is_rate_limited, quota_percentage, protected_models_count) = match live {
+                Some(s) => (s.rate_limit_remaining_secs > 0, s.remaining_quota, s.protected_models.len()),
+                None => (
+                    false,
+                    account
+                        .quota
+                        .as_ref()
+                        .and_then(|q| q.models.iter().map(|m| m.percentage).max()),
+                    account.protected_models.len(),
+                ),
+            };
+            AccountComparison {
+                account_id: account.id,
+                email: account.email,
+                total_requests: stats.total_requests,
+                error_count: stats.error_count,
+                avg_latency_ms: stats.avg_latency_ms,
+                p95_latency_ms: stats.p95_latency_ms,
+                input_tokens_used: stats.input_tokens,
+                output_tokens_used: stats.output_tokens,
+                estimated_cost: stats.estimated_cost_usd,
+                is_rate_limited,
+                quota_percentage,
+                protected_models_count,
+            }
+        })
+        .collect();
+    report.sort_by(|a, b| b.total_requests.cmp(&a.total_requests));
+    Ok(report)
+}
+
+/// 批量导入结果 (errors 中的行号从 1 开始，CSV 不含表头行)
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub imported: usize,
+    pub skipped: usize,
+    pub errors: Vec<(usize, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Json,
+}
+
+impl ImportFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            other => Err(format!("不支持的导入格式: {} (仅支持 csv / json)", other)),
+        }
+    }
+}
+
+/// 导入文件中的一行账号数据
+#[derive(Debug, Default, serde::Deserialize)]
+struct ImportRow {
+    #[serde(default)]
+    email: String,
+    #[serde(default)]
+    access_token: String,
+    #[serde(default)]
+    refresh_token: String,
+    #[serde(default)]
+    expires_in: Option<i64>,
+    #[serde(default)]
+    expiry_timestamp: Option<i64>,
+    #[serde(default)]
+    project_id: Option<String>,
+}
+
+impl ImportRow {
+    /// 校验必填字段并转换为 TokenData
+    /// access_token 允许为空 (过期时间缺省为立即过期，由反代在首次使用时刷新)
+    fn into_token(self) -> Result<(String, TokenData), String> {
+        let email = self.email.trim().to_string();
+        if email.is_empty() || !email.contains('@') {
+            return Err(format!("email 无效: {:?}", self.email));
+        }
+        let refresh_token = self.refresh_token.trim().to_string();
+        if refresh_token.is_empty() {
+            return Err("缺少 refresh_token".to_string());
+        }
+
+        let expires_in = self.expires_in.unwrap_or(0);
+        let project_id = self.project_id.filter(|p| !p.trim().is_empty());
+        let mut token = TokenData::new(
+            self.access_token.trim().to_string(),
+            refresh_token,
+            expires_in,
+            Some(email.clone()),
+            project_id,
+            None,
+        );
+        if let Some(ts) = self.expiry_timestamp {
+            token.expiry_timestamp = ts;
+        }
+        Ok((email, token))
+    }
+}
+
+/// 拆分一行 CSV (支持双引号包裹与 "" 转义)
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// 解析 CSV (首行为表头，列顺序不限)，返回 (行号, 解析结果)
+fn parse_csv_rows(content: &str) -> Result<Vec<(usize, Result<ImportRow, String>)>, String> {
+    let mut lines = content
+        .lines()
+        .map(|l| l.trim_end_matches('\r'))
+        .filter(|l| !l.trim().is_empty());
+    let header: Vec<String> = split_csv_line(lines.next().ok_or("CSV 文件为空")?)
+        .into_iter()
+        .map(|h| h.trim().trim_start_matches('\u{feff}').to_ascii_lowercase())
+        .collect();
+    if !header.iter().any(|h| h == "email") {
+        return Err("CSV 表头缺少 email 列".to_string());
+    }
+
+    let rows = lines
+        .enumerate()
+        .map(|(i, line)| {
+            let mut row = ImportRow::default();
+            for (name, value) in header.iter().zip(split_csv_line(line)) {
+                let value = value.trim().to_string();
+                let parse_i64 = |v: &str| -> Result<Option<i64>, String> {
+                    if v.is_empty() {
+                        return Ok(None);
+                    }
+                    v.parse::<i64>()
+                        .map(Some)
+                        .map_err(|_| format!("{} 不是有效整数: {}", name, v))
+                };
+                match name.as_str() {
+                    "email" => row.email = value,
+                    "access_token" => row.access_token = value,
+                    "refresh_token" => row.refresh_token = value,
+                    "expires_in" => match parse_i64(&value) {
+                        Ok(v) => row.expires_in = v,
+                        Err(e) => return (i + 1, Err(e)),
+                    },
+                    "expiry_timestamp" => match parse_i64(&value) {
+                        Ok(v) => row.expiry_timestamp = v,
+                        Err(e) => return (i + 1, Err(e)),
+                    },
+                    "project_id" => row.project_id = Some(value),
+                    _ => {}
+                }
+            }
+            (i + 1, Ok(row))
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// 解析 JSON 数组，单个元素解析失败不影响其它行
+fn parse_json_rows(content: &str) -> Result<Vec<(usize, Result<ImportRow, String>)>, String> {
+    let items: Vec<serde_json::Value> = serde_json::from_str(content)
+        .map_err(|e| format!("解析 JSON 失败 (需要账号对象数组): {}", e))?;
+    Ok(items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let row = serde_json::from_value::<ImportRow>(item).map_err(|e| format!("字段格式错误: {}", e));
+            (i + 1, row)
+        })
+        .collect())
+}
+
+/// 从 CSV / JSON 文件批量导入账号 (按扩展名识别格式)
+pub async fn import_accounts_from_file(path: &std::path::Path) -> Result<ImportReport, String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .ok_or_else(|| format!("无法识别导入文件格式: {:?}", path))?;
+    import_accounts_with_format(path, ImportFormat::parse(ext)?).await
+}
+
+/// 用反代的账号加载逻辑校验账号文件 (字段缺失、无法解密等均视为失败)
+async fn verify_account_file(verifier: &crate::proxy::TokenManager, path: &std::path::Path) -> Result<(), String> {
+    verifier.load_single_account(&path.to_path_buf()).await.map(|_| ())
+}
+
+/// 按指定格式批量导入账号
+/// 每行写入 accounts/{id}.json 后按反代的加载逻辑重新读取，确认账号可被反代正常加载；
+/// 已存在的邮箱 (含文件内重复) 计入 skipped
+pub async fn import_accounts_with_format(path: &std::path::Path, format: ImportFormat) -> Result<ImportReport, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("读取导入文件失败: {}", e))?;
+    let rows = match format {
+        ImportFormat::Csv => parse_csv_rows(&content)?,
+        ImportFormat::Json => parse_json_rows(&content)?,
+    };
+
+    let mut existing: std::collections::HashSet<String> =
+        load_account_index()?.accounts.into_iter().map(|s| s.email).collect();
+    let accounts_dir = get_accounts_dir()?;
+    let verifier = crate::proxy::TokenManager::new(get_data_dir()?);
+    let mut report = ImportReport::default();
+
+    for (row_no, row) in rows {
+        let (email, token) = match row.and_then(ImportRow::into_token) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                report.errors.push((row_no, e));
+                continue;
+            }
+        };
+        if existing.contains(&email) {
+            report.skipped += 1;
+            continue;
+        }
+
+        let account = match add_account(email.clone(), None, token) {
+            Ok(account) => account,
+            Err(e) => {
+                report.errors.push((row_no, e));
+                continue;
+            }
+        };
+        existing.insert(email);
+        match verify_account_file(&verifier, &accounts_dir.join(format!("{}.json", account.id))).await {
+            Ok(()) => report.imported += 1,
+            Err(e) => {
+                // 写入的文件无法加载，回滚该账号
+                let _ = delete_account(&account.id);
+                report.errors.push((row_no, e));
+            }
+        }
+    }
+
+    crate::modules::logger::log_info(&format!(
+        "批量导入账号完成: {} 导入, {} 跳过, {} 失败",
+        report.imported,
+        report.skipped,
+        report.errors.len()
+    ));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_profile_expiring() {
+        let now = 1_700_000_000;
+        assert!(!device_profile_expiring(None, now));
+        assert!(!device_profile_expiring(Some(now + 3 * 24 * 3600), now));
+        assert!(device_profile_expiring(Some(now + 47 * 3600), now));
+        assert!(device_profile_expiring(Some(now - 60), now));
+    }
+
+    #[test]
+    fn test_parse_csv_rows_with_quotes_and_errors() {
+        let csv = "email,refresh_token,expires_in,project_id\n\
+                   a@example.com,\"1//tok,en\",3600,proj-a\n\
+                   b@example.com,rt,abc,\n";
+        let rows = parse_csv_rows(csv).unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let (row_no, first) = &rows[0];
+        assert_eq!(*row_no, 1);
+        let first = first.as_ref().unwrap();
+        assert_eq!(first.refresh_token, "1//tok,en");
+        assert_eq!(first.expires_in, Some(3600));
+
+        let (row_no, second) = &rows[1];
+        assert_eq!(*row_no, 2);
+        assert!(second.as_ref().unwrap_err().contains("expires_in"));
+    }
+
+    fn health(total: u64, success_rate: f32, rate_limited: u64, quota: Option<i32>, refresh_failures: u64) -> AccountHealth {
+        AccountHealth {
+            account_id: "id".to_string(),
+            email: "a@example.com".to_string(),
+            window_hours: 24,
+            total_requests: total,
+            success_rate,
+            avg_latency_ms: None,
+            rate_limit_events: rate_limited,
+            quota_remaining: quota,
+            token_refresh_failures: refresh_failures,
+            disabled: false,
+            proxy_disabled: false,
+            health_score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_score_health() {
+        let weights = crate::models::config::HealthWeights::default();
+        let idle = score_health(&health(0, 1.0, 0, None, 0), &weights);
+        assert!((idle - 1.0).abs() < 1e-6);
+
+        let troubled = score_health(&health(100, 0.5, 40, Some(10), 3), &weights);
+        let healthy = score_health(&health(100, 0.99, 1, Some(90), 0), &weights);
+        assert!(troubled < healthy);
+        assert!((0.0..=1.0).contains(&troubled));
+
+        let mut disabled = health(0, 1.0, 0, None, 0);
+        disabled.disabled = true;
+        assert_eq!(score_health(&disabled, &weights), 0.0);
+    }
+
+    #[test]
+    fn test_apply_proxy_disabled_roundtrip() {
+        let mut account = serde_json::json!({ "id": "a", "custom_field": 1 });
+        let long_reason = "x".repeat(1000);
+        apply_proxy_disabled(&mut account, Some(&long_reason), 100);
+        assert_eq!(account["proxy_disabled"], true);
+        assert_eq!(account["proxy_disabled_at"], 100);
+        assert!(account["proxy_disabled_reason"].as_str().unwrap().chars().count() <= 801);
+
+        apply_proxy_disabled(&mut account, None, 200);
+        assert_eq!(account["proxy_disabled"], false);
+        assert!(account["proxy_disabled_reason"].is_null());
+        assert!(account["proxy_disabled_at"].is_null());
+        assert_eq!(account["custom_field"], 1);
+    }
+
+    #[test]
+    fn test_import_row_validation() {
+        let rows = parse_json_rows(
+            r#"[{"email":"a@example.com","refresh_token":"rt","expiry_timestamp":42},
+                {"email":"not-an-email","refresh_token":"rt"},
+                {"email":"c@example.com"}]"#,
+        )
+        .unwrap();
+        let results: Vec<_> = rows.into_iter().map(|(_, r)| r.and_then(ImportRow::into_token)).collect();
+
+        let (email, token) = results[0].as_ref().unwrap();
+        assert_eq!(email, "a@example.com");
+        assert_eq!(token.expiry_timestamp, 42);
+        assert!(results[1].is_err());
+        assert!(results[2].as_ref().unwrap_err().contains("refresh_token"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_account_file_rejects_malformed_account() {
+        let data_dir = std::env::temp_dir().join(format!("import-verify-{}", Uuid::new_v4()));
+        fs::create_dir_all(data_dir.join("accounts")).unwrap();
+        let verifier = crate::proxy::TokenManager::new(data_dir.clone());
+
+        // 能反序列化出 id/email，但缺少 token，反代无法加载
+        let malformed = data_dir.join("accounts").join("bad.json");
+        fs::write(&malformed, r#"{"id":"bad","email":"bad@example.com"}"#).unwrap();
+        assert!(verify_account_file(&verifier, &malformed).await.unwrap_err().contains("token"));
+
+        let valid = data_dir.join("accounts").join("good.json");
+        fs::write(
+            &valid,
+            r#"{"id":"good","email":"good@example.com","token":{"access_token":"at","refresh_token":"rt","expires_in":3600,"expiry_timestamp":42}}"#,
+        )
+        .unwrap();
+        assert!(verify_account_file(&verifier, &valid).await.is_ok());
+        let _ = fs::remove_dir_all(&data_dir);
+    }
+}
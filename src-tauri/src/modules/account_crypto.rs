@@ -0,0 +1,222 @@
+// 账号文件静态加密
+// 开启 encrypt_accounts 后账号 JSON (含 OAuth token) 以 AES-256-GCM 加密落盘
+// 主密钥随机生成并保存在系统钥匙串 (service = "antigravity", username = "account-encryption-key")，
+// 文件密钥由主密钥经 PBKDF2-HMAC-SHA256 (100k 次迭代) 派生
+// 文件格式: AGENC\0 | salt (16B) | nonce (12B) | 密文
+// 读取时按魔数自动识别，明文文件保持兼容
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+const MAGIC: &[u8] = b"AGENC\x00";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+const KEYRING_SERVICE: &str = "antigravity";
+const KEYRING_USERNAME: &str = "account-encryption-key";
+
+/// 钥匙串中的主密钥 (缓存以避免每次读写都访问钥匙串)
+static MASTER_SECRET: Lazy<Mutex<Option<Vec<u8>>>> = Lazy::new(|| Mutex::new(None));
+
+/// 已派生的文件密钥 (salt -> key)，PBKDF2 开销较大，同一 salt 只派生一次
+static DERIVED_KEYS: Lazy<Mutex<HashMap<[u8; SALT_LEN], [u8; KEY_LEN]>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 当前 encrypt_accounts 配置 (加载/保存配置时同步)，写账号文件时无需再读取配置文件
+static ENCRYPT_ACCOUNTS: AtomicBool = AtomicBool::new(false);
+
+/// 本进程写入时使用的 salt
+static WRITE_SALT: Lazy<[u8; SALT_LEN]> = Lazy::new(rand::random);
+
+/// 同步 encrypt_accounts 配置，由 `load_app_config` / `save_app_config` 调用
+pub fn set_encryption_enabled(enabled: bool) {
+    ENCRYPT_ACCOUNTS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USERNAME).map_err(|e| format!("访问系统钥匙串失败: {}", e))
+}
+
+/// 获取主密钥；create 为 true 时不存在则生成并写入钥匙串
+fn master_secret(create: bool) -> Result<Vec<u8>, String> {
+    let mut cached = MASTER_SECRET.lock().map_err(|_| "主密钥缓存锁异常".to_string())?;
+    if let Some(secret) = cached.as_ref() {
+        return Ok(secret.clone());
+    }
+
+    let entry = keyring_entry()?;
+    let encoded = match entry.get_password() {
+        Ok(encoded) => encoded,
+        Err(keyring::Error::NoEntry) if create => {
+            let secret: [u8; KEY_LEN] = rand::random();
+            let encoded = base64::engine::general_purpose::STANDARD.encode(secret);
+            entry
+                .set_password(&encoded)
+                .map_err(|e| format!("写入系统钥匙串失败: {}", e))?;
+            crate::modules::logger::log_info("已生成账号加密密钥并保存到系统钥匙串");
+            encoded
+        }
+        Err(keyring::Error::NoEntry) => return Err("系统钥匙串中没有账号加密密钥".to_string()),
+        Err(e) => return Err(format!("读取系统钥匙串失败: {}", e)),
+    };
+    let secret = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("账号加密密钥格式无效: {}", e))?;
+    *cached = Some(secret.clone());
+    Ok(secret)
+}
+
+/// 删除钥匙串中的主密钥 (下次加密时重新生成，用于密钥轮换)
+fn delete_master_secret() -> Result<(), String> {
+    match keyring_entry()?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(format!("删除系统钥匙串密钥失败: {}", e)),
+    }
+    if let Ok(mut cached) = MASTER_SECRET.lock() {
+        *cached = None;
+    }
+    if let Ok(mut keys) = DERIVED_KEYS.lock() {
+        keys.clear();
+    }
+    Ok(())
+}
+
+fn derive_key(secret: &[u8], salt: &[u8; SALT_LEN]) -> [u8; KEY_LEN] {
+    if let Some(key) = DERIVED_KEYS.lock().ok().and_then(|keys| keys.get(salt).copied()) {
+        return key;
+    }
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(secret, salt, PBKDF2_ITERATIONS, &mut key);
+    if let Ok(mut keys) = DERIVED_KEYS.lock() {
+        keys.insert(*salt, key);
+    }
+    key
+}
+
+fn encrypt(plaintext: &[u8], secret: &[u8], salt: &[u8; SALT_LEN]) -> Result<Vec<u8>, String> {
+    let key = derive_key(secret, salt);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("初始化加密失败: {}", e))?;
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| "加密账号数据失败".to_string())?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt(data: &[u8], secret: &[u8]) -> Result<Vec<u8>, String> {
+    let body = data.strip_prefix(MAGIC).ok_or("不是加密的账号文件")?;
+    if body.len() < SALT_LEN + NONCE_LEN {
+        return Err("加密账号文件已损坏".to_string());
+    }
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let salt: [u8; SALT_LEN] = salt.try_into().map_err(|_| "加密账号文件已损坏".to_string())?;
+
+    let key = derive_key(secret, &salt);
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| format!("初始化解密失败: {}", e))?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "解密账号文件失败 (密钥不匹配或文件已损坏)".to_string())
+}
+
+/// 读取账号文件，加密文件自动解密
+pub fn read_account_file(path: &Path) -> Result<String, String> {
+    let data = std::fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let plaintext = if is_encrypted(&data) {
+        decrypt(&data, &master_secret(false)?)?
+    } else {
+        data
+    };
+    String::from_utf8(plaintext).map_err(|e| format!("账号文件不是有效的 UTF-8: {}", e))
+}
+
+/// 写入账号文件，按内存中的 encrypt_accounts 配置决定是否加密
+pub fn write_account_file(path: &Path, content: &str) -> Result<(), String> {
+    write_account_file_as(path, content, ENCRYPT_ACCOUNTS.load(Ordering::Relaxed))
+}
+
+fn write_account_file_as(path: &Path, content: &str, encrypted: bool) -> Result<(), String> {
+    let data = if encrypted {
+        encrypt(content.as_bytes(), &master_secret(true)?, &WRITE_SALT)?
+    } else {
+        content.as_bytes().to_vec()
+    };
+    std::fs::write(path, data).map_err(|e| format!("写入文件失败: {}", e))
+}
+
+/// 以指定格式重写全部账号文件并更新配置，返回重写的文件数
+/// 迁移为明文后删除钥匙串中的旧密钥，再次加密时会生成新密钥 (密钥轮换)
+pub fn migrate_accounts(encrypted: bool) -> Result<usize, String> {
+    let accounts_dir = crate::modules::account::get_accounts_dir()?;
+    let entries = std::fs::read_dir(&accounts_dir).map_err(|e| format!("读取账号目录失败: {}", e))?;
+
+    // 先全部解密到内存，任一文件失败则不做任何修改
+    let mut accounts = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+            continue;
+        }
+        let content = read_account_file(&path)?;
+        accounts.push((path, content));
+    }
+
+    for (path, content) in &accounts {
+        let temp_path = path.with_extension("json.tmp");
+        write_account_file_as(&temp_path, content, encrypted)?;
+        std::fs::rename(&temp_path, path).map_err(|e| format!("替换账号文件失败: {}", e))?;
+    }
+
+    let mut config = crate::modules::config::load_app_config()?;
+    config.encrypt_accounts = encrypted;
+    crate::modules::config::save_app_config(&config)?;
+
+    if !encrypted {
+        delete_master_secret()?;
+    }
+    crate::modules::logger::log_info(&format!(
+        "已将 {} 个账号文件迁移为{}格式",
+        accounts.len(),
+        if encrypted { "加密" } else { "明文" }
+    ));
+    Ok(accounts.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let secret = b"test-secret";
+        let salt = [7u8; SALT_LEN];
+        let plaintext = br#"{"id":"acc-1","token":{"refresh_token":"1//secret"}}"#;
+
+        let encrypted = encrypt(plaintext, secret, &salt).unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.windows(8).any(|w| w == b"1//secre"));
+        assert_eq!(decrypt(&encrypted, secret).unwrap(), plaintext);
+
+        assert!(decrypt(&encrypted, b"wrong-secret").is_err());
+        assert!(decrypt(&encrypted[..MAGIC.len() + 4], secret).is_err());
+        assert!(!is_encrypted(plaintext));
+    }
+}
@@ -0,0 +1,179 @@
+//! 配置变更审计日志
+//! 修改反代配置的 Tauri 命令在保存后追加一行 JSON 到数据目录下的 audit.log，
+//! 仅记录变更前后配置的 SHA-256 摘要 (不落盘配置明文，避免泄露密钥)
+//! 日志超过 10 MB 时轮转为 audit.log.1 (仅保留一份历史)
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const AUDIT_LOG_FILE: &str = "audit.log";
+const MAX_AUDIT_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+static GLOBAL_AUDIT_LOGGER: Lazy<AuditLogger> = Lazy::new(|| {
+    let dir = crate::modules::account::get_data_dir().unwrap_or_else(|_| std::env::temp_dir());
+    AuditLogger::new(dir.join(AUDIT_LOG_FILE))
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: i64,
+    pub command: String,
+    pub old_value_hash: String,
+    pub new_value_hash: String,
+    pub operator: String,
+}
+
+pub struct AuditLogger {
+    path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+/// 对象键排序后的 JSON，保证 HashMap 等无序字段在内容相同时摘要一致
+fn sorted_value(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(entries.into_iter().map(|(k, v)| (k, sorted_value(v))).collect())
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(sorted_value).collect()),
+        other => other,
+    }
+}
+
+fn value_hash<T: Serialize>(value: &T) -> String {
+    let value = serde_json::to_value(value).map(sorted_value).unwrap_or_default();
+    let json = serde_json::to_vec(&value).unwrap_or_default();
+    format!("{:x}", Sha256::digest(&json))
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+impl AuditLogger {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn global() -> &'static AuditLogger {
+        &GLOBAL_AUDIT_LOGGER
+    }
+
+    /// 记录一次配置变更；写入失败只记录日志，不影响命令本身
+    pub fn record_change<T: Serialize>(&self, command: &str, old: &T, new: &T) {
+        let entry = AuditEntry {
+            timestamp: chrono::Utc::now().timestamp(),
+            command: command.to_string(),
+            old_value_hash: value_hash(old),
+            new_value_hash: value_hash(new),
+            operator: "tauri".to_string(),
+        };
+        if let Err(e) = self.append(&entry) {
+            tracing::warn!("[Audit] 写入审计日志失败: {}", e);
+        }
+    }
+
+    fn append(&self, entry: &AuditEntry) -> Result<(), String> {
+        let _guard = self.write_lock.lock().map_err(|e| e.to_string())?;
+        if fs::metadata(&self.path).map(|m| m.len() >= MAX_AUDIT_LOG_BYTES).unwrap_or(false) {
+            fs::rename(&self.path, rotated_path(&self.path)).map_err(|e| format!("轮转审计日志失败: {}", e))?;
+        }
+
+        let mut line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("打开审计日志失败: {}", e))?;
+        file.write_all(line.as_bytes()).map_err(|e| e.to_string())
+    }
+
+    /// 读取最近的审计记录 (新记录在前)，当前日志不足时继续读取轮转的历史日志
+    pub fn recent(&self, limit: usize) -> Result<Vec<AuditEntry>, String> {
+        let mut entries = Vec::new();
+        for path in [self.path.clone(), rotated_path(&self.path)] {
+            if entries.len() >= limit {
+                break;
+            }
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("读取审计日志失败: {}", e)),
+            };
+            entries.extend(
+                content
+                    .lines()
+                    .rev()
+                    .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+                    .take(limit - entries.len()),
+            );
+        }
+        Ok(entries)
+    }
+}
+
+/// 使用全局审计日志记录配置变更
+pub fn record_change<T: Serialize>(command: &str, old: &T, new: &T) {
+    AuditLogger::global().record_change(command, old, new);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_read_recent_with_rotation() {
+        let dir = std::env::temp_dir().join(format!("audit-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let logger = AuditLogger::new(dir.join(AUDIT_LOG_FILE));
+
+        logger.record_change("update_model_mapping", &vec!["a"], &vec!["a", "b"]);
+        // 模拟超过大小上限的日志，下一次写入时轮转
+        let rotated_line = fs::read_to_string(&logger.path).unwrap();
+        let padding = " ".repeat(MAX_AUDIT_LOG_BYTES as usize);
+        fs::write(&logger.path, format!("{}{}\n", rotated_line, padding)).unwrap();
+        logger.record_change("update_proxy_scheduling_config", &1, &1);
+
+        assert!(fs::metadata(&logger.path).unwrap().len() < 1024);
+        let entries = logger.recent(10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].command, "update_proxy_scheduling_config");
+        assert_eq!(entries[0].old_value_hash, entries[0].new_value_hash);
+        assert_eq!(entries[1].command, "update_model_mapping");
+        assert_ne!(entries[1].old_value_hash, entries[1].new_value_hash);
+        assert_eq!(entries[1].operator, "tauri");
+
+        assert_eq!(logger.recent(1).unwrap().len(), 1);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_value_hash_ignores_map_order() {
+        let build = |keys: Vec<usize>| {
+            let mut config = crate::models::AppConfig::new();
+            for i in keys {
+                config.proxy.custom_mapping.insert(format!("model-{}", i), format!("target-{}", i));
+            }
+            config
+        };
+        let a = build((0..32).collect());
+        let b = build((0..32).rev().collect());
+        assert_eq!(value_hash(&a), value_hash(&b));
+
+        let mut c = build((0..32).collect());
+        c.proxy.custom_mapping.insert("model-0".to_string(), "changed".to_string());
+        assert_ne!(value_hash(&a), value_hash(&c));
+    }
+}
@@ -1,18 +1,226 @@
 use std::fs;
+use once_cell::sync::Lazy;
+use serde::Serialize;
 use serde_json;
 
 use crate::models::AppConfig;
+use crate::proxy::ProxyConfig;
 use super::account::get_data_dir;
 
 const CONFIG_FILE: &str = "gui_config.json";
 
+/// 环境变量覆盖前缀
+const ENV_PREFIX: &str = "ANTIGRAVITY_";
+
+/// 配置校验错误 (供前端展示)
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ConfigError {
+    /// 出错字段路径，如 `proxy.port`
+    pub path: String,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl ConfigError {
+    fn new(path: impl Into<String>, message: impl Into<String>, suggestion: Option<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+            suggestion,
+        }
+    }
+}
+
+/// 按字段路径反序列化配置，类型错误时返回带路径的错误
+fn parse_app_config(v: serde_json::Value) -> Result<AppConfig, ConfigError> {
+    serde_path_to_error::deserialize(v).map_err(|e| {
+        let path = e.path().to_string();
+        let suggestion = if path.ends_with("scheduling.mode") {
+            Some("可选值: CacheFirst, Balance, PerformanceFirst".to_string())
+        } else if path.ends_with("port") {
+            Some("端口应为 1024-65535 之间的整数 (不要加引号)".to_string())
+        } else {
+            None
+        };
+        ConfigError::new(path, e.into_inner().to_string(), suggestion)
+    })
+}
+
+/// 可通过环境变量覆盖的配置项
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvVarEntry {
+    /// 环境变量名，嵌套字段以 `__` 分隔，如 `ANTIGRAVITY_ZAI__API_KEY`
+    pub name: String,
+    /// 兼容写法 (全部以单下划线连接)，如 `ANTIGRAVITY_ZAI_API_KEY`
+    pub alias: Option<String>,
+    /// 对应的配置路径，如 `proxy.zai.api_key`
+    pub path: String,
+    /// 值类型 (string / number / boolean / array / object / null)
+    pub kind: &'static str,
+}
+
+/// 环境变量注册表，由默认配置的字段结构生成
+/// 反代配置 (`proxy.*`) 省略 `PROXY` 层级：`ANTIGRAVITY_PORT` 对应 `proxy.port`；
+/// 其他顶层配置保留层级：`ANTIGRAVITY_QUOTA_PROTECTION__THRESHOLD_PERCENTAGE`
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvVarRegistry {
+    pub entries: Vec<EnvVarEntry>,
+}
+
+static ENV_VAR_REGISTRY: Lazy<EnvVarRegistry> = Lazy::new(EnvVarRegistry::generate);
+
+impl EnvVarRegistry {
+    pub fn global() -> &'static EnvVarRegistry {
+        &ENV_VAR_REGISTRY
+    }
+
+    fn generate() -> Self {
+        fn kind_of(v: &serde_json::Value) -> &'static str {
+            match v {
+                serde_json::Value::Null => "null",
+                serde_json::Value::Bool(_) => "boolean",
+                serde_json::Value::Number(_) => "number",
+                serde_json::Value::String(_) => "string",
+                serde_json::Value::Array(_) => "array",
+                serde_json::Value::Object(_) => "object",
+            }
+        }
+
+        fn walk(value: &serde_json::Value, path: &mut Vec<String>, entries: &mut Vec<EnvVarEntry>) {
+            match value {
+                serde_json::Value::Object(map) if !map.is_empty() => {
+                    for (key, child) in map {
+                        path.push(key.clone());
+                        walk(child, path, entries);
+                        path.pop();
+                    }
+                }
+                _ => {
+                    // proxy.* 省略 PROXY 层级
+                    let segments: Vec<String> = path
+                        .iter()
+                        .skip(if path.first().map(|s| s.as_str()) == Some("proxy") { 1 } else { 0 })
+                        .map(|s| s.to_uppercase())
+                        .collect();
+                    let name = format!("{}{}", ENV_PREFIX, segments.join("__"));
+                    let alias = format!("{}{}", ENV_PREFIX, segments.join("_"));
+                    entries.push(EnvVarEntry {
+                        alias: (alias != name).then_some(alias),
+                        name,
+                        path: path.join("."),
+                        kind: kind_of(value),
+                    });
+                }
+            }
+        }
+
+        let mut entries = Vec::new();
+        if let Ok(defaults) = serde_json::to_value(AppConfig::new()) {
+            walk(&defaults, &mut Vec::new(), &mut entries);
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { entries }
+    }
+}
+
+/// 将环境变量值解析为 JSON (字符串字段保留原文，除非传入的是 JSON 对象，如 JWT 认证模式)
+fn parse_env_value(raw: &str, kind: &str) -> serde_json::Value {
+    let trimmed = raw.trim();
+    if kind == "string" && !trimmed.starts_with('{') {
+        return serde_json::Value::String(raw.to_string());
+    }
+    serde_json::from_str(trimmed).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()))
+}
+
+/// 将环境变量覆盖写入配置 JSON (优先级: 默认值 < 文件 < 环境变量)
+fn apply_env_overrides_with(
+    value: &mut serde_json::Value,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> usize {
+    let mut applied = 0;
+    for entry in &EnvVarRegistry::global().entries {
+        let raw = lookup(&entry.name).or_else(|| entry.alias.as_deref().and_then(&lookup));
+        let Some(raw) = raw else { continue };
+
+        let mut target = &mut *value;
+        for segment in entry.path.split('.') {
+            if !target.is_object() {
+                *target = serde_json::Value::Object(Default::default());
+            }
+            target = target
+                .as_object_mut()
+                .unwrap()
+                .entry(segment.to_string())
+                .or_insert(serde_json::Value::Null);
+        }
+        *target = parse_env_value(&raw, entry.kind);
+        applied += 1;
+    }
+    applied
+}
+
+/// 保存前还原被环境变量覆盖的字段：使用配置文件中的原值 (文件中没有时移除该字段，加载时取默认值)，
+/// 避免把通过环境变量注入的密钥等写入 config.json
+fn strip_env_overrides_with(
+    value: &mut serde_json::Value,
+    on_disk: Option<&serde_json::Value>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> usize {
+    let mut restored = 0;
+    for entry in &EnvVarRegistry::global().entries {
+        if lookup(&entry.name).or_else(|| entry.alias.as_deref().and_then(&lookup)).is_none() {
+            continue;
+        }
+        let pointer = format!("/{}", entry.path.replace('.', "/"));
+        let original = on_disk.and_then(|disk| disk.pointer(&pointer)).cloned();
+        match (original, value.pointer_mut(&pointer)) {
+            (Some(original), Some(target)) => *target = original,
+            (None, Some(_)) => {
+                let (parent, key) = pointer.rsplit_once('/').unwrap_or(("", ""));
+                if let Some(obj) = value.pointer_mut(parent).and_then(|p| p.as_object_mut()) {
+                    obj.remove(key);
+                }
+            }
+            _ => continue,
+        }
+        restored += 1;
+    }
+    restored
+}
+
+fn apply_env_overrides(value: &mut serde_json::Value) {
+    // 快速路径：未设置任何相关环境变量
+    if !std::env::vars_os().any(|(k, _)| k.to_string_lossy().starts_with(ENV_PREFIX)) {
+        return;
+    }
+    let applied = apply_env_overrides_with(value, |name| std::env::var(name).ok());
+    if applied > 0 {
+        tracing::debug!("已应用 {} 个环境变量配置覆盖", applied);
+    }
+}
+
+/// 仅由默认值与环境变量构建反代配置 (不读取配置文件)
+pub fn load_proxy_config_from_env() -> ProxyConfig {
+    let mut value = serde_json::json!({ "proxy": ProxyConfig::default() });
+    apply_env_overrides(&mut value);
+    value
+        .get_mut("proxy")
+        .map(serde_json::Value::take)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
 /// 加载应用配置
+/// 环境变量 (`ANTIGRAVITY_*`) 覆盖文件中的值；`save_app_config` 保存时会还原这些字段，覆盖值不会写回文件
 pub fn load_app_config() -> Result<AppConfig, String> {
     let data_dir = get_data_dir()?;
     let config_path = data_dir.join(CONFIG_FILE);
     
     if !config_path.exists() {
-        return Ok(AppConfig::new());
+        let mut v = serde_json::to_value(AppConfig::new())
+            .map_err(|e| format!("序列化默认配置失败: {}", e))?;
+        apply_env_overrides(&mut v);
+        return parse_app_config(v).map_err(|e| format!("环境变量配置无效: {}: {}", e.path, e.message));
     }
     
     let content = fs::read_to_string(&config_path)
@@ -64,25 +272,277 @@ pub fn load_app_config() -> Result<AppConfig, String> {
         }
     }
 
-    let config: AppConfig = serde_json::from_value(v)
-        .map_err(|e| format!("迁移后转换配置失败: {}", e))?;
-    
-    // 如果发生了迁移，自动保存一次以清理文件
+    // 如果发生了迁移，自动保存一次以清理文件 (在应用环境变量覆盖之前，避免写入覆盖值)
     if modified {
-        let _ = save_app_config(&config);
+        let migrated = parse_app_config(v.clone())
+            .map_err(|e| format!("迁移后转换配置失败: {}: {}", e.path, e.message))?;
+        let _ = save_app_config(&migrated);
     }
 
+    apply_env_overrides(&mut v);
+    let config = parse_app_config(v)
+        .map_err(|e| format!("迁移后转换配置失败: {}: {}", e.path, e.message))?;
+    crate::modules::account_crypto::set_encryption_enabled(config.encrypt_accounts);
+
     Ok(config)
 }
 
+/// 语义校验 (端口范围、配额阈值、映射目标模型是否已登记)
+/// 调度模式等枚举字段在反序列化时已校验，见 `check_config_file`
+pub fn validate_app_config(config: &AppConfig) -> Vec<ConfigError> {
+    use crate::proxy::common::model_mapping::{closest_known_model, is_known_model};
+
+    let mut errors = Vec::new();
+
+    if config.proxy.port < 1024 {
+        errors.push(ConfigError::new(
+            "proxy.port",
+            format!("端口 {} 不在 1024-65535 范围内", config.proxy.port),
+            Some("使用 1024 以上的端口，例如默认的 8045".to_string()),
+        ));
+    }
+
+    if config.proxy.max_request_body_mb == 0 {
+        errors.push(ConfigError::new(
+            "proxy.max_request_body_mb",
+            "请求体大小上限不能为 0",
+            Some("使用默认值 10 (MB)".to_string()),
+        ));
+    }
+
+    if let Err(e) = crate::proxy::middleware::static_headers::parse_response_headers(&config.proxy.response_headers) {
+        errors.push(ConfigError::new("proxy.response_headers", e, None));
+    }
+
+    if config.quota_protection.threshold_percentage > 100 {
+        errors.push(ConfigError::new(
+            "quota_protection.threshold_percentage",
+            format!("阈值 {} 不在 0-100 范围内", config.quota_protection.threshold_percentage),
+            None,
+        ));
+    }
+
+    errors.extend(validate_domain_timeouts(config));
+
+    let mut check_model = |path: String, model: &str| {
+        if !is_known_model(model) {
+            errors.push(ConfigError::new(
+                path,
+                format!("模型 '{}' 不在模型能力注册表中", model),
+                closest_known_model(model).map(|m| format!("是否想使用 '{}'?", m)),
+            ));
+        }
+    };
+    let mut mapping: Vec<_> = config.proxy.custom_mapping.iter().collect();
+    mapping.sort();
+    for (from, target) in mapping {
+        check_model(format!("proxy.custom_mapping.{}", from), target);
+    }
+    for (idx, rule) in config.proxy.regex_mapping.iter().enumerate() {
+        check_model(format!("proxy.regex_mapping[{}].target", idx), &rule.target);
+    }
+    let mut chains: Vec<_> = config.proxy.fallback_chains.iter().collect();
+    chains.sort();
+    for (from, chain) in chains {
+        for (idx, model) in chain.iter().enumerate() {
+            check_model(format!("proxy.fallback_chains.{}[{}]", from, idx), model);
+        }
+    }
+
+    errors
+}
+
+/// 校验 Web 工具按域名超时的域名规则 (由配置保存命令调用；保存到磁盘本身不做拦截)
+pub fn validate_domain_timeouts(config: &AppConfig) -> Vec<ConfigError> {
+    let mut domains: Vec<_> = config.proxy.zai.domain_timeouts.iter().collect();
+    domains.sort();
+    domains
+        .into_iter()
+        .filter_map(|(domain, secs)| {
+            let path = format!("proxy.zai.domain_timeouts.{}", domain);
+            if !crate::proxy::zai_web_tools::is_valid_domain_pattern(domain) {
+                Some(ConfigError::new(
+                    path,
+                    format!("'{}' 不是合法的域名", domain),
+                    Some("只填写主机名，例如 example.com 或 *.example.com (不含协议和路径)".to_string()),
+                ))
+            } else if *secs == 0 {
+                Some(ConfigError::new(path, "超时时间必须大于 0", None))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// 读取并解析配置文件 (JSON 语法与字段类型错误)，文件不存在时返回 Ok(None)
+fn parse_config_file() -> Result<Option<AppConfig>, ConfigError> {
+    let path = config_path().map_err(|e| ConfigError::new("", e, None))?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| ConfigError::new("", format!("读取配置文件失败: {}", e), None))?;
+    let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+        ConfigError::new(
+            "",
+            format!("JSON 语法错误 (第 {} 行第 {} 列): {}", e.line(), e.column(), e),
+            Some("检查是否缺少逗号、引号或括号".to_string()),
+        )
+    })?;
+    parse_app_config(value).map(Some)
+}
+
+/// 读取并校验配置文件 (包括 JSON 语法与字段类型错误)
+pub fn check_config_file() -> Vec<ConfigError> {
+    match parse_config_file() {
+        Ok(config) => config.map(|c| validate_app_config(&c)).unwrap_or_default(),
+        Err(e) => vec![e],
+    }
+}
+
+/// 校验配置文件，存在错误时通过 `config://validation_errors` 事件通知前端
+/// 返回 false 表示配置文件无法解析 (JSON 语法或字段类型错误)；
+/// 语义校验问题 (如映射目标模型未登记) 仅作提示，不阻止配置生效
+pub fn emit_validation_errors(app_handle: &tauri::AppHandle) -> bool {
+    use tauri::Emitter;
+
+    let (parsed, errors) = match parse_config_file() {
+        Ok(config) => (true, config.map(|c| validate_app_config(&c)).unwrap_or_default()),
+        Err(e) => (false, vec![e]),
+    };
+    if !errors.is_empty() {
+        for e in &errors {
+            super::logger::log_warn(&format!("[Config] {}: {}", e.path, e.message));
+        }
+        let _ = app_handle.emit("config://validation_errors", &errors);
+    }
+    parsed
+}
+
+/// 配置文件路径
+pub fn config_path() -> Result<std::path::PathBuf, String> {
+    Ok(get_data_dir()?.join(CONFIG_FILE))
+}
+
 /// 保存应用配置
 pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
     let data_dir = get_data_dir()?;
     let config_path = data_dir.join(CONFIG_FILE);
-    
-    let content = serde_json::to_string_pretty(config)
+
+    let mut value = serde_json::to_value(config)
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+    if std::env::vars_os().any(|(k, _)| k.to_string_lossy().starts_with(ENV_PREFIX)) {
+        let on_disk: Option<serde_json::Value> = fs::read_to_string(&config_path)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok());
+        strip_env_overrides_with(&mut value, on_disk.as_ref(), |name| std::env::var(name).ok());
+    }
+
+    let content = serde_json::to_string_pretty(&value)
         .map_err(|e| format!("序列化配置失败: {}", e))?;
     
     fs::write(&config_path, content)
-        .map_err(|e| format!("保存配置失败: {}", e))
+        .map_err(|e| format!("保存配置失败: {}", e))?;
+    crate::modules::account_crypto::set_encryption_enabled(config.encrypt_accounts);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_app_config() {
+        let mut config = AppConfig::new();
+        assert!(validate_app_config(&config).is_empty());
+
+        config.proxy.port = 80;
+        config.quota_protection.threshold_percentage = 120;
+        config
+            .proxy
+            .response_headers
+            .insert("Bad Header".to_string(), "x".to_string());
+        config
+            .proxy
+            .custom_mapping
+            .insert("gpt-4o".to_string(), "gemini-2.5-flsh".to_string());
+        let errors = validate_app_config(&config);
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "proxy.port",
+                "proxy.response_headers",
+                "quota_protection.threshold_percentage",
+                "proxy.custom_mapping.gpt-4o"
+            ]
+        );
+        assert_eq!(errors[3].suggestion.as_deref(), Some("是否想使用 'gemini-2.5-flash'?"));
+    }
+
+    #[test]
+    fn test_validate_domain_timeouts() {
+        let mut config = AppConfig::new();
+        config.proxy.zai.domain_timeouts.insert("intranet.corp".to_string(), 120);
+        assert!(validate_app_config(&config).is_empty());
+
+        config.proxy.zai.domain_timeouts.insert("https://bad.com/".to_string(), 10);
+        config.proxy.zai.domain_timeouts.insert("zero.com".to_string(), 0);
+        let errors = validate_domain_timeouts(&config);
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["proxy.zai.domain_timeouts.https://bad.com/", "proxy.zai.domain_timeouts.zero.com"]
+        );
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let registry = EnvVarRegistry::global();
+        let port = registry.entries.iter().find(|e| e.path == "proxy.port").unwrap();
+        assert_eq!(port.name, "ANTIGRAVITY_PORT");
+        let zai_key = registry.entries.iter().find(|e| e.path == "proxy.zai.api_key").unwrap();
+        assert_eq!(zai_key.name, "ANTIGRAVITY_ZAI__API_KEY");
+        assert_eq!(zai_key.alias.as_deref(), Some("ANTIGRAVITY_ZAI_API_KEY"));
+
+        let mut value = serde_json::to_value(AppConfig::new()).unwrap();
+        let applied = apply_env_overrides_with(&mut value, |name| match name {
+            "ANTIGRAVITY_PORT" => Some("8080".to_string()),
+            "ANTIGRAVITY_AUTH_MODE" => Some("strict".to_string()),
+            "ANTIGRAVITY_ZAI_API_KEY" => Some("zai-key".to_string()),
+            "ANTIGRAVITY_QUOTA_PROTECTION__THRESHOLD_PERCENTAGE" => Some("15".to_string()),
+            _ => None,
+        });
+        assert_eq!(applied, 4);
+
+        let config = parse_app_config(value).unwrap();
+        assert_eq!(config.proxy.port, 8080);
+        assert!(matches!(config.proxy.auth_mode, crate::proxy::ProxyAuthMode::Strict));
+        assert_eq!(config.proxy.zai.api_key, "zai-key");
+        assert_eq!(config.quota_protection.threshold_percentage, 15);
+
+        // 保存前还原覆盖字段：文件中有原值则还原，没有则移除
+        let mut on_disk = serde_json::to_value(AppConfig::new()).unwrap();
+        on_disk["proxy"]["port"] = serde_json::json!(9000);
+        on_disk["proxy"]["zai"].as_object_mut().unwrap().remove("api_key");
+        let mut to_save = serde_json::to_value(&config).unwrap();
+        let restored = strip_env_overrides_with(&mut to_save, Some(&on_disk), |name| match name {
+            "ANTIGRAVITY_PORT" | "ANTIGRAVITY_ZAI_API_KEY" => Some("x".to_string()),
+            _ => None,
+        });
+        assert_eq!(restored, 2);
+        assert_eq!(to_save["proxy"]["port"], 9000);
+        assert!(to_save["proxy"]["zai"].get("api_key").is_none());
+        assert_eq!(to_save["quota_protection"]["threshold_percentage"], 15);
+    }
+
+    #[test]
+    fn test_parse_error_reports_path() {
+        let mut value = serde_json::to_value(AppConfig::new()).unwrap();
+        value["proxy"]["port"] = serde_json::json!("8080");
+        let err = parse_app_config(value).unwrap_err();
+        assert_eq!(err.path, "proxy.port");
+        assert!(err.suggestion.is_some());
+    }
 }
@@ -0,0 +1,68 @@
+//! 配置文件监听
+//! 配置文件被外部修改 (或由应用自身保存) 后自动重新加载，并热更新正在运行的反代服务
+
+use notify::{RecursiveMode, Watcher};
+use std::time::Duration;
+use tauri::Manager;
+use tokio::sync::mpsc;
+
+use crate::modules::logger;
+
+/// 去抖间隔：编辑器保存时通常会产生多次写入事件
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 启动配置文件监听 (失败时仅记录日志，不影响应用启动)
+pub fn start(app_handle: tauri::AppHandle) {
+    let config_path = match crate::modules::config::config_path() {
+        Ok(p) => p,
+        Err(e) => {
+            logger::log_warn(&format!("[ConfigWatcher] 无法获取配置文件路径: {}", e));
+            return;
+        }
+    };
+    let Some(config_dir) = config_path.parent().map(|p| p.to_path_buf()) else {
+        return;
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let watched_path = config_path.clone();
+    let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            // 监听所在目录而非文件本身，兼容"写临时文件再重命名"的保存方式
+            if (event.kind.is_modify() || event.kind.is_create())
+                && event.paths.iter().any(|p| p == &watched_path)
+            {
+                let _ = tx.send(());
+            }
+        }
+    });
+    let mut watcher = match watcher {
+        Ok(w) => w,
+        Err(e) => {
+            logger::log_warn(&format!("[ConfigWatcher] 创建文件监听失败: {}", e));
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&config_dir, RecursiveMode::NonRecursive) {
+        logger::log_warn(&format!("[ConfigWatcher] 监听 {:?} 失败: {}", config_dir, e));
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        // watcher 需随任务存活，drop 后停止监听
+        let _watcher = watcher;
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(DEBOUNCE).await;
+            while rx.try_recv().is_ok() {}
+
+            // 仅在无法解析时跳过；语义校验警告已记录并通知前端，配置照常生效
+            if !crate::modules::config::emit_validation_errors(&app_handle) {
+                continue;
+            }
+            let state = app_handle.state::<crate::commands::proxy::ProxyServiceState>();
+            if let Err(e) = crate::commands::proxy::reload_running_config(&state).await {
+                logger::log_warn(&format!("[ConfigWatcher] 重新加载配置失败: {}", e));
+            }
+        }
+    });
+}
@@ -141,6 +141,7 @@ pub fn read_profile(storage_path: &Path) -> Result<DeviceProfile, String> {
         mac_machine_id: get_field("macMachineId").ok_or("缺少 telemetry.macMachineId")?,
         dev_device_id: get_field("devDeviceId").ok_or("缺少 telemetry.devDeviceId")?,
         sqm_id: get_field("sqmId").ok_or("缺少 telemetry.sqmId")?,
+        expires_at: None,
     })
 }
 
@@ -387,6 +388,9 @@ pub fn restore_backup(storage_path: &Path, use_oldest: bool) -> Result<PathBuf,
     Ok(target)
 }
 
+/// 生成的 dev_device_id 有效期 (Google 账号通常为 30 天)
+pub const DEVICE_PROFILE_TTL_SECS: i64 = 30 * 24 * 3600;
+
 /// 生成一组新的设备指纹（符合 Cursor/VSCode 风格）
 pub fn generate_profile() -> DeviceProfile {
     DeviceProfile {
@@ -394,6 +398,7 @@ pub fn generate_profile() -> DeviceProfile {
         mac_machine_id: new_standard_machine_id(),
         dev_device_id: Uuid::new_v4().to_string(),
         sqm_id: format!("{{{}}}", Uuid::new_v4().to_string().to_uppercase()),
+        expires_at: Some(chrono::Utc::now().timestamp() + DEVICE_PROFILE_TTL_SECS),
     }
 }
 
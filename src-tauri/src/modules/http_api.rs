@@ -8,20 +8,36 @@
 //! - POST /accounts/switch           切换账号（异步执行）
 //! - POST /accounts/refresh          刷新所有配额
 //! - POST /accounts/:id/bind-device  绑定设备指纹
+//! - GET  /pool/snapshot             获取反代账号池状态快照（脱敏）
+//! - GET  /logs/stream               WebSocket 实时推送代理日志
+//!
+//! 默认开启认证 (`auth_enabled`)：除 /health 外的端点都需要 `Authorization: Bearer <token>`，
+//! 令牌在首次启动时生成并持久化；CORS 只放行 Tauri 前端与 localhost 来源
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, Request, State,
+    },
+    http::{header, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tower_http::cors::{Any, CorsLayer};
-
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tokio::sync::{broadcast, RwLock};
+use tower::ServiceBuilder;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::modules::webhook::{WebhookConfig, WebhookDispatcher};
 use crate::modules::{account, logger, proxy_db};
+use crate::proxy::monitor::{LogBroadcaster, ProxyRequestLog};
 
 /// HTTP API 服务器默认端口
 pub const DEFAULT_PORT: u16 = 19527;
@@ -39,6 +55,43 @@ pub struct HttpApiSettings {
     /// 监听端口
     #[serde(default = "default_port")]
     pub port: u16,
+    /// 是否要求 Bearer Token 认证 (/health 除外)，默认开启
+    #[serde(default = "default_enabled")]
+    pub auth_enabled: bool,
+    /// 访问令牌，首次启动时自动生成
+    #[serde(default)]
+    pub token: String,
+    /// 按端点的限流规则
+    #[serde(default = "default_rate_limits")]
+    pub rate_limits: Vec<RateLimitRule>,
+    /// 关键事件 Webhook 通知
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+/// 单个端点的限流规则 (固定窗口)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitRule {
+    pub method: String,
+    pub path: String,
+    /// 窗口内允许的最大请求数
+    pub max_requests: u32,
+    /// 窗口长度 (秒)
+    pub window_secs: u64,
+}
+
+fn default_rate_limits() -> Vec<RateLimitRule> {
+    let rule = |method: &str, path: &str, max_requests: u32, window_secs: u64| RateLimitRule {
+        method: method.to_string(),
+        path: path.to_string(),
+        max_requests,
+        window_secs,
+    };
+    vec![
+        rule("POST", "/accounts/switch", 1, 5),
+        rule("POST", "/accounts/refresh", 1, 60),
+        rule("GET", "/logs", 10, 1),
+    ]
 }
 
 fn default_enabled() -> bool {
@@ -54,38 +107,95 @@ impl Default for HttpApiSettings {
         Self {
             enabled: true,
             port: DEFAULT_PORT,
+            auth_enabled: true,
+            token: String::new(),
+            rate_limits: default_rate_limits(),
+            webhooks: Vec::new(),
         }
     }
 }
 
-/// 加载 HTTP API 设置
+/// 生成新的 HTTP API 访问令牌
+pub fn generate_token() -> String {
+    format!("sk-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// 当前生效的认证配置 (由 load/save_settings 同步，供认证中间件读取)
+/// 设置加载前令牌为空，认证中间件拒绝所有请求
+static AUTH_CONFIG: Lazy<std::sync::RwLock<(bool, String)>> =
+    Lazy::new(|| std::sync::RwLock::new((true, String::new())));
+
+/// 当前生效的限流规则 (由 load/save_settings 同步，保存设置后无需重启即可生效)
+static RATE_LIMIT_RULES: Lazy<Arc<std::sync::RwLock<Vec<RateLimitRule>>>> =
+    Lazy::new(|| Arc::new(std::sync::RwLock::new(default_rate_limits())));
+
+fn sync_runtime_settings(settings: &HttpApiSettings) {
+    if let Ok(mut auth) = AUTH_CONFIG.write() {
+        *auth = (settings.auth_enabled, settings.token.clone());
+    }
+    if let Ok(mut rules) = RATE_LIMIT_RULES.write() {
+        *rules = settings.rate_limits.clone();
+    }
+}
+
+/// 加载 HTTP API 设置 (首次加载时自动生成并持久化访问令牌)
 pub fn load_settings() -> Result<HttpApiSettings, String> {
     let data_dir = crate::modules::account::get_data_dir()
         .map_err(|e| format!("Failed to get data dir: {}", e))?;
     let settings_path = data_dir.join("http_api_settings.json");
 
-    if !settings_path.exists() {
-        return Ok(HttpApiSettings::default());
+    let mut settings: HttpApiSettings = if settings_path.exists() {
+        let content = std::fs::read_to_string(&settings_path)
+            .map_err(|e| format!("Failed to read settings file: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse settings: {}", e))?
+    } else {
+        HttpApiSettings::default()
+    };
+
+    if settings.token.is_empty() {
+        settings.token = generate_token();
+        save_settings(&settings)?;
     }
 
-    let content = std::fs::read_to_string(&settings_path)
-        .map_err(|e| format!("Failed to read settings file: {}", e))?;
-
-    serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse settings: {}", e))
+    sync_runtime_settings(&settings);
+    Ok(settings)
 }
 
 /// 保存 HTTP API 设置
+/// 传入的 token 为空时保留现有令牌 (前端设置页不会回传 token)
 pub fn save_settings(settings: &HttpApiSettings) -> Result<(), String> {
     let data_dir = crate::modules::account::get_data_dir()
         .map_err(|e| format!("Failed to get data dir: {}", e))?;
     let settings_path = data_dir.join("http_api_settings.json");
 
-    let content = serde_json::to_string_pretty(settings)
+    let mut settings = settings.clone();
+    if settings.token.is_empty() {
+        settings.token = std::fs::read_to_string(&settings_path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<HttpApiSettings>(&c).ok())
+            .map(|s| s.token)
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(generate_token);
+    }
+
+    let content = serde_json::to_string_pretty(&settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
 
     std::fs::write(&settings_path, content)
-        .map_err(|e| format!("Failed to write settings file: {}", e))
+        .map_err(|e| format!("Failed to write settings file: {}", e))?;
+
+    sync_runtime_settings(&settings);
+    crate::modules::webhook::reload(settings.webhooks);
+    Ok(())
+}
+
+/// 轮换访问令牌并返回新令牌
+pub fn regenerate_token() -> Result<String, String> {
+    let mut settings = load_settings()?;
+    settings.token = generate_token();
+    save_settings(&settings)?;
+    Ok(settings.token)
 }
 
 /// 服务器状态
@@ -93,12 +203,24 @@ pub fn save_settings(settings: &HttpApiSettings) -> Result<(), String> {
 pub struct ApiState {
     /// 当前是否有切换操作正在进行
     switching: Arc<RwLock<bool>>,
+    /// 用于访问反代服务等 Tauri 托管状态
+    app_handle: tauri::AppHandle,
+    /// 实时日志广播器 (由 ProxyMonitor 写入)
+    log_broadcaster: LogBroadcaster,
+    /// 限流规则 (与 RATE_LIMIT_RULES 共享，保存设置时热更新)
+    rate_limit_rules: Arc<std::sync::RwLock<Vec<RateLimitRule>>>,
+    /// 限流计数器 ("METHOD path" -> (窗口开始时间, 窗口内请求数))
+    rate_limit_counters: Arc<Mutex<HashMap<String, (Instant, u32)>>>,
 }
 
 impl ApiState {
-    pub fn new() -> Self {
+    pub fn new(app_handle: tauri::AppHandle) -> Self {
         Self {
             switching: Arc::new(RwLock::new(false)),
+            app_handle,
+            log_broadcaster: LogBroadcaster::global().clone(),
+            rate_limit_rules: RATE_LIMIT_RULES.clone(),
+            rate_limit_counters: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -187,6 +309,13 @@ struct ErrorResponse {
 struct LogsResponse {
     total: u64,
     logs: Vec<crate::proxy::monitor::ProxyRequestLog>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+struct PoolSnapshotResponse {
+    accounts: Vec<crate::proxy::token_manager::AccountSnapshot>,
 }
 
 // ============================================================================
@@ -218,6 +347,9 @@ struct LogsRequest {
     filter: String,
     #[serde(default)]
     errors_only: bool,
+    /// 游标分页 (上一页返回的 next_cursor)，提供时忽略 offset/filter
+    #[serde(default)]
+    cursor: Option<String>,
 }
 
 // ============================================================================
@@ -432,29 +564,274 @@ async fn get_logs(
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let limit = if params.limit == 0 { 50 } else { params.limit };
 
+    if let Some(cursor) = params.cursor.as_deref().filter(|c| !c.is_empty()) {
+        let (after_ts, after_id) = proxy_db::parse_cursor(cursor)
+            .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+        let total = proxy_db::get_logs_count()
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+        let (logs, next_cursor) = proxy_db::get_logs_page_after(Some(&after_id), Some(after_ts), limit)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+        return Ok(Json(LogsResponse {
+            total,
+            logs,
+            next_cursor,
+        }));
+    }
+
     let total = proxy_db::get_logs_count_filtered(&params.filter, params.errors_only)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
 
     let logs = proxy_db::get_logs_filtered(&params.filter, params.errors_only, limit, params.offset)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
 
+    // 首页也返回游标，便于客户端切换到游标分页
+    let next_cursor = if params.offset == 0 && params.filter.is_empty() && !params.errors_only && logs.len() == limit {
+        logs.last().map(|log| proxy_db::encode_cursor(log.timestamp, &log.id))
+    } else {
+        None
+    };
+
     Ok(Json(LogsResponse {
         total,
         logs,
+        next_cursor,
     }))
 }
 
+/// GET /logs/conversation/:id - 按时间顺序获取同一对话的所有请求
+async fn get_conversation_logs(
+    Path(conversation_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let logs = proxy_db::get_logs_by_conversation(&conversation_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(logs))
+}
+
+#[derive(Deserialize)]
+struct LogsStreamQuery {
+    /// 浏览器 WebSocket 无法设置 Authorization 头，可通过 ?token= 传递令牌
+    token: Option<String>,
+}
+
+/// GET /logs/stream - WebSocket 实时推送代理日志 (不含请求/响应体)
+/// WebSocket 不受 CORS 约束，升级前自行校验 Origin 与令牌
+async fn logs_stream(
+    ws: WebSocketUpgrade,
+    headers: header::HeaderMap,
+    Query(query): Query<LogsStreamQuery>,
+    State(state): State<ApiState>,
+) -> Response {
+    if let Some(origin) = headers.get(header::ORIGIN) {
+        if !origin.to_str().map(is_allowed_origin).unwrap_or(false) {
+            logger::log_warn(&format!("[HTTP API] 拒绝来自 {:?} 的日志流连接", origin));
+            return StatusCode::FORBIDDEN.into_response();
+        }
+    }
+    let provided = bearer_token(&headers).or(query.token.as_deref());
+    if let Err(status) = check_token(provided) {
+        return status.into_response();
+    }
+
+    let rx = state.log_broadcaster.subscribe();
+    ws.on_upgrade(move |socket| forward_logs(socket, rx))
+}
+
+async fn forward_logs(mut socket: WebSocket, mut rx: broadcast::Receiver<ProxyRequestLog>) {
+    loop {
+        tokio::select! {
+            received = rx.recv() => match received {
+                Ok(log) => {
+                    let text = match serde_json::to_string(&log) {
+                        Ok(t) => t,
+                        Err(_) => continue,
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    logger::log_warn(&format!("[HTTP API] 日志流订阅者落后，跳过 {} 条日志", skipped));
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                // 客户端断开或出错时结束，忽略客户端发来的其他消息
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// GET /pool/snapshot - 获取反代账号池状态快照
+async fn pool_snapshot(
+    State(state): State<ApiState>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let proxy_state = state.app_handle.state::<crate::commands::proxy::ProxyServiceState>();
+    let instance_lock = proxy_state.instance.read().await;
+    let instance = instance_lock.as_ref().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: "反代服务未运行".to_string(),
+            }),
+        )
+    })?;
+
+    Ok(Json(PoolSnapshotResponse {
+        accounts: instance.token_manager.snapshot().await,
+    }))
+}
+
+// ============================================================================
+// Auth
+// ============================================================================
+
+/// Bearer Token 认证中间件 (/health 与 CORS 预检请求始终放行)
+/// /logs/stream 由处理器自行校验 (支持查询参数传递令牌)
+async fn auth_middleware(request: Request, next: Next) -> Result<Response, StatusCode> {
+    let path = request.uri().path();
+    if request.method() == Method::OPTIONS || path == "/health" || path == "/logs/stream" {
+        return Ok(next.run(request).await);
+    }
+
+    check_token(bearer_token(request.headers()))?;
+    Ok(next.run(request).await)
+}
+
+fn bearer_token(headers: &header::HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+}
+
+/// 按当前认证配置校验令牌 (认证关闭时放行)
+fn check_token(provided: Option<&str>) -> Result<(), StatusCode> {
+    let (enabled, token) = AUTH_CONFIG
+        .read()
+        .map(|auth| auth.clone())
+        .unwrap_or((true, String::new()));
+    if !enabled {
+        return Ok(());
+    }
+
+    if token.is_empty() {
+        logger::log_error("[HTTP API] 已启用认证但 token 为空，拒绝请求");
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if provided.is_some_and(|p| tokens_match(p, &token)) {
+        Ok(())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// 允许访问管理 API 的浏览器来源：Tauri 前端与本机 localhost / 127.0.0.1 (任意端口)
+fn is_allowed_origin(origin: &str) -> bool {
+    const TAURI_ORIGINS: [&str; 3] = ["tauri://localhost", "http://tauri.localhost", "https://tauri.localhost"];
+    if TAURI_ORIGINS.contains(&origin) {
+        return true;
+    }
+    ["http://localhost", "http://127.0.0.1"].iter().any(|base| {
+        origin
+            .strip_prefix(base)
+            .map(|rest| rest.is_empty() || rest.strip_prefix(':').is_some_and(|port| port.parse::<u16>().is_ok()))
+            .unwrap_or(false)
+    })
+}
+
+/// 比较两者的 SHA-256 摘要而非原文，比较耗时与令牌有多少前缀字符匹配无关
+fn tokens_match(provided: &str, expected: &str) -> bool {
+    crate::proxy::security::hash_api_key(provided) == crate::proxy::security::hash_api_key(expected)
+}
+
+// ============================================================================
+// Rate Limit
+// ============================================================================
+
+/// 固定窗口计数，超限时返回需要等待的秒数
+fn check_rate_limit(
+    counters: &mut HashMap<String, (Instant, u32)>,
+    key: &str,
+    rule: &RateLimitRule,
+    now: Instant,
+) -> Result<(), u64> {
+    let window = Duration::from_secs(rule.window_secs);
+    let entry = counters.entry(key.to_string()).or_insert((now, 0));
+
+    if now.duration_since(entry.0) >= window {
+        *entry = (now, 0);
+    }
+
+    if entry.1 >= rule.max_requests {
+        let remaining = window.saturating_sub(now.duration_since(entry.0));
+        // 向上取整，至少 1 秒
+        let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        return Err(secs.max(1));
+    }
+
+    entry.1 += 1;
+    Ok(())
+}
+
+/// 按端点限流中间件，超限返回 429 + Retry-After
+async fn rate_limit_middleware(
+    State(state): State<ApiState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().as_str().to_string();
+    let path = request.uri().path().to_string();
+
+    let rule = state.rate_limit_rules.read().ok().and_then(|rules| {
+        rules
+            .iter()
+            .find(|r| r.method.eq_ignore_ascii_case(&method) && r.path == path)
+            .cloned()
+    });
+
+    if let Some(rule) = rule {
+        let key = format!("{} {}", method, path);
+        let result = match state.rate_limit_counters.lock() {
+            Ok(mut counters) => check_rate_limit(&mut counters, &key, &rule, Instant::now()),
+            Err(_) => Ok(()),
+        };
+
+        if let Err(retry_after) = result {
+            logger::log_warn(&format!("[HTTP API] 限流: {} (Retry-After: {}s)", key, retry_after));
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(ErrorResponse {
+                    error: format!("Rate limit exceeded, retry after {} seconds", retry_after),
+                }),
+            )
+                .into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
 // ============================================================================
 // Server
 // ============================================================================
 
 /// 启动 HTTP API 服务器
-pub async fn start_server(port: u16) -> Result<(), String> {
-    let state = ApiState::new();
+pub async fn start_server(port: u16, app_handle: tauri::AppHandle) -> Result<(), String> {
+    // 加载设置同步认证配置与限流规则；失败时沿用默认规则
+    if let Err(e) = load_settings() {
+        logger::log_warn(&format!("[HTTP API] 加载设置失败，使用默认限流规则: {}", e));
+    }
+    let state = ApiState::new(app_handle);
 
-    // CORS 配置 - 允许本地调用
+    // CORS 配置 - 仅允许 Tauri 前端与 localhost 页面跨域调用
     let cors = CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(AllowOrigin::predicate(|origin, _| {
+            origin.to_str().map(is_allowed_origin).unwrap_or(false)
+        }))
         .allow_methods(Any)
         .allow_headers(Any);
 
@@ -466,7 +843,16 @@ pub async fn start_server(port: u16) -> Result<(), String> {
         .route("/accounts/refresh", post(refresh_all_quotas))
         .route("/accounts/{id}/bind-device", post(bind_device))
         .route("/logs", get(get_logs))
-        .layer(cors)
+        .route("/logs/stream", get(logs_stream))
+        .route("/logs/conversation/:id", get(get_conversation_logs))
+        .route("/pool/snapshot", get(pool_snapshot))
+        .layer(
+            // 请求依次经过: CORS -> 认证 -> 限流
+            ServiceBuilder::new()
+                .layer(cors)
+                .layer(middleware::from_fn(auth_middleware))
+                .layer(middleware::from_fn_with_state(state.clone(), rate_limit_middleware)),
+        )
         .with_state(state);
 
     let addr = format!("127.0.0.1:{}", port);
@@ -484,11 +870,63 @@ pub async fn start_server(port: u16) -> Result<(), String> {
 }
 
 /// 在后台启动 HTTP API 服务器（非阻塞）
-pub fn spawn_server(port: u16) {
+pub fn spawn_server(port: u16, app_handle: tauri::AppHandle) {
+    // 启动 Webhook 分发器 (反代等模块通过 webhook::emit 投递事件)
+    WebhookDispatcher::start(load_settings().map(|s| s.webhooks).unwrap_or_default());
+
     // 使用 tauri::async_runtime::spawn 以确保在 Tauri 的 runtime 中运行
     tauri::async_runtime::spawn(async move {
-        if let Err(e) = start_server(port).await {
+        if let Err(e) = start_server(port, app_handle).await {
             logger::log_error(&format!("[HTTP API] 服务器启动失败: {}", e));
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_origins() {
+        assert!(is_allowed_origin("tauri://localhost"));
+        assert!(is_allowed_origin("http://localhost:1420"));
+        assert!(is_allowed_origin("http://127.0.0.1"));
+        assert!(!is_allowed_origin("http://localhost.evil.dev"));
+        assert!(!is_allowed_origin("http://127.0.0.1.nip.io:80"));
+        assert!(!is_allowed_origin("https://example.com"));
+    }
+
+    #[test]
+    fn test_default_settings_require_auth() {
+        assert!(HttpApiSettings::default().auth_enabled);
+        let legacy: HttpApiSettings = serde_json::from_str(r#"{"enabled": true, "port": 19527}"#).unwrap();
+        assert!(legacy.auth_enabled);
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("secret-token", "secret-token"));
+        assert!(!tokens_match("secret-toke", "secret-token"));
+        assert!(!tokens_match("", "secret-token"));
+    }
+
+    #[test]
+    fn test_check_rate_limit_fixed_window() {
+        let rule = RateLimitRule {
+            method: "POST".to_string(),
+            path: "/accounts/switch".to_string(),
+            max_requests: 1,
+            window_secs: 5,
+        };
+        let mut counters = HashMap::new();
+        let start = Instant::now();
+
+        assert!(check_rate_limit(&mut counters, "POST /accounts/switch", &rule, start).is_ok());
+        assert_eq!(
+            check_rate_limit(&mut counters, "POST /accounts/switch", &rule, start + Duration::from_millis(1500)),
+            Err(4)
+        );
+        // 窗口结束后重置
+        assert!(check_rate_limit(&mut counters, "POST /accounts/switch", &rule, start + Duration::from_secs(5)).is_ok());
+    }
+}
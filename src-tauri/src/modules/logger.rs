@@ -62,11 +62,28 @@ pub fn init_logger() {
     let filter_layer = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info"));
 
-    // 5. 初始化全局订阅器 (使用 try_init 避免重复初始化崩溃)
+    // 5. OpenTelemetry 导出层 (仅 otel feature，按反代配置启用)
+    // 此时订阅器尚未安装，构建结果在初始化完成后再记录
+    #[cfg(feature = "otel")]
+    let (otel_layer, otel_error) = match crate::modules::config::load_app_config()
+        .ok()
+        .map(|config| crate::proxy::otel::build_layer(&config.proxy))
+    {
+        Some(Ok(layer)) => (layer, None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    #[cfg(feature = "otel")]
+    let otel_enabled = otel_layer.is_some();
+    #[cfg(not(feature = "otel"))]
+    let otel_layer: Option<tracing_subscriber::layer::Identity> = None;
+
+    // 6. 初始化全局订阅器 (使用 try_init 避免重复初始化崩溃)
     let _ = tracing_subscriber::registry()
         .with(filter_layer)
         .with(console_layer)
         .with(file_layer)
+        .with(otel_layer)
         .try_init();
 
     // 泄漏 _guard 以确保其生命周期持续到程序退出
@@ -74,6 +91,13 @@ pub fn init_logger() {
     std::mem::forget(_guard);
     
     info!("日志系统已完成初始化 (终端控制台 + 文件持久化)");
+
+    #[cfg(feature = "otel")]
+    if let Some(e) = otel_error {
+        error!("初始化 OpenTelemetry 失败: {}", e);
+    } else if otel_enabled {
+        info!("OpenTelemetry 已启用");
+    }
     
     // 自动清理 7 天前的旧日志
     if let Err(e) = cleanup_old_logs(7) {
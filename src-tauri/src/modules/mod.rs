@@ -14,6 +14,12 @@ pub mod device;
 pub mod update_checker;
 pub mod scheduler;
 pub mod http_api;
+pub mod webhook;
+pub mod config_watcher;
+pub mod report;
+pub mod privacy;
+pub mod account_crypto;
+pub mod audit;
 
 use crate::models;
 
@@ -8,6 +8,13 @@ const USERINFO_URL: &str = "https://www.googleapis.com/oauth2/v2/userinfo";
 
 const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
 
+/// PKCE 授权参数 (code_verifier 需保留到交换 Token 时使用)
+#[derive(Debug, Clone)]
+pub struct PkceChallenge {
+    pub code_verifier: String,
+    pub code_challenge: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenResponse {
     pub access_token: String,
@@ -48,8 +55,8 @@ impl UserInfo {
 }
 
 
-/// 生成 OAuth 授权 URL
-pub fn get_auth_url(redirect_uri: &str) -> String {
+/// 生成 OAuth 授权 URL (PKCE S256)
+pub fn get_auth_url(redirect_uri: &str, code_challenge: &str) -> String {
     let scopes = vec![
         "https://www.googleapis.com/auth/cloud-platform",
         "https://www.googleapis.com/auth/userinfo.email",
@@ -66,15 +73,35 @@ pub fn get_auth_url(redirect_uri: &str) -> String {
         ("access_type", "offline"),
         ("prompt", "consent"),
         ("include_granted_scopes", "true"),
+        ("code_challenge", code_challenge),
+        ("code_challenge_method", "S256"),
     ];
     
     let url = url::Url::parse_with_params(AUTH_URL, &params).expect("无效的 Auth URL");
     url.to_string()
 }
 
-/// 使用 Authorization Code 交换 Token
-pub async fn exchange_code(code: &str, redirect_uri: &str) -> Result<TokenResponse, String> {
-    let client = crate::utils::http::create_client(15);
+/// PKCE S256: code_challenge = BASE64URL(SHA256(code_verifier))
+fn pkce_code_challenge(code_verifier: &str) -> String {
+    use base64::Engine;
+    use sha2::{Digest, Sha256};
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// 生成一次授权使用的 PKCE 参数
+pub fn new_pkce_challenge() -> PkceChallenge {
+    use base64::Engine;
+
+    // 32 字节随机数 -> 43 字符的 code_verifier (RFC 7636 要求 43~128 字符)
+    let bytes: [u8; 32] = rand::random();
+    let code_verifier = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+    let code_challenge = pkce_code_challenge(&code_verifier);
+    PkceChallenge { code_verifier, code_challenge }
+}
+
+/// 使用 Authorization Code 与 PKCE code_verifier 交换 Token
+pub async fn exchange_code(code: &str, redirect_uri: &str, code_verifier: &str) -> Result<TokenResponse, String> {
+    let client = crate::utils::http::create_pooled_client(15);
     
     let params = [
         ("client_id", CLIENT_ID),
@@ -82,14 +109,17 @@ pub async fn exchange_code(code: &str, redirect_uri: &str) -> Result<TokenRespon
         ("code", code),
         ("redirect_uri", redirect_uri),
         ("grant_type", "authorization_code"),
+        ("code_verifier", code_verifier),
     ];
 
     let response = client
+        .client
         .post(TOKEN_URL)
         .form(&params)
         .send()
-        .await
-        .map_err(|e| format!("Token 交换请求失败: {}", e))?;
+        .await;
+    client.report(&response);
+    let response = response.map_err(|e| format!("Token 交换请求失败: {}", e))?;
 
     if response.status().is_success() {
         let token_res = response.json::<TokenResponse>()
@@ -122,7 +152,7 @@ pub async fn exchange_code(code: &str, redirect_uri: &str) -> Result<TokenRespon
 
 /// 使用 refresh_token 刷新 access_token
 pub async fn refresh_access_token(refresh_token: &str) -> Result<TokenResponse, String> {
-    let client = crate::utils::http::create_client(15);
+    let client = crate::utils::http::create_pooled_client(15);
     
     let params = [
         ("client_id", CLIENT_ID),
@@ -134,11 +164,13 @@ pub async fn refresh_access_token(refresh_token: &str) -> Result<TokenResponse,
     crate::modules::logger::log_info("正在刷新 Token...");
     
     let response = client
+        .client
         .post(TOKEN_URL)
         .form(&params)
         .send()
-        .await
-        .map_err(|e| format!("刷新请求失败: {}", e))?;
+        .await;
+    client.report(&response);
+    let response = response.map_err(|e| format!("刷新请求失败: {}", e))?;
 
     if response.status().is_success() {
         let token_data = response
@@ -156,14 +188,16 @@ pub async fn refresh_access_token(refresh_token: &str) -> Result<TokenResponse,
 
 /// 获取用户信息
 pub async fn get_user_info(access_token: &str) -> Result<UserInfo, String> {
-    let client = crate::utils::http::create_client(15);
+    let client = crate::utils::http::create_pooled_client(15);
     
     let response = client
+        .client
         .get(USERINFO_URL)
         .bearer_auth(access_token)
         .send()
-        .await
-        .map_err(|e| format!("用户信息请求失败: {}", e))?;
+        .await;
+    client.report(&response);
+    let response = response.map_err(|e| format!("用户信息请求失败: {}", e))?;
 
     if response.status().is_success() {
         response.json::<UserInfo>()
@@ -201,3 +235,32 @@ pub async fn ensure_fresh_token(
         None,  // session_id 会在 token_manager 中生成
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_code_challenge_matches_rfc7636_example() {
+        // RFC 7636 Appendix B
+        assert_eq!(
+            pkce_code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_auth_url_carries_pkce_challenge() {
+        let pkce = new_pkce_challenge();
+        assert_eq!(pkce.code_verifier.len(), 43);
+        assert_eq!(pkce.code_challenge, pkce_code_challenge(&pkce.code_verifier));
+
+        let redirect_uri = "http://127.0.0.1:8123/oauth-callback";
+        let url = url::Url::parse(&get_auth_url(redirect_uri, &pkce.code_challenge)).unwrap();
+        let params: std::collections::HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["code_challenge"], pkce.code_challenge);
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(params["redirect_uri"], redirect_uri);
+        assert_eq!(params["access_type"], "offline");
+    }
+}
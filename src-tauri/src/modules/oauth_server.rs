@@ -9,6 +9,8 @@ use crate::modules::oauth;
 struct OAuthFlowState {
     auth_url: String,
     redirect_uri: String,
+    /// PKCE code_verifier，交换 Token 时回传
+    code_verifier: String,
     cancel_tx: watch::Sender<bool>,
     code_rx: Option<oneshot::Receiver<Result<String, String>>>,
 }
@@ -111,7 +113,8 @@ async fn ensure_oauth_flow_prepared(app_handle: &tauri::AppHandle) -> Result<Str
         format!("http://[::1]:{}/oauth-callback", port)
     };
 
-    let auth_url = oauth::get_auth_url(&redirect_uri);
+    let pkce = oauth::new_pkce_challenge();
+    let auth_url = oauth::get_auth_url(&redirect_uri, &pkce.code_challenge);
 
     // 取消信号（支持多消费者）
     let (cancel_tx, cancel_rx) = watch::channel(false);
@@ -206,6 +209,7 @@ async fn ensure_oauth_flow_prepared(app_handle: &tauri::AppHandle) -> Result<Str
         *state = Some(OAuthFlowState {
             auth_url: auth_url.clone(),
             redirect_uri,
+            code_verifier: pkce.code_verifier,
             cancel_tx,
             code_rx: Some(code_rx),
         });
@@ -245,7 +249,7 @@ pub async fn start_oauth_flow(app_handle: tauri::AppHandle) -> Result<oauth::Tok
         .map_err(|e| format!("无法打开浏览器: {}", e))?;
 
     // 取出 code_rx 用于等待
-    let (code_rx, redirect_uri) = {
+    let (code_rx, redirect_uri, code_verifier) = {
         let mut lock = get_oauth_flow_state()
             .lock()
             .map_err(|_| "OAuth 状态锁被污染".to_string())?;
@@ -256,7 +260,7 @@ pub async fn start_oauth_flow(app_handle: tauri::AppHandle) -> Result<oauth::Tok
             .code_rx
             .take()
             .ok_or_else(|| "OAuth 授权已在进行中".to_string())?;
-        (rx, state.redirect_uri.clone())
+        (rx, state.redirect_uri.clone(), state.code_verifier.clone())
     };
 
     // 等待 code（如果用户已完成授权，此处会立即返回）
@@ -271,7 +275,7 @@ pub async fn start_oauth_flow(app_handle: tauri::AppHandle) -> Result<oauth::Tok
         *lock = None;
     }
 
-    oauth::exchange_code(&code, &redirect_uri).await
+    oauth::exchange_code(&code, &redirect_uri, &code_verifier).await
 }
 
 /// Завершить OAuth flow без открытия браузера.
@@ -282,7 +286,7 @@ pub async fn complete_oauth_flow(app_handle: tauri::AppHandle) -> Result<oauth::
     let _ = ensure_oauth_flow_prepared(&app_handle).await?;
 
     // Take receiver to wait for code
-    let (code_rx, redirect_uri) = {
+    let (code_rx, redirect_uri, code_verifier) = {
         let mut lock = get_oauth_flow_state()
             .lock()
             .map_err(|_| "OAuth 状态锁被污染".to_string())?;
@@ -293,7 +297,7 @@ pub async fn complete_oauth_flow(app_handle: tauri::AppHandle) -> Result<oauth::
             .code_rx
             .take()
             .ok_or_else(|| "OAuth 授权已在进行中".to_string())?;
-        (rx, state.redirect_uri.clone())
+        (rx, state.redirect_uri.clone(), state.code_verifier.clone())
     };
 
     let code = match code_rx.await {
@@ -306,5 +310,5 @@ pub async fn complete_oauth_flow(app_handle: tauri::AppHandle) -> Result<oauth::
         *lock = None;
     }
 
-    oauth::exchange_code(&code, &redirect_uri).await
+    oauth::exchange_code(&code, &redirect_uri, &code_verifier).await
 }
@@ -0,0 +1,354 @@
+// 隐私相关功能
+// 1. 请求日志 PII 脱敏：在请求/响应体写入 SQLite 前替换邮箱、信用卡号、美国电话号码与 SSN
+// 2. 按账号导出/删除全部关联数据 (账号文件、请求日志、会话绑定、限流记录)
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::proxy::TokenManager;
+
+static EMAIL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap()
+});
+
+// 13-19 位数字，允许空格或连字符分隔；命中后还需通过 Luhn 校验
+static CREDIT_CARD_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap()
+});
+
+static SSN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap()
+});
+
+static US_PHONE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:\+1[\s.-]?)?(?:\(\d{3}\)\s?|\b\d{3}[\s.-])\d{3}[\s.-]\d{4}\b").unwrap()
+});
+
+/// 脱敏命中统计 (key: 规则名，自定义规则使用正则原文)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ScrubStats {
+    pub patterns_matched: HashMap<String, u32>,
+}
+
+impl ScrubStats {
+    pub fn merge(&mut self, matched: &HashMap<String, u32>) {
+        for (name, count) in matched {
+            *self.patterns_matched.entry(name.clone()).or_insert(0) += count;
+        }
+    }
+}
+
+pub struct PiiScrubber {
+    extra_patterns: Vec<String>,
+    custom: Vec<(String, Regex)>,
+}
+
+impl PiiScrubber {
+    /// 内置规则 + 自定义正则，无效的自定义正则会被跳过并记录警告
+    pub fn new(extra_patterns: &[String]) -> Self {
+        let custom = extra_patterns
+            .iter()
+            .filter_map(|p| match Regex::new(p) {
+                Ok(re) => Some((p.clone(), re)),
+                Err(e) => {
+                    tracing::warn!("[Privacy] 忽略无效的脱敏正则 '{}': {}", p, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            extra_patterns: extra_patterns.to_vec(),
+            custom,
+        }
+    }
+
+    /// 构建时使用的自定义正则 (用于判断配置是否变化)
+    pub fn extra_patterns(&self) -> &[String] {
+        &self.extra_patterns
+    }
+
+    /// 脱敏文本，返回替换结果与各规则命中次数
+    pub fn scrub(&self, text: &str) -> (String, HashMap<String, u32>) {
+        let mut matched = HashMap::new();
+        // 信用卡先于电话号码处理，避免长数字串被拆成电话号码
+        let mut out = replace_counted(&CREDIT_CARD_RE, text, "credit_card", "[REDACTED_CARD]", &mut matched, |m| {
+            luhn_valid(m)
+        });
+        out = replace_counted(&SSN_RE, &out, "ssn", "[REDACTED_SSN]", &mut matched, |_| true);
+        out = replace_counted(&US_PHONE_RE, &out, "us_phone", "[REDACTED_PHONE]", &mut matched, |_| true);
+        out = replace_counted(&EMAIL_RE, &out, "email", "[REDACTED_EMAIL]", &mut matched, |_| true);
+        for (name, re) in &self.custom {
+            out = replace_counted(re, &out, name, "[REDACTED]", &mut matched, |_| true);
+        }
+        (out, matched)
+    }
+}
+
+fn replace_counted(
+    re: &Regex,
+    text: &str,
+    name: &str,
+    replacement: &str,
+    matched: &mut HashMap<String, u32>,
+    accept: impl Fn(&str) -> bool,
+) -> String {
+    let mut count = 0u32;
+    let out = re.replace_all(text, |caps: &Captures| {
+        let m = &caps[0];
+        if accept(m) {
+            count += 1;
+            replacement.to_string()
+        } else {
+            m.to_string()
+        }
+    });
+    if count > 0 {
+        *matched.entry(name.to_string()).or_insert(0) += count;
+    }
+    out.into_owned()
+}
+
+/// Luhn 校验 (忽略空格与连字符)
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() < 13 {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// 账号数据导出结果 (archive 为 zip 内容，不随命令返回)
+#[derive(Debug, Serialize)]
+pub struct AccountDataExport {
+    pub email: String,
+    pub account_found: bool,
+    pub log_count: usize,
+    pub session_count: usize,
+    #[serde(skip)]
+    pub archive: Vec<u8>,
+}
+
+/// 账号数据删除结果
+#[derive(Debug, Serialize)]
+pub struct DeletionReport {
+    pub email: String,
+    pub account_deleted: bool,
+    pub logs_deleted: usize,
+    pub refresh_events_deleted: usize,
+    pub quota_snapshots_deleted: usize,
+    /// 已归档日志 (log_archives/*.jsonl.gz) 中删除的行数
+    pub archived_logs_deleted: usize,
+    pub sessions_removed: usize,
+}
+
+/// 账号文件中需要隐去的凭证字段
+const REDACTED_TOKEN_FIELDS: [&str; 3] = ["access_token", "refresh_token", "session_id"];
+
+fn find_account_id(email: &str) -> Result<Option<String>, String> {
+    let index = crate::modules::account::load_account_index()?;
+    Ok(index.accounts.into_iter().find(|a| a.email == email).map(|a| a.id))
+}
+
+fn redact_account_json(value: &mut serde_json::Value) {
+    if let Some(token) = value.get_mut("token").and_then(|t| t.as_object_mut()) {
+        for field in REDACTED_TOKEN_FIELDS {
+            if token.contains_key(field) {
+                token.insert(field.to_string(), serde_json::Value::String("[REDACTED]".to_string()));
+            }
+        }
+    }
+}
+
+fn sessions_path() -> Result<std::path::PathBuf, String> {
+    Ok(crate::modules::account::get_data_dir()?.join("sticky_sessions.json"))
+}
+
+/// 账号使用过的会话：反代运行时取内存中的绑定，否则读取停止时持久化的会话文件
+fn account_sessions(
+    account_id: &str,
+    token_manager: Option<&TokenManager>,
+) -> Result<Vec<crate::proxy::token_manager::SessionSnapshot>, String> {
+    let sessions = match token_manager {
+        Some(tm) => tm.list_sessions(),
+        None => crate::proxy::token_manager::snapshot_sessions(
+            &crate::proxy::session_manager::load_sessions(&sessions_path()?)?,
+        ),
+    };
+    Ok(sessions.into_iter().filter(|s| s.account_id == account_id).collect())
+}
+
+fn build_archive(files: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, content) in files {
+        writer
+            .start_file(*name, zip::write::SimpleFileOptions::default())
+            .map_err(|e| format!("写入压缩包失败: {}", e))?;
+        writer.write_all(content).map_err(|e| format!("写入压缩包失败: {}", e))?;
+    }
+    let cursor = writer.finish().map_err(|e| format!("写入压缩包失败: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+/// 导出邮箱关联的全部数据为 zip：account.json (凭证已隐去)、request_logs.json、sessions.json
+pub fn export_account_data(email: &str, token_manager: Option<&TokenManager>) -> Result<AccountDataExport, String> {
+    let account_id = find_account_id(email)?;
+
+    let account_json = match &account_id {
+        Some(id) => {
+            let account = crate::modules::account::load_account(id)?;
+            let mut value = serde_json::to_value(&account).map_err(|e| e.to_string())?;
+            redact_account_json(&mut value);
+            value
+        }
+        None => serde_json::Value::Null,
+    };
+    let logs = crate::modules::proxy_db::get_logs_by_account(email)?;
+    let sessions = match &account_id {
+        Some(id) => account_sessions(id, token_manager)?,
+        None => Vec::new(),
+    };
+
+    let archive = build_archive(&[
+        ("account.json", serde_json::to_vec_pretty(&account_json).map_err(|e| e.to_string())?),
+        ("request_logs.json", serde_json::to_vec_pretty(&logs).map_err(|e| e.to_string())?),
+        ("sessions.json", serde_json::to_vec_pretty(&sessions).map_err(|e| e.to_string())?),
+    ])?;
+
+    Ok(AccountDataExport {
+        email: email.to_string(),
+        account_found: account_id.is_some(),
+        log_count: logs.len(),
+        session_count: sessions.len(),
+        archive,
+    })
+}
+
+/// 删除邮箱关联的全部数据 (不可恢复)：请求日志与刷新记录、归档日志、配额快照、会话绑定、限流记录、账号文件
+pub fn delete_account_data(email: &str, token_manager: Option<&TokenManager>) -> Result<DeletionReport, String> {
+    let account_id = find_account_id(email)?;
+    let (logs_deleted, refresh_events_deleted) = crate::modules::proxy_db::delete_logs_by_account(email)?;
+    let archived_logs_deleted =
+        crate::modules::proxy_db::purge_account_from_archives(email, &crate::modules::proxy_db::get_archive_dir()?)?;
+
+    let mut sessions_removed = 0;
+    let mut quota_snapshots_deleted = 0;
+    if let Some(id) = &account_id {
+        quota_snapshots_deleted = crate::modules::proxy_db::delete_quota_snapshots_by_account(id)?;
+        match token_manager {
+            // 运行中：清理内存，停止时会覆盖写入持久化文件
+            Some(tm) => sessions_removed = tm.purge_account(id),
+            None => {
+                let path = sessions_path()?;
+                let sessions = crate::proxy::session_manager::load_sessions(&path)?;
+                let before = sessions.len();
+                sessions.retain(|_, meta| &meta.account_id != id);
+                sessions_removed = before - sessions.len();
+                if sessions_removed > 0 {
+                    crate::proxy::session_manager::save_sessions(&path, &sessions)?;
+                }
+
+                let path = crate::modules::account::get_data_dir()?.join(crate::proxy::rate_limit::RATE_LIMITS_FILE);
+                let tracker = crate::proxy::rate_limit::load_rate_limits(&path)?;
+                if tracker.clear(id) {
+                    crate::proxy::rate_limit::save_rate_limits(&path, &tracker)?;
+                }
+            }
+        }
+        crate::modules::account::delete_account(id)?;
+    }
+
+    tracing::warn!(
+        "[Privacy] Deleted all data of {}: {} logs, {} archived logs, {} refresh events, {} quota snapshots, {} sessions",
+        crate::utils::privacy::mask_email(email),
+        logs_deleted,
+        archived_logs_deleted,
+        refresh_events_deleted,
+        quota_snapshots_deleted,
+        sessions_removed
+    );
+    Ok(DeletionReport {
+        email: email.to_string(),
+        account_deleted: account_id.is_some(),
+        logs_deleted,
+        refresh_events_deleted,
+        quota_snapshots_deleted,
+        archived_logs_deleted,
+        sessions_removed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrub_builtin_patterns() {
+        let scrubber = PiiScrubber::new(&[]);
+        let (out, matched) = scrubber.scrub(
+            "mail alice@example.com, card 4111 1111 1111 1111, phone (415) 555-2671, ssn 123-45-6789",
+        );
+        assert_eq!(
+            out,
+            "mail [REDACTED_EMAIL], card [REDACTED_CARD], phone [REDACTED_PHONE], ssn [REDACTED_SSN]"
+        );
+        for name in ["email", "credit_card", "us_phone", "ssn"] {
+            assert_eq!(matched.get(name), Some(&1), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_card_requires_luhn() {
+        let scrubber = PiiScrubber::new(&[]);
+        let (out, matched) = scrubber.scrub("order 1234567890123456");
+        assert_eq!(out, "order 1234567890123456");
+        assert!(matched.get("credit_card").is_none());
+    }
+
+    #[test]
+    fn test_redact_account_json() {
+        let mut value = serde_json::json!({
+            "email": "alice@example.com",
+            "token": {"access_token": "ya29.secret", "refresh_token": "1//secret", "expires_in": 3600}
+        });
+        redact_account_json(&mut value);
+        assert_eq!(value["token"]["access_token"], "[REDACTED]");
+        assert_eq!(value["token"]["refresh_token"], "[REDACTED]");
+        assert_eq!(value["token"]["expires_in"], 3600);
+        assert!(value["token"].get("session_id").is_none());
+    }
+
+    #[test]
+    fn test_build_archive() {
+        let archive = build_archive(&[("a.json", b"{}".to_vec()), ("b.json", b"[]".to_vec())]).unwrap();
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        assert_eq!(zip.len(), 2);
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut zip.by_name("b.json").unwrap(), &mut content).unwrap();
+        assert_eq!(content, "[]");
+    }
+
+    #[test]
+    fn test_custom_patterns_and_invalid_skipped() {
+        let scrubber = PiiScrubber::new(&[r"EMP-\d{6}".to_string(), "(".to_string()]);
+        let (out, matched) = scrubber.scrub("ids EMP-000123 and EMP-999999");
+        assert_eq!(out, "ids [REDACTED] and [REDACTED]");
+        assert_eq!(matched.get(r"EMP-\d{6}"), Some(&2));
+        assert_eq!(scrubber.extra_patterns().len(), 2);
+    }
+}
@@ -1,21 +1,118 @@
 use rusqlite::{params, Connection};
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use crate::proxy::monitor::ProxyRequestLog;
 
+const HOUR_MS: i64 = 3600 * 1000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+
+/// Logs younger than this (minutes) are not rolled up yet: streaming requests are saved
+/// when the stream ends, so their rows can appear a while after their timestamp
+const AGGREGATE_SETTLE_MINUTES: i64 = 10;
+
+/// Interval between scheduled WAL checkpoints
+const WAL_CHECKPOINT_INTERVAL_SECS: u64 = 5 * 60;
+
+/// Logs whose request + response body exceed this size use `large_body_days`
+const LARGE_BODY_BYTES: i64 = 1024 * 1024;
+
+/// Rows read per batch when archiving old logs
+const ARCHIVE_BATCH_SIZE: i64 = 1000;
+
+/// Busy timeout for the checkpoint connection, so it gives up quickly instead of blocking active readers
+const WAL_CHECKPOINT_BUSY_TIMEOUT_MS: u64 = 500;
+
+static WAL_CHECKPOINT_SCHEDULED: std::sync::Once = std::sync::Once::new();
+
+/// Interval between checks of the database size against `max_db_size_mb`
+const DB_SIZE_CHECK_INTERVAL_SECS: u64 = 5 * 60;
+
+static DB_SIZE_CHECK_SCHEDULED: std::sync::Once = std::sync::Once::new();
+
+/// Columns that may be selected for CSV/JSONL export
+pub const EXPORT_COLUMNS: &[&str] = &[
+    "id", "timestamp", "method", "url", "status", "duration", "model", "mapped_model",
+    "account_email", "error", "input_tokens", "output_tokens", "request_body", "response_body",
+];
+
+/// Per-model latency distribution (milliseconds)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ModelLatencyStats {
+    pub model: String,
+    pub request_count: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub mean_ms: f64,
+}
+
+/// Database size and row count, for the log settings panel
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyDbStats {
+    pub size_bytes: u64,
+    pub log_count: u64,
+}
+
+/// Result of archiving old logs to gzip-compressed JSONL files
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct ArchiveStats {
+    pub rows_archived: usize,
+    /// Compressed bytes appended to the archive files
+    pub bytes_written: u64,
+    pub files_created: Vec<PathBuf>,
+}
+
+/// Result of `PRAGMA wal_checkpoint`
+#[derive(Debug, Clone, Serialize)]
+pub struct WalCheckpointStats {
+    /// The checkpoint could not complete because of concurrent readers/writers
+    pub busy: bool,
+    /// Pages in the WAL file before the checkpoint
+    pub wal_pages: i64,
+    /// Pages moved from the WAL into the database file
+    pub checkpointed_pages: i64,
+}
+
+/// Time-series aggregate bucket (hourly or daily)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateBucket {
+    /// Bucket start (ms since epoch, same unit as request_logs.timestamp)
+    pub bucket_start: i64,
+    /// "hour" | "day"
+    pub bucket_size: String,
+    pub model: String,
+    pub provider: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
 pub fn get_proxy_db_path() -> Result<PathBuf, String> {
     let data_dir = crate::modules::account::get_data_dir()?;
     Ok(data_dir.join("proxy_logs.db"))
 }
 
-fn connect_db() -> Result<Connection, String> {
+/// Default directory for archived logs (`archive_logs_older_than`)
+pub fn get_archive_dir() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("log_archives"))
+}
+
+pub(crate) fn connect_db() -> Result<Connection, String> {
+    // Set busy timeout to 5000ms to avoid "database is locked" errors
+    connect_db_with_busy_timeout(5000)
+}
+
+fn connect_db_with_busy_timeout(busy_timeout_ms: u64) -> Result<Connection, String> {
     let db_path = get_proxy_db_path()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     
     // Enable WAL mode for better concurrency
     conn.pragma_update(None, "journal_mode", "WAL").map_err(|e| e.to_string())?;
     
-    // Set busy timeout to 5000ms to avoid "database is locked" errors
-    conn.pragma_update(None, "busy_timeout", 5000).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", busy_timeout_ms).map_err(|e| e.to_string())?;
     
     // Synchronous NORMAL is faster and safe enough for WAL
     conn.pragma_update(None, "synchronous", "NORMAL").map_err(|e| e.to_string())?;
@@ -23,52 +120,396 @@ fn connect_db() -> Result<Connection, String> {
     Ok(conn)
 }
 
-pub fn init_db() -> Result<(), String> {
-    // connect_db will initialize WAL mode and other pragmas
-    let conn = connect_db()?;
-    
+/// A single schema change, applied at most once and recorded in `schema_migrations`
+struct Migration {
+    version: u32,
+    description: &'static str,
+    up: fn(&Connection) -> Result<(), String>,
+}
+
+/// Ordered schema history. Never edit or reorder an applied migration; append a new one instead.
+/// Early steps are written to be idempotent because databases created before version tracking
+/// already contain some of these objects.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create request_logs with timestamp index",
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS request_logs (
+                    id TEXT PRIMARY KEY,
+                    timestamp INTEGER,
+                    method TEXT,
+                    url TEXT,
+                    status INTEGER,
+                    duration INTEGER,
+                    model TEXT,
+                    error TEXT
+                );
+                CREATE INDEX IF NOT EXISTS idx_timestamp ON request_logs (timestamp DESC);"
+            ).map_err(|e| e.to_string())
+        },
+    },
+    Migration {
+        version: 2,
+        description: "add body, token usage, account and mapped model columns",
+        up: |conn| {
+            for (column, ty) in [
+                ("request_body", "TEXT"),
+                ("response_body", "TEXT"),
+                ("input_tokens", "INTEGER"),
+                ("output_tokens", "INTEGER"),
+                ("account_email", "TEXT"),
+                ("mapped_model", "TEXT"),
+            ] {
+                add_column_if_missing(conn, "request_logs", column, ty)?;
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        version: 3,
+        description: "add status index for stats queries",
+        up: |conn| {
+            conn.execute("CREATE INDEX IF NOT EXISTS idx_status ON request_logs (status)", [])
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        },
+    },
+    Migration {
+        version: 4,
+        description: "create request_aggregates (hourly/daily buckets that survive log cleanup)",
+        up: |conn| {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS request_aggregates (
+                    bucket_start INTEGER NOT NULL,
+                    bucket_size TEXT NOT NULL,
+                    model TEXT NOT NULL,
+                    provider TEXT NOT NULL,
+                    requests INTEGER NOT NULL DEFAULT 0,
+                    errors INTEGER NOT NULL DEFAULT 0,
+                    input_tokens INTEGER NOT NULL DEFAULT 0,
+                    output_tokens INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (bucket_start, bucket_size, model, provider)
+                )",
+                [],
+            ).map(|_| ()).map_err(|e| e.to_string())
+        },
+    },
+    Migration {
+        version: 5,
+        description: "create request_logs_fts full-text index with sync triggers",
+        up: |conn| {
+            // External-content table: bodies are stored once in request_logs and the index is
+            // keyed by its rowid, so deletes are index lookups instead of full FTS scans
+            conn.execute_batch(
+                "CREATE VIRTUAL TABLE IF NOT EXISTS request_logs_fts USING fts5(
+                    request_body,
+                    response_body,
+                    content='request_logs',
+                    content_rowid='rowid'
+                );
+                CREATE TRIGGER IF NOT EXISTS request_logs_fts_insert AFTER INSERT ON request_logs BEGIN
+                    INSERT INTO request_logs_fts (rowid, request_body, response_body)
+                    VALUES (new.rowid, new.request_body, new.response_body);
+                END;
+                CREATE TRIGGER IF NOT EXISTS request_logs_fts_delete AFTER DELETE ON request_logs BEGIN
+                    INSERT INTO request_logs_fts (request_logs_fts, rowid, request_body, response_body)
+                    VALUES ('delete', old.rowid, old.request_body, old.response_body);
+                END;
+                CREATE TRIGGER IF NOT EXISTS request_logs_fts_update AFTER UPDATE ON request_logs BEGIN
+                    INSERT INTO request_logs_fts (request_logs_fts, rowid, request_body, response_body)
+                    VALUES ('delete', old.rowid, old.request_body, old.response_body);
+                    INSERT INTO request_logs_fts (rowid, request_body, response_body)
+                    VALUES (new.rowid, new.request_body, new.response_body);
+                END;"
+            ).map_err(|e| e.to_string())?;
+
+            // Index rows logged before the FTS table existed
+            rebuild_fts_index(conn)
+        },
+    },
+    Migration {
+        version: 6,
+        description: "add compound (timestamp, model, status) index for error analytics",
+        up: |conn| {
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_ts_model_status ON request_logs (timestamp DESC, model, status)",
+                [],
+            ).map(|_| ()).map_err(|e| e.to_string())
+        },
+    },
+    Migration {
+        version: 7,
+        description: "create token_refresh_events for per-account refresh failure tracking",
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS token_refresh_events (
+                    timestamp INTEGER NOT NULL,
+                    account_email TEXT NOT NULL,
+                    success INTEGER NOT NULL,
+                    error TEXT
+                );
+                CREATE INDEX IF NOT EXISTS idx_refresh_email_ts ON token_refresh_events (account_email, timestamp);"
+            ).map_err(|e| e.to_string())
+        },
+    },
+    Migration {
+        version: 8,
+        description: "add conversation_id to request_logs for multi-turn grouping",
+        up: |conn| {
+            add_column_if_missing(conn, "request_logs", "conversation_id", "TEXT")?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_conversation_ts ON request_logs (conversation_id, timestamp)",
+                [],
+            ).map(|_| ()).map_err(|e| e.to_string())
+        },
+    },
+    Migration {
+        version: 9,
+        description: "add estimated_cost_usd to request_logs",
+        up: |conn| add_column_if_missing(conn, "request_logs", "estimated_cost_usd", "REAL"),
+    },
+    Migration {
+        version: 10,
+        description: "add request_id to request_logs for X-Request-ID correlation",
+        up: |conn| {
+            add_column_if_missing(conn, "request_logs", "request_id", "TEXT")?;
+            conn.execute(
+                "CREATE INDEX IF NOT EXISTS idx_request_id ON request_logs (request_id)",
+                [],
+            ).map(|_| ()).map_err(|e| e.to_string())
+        },
+    },
+    Migration {
+        version: 11,
+        description: "create quota_snapshots for quota trend history",
+        up: |conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS quota_snapshots (
+                    account_id TEXT NOT NULL,
+                    model TEXT NOT NULL,
+                    percentage INTEGER NOT NULL,
+                    recorded_at INTEGER NOT NULL,
+                    UNIQUE (account_id, model, recorded_at)
+                );
+                CREATE INDEX IF NOT EXISTS idx_quota_account_ts ON quota_snapshots (account_id, recorded_at);"
+            ).map_err(|e| e.to_string())
+        },
+    },
+    Migration {
+        version: 12,
+        description: "track the request_aggregates flush watermark so buckets accumulate",
+        up: |conn| {
+            conn.execute(
+                "CREATE TABLE IF NOT EXISTS aggregate_watermark (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    flushed_until INTEGER NOT NULL
+                )",
+                [],
+            ).map_err(|e| e.to_string())?;
+
+            // Buckets were previously recomputed from raw logs. Drop the latest hour (whose logs
+            // still exist) and rebuild its day bucket from the surviving hour buckets, then resume
+            // accumulating from the start of that hour.
+            let watermark: i64 = conn.query_row(
+                "SELECT COALESCE(MAX(bucket_start), 0) FROM request_aggregates WHERE bucket_size = 'hour'",
+                [],
+                |row| row.get(0),
+            ).map_err(|e| e.to_string())?;
+            let day_start = (watermark / DAY_MS) * DAY_MS;
+            conn.execute(
+                "DELETE FROM request_aggregates
+                 WHERE (bucket_size = 'hour' AND bucket_start >= ?1) OR (bucket_size = 'day' AND bucket_start >= ?2)",
+                params![watermark, day_start],
+            ).map_err(|e| e.to_string())?;
+            conn.execute(
+                "INSERT INTO request_aggregates
+                    (bucket_start, bucket_size, model, provider, requests, errors, input_tokens, output_tokens)
+                 SELECT ?1, 'day', model, provider, SUM(requests), SUM(errors), SUM(input_tokens), SUM(output_tokens)
+                 FROM request_aggregates
+                 WHERE bucket_size = 'hour' AND bucket_start >= ?1
+                 GROUP BY model, provider",
+                [day_start],
+            ).map_err(|e| e.to_string())?;
+            conn.execute("INSERT OR REPLACE INTO aggregate_watermark (id, flushed_until) VALUES (1, ?1)", [watermark])
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        },
+    },
+];
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, ty: &str) -> Result<(), String> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT EXISTS (SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1)", table),
+        [column],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, ty), [])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Apply every migration newer than the recorded schema version, in order.
+/// Each migration runs in its own transaction together with its `schema_migrations` row.
+fn run_migrations(conn: &Connection) -> Result<(), String> {
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS request_logs (
-            id TEXT PRIMARY KEY,
-            timestamp INTEGER,
-            method TEXT,
-            url TEXT,
-            status INTEGER,
-            duration INTEGER,
-            model TEXT,
-            error TEXT
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            applied_at INTEGER NOT NULL,
+            description TEXT NOT NULL
         )",
         [],
     ).map_err(|e| e.to_string())?;
 
-    // Try to add new columns (ignore errors if they exist)
-    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN request_body TEXT", []);
-    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN response_body TEXT", []);
-    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN input_tokens INTEGER", []);
-    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN output_tokens INTEGER", []);
-    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN account_email TEXT", []);
-    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN mapped_model TEXT", []);
-
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_timestamp ON request_logs (timestamp DESC)",
+    let current: u32 = conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
         [],
+        |row| row.get(0),
     ).map_err(|e| e.to_string())?;
 
-    // Add status index for faster stats queries
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_status ON request_logs (status)",
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        conn.execute_batch("BEGIN").map_err(|e| e.to_string())?;
+        let result = (migration.up)(conn).and_then(|_| {
+            conn.execute(
+                "INSERT INTO schema_migrations (version, applied_at, description) VALUES (?1, ?2, ?3)",
+                params![migration.version, chrono::Utc::now().timestamp(), migration.description],
+            ).map(|_| ()).map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(()) => {
+                conn.execute_batch("COMMIT").map_err(|e| e.to_string())?;
+                tracing::info!("Proxy DB migrated to v{}: {}", migration.version, migration.description);
+            }
+            Err(e) => {
+                let _ = conn.execute_batch("ROLLBACK");
+                return Err(format!("Proxy DB migration v{} failed: {}", migration.version, e));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn init_db() -> Result<(), String> {
+    // connect_db will initialize WAL mode and other pragmas
+    let conn = connect_db()?;
+    run_migrations(&conn)
+}
+
+/// Roll request_logs up into hourly and daily buckets.
+///
+/// Only logs between the stored watermark and `AGGREGATE_SETTLE_MINUTES` ago are added to
+/// the buckets, and the watermark then advances, so each log is counted exactly once and
+/// buckets keep their totals after the raw logs are deleted.
+/// Returns the number of bucket rows upserted.
+pub fn flush_aggregates(conn: &Connection) -> Result<usize, String> {
+    let settled_until = chrono::Utc::now().timestamp_millis() - AGGREGATE_SETTLE_MINUTES * 60 * 1000;
+    flush_aggregates_until(conn, settled_until)
+}
+
+fn flush_aggregates_until(conn: &Connection, until: i64) -> Result<usize, String> {
+    // IMMEDIATE: concurrent flushes must not read the same watermark
+    conn.execute_batch("BEGIN IMMEDIATE").map_err(|e| e.to_string())?;
+    let result = accumulate_aggregates(conn, until);
+    match result {
+        Ok(_) => conn.execute_batch("COMMIT").map_err(|e| e.to_string())?,
+        Err(_) => {
+            let _ = conn.execute_batch("ROLLBACK");
+        }
+    }
+    result
+}
+
+fn accumulate_aggregates(conn: &Connection, until: i64) -> Result<usize, String> {
+    let watermark: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(flushed_until), 0) FROM aggregate_watermark",
         [],
+        |row| row.get(0),
     ).map_err(|e| e.to_string())?;
+    if until <= watermark {
+        return Ok(0);
+    }
 
-    Ok(())
+    let mut upserted = 0;
+    for (size, size_ms) in [("hour", HOUR_MS), ("day", DAY_MS)] {
+        upserted += conn.execute(
+            "INSERT INTO request_aggregates
+                (bucket_start, bucket_size, model, provider, requests, errors, input_tokens, output_tokens)
+             SELECT
+                (timestamp / ?1) * ?1,
+                ?2,
+                COALESCE(model, ''),
+                CASE
+                    WHEN url LIKE '%/v1/messages%' THEN 'anthropic'
+                    WHEN url LIKE '%/v1beta/%' THEN 'gemini'
+                    WHEN url LIKE '%/v1/%' THEN 'openai'
+                    ELSE 'other'
+                END AS provider,
+                COUNT(*),
+                SUM(CASE WHEN status < 200 OR status >= 400 THEN 1 ELSE 0 END),
+                COALESCE(SUM(input_tokens), 0),
+                COALESCE(SUM(output_tokens), 0)
+             FROM request_logs
+             WHERE timestamp >= ?3 AND timestamp < ?4
+             GROUP BY 1, 3, 4
+             ON CONFLICT (bucket_start, bucket_size, model, provider) DO UPDATE SET
+                requests = requests + excluded.requests,
+                errors = errors + excluded.errors,
+                input_tokens = input_tokens + excluded.input_tokens,
+                output_tokens = output_tokens + excluded.output_tokens",
+            params![size_ms, size, watermark, until],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    conn.execute("INSERT OR REPLACE INTO aggregate_watermark (id, flushed_until) VALUES (1, ?1)", [until])
+        .map_err(|e| e.to_string())?;
+    Ok(upserted)
+}
+
+/// Get hourly aggregate buckets for the last `hours` hours
+pub fn get_hourly_stats(hours: u64) -> Result<Vec<AggregateBucket>, String> {
+    let conn = connect_db()?;
+    flush_aggregates(&conn)?;
+
+    let since = chrono::Utc::now().timestamp_millis() - (hours as i64) * HOUR_MS;
+    let since = (since / HOUR_MS) * HOUR_MS;
+
+    let mut stmt = conn.prepare(
+        "SELECT bucket_start, bucket_size, model, provider, requests, errors, input_tokens, output_tokens
+         FROM request_aggregates
+         WHERE bucket_size = 'hour' AND bucket_start >= ?1
+         ORDER BY bucket_start ASC, model ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let buckets_iter = stmt.query_map([since], |row| {
+        Ok(AggregateBucket {
+            bucket_start: row.get(0)?,
+            bucket_size: row.get(1)?,
+            model: row.get(2)?,
+            provider: row.get(3)?,
+            requests: row.get(4)?,
+            errors: row.get(5)?,
+            input_tokens: row.get(6)?,
+            output_tokens: row.get(7)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut buckets = Vec::new();
+    for bucket in buckets_iter {
+        buckets.push(bucket.map_err(|e| e.to_string())?);
+    }
+    Ok(buckets)
 }
 
 pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
     let conn = connect_db()?;
 
     conn.execute(
-        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, conversation_id, estimated_cost_usd, request_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
         params![
             log.id,
             log.timestamp,
@@ -84,12 +525,87 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
             log.output_tokens,
             log.account_email,
             log.mapped_model,
+            log.conversation_id,
+            log.estimated_cost_usd,
+            log.request_id,
         ],
     ).map_err(|e| e.to_string())?;
 
     Ok(())
 }
 
+fn db_size_bytes(conn: &Connection) -> Result<u64, String> {
+    let page_count: u64 = conn
+        .query_row("PRAGMA page_count", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let page_size: u64 = conn
+        .query_row("PRAGMA page_size", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    Ok(page_count * page_size)
+}
+
+/// Estimated database file size (page_count * page_size, excluding the WAL)
+pub fn get_db_size_bytes() -> Result<u64, String> {
+    db_size_bytes(&connect_db()?)
+}
+
+/// Delete the oldest 10% of request logs (at least one row)
+fn trim_oldest_logs(conn: &Connection) -> Result<usize, String> {
+    let count: u64 = conn
+        .query_row("SELECT COUNT(*) FROM request_logs", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    let to_delete = (count / 10).max(1);
+    conn.execute(
+        "DELETE FROM request_logs WHERE id IN (
+            SELECT id FROM request_logs ORDER BY timestamp ASC LIMIT ?1
+        )",
+        [to_delete],
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Rebuild the full-text index from request_logs
+fn rebuild_fts_index(conn: &Connection) -> Result<(), String> {
+    conn.execute("INSERT INTO request_logs_fts (request_logs_fts) VALUES ('rebuild')", [])
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// VACUUM the database. request_logs has no INTEGER PRIMARY KEY, so VACUUM may renumber
+/// its rowids; the external-content FTS index is keyed by rowid and must be rebuilt.
+fn vacuum(conn: &Connection) -> Result<(), String> {
+    conn.execute("VACUUM", []).map_err(|e| e.to_string())?;
+    rebuild_fts_index(conn)
+}
+
+/// Trim the oldest logs and VACUUM when the database exceeds `max_mb`
+fn enforce_db_size_limit(max_mb: u64) -> Result<(), String> {
+    let conn = connect_db()?;
+    let size = db_size_bytes(&conn)?;
+    if size <= max_mb * 1024 * 1024 {
+        return Ok(());
+    }
+
+    // Roll raw logs into aggregates before they are deleted
+    if let Err(e) = flush_aggregates(&conn) {
+        tracing::warn!("Failed to flush request aggregates: {}", e);
+    }
+    let deleted = trim_oldest_logs(&conn)?;
+    vacuum(&conn)?;
+    tracing::info!(
+        "Proxy DB size {} bytes exceeded limit of {} MB, removed {} oldest logs",
+        size,
+        max_mb,
+        deleted
+    );
+    drop(conn);
+
+    if let Err(e) = wal_checkpoint() {
+        tracing::warn!("WAL checkpoint after size trim failed: {}", e);
+    }
+    Ok(())
+}
+
 /// Get logs summary (without large request_body and response_body fields) with pagination
 pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestLog>, String> {
     let conn = connect_db()?;
@@ -119,6 +635,9 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
             response_body: None, // Don't query large fields for list view
             input_tokens: row.get(10).unwrap_or(None),
             output_tokens: row.get(11).unwrap_or(None),
+            conversation_id: None,
+            estimated_cost_usd: None,
+            request_id: None,
         })
     }).map_err(|e| e.to_string())?;
 
@@ -129,6 +648,109 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
     Ok(logs)
 }
 
+/// Encode a keyset pagination cursor (opaque base64 of `{timestamp}:{id}`)
+pub fn encode_cursor(timestamp: i64, id: &str) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(format!("{}:{}", timestamp, id))
+}
+
+/// Decode a cursor produced by `encode_cursor` into `(timestamp, id)`
+pub fn parse_cursor(cursor: &str) -> Result<(i64, String), String> {
+    use base64::Engine;
+    let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor.trim())
+        .map_err(|_| "Invalid cursor".to_string())?;
+    let decoded = String::from_utf8(decoded).map_err(|_| "Invalid cursor".to_string())?;
+    let (timestamp, id) = decoded.split_once(':').ok_or("Invalid cursor")?;
+    let timestamp = timestamp.parse::<i64>().map_err(|_| "Invalid cursor".to_string())?;
+    Ok((timestamp, id.to_string()))
+}
+
+/// Keyset pagination over log summaries (newest first).
+/// Unlike LIMIT/OFFSET the cost does not grow with the page depth.
+/// Returns the page and the cursor for the next page (None when there are no more rows).
+pub fn get_logs_page_after(
+    after_id: Option<&str>,
+    after_timestamp: Option<i64>,
+    limit: usize,
+) -> Result<(Vec<ProxyRequestLog>, Option<String>), String> {
+    let conn = connect_db()?;
+    query_logs_page_after(&conn, after_id, after_timestamp, limit)
+}
+
+fn query_logs_page_after(
+    conn: &Connection,
+    after_id: Option<&str>,
+    after_timestamp: Option<i64>,
+    limit: usize,
+) -> Result<(Vec<ProxyRequestLog>, Option<String>), String> {
+    let map_row = |row: &rusqlite::Row| -> rusqlite::Result<ProxyRequestLog> {
+        Ok(ProxyRequestLog {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            method: row.get(2)?,
+            url: row.get(3)?,
+            status: row.get(4)?,
+            duration: row.get(5)?,
+            model: row.get(6)?,
+            mapped_model: row.get(11).unwrap_or(None),
+            account_email: row.get(10).unwrap_or(None),
+            error: row.get(7)?,
+            request_body: None,
+            response_body: None,
+            input_tokens: row.get(8).unwrap_or(None),
+            output_tokens: row.get(9).unwrap_or(None),
+            conversation_id: None,
+            estimated_cost_usd: None,
+            request_id: None,
+        })
+    };
+    let columns = "id, timestamp, method, url, status, duration, model, error,
+                   input_tokens, output_tokens, account_email, mapped_model";
+
+    let rows = match (after_timestamp, after_id) {
+        (Some(ts), Some(id)) => {
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT {} FROM request_logs
+                     WHERE (timestamp, id) < (?1, ?2)
+                     ORDER BY timestamp DESC, id DESC
+                     LIMIT ?3",
+                    columns
+                ))
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![ts, id, limit as i64], map_row)
+                .map_err(|e| e.to_string())?
+                .collect::<rusqlite::Result<Vec<_>>>();
+            rows
+        }
+        _ => {
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT {} FROM request_logs
+                     ORDER BY timestamp DESC, id DESC
+                     LIMIT ?1",
+                    columns
+                ))
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![limit as i64], map_row)
+                .map_err(|e| e.to_string())?
+                .collect::<rusqlite::Result<Vec<_>>>();
+            rows
+        }
+    }
+    .map_err(|e| e.to_string())?;
+
+    let next_cursor = if limit > 0 && rows.len() == limit {
+        rows.last().map(|log| encode_cursor(log.timestamp, &log.id))
+    } else {
+        None
+    };
+    Ok((rows, next_cursor))
+}
+
 /// Get logs (backward compatible, calls get_logs_summary)
 pub fn get_logs(limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
     get_logs_summary(limit, 0)
@@ -152,22 +774,424 @@ pub fn get_stats() -> Result<crate::proxy::monitor::ProxyStats, String> {
         total_requests,
         success_count,
         error_count,
+        latency_by_model: Vec::new(),
+        dedup_hits: 0,
+        scrub_stats: Default::default(),
     })
 }
 
+/// Get p50/p95/p99/mean request duration per model over the last `limit_days` days (0 = all time)
+pub fn get_model_latency_stats(model_filter: Option<&str>, limit_days: u32) -> Result<Vec<ModelLatencyStats>, String> {
+    let conn = connect_db()?;
+    let since = if limit_days == 0 {
+        0
+    } else {
+        chrono::Utc::now().timestamp_millis() - (limit_days as i64) * DAY_MS
+    };
+    query_model_latency_stats(&conn, model_filter, since)
+}
+
+/// Nearest-rank percentiles computed with window functions (SQLite has no built-in percentile)
+fn query_model_latency_stats(conn: &Connection, model_filter: Option<&str>, since: i64) -> Result<Vec<ModelLatencyStats>, String> {
+    let mut stmt = conn.prepare(
+        "WITH ranked AS (
+            SELECT model, duration,
+                   ROW_NUMBER() OVER (PARTITION BY model ORDER BY duration) AS rn,
+                   COUNT(*) OVER (PARTITION BY model) AS cnt
+            FROM request_logs
+            WHERE model IS NOT NULL AND timestamp >= ?1 AND (?2 IS NULL OR model = ?2)
+        )
+        SELECT model,
+               MAX(cnt),
+               MIN(CASE WHEN rn >= (cnt * 50 + 99) / 100 THEN duration END),
+               MIN(CASE WHEN rn >= (cnt * 95 + 99) / 100 THEN duration END),
+               MIN(CASE WHEN rn >= (cnt * 99 + 99) / 100 THEN duration END),
+               AVG(duration)
+        FROM ranked
+        GROUP BY model
+        ORDER BY MAX(cnt) DESC"
+    ).map_err(|e| e.to_string())?;
+
+    let stats_iter = stmt.query_map(params![since, model_filter], |row| {
+        Ok(ModelLatencyStats {
+            model: row.get(0)?,
+            request_count: row.get(1)?,
+            p50_ms: row.get(2)?,
+            p95_ms: row.get(3)?,
+            p99_ms: row.get(4)?,
+            mean_ms: row.get(5)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut stats = Vec::new();
+    for s in stats_iter {
+        stats.push(s.map_err(|e| e.to_string())?);
+    }
+    Ok(stats)
+}
+
+/// Estimated cost of a model (or of all models when `model` is None)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelCost {
+    pub model: Option<String>,
+    pub request_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub estimated_cost_usd: f64,
+}
+
+/// Estimated cost since a point in time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CostSummary {
+    pub since_timestamp: i64,
+    pub total: ModelCost,
+    /// Per-model breakdown, highest cost first (empty unless requested)
+    pub by_model: Vec<ModelCost>,
+}
+
+/// Sum estimated costs of logs newer than `since_timestamp` (ms), optionally grouped by model
+pub fn get_cost_summary(since_timestamp: i64, by_model: bool) -> Result<CostSummary, String> {
+    let conn = connect_db()?;
+    query_cost_summary(&conn, since_timestamp, by_model)
+}
+
+fn map_model_cost(row: &rusqlite::Row) -> rusqlite::Result<ModelCost> {
+    Ok(ModelCost {
+        model: row.get(0)?,
+        request_count: row.get(1)?,
+        input_tokens: row.get(2)?,
+        output_tokens: row.get(3)?,
+        estimated_cost_usd: row.get(4)?,
+    })
+}
+
+fn query_cost_summary(conn: &Connection, since_timestamp: i64, by_model: bool) -> Result<CostSummary, String> {
+    let columns = "COUNT(*), COALESCE(SUM(input_tokens), 0), COALESCE(SUM(output_tokens), 0),
+                   COALESCE(SUM(estimated_cost_usd), 0.0)";
+
+    let total = conn.query_row(
+        &format!("SELECT NULL, {} FROM request_logs WHERE timestamp >= ?1", columns),
+        [since_timestamp],
+        map_model_cost,
+    ).map_err(|e| e.to_string())?;
+
+    let mut models = Vec::new();
+    if by_model {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT COALESCE(mapped_model, model, 'unknown') AS m, {}
+             FROM request_logs WHERE timestamp >= ?1
+             GROUP BY m
+             ORDER BY 5 DESC",
+            columns
+        )).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([since_timestamp], map_model_cost).map_err(|e| e.to_string())?;
+        for row in rows {
+            models.push(row.map_err(|e| e.to_string())?);
+        }
+    }
+
+    Ok(CostSummary {
+        since_timestamp,
+        total,
+        by_model: models,
+    })
+}
+
+/// Per-account request counters used for health scoring
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountRequestStats {
+    pub total: u64,
+    pub success: u64,
+    pub rate_limited: u64,
+    pub avg_latency_ms: Option<f64>,
+    pub refresh_failures: u64,
+}
+
+/// Record a token refresh attempt for an account (timestamp in ms, same as request_logs)
+pub fn record_token_refresh(account_email: &str, success: bool, error: Option<&str>) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "INSERT INTO token_refresh_events (timestamp, account_email, success, error) VALUES (?1, ?2, ?3, ?4)",
+        params![chrono::Utc::now().timestamp_millis(), account_email, success, error],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Request outcome and refresh failure counts for one account since `since_ms`
+pub fn get_account_request_stats(account_email: &str, since_ms: i64) -> Result<AccountRequestStats, String> {
+    let conn = connect_db()?;
+    query_account_request_stats(&conn, account_email, since_ms)
+}
+
+fn query_account_request_stats(conn: &Connection, account_email: &str, since_ms: i64) -> Result<AccountRequestStats, String> {
+    let (total, success, rate_limited, avg_latency_ms) = conn.query_row(
+        "SELECT COUNT(*),
+                COALESCE(SUM(CASE WHEN status >= 200 AND status < 400 THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN status = 429 THEN 1 ELSE 0 END), 0),
+                AVG(duration)
+         FROM request_logs
+         WHERE account_email = ?1 AND timestamp >= ?2",
+        params![account_email, since_ms],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    ).map_err(|e| e.to_string())?;
+
+    let refresh_failures = conn.query_row(
+        "SELECT COUNT(*) FROM token_refresh_events
+         WHERE account_email = ?1 AND timestamp >= ?2 AND success = 0",
+        params![account_email, since_ms],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())?;
+
+    Ok(AccountRequestStats {
+        total,
+        success,
+        rate_limited,
+        avg_latency_ms,
+        refresh_failures,
+    })
+}
+
+/// Per-account usage over a time window (accounts without logs are omitted)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountUsageStats {
+    pub account_email: String,
+    pub total_requests: u64,
+    pub error_count: u64,
+    pub avg_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<u64>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub estimated_cost_usd: f64,
+}
+
+/// Usage of every account with logs newer than `since_ms`
+pub fn get_account_usage_stats(since_ms: i64) -> Result<Vec<AccountUsageStats>, String> {
+    let conn = connect_db()?;
+    query_account_usage_stats(&conn, since_ms)
+}
+
+fn query_account_usage_stats(conn: &Connection, since_ms: i64) -> Result<Vec<AccountUsageStats>, String> {
+    let mut stmt = conn.prepare(
+        "WITH ranked AS (
+            SELECT account_email, status, duration, input_tokens, output_tokens, estimated_cost_usd,
+                   ROW_NUMBER() OVER (PARTITION BY account_email ORDER BY duration) AS rn,
+                   COUNT(*) OVER (PARTITION BY account_email) AS cnt
+            FROM request_logs
+            WHERE account_email IS NOT NULL AND timestamp >= ?1
+        )
+        SELECT account_email,
+               COUNT(*),
+               COALESCE(SUM(CASE WHEN status >= 400 THEN 1 ELSE 0 END), 0),
+               AVG(duration),
+               MIN(CASE WHEN rn >= (cnt * 95 + 99) / 100 THEN duration END),
+               COALESCE(SUM(input_tokens), 0),
+               COALESCE(SUM(output_tokens), 0),
+               COALESCE(SUM(estimated_cost_usd), 0.0)
+        FROM ranked
+        GROUP BY account_email"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map([since_ms], |row| {
+        Ok(AccountUsageStats {
+            account_email: row.get(0)?,
+            total_requests: row.get(1)?,
+            error_count: row.get(2)?,
+            avg_latency_ms: row.get(3)?,
+            p95_latency_ms: row.get(4)?,
+            input_tokens: row.get(5)?,
+            output_tokens: row.get(6)?,
+            estimated_cost_usd: row.get(7)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut stats = Vec::new();
+    for row in rows {
+        stats.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(stats)
+}
+
+/// Quota snapshots older than this are pruned on every write
+const QUOTA_HISTORY_RETENTION_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaSnapshot {
+    pub account_id: String,
+    pub model: String,
+    pub percentage: i32,
+    /// Unix ms at which the quota was fetched
+    pub recorded_at: i64,
+}
+
+/// Record per-model remaining quota of one account. `recorded_at` is the quota fetch time,
+/// so reloading an unchanged account file does not create duplicate snapshots.
+pub fn record_quota_snapshot(account_id: &str, models: &[(String, i32)], recorded_at: i64) -> Result<usize, String> {
+    let conn = connect_db()?;
+    insert_quota_snapshots(&conn, account_id, models, recorded_at)
+}
+
+fn insert_quota_snapshots(
+    conn: &Connection,
+    account_id: &str,
+    models: &[(String, i32)],
+    recorded_at: i64,
+) -> Result<usize, String> {
+    let mut inserted = 0;
+    for (model, percentage) in models {
+        inserted += conn.execute(
+            "INSERT OR IGNORE INTO quota_snapshots (account_id, model, percentage, recorded_at) VALUES (?1, ?2, ?3, ?4)",
+            params![account_id, model, percentage, recorded_at],
+        ).map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "DELETE FROM quota_snapshots WHERE account_id = ?1 AND recorded_at < ?2",
+        params![account_id, recorded_at - QUOTA_HISTORY_RETENTION_DAYS * DAY_MS],
+    ).map_err(|e| e.to_string())?;
+    Ok(inserted)
+}
+
+/// Quota snapshots of one account from the last `days` days, oldest first
+pub fn get_quota_history(account_id: &str, model: Option<&str>, days: u32) -> Result<Vec<QuotaSnapshot>, String> {
+    let conn = connect_db()?;
+    let since = chrono::Utc::now().timestamp_millis() - days as i64 * DAY_MS;
+    query_quota_history(&conn, account_id, model, since)
+}
+
+fn query_quota_history(
+    conn: &Connection,
+    account_id: &str,
+    model: Option<&str>,
+    since_ms: i64,
+) -> Result<Vec<QuotaSnapshot>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT account_id, model, percentage, recorded_at
+         FROM quota_snapshots
+         WHERE account_id = ?1 AND recorded_at >= ?2 AND (?3 IS NULL OR model = ?3)
+         ORDER BY recorded_at ASC, model ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![account_id, since_ms, model], |row| {
+        Ok(QuotaSnapshot {
+            account_id: row.get(0)?,
+            model: row.get(1)?,
+            percentage: row.get(2)?,
+            recorded_at: row.get(3)?,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut snapshots = Vec::new();
+    for row in rows {
+        snapshots.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(snapshots)
+}
+
 /// Get single log detail (with request_body and response_body)
 pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
     let conn = connect_db()?;
+    query_log_detail(&conn, "id", log_id)
+}
 
-    let mut stmt = conn.prepare(
+/// Get the log of a request by its X-Request-ID (latest one if the client reused the ID)
+pub fn get_log_by_request_id(request_id: &str) -> Result<ProxyRequestLog, String> {
+    let conn = connect_db()?;
+    query_log_detail(&conn, "request_id", request_id)
+}
+
+fn query_log_detail(conn: &Connection, column: &str, value: &str) -> Result<ProxyRequestLog, String> {
+    let mut stmt = conn.prepare(&format!(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 request_body, response_body, input_tokens, output_tokens, 
-                account_email, mapped_model
+                account_email, mapped_model, conversation_id, estimated_cost_usd, request_id
          FROM request_logs 
-         WHERE id = ?1"
+         WHERE {} = ?1
+         ORDER BY timestamp DESC
+         LIMIT 1",
+        column
+    )).map_err(|e| e.to_string())?;
+
+    stmt.query_row([value], |row| {
+        Ok(ProxyRequestLog {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            method: row.get(2)?,
+            url: row.get(3)?,
+            status: row.get(4)?,
+            duration: row.get(5)?,
+            model: row.get(6)?,
+            mapped_model: row.get(13).unwrap_or(None),
+            account_email: row.get(12).unwrap_or(None),
+            error: row.get(7)?,
+            request_body: row.get(8).unwrap_or(None),
+            response_body: row.get(9).unwrap_or(None),
+            input_tokens: row.get(10).unwrap_or(None),
+            output_tokens: row.get(11).unwrap_or(None),
+            conversation_id: row.get(14).unwrap_or(None),
+            estimated_cost_usd: row.get(15).unwrap_or(None),
+            request_id: row.get(16).unwrap_or(None),
+        })
+    }).map_err(|e| e.to_string())
+}
+
+/// All requests of one conversation in chronological order (without bodies)
+pub fn get_logs_by_conversation(conversation_id: &str) -> Result<Vec<ProxyRequestLog>, String> {
+    let conn = connect_db()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error,
+                input_tokens, output_tokens, account_email, mapped_model, conversation_id,
+                estimated_cost_usd
+         FROM request_logs
+         WHERE conversation_id = ?1
+         ORDER BY timestamp ASC"
+    ).map_err(|e| e.to_string())?;
+
+    let logs_iter = stmt.query_map([conversation_id], |row| {
+        Ok(ProxyRequestLog {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            method: row.get(2)?,
+            url: row.get(3)?,
+            status: row.get(4)?,
+            duration: row.get(5)?,
+            model: row.get(6)?,
+            mapped_model: row.get(11).unwrap_or(None),
+            account_email: row.get(10).unwrap_or(None),
+            error: row.get(7)?,
+            request_body: None,
+            response_body: None,
+            input_tokens: row.get(8).unwrap_or(None),
+            output_tokens: row.get(9).unwrap_or(None),
+            conversation_id: row.get(12).unwrap_or(None),
+            estimated_cost_usd: row.get(13).unwrap_or(None),
+            request_id: None,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut logs = Vec::new();
+    for log in logs_iter {
+        logs.push(log.map_err(|e| e.to_string())?);
+    }
+    Ok(logs)
+}
+
+/// All requests served by one account, including bodies (used by per-account data export)
+pub fn get_logs_by_account(account_email: &str) -> Result<Vec<ProxyRequestLog>, String> {
+    let conn = connect_db()?;
+    query_logs_by_account(&conn, account_email)
+}
+
+fn query_logs_by_account(conn: &Connection, account_email: &str) -> Result<Vec<ProxyRequestLog>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error,
+                request_body, response_body, input_tokens, output_tokens,
+                account_email, mapped_model, conversation_id, estimated_cost_usd, request_id
+         FROM request_logs
+         WHERE account_email = ?1
+         ORDER BY timestamp ASC"
     ).map_err(|e| e.to_string())?;
 
-    stmt.query_row([log_id], |row| {
+    let logs_iter = stmt.query_map([account_email], |row| {
         Ok(ProxyRequestLog {
             id: row.get(0)?,
             timestamp: row.get(1)?,
@@ -183,31 +1207,362 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
             response_body: row.get(9).unwrap_or(None),
             input_tokens: row.get(10).unwrap_or(None),
             output_tokens: row.get(11).unwrap_or(None),
+            conversation_id: row.get(14).unwrap_or(None),
+            estimated_cost_usd: row.get(15).unwrap_or(None),
+            request_id: row.get(16).unwrap_or(None),
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut logs = Vec::new();
+    for log in logs_iter {
+        logs.push(log.map_err(|e| e.to_string())?);
+    }
+    Ok(logs)
+}
+
+/// Delete every request log and token refresh event of one account.
+/// Returns (request logs deleted, refresh events deleted)
+pub fn delete_logs_by_account(account_email: &str) -> Result<(usize, usize), String> {
+    let conn = connect_db()?;
+    // Roll raw logs into aggregates before they are deleted
+    if let Err(e) = flush_aggregates(&conn) {
+        tracing::warn!("Failed to flush request aggregates: {}", e);
+    }
+    delete_account_rows(&conn, account_email)
+}
+
+fn delete_account_rows(conn: &Connection, account_email: &str) -> Result<(usize, usize), String> {
+    let logs = conn
+        .execute("DELETE FROM request_logs WHERE account_email = ?1", [account_email])
+        .map_err(|e| e.to_string())?;
+    let refresh_events = conn
+        .execute("DELETE FROM token_refresh_events WHERE account_email = ?1", [account_email])
+        .map_err(|e| e.to_string())?;
+    Ok((logs, refresh_events))
+}
+
+/// Delete every quota trend snapshot of one account. Returns the number of deleted rows.
+pub fn delete_quota_snapshots_by_account(account_id: &str) -> Result<usize, String> {
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM quota_snapshots WHERE account_id = ?1", [account_id])
+        .map_err(|e| e.to_string())
+}
+
+/// Rewrite the `*.jsonl.gz` archives under `archive_dir` without the lines of one account.
+/// Affected files are written to a temp file and renamed over the original.
+/// Returns the number of removed log lines.
+pub fn purge_account_from_archives(account_email: &str, archive_dir: &Path) -> Result<usize, String> {
+    use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+    use std::io::BufRead;
+
+    if !archive_dir.exists() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    let entries = std::fs::read_dir(archive_dir).map_err(|e| format!("Failed to read archive dir: {}", e))?;
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if !path.to_string_lossy().ends_with(".jsonl.gz") {
+            continue;
+        }
+
+        let file = std::fs::File::open(&path).map_err(|e| format!("Failed to open archive file: {}", e))?;
+        let mut kept = Vec::new();
+        let mut file_removed = 0;
+        for line in std::io::BufReader::new(MultiGzDecoder::new(file)).lines() {
+            let line = line.map_err(|e| format!("Failed to read archive {:?}: {}", path, e))?;
+            let owned_by_account = serde_json::from_str::<serde_json::Value>(&line)
+                .ok()
+                .and_then(|log| log.get("account_email").and_then(|e| e.as_str()).map(|e| e == account_email))
+                .unwrap_or(false);
+            if owned_by_account {
+                file_removed += 1;
+            } else {
+                kept.push(line);
+            }
+        }
+        if file_removed == 0 {
+            continue;
+        }
+
+        let tmp_path = path.with_extension("gz.tmp");
+        let tmp = std::fs::File::create(&tmp_path).map_err(|e| format!("Failed to create archive file: {}", e))?;
+        let mut encoder = GzEncoder::new(std::io::BufWriter::new(tmp), Compression::default());
+        for line in &kept {
+            writeln!(encoder, "{}", line).map_err(|e| e.to_string())?;
+        }
+        let mut writer = encoder.finish().map_err(|e| format!("Failed to finish archive: {}", e))?;
+        writer.flush().map_err(|e| e.to_string())?;
+        drop(writer);
+        std::fs::rename(&tmp_path, &path).map_err(|e| format!("Failed to replace archive file: {}", e))?;
+        removed += file_removed;
+    }
+    Ok(removed)
+}
+
+/// Delete logs according to a per-category retention policy (success / error / large body).
+/// Categories with 0 days are skipped. Returns the number of deleted rows.
+pub fn cleanup_with_policy(policy: &crate::models::LogRetentionPolicy) -> Result<usize, String> {
+    let conn = connect_db()?;
+
+    // Roll raw logs into aggregates before they are deleted
+    if let Err(e) = flush_aggregates(&conn) {
+        tracing::warn!("Failed to flush request aggregates: {}", e);
+    }
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let deleted = delete_by_policy(&conn, policy, now_ms)?;
+
+    let max_days = policy.success_days.max(policy.error_days).max(policy.large_body_days);
+    if max_days > 0 {
+        conn.execute(
+            "DELETE FROM token_refresh_events WHERE timestamp < ?1",
+            [now_ms - max_days as i64 * DAY_MS],
+        ).map_err(|e| e.to_string())?;
+    }
+
+    if deleted > 0 {
+        vacuum(&conn)?;
+        drop(conn);
+        if let Err(e) = wal_checkpoint() {
+            tracing::warn!("WAL checkpoint after cleanup failed: {}", e);
+        }
+    }
+    Ok(deleted)
+}
+
+fn delete_by_policy(conn: &Connection, policy: &crate::models::LogRetentionPolicy, now_ms: i64) -> Result<usize, String> {
+    let rules: [(u32, &str); 3] = [
+        (policy.success_days, "status >= 200 AND status < 400"),
+        (policy.error_days, "(status >= 400 OR status < 200)"),
+        (
+            policy.large_body_days,
+            "COALESCE(LENGTH(request_body), 0) + COALESCE(LENGTH(response_body), 0) > ?2",
+        ),
+    ];
+
+    let mut deleted = 0;
+    for (days, condition) in rules {
+        if days == 0 {
+            continue;
+        }
+        let cutoff = now_ms - days as i64 * DAY_MS;
+        let sql = format!("DELETE FROM request_logs WHERE timestamp < ?1 AND {}", condition);
+        deleted += if condition.contains("?2") {
+            conn.execute(&sql, params![cutoff, LARGE_BODY_BYTES])
+        } else {
+            conn.execute(&sql, params![cutoff])
+        }
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(deleted)
+}
+
+/// Archive logs older than `days` into `proxy_logs_{YYYY-MM}.jsonl.gz` files (one per month)
+/// under `archive_dir`, then delete the archived rows and VACUUM.
+/// Existing archives for the same month are appended to as additional gzip members.
+pub fn archive_logs_older_than(days: i64, archive_dir: &Path) -> Result<ArchiveStats, String> {
+    let conn = connect_db()?;
+
+    // Roll raw logs into aggregates before they are deleted
+    if let Err(e) = flush_aggregates(&conn) {
+        tracing::warn!("Failed to flush request aggregates: {}", e);
+    }
+
+    let cutoff_ms = chrono::Utc::now().timestamp_millis() - days * DAY_MS;
+    let stats = archive_logs_before(&conn, cutoff_ms, archive_dir)?;
+    if stats.rows_archived == 0 {
+        return Ok(stats);
+    }
+
+    vacuum(&conn)?;
+    drop(conn);
+
+    if let Err(e) = wal_checkpoint() {
+        tracing::warn!("WAL checkpoint after archive failed: {}", e);
+    }
+    Ok(stats)
+}
+
+fn archive_logs_before(conn: &Connection, cutoff_ms: i64, archive_dir: &Path) -> Result<ArchiveStats, String> {
+    use flate2::{write::GzEncoder, Compression};
+    use std::collections::HashMap;
+
+    std::fs::create_dir_all(archive_dir).map_err(|e| format!("Failed to create archive dir: {}", e))?;
+
+    let mut stats = ArchiveStats::default();
+    // month -> (encoder, file size before this run)
+    let mut writers: HashMap<String, (GzEncoder<std::io::BufWriter<std::fs::File>>, u64, PathBuf)> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error,
+                request_body, response_body, input_tokens, output_tokens,
+                account_email, mapped_model, conversation_id, estimated_cost_usd, request_id
+         FROM request_logs
+         WHERE timestamp < ?1 AND (timestamp, id) > (?2, ?3)
+         ORDER BY timestamp ASC, id ASC
+         LIMIT ?4"
+    ).map_err(|e| e.to_string())?;
+
+    let mut last_key: (i64, String) = (i64::MIN, String::new());
+    loop {
+        let batch = stmt.query_map(params![cutoff_ms, last_key.0, last_key.1, ARCHIVE_BATCH_SIZE], |row| {
+            Ok(ProxyRequestLog {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                method: row.get(2)?,
+                url: row.get(3)?,
+                status: row.get(4)?,
+                duration: row.get(5)?,
+                model: row.get(6)?,
+                mapped_model: row.get(13).unwrap_or(None),
+                account_email: row.get(12).unwrap_or(None),
+                error: row.get(7)?,
+                request_body: row.get(8).unwrap_or(None),
+                response_body: row.get(9).unwrap_or(None),
+                input_tokens: row.get(10).unwrap_or(None),
+                output_tokens: row.get(11).unwrap_or(None),
+                conversation_id: row.get(14).unwrap_or(None),
+                estimated_cost_usd: row.get(15).unwrap_or(None),
+                request_id: row.get(16).unwrap_or(None),
+            })
+        }).map_err(|e| e.to_string())?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(|e| e.to_string())?;
+
+        let Some(last) = batch.last() else {
+            break;
+        };
+        last_key = (last.timestamp, last.id.clone());
+
+        for log in &batch {
+            let month = chrono::DateTime::from_timestamp_millis(log.timestamp)
+                .map(|dt| dt.format("%Y-%m").to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            if !writers.contains_key(&month) {
+                let path = archive_dir.join(format!("proxy_logs_{}.jsonl.gz", month));
+                let existing_len = std::fs::metadata(&path).map(|m| m.len()).ok();
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .map_err(|e| format!("Failed to open archive file: {}", e))?;
+                if existing_len.is_none() {
+                    stats.files_created.push(path.clone());
+                }
+                let encoder = GzEncoder::new(std::io::BufWriter::new(file), Compression::default());
+                writers.insert(month.clone(), (encoder, existing_len.unwrap_or(0), path));
+            }
+            let (encoder, _, _) = writers.get_mut(&month).expect("writer inserted above");
+            serde_json::to_writer(&mut *encoder, log).map_err(|e| e.to_string())?;
+            writeln!(encoder).map_err(|e| e.to_string())?;
+            stats.rows_archived += 1;
+        }
+    }
+    drop(stmt);
+
+    // Only delete rows once every archive file has been flushed successfully
+    for (_, (encoder, initial_len, path)) in writers {
+        let mut writer = encoder.finish().map_err(|e| format!("Failed to finish archive: {}", e))?;
+        writer.flush().map_err(|e| e.to_string())?;
+        drop(writer);
+        let len = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(initial_len);
+        stats.bytes_written += len.saturating_sub(initial_len);
+    }
+
+    if stats.rows_archived > 0 {
+        conn.execute(
+            "DELETE FROM request_logs WHERE timestamp < ?1 AND (timestamp, id) <= (?2, ?3)",
+            params![cutoff_ms, last_key.0, last_key.1],
+        ).map_err(|e| e.to_string())?;
+    }
+    Ok(stats)
+}
+
+fn checkpoint_conn(conn: &Connection) -> Result<WalCheckpointStats, String> {
+    let run = |mode: &str| {
+        conn.query_row(&format!("PRAGMA wal_checkpoint({})", mode), [], |row| {
+            Ok(WalCheckpointStats {
+                busy: row.get::<_, i64>(0)? != 0,
+                wal_pages: row.get(1)?,
+                checkpointed_pages: row.get(2)?,
+            })
         })
-    }).map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())
+    };
+    // A successful TRUNCATE reports 0/0 because the WAL is already empty, so take the
+    // page counts from a PASSIVE pass first and the busy flag from the TRUNCATE pass
+    let passive = run("PASSIVE")?;
+    let truncate = run("TRUNCATE")?;
+    Ok(WalCheckpointStats {
+        busy: truncate.busy,
+        ..passive
+    })
 }
 
-/// Cleanup old logs (keep last N days)
-pub fn cleanup_old_logs(days: i64) -> Result<usize, String> {
-    let conn = connect_db()?;
-    
-    let cutoff_timestamp = chrono::Utc::now().timestamp() - (days * 24 * 3600);
-    
-    let deleted = conn.execute(
-        "DELETE FROM request_logs WHERE timestamp < ?1",
-        [cutoff_timestamp],
-    ).map_err(|e| e.to_string())?;
-    
-    // Execute VACUUM to reclaim disk space
-    conn.execute("VACUUM", []).map_err(|e| e.to_string())?;
-    
-    Ok(deleted)
+/// Move WAL contents into the database file and truncate the WAL
+pub fn wal_checkpoint() -> Result<WalCheckpointStats, String> {
+    let conn = connect_db_with_busy_timeout(WAL_CHECKPOINT_BUSY_TIMEOUT_MS)?;
+    let stats = checkpoint_conn(&conn)?;
+    tracing::debug!(
+        "WAL checkpoint: busy={}, wal_pages={}, checkpointed_pages={}",
+        stats.busy,
+        stats.wal_pages,
+        stats.checkpointed_pages
+    );
+    Ok(stats)
+}
+
+/// Start the periodic WAL checkpoint task (once per process)
+pub fn schedule_wal_checkpoint() {
+    WAL_CHECKPOINT_SCHEDULED.call_once(|| {
+        tokio::spawn(async {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(WAL_CHECKPOINT_INTERVAL_SECS));
+            interval.tick().await; // skip the immediate first tick
+            loop {
+                interval.tick().await;
+                match tokio::task::spawn_blocking(wal_checkpoint).await {
+                    Ok(Err(e)) => tracing::warn!("Scheduled WAL checkpoint failed: {}", e),
+                    Err(e) => tracing::warn!("Scheduled WAL checkpoint task panicked: {}", e),
+                    Ok(Ok(_)) => {}
+                }
+            }
+        });
+    });
+}
+
+/// Start the periodic `max_db_size_mb` check (once per process), keeping VACUUM off the insert path
+pub fn schedule_db_size_check() {
+    DB_SIZE_CHECK_SCHEDULED.call_once(|| {
+        tokio::spawn(async {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(DB_SIZE_CHECK_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                let Some(max_mb) = crate::modules::config::load_app_config()
+                    .ok()
+                    .and_then(|c| c.proxy.max_db_size_mb)
+                else {
+                    continue;
+                };
+                match tokio::task::spawn_blocking(move || enforce_db_size_limit(max_mb)).await {
+                    Ok(Err(e)) => tracing::warn!("Failed to enforce proxy DB size limit: {}", e),
+                    Err(e) => tracing::warn!("Proxy DB size check task panicked: {}", e),
+                    Ok(Ok(_)) => {}
+                }
+            }
+        });
+    });
 }
 
 /// Limit maximum log count (keep newest N records)
 #[allow(dead_code)]
 pub fn limit_max_logs(max_count: usize) -> Result<usize, String> {
     let conn = connect_db()?;
+
+    // Roll raw logs into aggregates before they are deleted
+    if let Err(e) = flush_aggregates(&conn) {
+        tracing::warn!("Failed to flush request aggregates: {}", e);
+    }
     
     let deleted = conn.execute(
         "DELETE FROM request_logs WHERE id NOT IN (
@@ -216,17 +1571,68 @@ pub fn limit_max_logs(max_count: usize) -> Result<usize, String> {
         [max_count],
     ).map_err(|e| e.to_string())?;
     
-    conn.execute("VACUUM", []).map_err(|e| e.to_string())?;
+    vacuum(&conn)?;
     
     Ok(deleted)
 }
 
 pub fn clear_logs() -> Result<(), String> {
     let conn = connect_db()?;
+    // Roll raw logs into aggregates before they are deleted
+    if let Err(e) = flush_aggregates(&conn) {
+        tracing::warn!("Failed to flush request aggregates: {}", e);
+    }
     conn.execute("DELETE FROM request_logs", []).map_err(|e| e.to_string())?;
     Ok(())
 }
 
+fn backup_conn(src: &Connection, dest: &mut Connection) -> Result<u64, String> {
+    let backup = rusqlite::backup::Backup::new(src, dest).map_err(|e| e.to_string())?;
+    backup
+        .run_to_completion(100, std::time::Duration::from_millis(10), None)
+        .map_err(|e| e.to_string())?;
+    Ok(backup.progress().pagecount.max(0) as u64)
+}
+
+/// Copy the live database to `dest_path` with SQLite's online backup API.
+/// Returns the number of pages copied.
+pub fn backup_proxy_db(dest_path: &Path) -> Result<u64, String> {
+    let src = connect_db()?;
+    let mut dest = Connection::open(dest_path).map_err(|e| e.to_string())?;
+    backup_conn(&src, &mut dest)
+}
+
+/// Open `path` read-only and make sure it is a SQLite database containing request logs
+fn open_valid_backup(path: &Path) -> Result<Connection, String> {
+    let invalid = |reason: String| format!("{} is not a valid proxy log database: {}", path.display(), reason);
+    if !path.is_file() {
+        return Err(invalid("file not found".to_string()));
+    }
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| invalid(e.to_string()))?;
+    // Opening succeeds lazily for any file; the first read validates the header
+    let has_logs: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'request_logs'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| invalid(e.to_string()))?;
+    if !has_logs {
+        return Err(invalid("missing request_logs table".to_string()));
+    }
+    Ok(conn)
+}
+
+/// Replace the active database with the contents of `src_path` (the proxy must be stopped).
+/// Older backups are migrated to the current schema afterwards.
+pub fn restore_proxy_db(src_path: &Path) -> Result<(), String> {
+    let src = open_valid_backup(src_path)?;
+    let mut dest = connect_db()?;
+    backup_conn(&src, &mut dest)?;
+    run_migrations(&dest)
+}
+
 /// Get total count of logs in database
 pub fn get_logs_count() -> Result<u64, String> {
     let conn = connect_db()?;
@@ -240,10 +1646,112 @@ pub fn get_logs_count() -> Result<u64, String> {
     Ok(count)
 }
 
+/// Prefix that switches log search to full-text search over request/response bodies
+pub const FTS_FILTER_PREFIX: &str = "fts:";
+
+/// Turn free-form user input into a safe FTS5 query: each whitespace-separated term
+/// becomes a quoted phrase (implicitly AND-ed), so operators/column filters can't be injected.
+fn sanitize_fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| term.replace('"', ""))
+        .filter(|term| !term.is_empty())
+        .map(|term| format!("\"{}\"", term))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Full-text search over request_body and response_body
+/// errors_only: if true, only return logs with status < 200 or >= 400
+pub fn get_logs_fts(query: &str, errors_only: bool, limit: usize, offset: usize) -> Result<Vec<ProxyRequestLog>, String> {
+    let conn = connect_db()?;
+    query_logs_fts(&conn, query, errors_only, limit, offset)
+}
+
+fn query_logs_fts(
+    conn: &Connection,
+    query: &str,
+    errors_only: bool,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<ProxyRequestLog>, String> {
+    let fts_query = sanitize_fts_query(query);
+    if fts_query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT r.id, r.timestamp, r.method, r.url, r.status, r.duration, r.model, r.error,
+                NULL as request_body, NULL as response_body,
+                r.input_tokens, r.output_tokens, r.account_email, r.mapped_model
+         FROM request_logs_fts f
+         JOIN request_logs r ON r.rowid = f.rowid
+         WHERE request_logs_fts MATCH ?1
+           AND (?4 = 0 OR r.status < 200 OR r.status >= 400)
+         ORDER BY r.timestamp DESC
+         LIMIT ?2 OFFSET ?3"
+    ).map_err(|e| e.to_string())?;
+
+    let logs_iter = stmt.query_map(params![fts_query, limit, offset, errors_only], |row| {
+        Ok(ProxyRequestLog {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            method: row.get(2)?,
+            url: row.get(3)?,
+            status: row.get(4)?,
+            duration: row.get(5)?,
+            model: row.get(6)?,
+            mapped_model: row.get(13).unwrap_or(None),
+            account_email: row.get(12).unwrap_or(None),
+            error: row.get(7)?,
+            request_body: None,
+            response_body: None,
+            input_tokens: row.get(10).unwrap_or(None),
+            output_tokens: row.get(11).unwrap_or(None),
+            conversation_id: None,
+            estimated_cost_usd: None,
+            request_id: None,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    let mut logs = Vec::new();
+    for log in logs_iter {
+        logs.push(log.map_err(|e| e.to_string())?);
+    }
+    Ok(logs)
+}
+
+/// Count logs matching a full-text query
+pub fn get_logs_fts_count(query: &str, errors_only: bool) -> Result<u64, String> {
+    let conn = connect_db()?;
+    count_logs_fts(&conn, query, errors_only)
+}
+
+fn count_logs_fts(conn: &Connection, query: &str, errors_only: bool) -> Result<u64, String> {
+    let fts_query = sanitize_fts_query(query);
+    if fts_query.is_empty() {
+        return Ok(0);
+    }
+
+    conn.query_row(
+        "SELECT COUNT(*)
+         FROM request_logs_fts f
+         JOIN request_logs r ON r.rowid = f.rowid
+         WHERE request_logs_fts MATCH ?1
+           AND (?2 = 0 OR r.status < 200 OR r.status >= 400)",
+        params![fts_query, errors_only],
+        |row| row.get(0),
+    ).map_err(|e| e.to_string())
+}
+
 /// Get count of logs matching search filter
-/// filter: search text to match in url, method, model, or status
+/// filter: search text to match in url, method, model, or status (`fts:` prefix searches bodies)
 /// errors_only: if true, only count logs with status < 200 or >= 400
 pub fn get_logs_count_filtered(filter: &str, errors_only: bool) -> Result<u64, String> {
+    if let Some(query) = filter.strip_prefix(FTS_FILTER_PREFIX) {
+        return get_logs_fts_count(query, errors_only);
+    }
+
     let conn = connect_db()?;
     
     let filter_pattern = format!("%{}%", filter);
@@ -269,14 +1777,27 @@ pub fn get_logs_count_filtered(filter: &str, errors_only: bool) -> Result<u64, S
 }
 
 /// Get logs with search filter and pagination
-/// filter: search text to match in url, method, model, or status
+/// filter: search text to match in url, method, model, or status (`fts:` prefix searches bodies)
 /// errors_only: if true, only return logs with status < 200 or >= 400
 pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset: usize) -> Result<Vec<ProxyRequestLog>, String> {
+    if let Some(query) = filter.strip_prefix(FTS_FILTER_PREFIX) {
+        return get_logs_fts(query, errors_only, limit, offset);
+    }
+
     let conn = connect_db()?;
 
     let filter_pattern = format!("%{}%", filter);
     
-    let sql = if errors_only {
+    let sql = if errors_only && filter.is_empty() {
+        // Walk the compound index in timestamp order and check status from the index entries
+        "SELECT id, timestamp, method, url, status, duration, model, error, 
+                NULL as request_body, NULL as response_body,
+                input_tokens, output_tokens, account_email, mapped_model
+         FROM request_logs INDEXED BY idx_ts_model_status
+         WHERE (status < 200 OR status >= 400)
+         ORDER BY timestamp DESC 
+         LIMIT ?1 OFFSET ?2"
+    } else if errors_only {
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
                 input_tokens, output_tokens, account_email, mapped_model
@@ -319,6 +1840,9 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 response_body: None,
                 input_tokens: row.get(10).unwrap_or(None),
                 output_tokens: row.get(11).unwrap_or(None),
+                conversation_id: None,
+                estimated_cost_usd: None,
+                request_id: None,
             })
         }).map_err(|e| e.to_string())?;
         logs_iter.filter_map(|r| r.ok()).collect()
@@ -340,6 +1864,9 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 response_body: None,
                 input_tokens: row.get(10).unwrap_or(None),
                 output_tokens: row.get(11).unwrap_or(None),
+                conversation_id: None,
+                estimated_cost_usd: None,
+                request_id: None,
             })
         }).map_err(|e| e.to_string())?;
         logs_iter.filter_map(|r| r.ok()).collect()
@@ -361,6 +1888,9 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 response_body: None,
                 input_tokens: row.get(10).unwrap_or(None),
                 output_tokens: row.get(11).unwrap_or(None),
+                conversation_id: None,
+                estimated_cost_usd: None,
+                request_id: None,
             })
         }).map_err(|e| e.to_string())?;
         logs_iter.filter_map(|r| r.ok()).collect()
@@ -369,6 +1899,47 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
     Ok(logs)
 }
 
+/// Get the most recent failed requests for a model (bodies omitted)
+pub fn get_errors_by_model(model: &str, limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
+    let conn = connect_db()?;
+    query_errors_by_model(&conn, model, limit)
+}
+
+fn query_errors_by_model(conn: &Connection, model: &str, limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, timestamp, method, url, status, duration, model, error,
+                input_tokens, output_tokens, account_email, mapped_model
+         FROM request_logs INDEXED BY idx_ts_model_status
+         WHERE model = ?1 AND (status < 200 OR status >= 400)
+         ORDER BY timestamp DESC
+         LIMIT ?2"
+    ).map_err(|e| e.to_string())?;
+
+    let logs_iter = stmt.query_map(params![model, limit], |row| {
+        Ok(ProxyRequestLog {
+            id: row.get(0)?,
+            timestamp: row.get(1)?,
+            method: row.get(2)?,
+            url: row.get(3)?,
+            status: row.get(4)?,
+            duration: row.get(5)?,
+            model: row.get(6)?,
+            mapped_model: row.get(11).unwrap_or(None),
+            account_email: row.get(10).unwrap_or(None),
+            error: row.get(7)?,
+            request_body: None,
+            response_body: None,
+            input_tokens: row.get(8).unwrap_or(None),
+            output_tokens: row.get(9).unwrap_or(None),
+            conversation_id: None,
+            estimated_cost_usd: None,
+            request_id: None,
+        })
+    }).map_err(|e| e.to_string())?;
+
+    Ok(logs_iter.filter_map(|r| r.ok()).collect())
+}
+
 /// Get all logs with full details for export
 pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
     let conn = connect_db()?;
@@ -397,6 +1968,9 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
             response_body: row.get(9).unwrap_or(None),
             input_tokens: row.get(10).unwrap_or(None),
             output_tokens: row.get(11).unwrap_or(None),
+            conversation_id: None,
+            estimated_cost_usd: None,
+            request_id: None,
         })
     }).map_err(|e| e.to_string())?;
 
@@ -448,6 +2022,9 @@ pub fn get_logs_by_ids(ids: &[String]) -> Result<Vec<ProxyRequestLog>, String> {
             response_body: row.get(9).unwrap_or(None),
             input_tokens: row.get(10).unwrap_or(None),
             output_tokens: row.get(11).unwrap_or(None),
+            conversation_id: None,
+            estimated_cost_usd: None,
+            request_id: None,
         })
     }).map_err(|e| e.to_string())?;
 
@@ -457,3 +2034,714 @@ pub fn get_logs_by_ids(ids: &[String]) -> Result<Vec<ProxyRequestLog>, String> {
     }
     Ok(logs)
 }
+
+/// Build the WHERE clause shared by the streaming exporters (filter is bound as ?1)
+fn export_where_clause(filter: &str, errors_only: bool) -> String {
+    let mut conditions = Vec::new();
+    if !filter.is_empty() {
+        conditions.push("(url LIKE ?1 OR method LIKE ?1 OR model LIKE ?1 OR CAST(status AS TEXT) LIKE ?1)");
+    }
+    if errors_only {
+        conditions.push("(status < 200 OR status >= 400)");
+    }
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    }
+}
+
+/// Validate the requested column set against `EXPORT_COLUMNS` (guards the SQL against injection)
+fn validate_export_columns(columns: &[&str]) -> Result<(), String> {
+    if columns.is_empty() {
+        return Err("No columns selected for export".to_string());
+    }
+    if let Some(bad) = columns.iter().find(|c| !EXPORT_COLUMNS.contains(c)) {
+        return Err(format!("Unknown export column: {}", bad));
+    }
+    Ok(())
+}
+
+/// Stream matching rows to `on_row`, one row at a time, returning the number of rows visited
+fn stream_export_rows<F>(columns: &[&str], filter: &str, errors_only: bool, mut on_row: F) -> Result<usize, String>
+where
+    F: FnMut(&[rusqlite::types::Value]) -> Result<(), String>,
+{
+    validate_export_columns(columns)?;
+    let conn = connect_db()?;
+
+    let sql = format!(
+        "SELECT {} FROM request_logs {} ORDER BY timestamp DESC",
+        columns.join(", "),
+        export_where_clause(filter, errors_only)
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+
+    let filter_pattern = format!("%{}%", filter);
+    let mut rows = if filter.is_empty() {
+        stmt.query([])
+    } else {
+        stmt.query([&filter_pattern])
+    }.map_err(|e| e.to_string())?;
+
+    let mut count = 0;
+    let mut values = Vec::with_capacity(columns.len());
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        values.clear();
+        for i in 0..columns.len() {
+            values.push(row.get::<_, rusqlite::types::Value>(i).map_err(|e| e.to_string())?);
+        }
+        on_row(&values)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn sql_value_to_string(value: &rusqlite::types::Value) -> String {
+    use rusqlite::types::Value;
+    match value {
+        Value::Null => String::new(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(t) => t.clone(),
+        Value::Blob(b) => String::from_utf8_lossy(b).into_owned(),
+    }
+}
+
+fn sql_value_to_json(value: &rusqlite::types::Value) -> serde_json::Value {
+    use rusqlite::types::Value;
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => serde_json::Value::from(*i),
+        Value::Real(f) => serde_json::Value::from(*f),
+        Value::Text(t) => serde_json::Value::String(t.clone()),
+        Value::Blob(b) => serde_json::Value::String(String::from_utf8_lossy(b).into_owned()),
+    }
+}
+
+/// Export logs as CSV, streaming rows directly to disk. Returns the number of rows written.
+pub fn export_logs_csv(path: &Path, filter: &str, errors_only: bool, columns: &[&str]) -> Result<usize, String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut writer = std::io::BufWriter::new(file);
+
+    writeln!(writer, "{}", columns.join(",")).map_err(|e| e.to_string())?;
+    let count = stream_export_rows(columns, filter, errors_only, |values| {
+        let line: Vec<String> = values.iter().map(|v| csv_escape(&sql_value_to_string(v))).collect();
+        writeln!(writer, "{}", line.join(",")).map_err(|e| e.to_string())
+    })?;
+
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// Export logs as JSON Lines, streaming rows directly to disk. Returns the number of rows written.
+pub fn export_logs_jsonl(path: &Path, filter: &str, errors_only: bool, columns: &[&str]) -> Result<usize, String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut writer = std::io::BufWriter::new(file);
+
+    let count = stream_export_rows(columns, filter, errors_only, |values| {
+        let obj: serde_json::Map<String, serde_json::Value> = columns
+            .iter()
+            .zip(values)
+            .map(|(col, v)| (col.to_string(), sql_value_to_json(v)))
+            .collect();
+        serde_json::to_writer(&mut writer, &obj).map_err(|e| e.to_string())?;
+        writeln!(writer).map_err(|e| e.to_string())
+    })?;
+
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_escape("line\nbreak"), "\"line\nbreak\"");
+    }
+
+    #[test]
+    fn test_backup_and_validate() {
+        let dir = std::env::temp_dir().join(format!("proxy-db-backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let src = Connection::open_in_memory().unwrap();
+        src.execute("CREATE TABLE request_logs (id TEXT, timestamp INTEGER)", []).unwrap();
+        src.execute("INSERT INTO request_logs (id, timestamp) VALUES ('a', 1)", []).unwrap();
+
+        let backup_path = dir.join("backup.db");
+        let mut dest = Connection::open(&backup_path).unwrap();
+        assert!(backup_conn(&src, &mut dest).unwrap() > 0);
+        drop(dest);
+
+        let restored = open_valid_backup(&backup_path).unwrap();
+        let count: u64 = restored.query_row("SELECT COUNT(*) FROM request_logs", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+
+        let garbage = dir.join("garbage.db");
+        std::fs::write(&garbage, b"definitely not sqlite, just some plain text padding").unwrap();
+        assert!(open_valid_backup(&garbage).is_err());
+        assert!(open_valid_backup(&dir.join("missing.db")).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_keyset_pagination() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE request_logs (id TEXT, timestamp INTEGER, method TEXT, url TEXT, status INTEGER,
+             duration INTEGER, model TEXT, error TEXT, input_tokens INTEGER, output_tokens INTEGER,
+             account_email TEXT, mapped_model TEXT)",
+            [],
+        ).unwrap();
+        // Two rows share a timestamp to exercise the id tie-breaker
+        for (id, ts) in [("a", 100), ("b", 200), ("c", 200), ("d", 300), ("e", 400)] {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, method, url, status, duration)
+                 VALUES (?1, ?2, 'POST', '/v1/messages', 200, 10)",
+                params![id, ts],
+            ).unwrap();
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let after = cursor.as_deref().map(|c| parse_cursor(c).unwrap());
+            let (page, next) = query_logs_page_after(
+                &conn,
+                after.as_ref().map(|(_, id)| id.as_str()),
+                after.as_ref().map(|(ts, _)| *ts),
+                2,
+            ).unwrap();
+            seen.extend(page.into_iter().map(|log| log.id));
+            match next {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(seen, vec!["e", "d", "c", "b", "a"]);
+
+        assert_eq!(parse_cursor(&encode_cursor(42, "id:with:colons")).unwrap(), (42, "id:with:colons".to_string()));
+        assert!(parse_cursor("not a cursor!").is_err());
+    }
+
+    #[test]
+    fn test_archive_logs_before() {
+        use std::io::BufRead;
+
+        let dir = std::env::temp_dir().join(format!("proxy-db-archive-{}", uuid::Uuid::new_v4()));
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE request_logs (id TEXT, timestamp INTEGER, method TEXT, url TEXT, status INTEGER,
+             duration INTEGER, model TEXT, error TEXT, request_body TEXT, response_body TEXT,
+             input_tokens INTEGER, output_tokens INTEGER, account_email TEXT, mapped_model TEXT,
+             conversation_id TEXT, estimated_cost_usd REAL, request_id TEXT)",
+            [],
+        ).unwrap();
+        // 2024-01-15, 2024-01-20, 2024-02-10, 2024-03-01 (after cutoff)
+        let rows = [("a", 1705276800000i64), ("b", 1705708800000), ("c", 1707523200000), ("keep", 1709251200000)];
+        for (id, ts) in rows {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, method, url, status, duration, request_body,
+                                           conversation_id, estimated_cost_usd, request_id)
+                 VALUES (?1, ?2, 'POST', '/v1/messages', 200, 10, '{}', 'conv-1', 0.25, 'req-' || ?1)",
+                params![id, ts],
+            ).unwrap();
+        }
+
+        let cutoff = 1709251200000;
+        let stats = archive_logs_before(&conn, cutoff, &dir).unwrap();
+        assert_eq!(stats.rows_archived, 3);
+        assert_eq!(stats.files_created.len(), 2);
+        assert!(stats.bytes_written > 0);
+
+        let january = dir.join("proxy_logs_2024-01.jsonl.gz");
+        let reader = std::io::BufReader::new(flate2::read::MultiGzDecoder::new(std::fs::File::open(&january).unwrap()));
+        let logs: Vec<ProxyRequestLog> = reader
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect();
+        let ids: Vec<&str> = logs.iter().map(|log| log.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert_eq!(logs[0].conversation_id.as_deref(), Some("conv-1"));
+        assert_eq!(logs[0].estimated_cost_usd, Some(0.25));
+        assert_eq!(logs[0].request_id.as_deref(), Some("req-a"));
+
+        let remaining: Vec<String> = conn
+            .prepare("SELECT id FROM request_logs").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(remaining, vec!["keep"]);
+
+        // Nothing left to archive; existing files are untouched
+        let stats = archive_logs_before(&conn, cutoff, &dir).unwrap();
+        assert_eq!(stats.rows_archived, 0);
+        assert!(stats.files_created.is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_delete_by_policy() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE request_logs (id TEXT, timestamp INTEGER, status INTEGER, request_body TEXT, response_body TEXT)",
+            [],
+        ).unwrap();
+        let now = 100 * DAY_MS;
+        let big = "x".repeat(LARGE_BODY_BYTES as usize);
+        let rows: [(&str, i64, i64, Option<&str>); 6] = [
+            ("ok_old", 20, 200, None),
+            ("ok_new", 5, 200, None),
+            ("err_old", 20, 500, None),
+            ("err_older", 100, 429, None),
+            ("big_old", 5, 200, Some(big.as_str())),
+            ("big_new", 1, 500, Some(big.as_str())),
+        ];
+        for (id, age_days, status, body) in rows {
+            conn.execute(
+                "INSERT INTO request_logs VALUES (?1, ?2, ?3, ?4, NULL)",
+                params![id, now - age_days * DAY_MS, status, body],
+            ).unwrap();
+        }
+
+        let policy = crate::models::LogRetentionPolicy {
+            success_days: 10,
+            error_days: 60,
+            large_body_days: 3,
+        };
+        assert_eq!(delete_by_policy(&conn, &policy, now).unwrap(), 3);
+        let mut remaining: Vec<String> = conn
+            .prepare("SELECT id FROM request_logs").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        remaining.sort();
+        assert_eq!(remaining, vec!["big_new", "err_old", "ok_new"]);
+
+        // 0 days disables the category
+        let keep_errors = crate::models::LogRetentionPolicy {
+            success_days: 1,
+            error_days: 0,
+            large_body_days: 0,
+        };
+        assert_eq!(delete_by_policy(&conn, &keep_errors, now).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_cost_summary() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE request_logs (id TEXT, timestamp INTEGER, model TEXT, mapped_model TEXT,
+             input_tokens INTEGER, output_tokens INTEGER, estimated_cost_usd REAL)",
+            [],
+        ).unwrap();
+        let rows: [(&str, i64, &str, i64, i64, Option<f64>); 4] = [
+            ("a", 100, "claude-sonnet-4-5", 1000, 1000, Some(0.018)),
+            ("b", 200, "claude-sonnet-4-5", 1000, 0, Some(0.003)),
+            ("c", 300, "gemini-3-flash", 2000, 1000, Some(0.004)),
+            ("old", 10, "gemini-3-flash", 5000, 5000, Some(1.0)),
+        ];
+        for (id, ts, model, input, output, cost) in rows {
+            conn.execute(
+                "INSERT INTO request_logs VALUES (?1, ?2, ?3, NULL, ?4, ?5, ?6)",
+                params![id, ts, model, input, output, cost],
+            ).unwrap();
+        }
+
+        let summary = query_cost_summary(&conn, 100, true).unwrap();
+        assert_eq!(summary.total.request_count, 3);
+        assert_eq!(summary.total.input_tokens, 4000);
+        assert!((summary.total.estimated_cost_usd - 0.025).abs() < 1e-9);
+        assert_eq!(summary.by_model.len(), 2);
+        assert_eq!(summary.by_model[0].model.as_deref(), Some("claude-sonnet-4-5"));
+
+        assert!(query_cost_summary(&conn, 100, false).unwrap().by_model.is_empty());
+    }
+
+    #[test]
+    fn test_trim_oldest_logs() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("CREATE TABLE request_logs (id TEXT, timestamp INTEGER)", []).unwrap();
+        for i in 0..50 {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp) VALUES (?1, ?2)",
+                params![format!("log{}", i), 1000 + i],
+            ).unwrap();
+        }
+        assert!(db_size_bytes(&conn).unwrap() > 0);
+
+        assert_eq!(trim_oldest_logs(&conn).unwrap(), 5);
+        let oldest: i64 = conn
+            .query_row("SELECT MIN(timestamp) FROM request_logs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(oldest, 1005);
+    }
+
+    #[test]
+    fn test_export_where_clause() {
+        assert_eq!(export_where_clause("", false), "");
+        assert_eq!(export_where_clause("", true), "WHERE (status < 200 OR status >= 400)");
+        assert!(export_where_clause("gemini", true).contains(" AND "));
+    }
+
+    #[test]
+    fn test_model_latency_percentiles() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE request_logs (id TEXT, timestamp INTEGER, model TEXT, duration INTEGER)",
+            [],
+        ).unwrap();
+        for i in 1..=100 {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, model, duration) VALUES (?1, ?2, 'gemini-3-flash', ?3)",
+                params![format!("g{}", i), 1000 + i, i * 10],
+            ).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO request_logs (id, timestamp, model, duration) VALUES ('c1', 1000, 'claude-sonnet-4-5', 500)",
+            [],
+        ).unwrap();
+
+        let stats = query_model_latency_stats(&conn, None, 0).unwrap();
+        assert_eq!(stats.len(), 2);
+        let gemini = &stats[0];
+        assert_eq!(gemini.model, "gemini-3-flash");
+        assert_eq!(gemini.request_count, 100);
+        assert_eq!(gemini.p50_ms, 500);
+        assert_eq!(gemini.p95_ms, 950);
+        assert_eq!(gemini.p99_ms, 990);
+        assert!((gemini.mean_ms - 505.0).abs() < f64::EPSILON);
+
+        let claude = query_model_latency_stats(&conn, Some("claude-sonnet-4-5"), 0).unwrap();
+        assert_eq!(claude.len(), 1);
+        assert_eq!(claude[0].p99_ms, 500);
+    }
+
+    #[test]
+    fn test_wal_checkpoint_truncates() {
+        let path = std::env::temp_dir().join(format!("proxy_db_wal_{}.db", uuid::Uuid::new_v4()));
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+        conn.execute("CREATE TABLE t (v INTEGER)", []).unwrap();
+        for i in 0..100 {
+            conn.execute("INSERT INTO t (v) VALUES (?1)", [i]).unwrap();
+        }
+
+        let stats = checkpoint_conn(&conn).unwrap();
+        assert!(!stats.busy);
+        assert!(stats.wal_pages > 0);
+        assert_eq!(stats.wal_pages, stats.checkpointed_pages);
+        assert_eq!(std::fs::metadata(format!("{}-wal", path.display())).unwrap().len(), 0);
+
+        drop(conn);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_errors_by_model_uses_compound_index() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE request_logs (
+                id TEXT PRIMARY KEY, timestamp INTEGER, method TEXT, url TEXT, status INTEGER,
+                duration INTEGER, model TEXT, error TEXT, input_tokens INTEGER, output_tokens INTEGER,
+                account_email TEXT, mapped_model TEXT
+            );
+            CREATE INDEX idx_ts_model_status ON request_logs (timestamp DESC, model, status);",
+        ).unwrap();
+        let rows = [
+            ("a", 1, 500, "gemini-3-flash"),
+            ("b", 2, 200, "gemini-3-flash"),
+            ("c", 3, 429, "gemini-3-flash"),
+            ("d", 4, 500, "claude-sonnet-4-5"),
+        ];
+        for (id, ts, status, model) in rows {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model)
+                 VALUES (?1, ?2, 'POST', '/v1/messages', ?3, 0, ?4)",
+                params![id, ts, status, model],
+            ).unwrap();
+        }
+
+        let errors = query_errors_by_model(&conn, "gemini-3-flash", 10).unwrap();
+        let ids: Vec<&str> = errors.iter().map(|l| l.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "a"]);
+        assert_eq!(query_errors_by_model(&conn, "gemini-3-flash", 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_run_migrations_idempotent_and_upgrades_legacy_schema() {
+        // Legacy database: table and some columns created by the old ALTER TABLE approach
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE request_logs (
+                id TEXT PRIMARY KEY, timestamp INTEGER, method TEXT, url TEXT,
+                status INTEGER, duration INTEGER, model TEXT, error TEXT, request_body TEXT
+            );",
+        ).unwrap();
+
+        run_migrations(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+
+        let version: u32 = conn
+            .query_row("SELECT MAX(version) FROM schema_migrations", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.last().unwrap().version);
+        let applied: u32 = conn
+            .query_row("SELECT COUNT(*) FROM schema_migrations", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(applied as usize, MIGRATIONS.len());
+
+        let has_mapped_model: bool = conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM pragma_table_info('request_logs') WHERE name = 'mapped_model')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(has_mapped_model);
+    }
+
+    #[test]
+    fn test_account_request_stats() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        for (i, (email, status, duration)) in [
+            ("a@example.com", 200, 100),
+            ("a@example.com", 429, 300),
+            ("a@example.com", 500, 200),
+            ("b@example.com", 200, 50),
+        ].iter().enumerate() {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, status, duration, account_email) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![i.to_string(), 1_000 + i as i64, status, duration, email],
+            ).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO token_refresh_events (timestamp, account_email, success) VALUES (1000, 'a@example.com', 0), (1000, 'a@example.com', 1), (10, 'a@example.com', 0)",
+            [],
+        ).unwrap();
+
+        let stats = query_account_request_stats(&conn, "a@example.com", 500).unwrap();
+        assert_eq!((stats.total, stats.success, stats.rate_limited), (3, 1, 1));
+        assert_eq!(stats.avg_latency_ms, Some(200.0));
+        assert_eq!(stats.refresh_failures, 1);
+
+        let empty = query_account_request_stats(&conn, "c@example.com", 0).unwrap();
+        assert_eq!(empty.total, 0);
+        assert_eq!(empty.avg_latency_ms, None);
+    }
+
+    #[test]
+    fn test_account_usage_stats() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        for i in 1..=20i64 {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, status, duration, account_email, input_tokens, output_tokens, estimated_cost_usd)
+                 VALUES (?1, 1000, ?2, ?3, 'a@example.com', 10, 5, 0.01)",
+                params![format!("a{}", i), if i % 5 == 0 { 429 } else { 200 }, i * 100],
+            ).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO request_logs (id, timestamp, status, duration, account_email) VALUES ('old', 10, 200, 50, 'b@example.com')",
+            [],
+        ).unwrap();
+
+        let stats = query_account_usage_stats(&conn, 500).unwrap();
+        assert_eq!(stats.len(), 1);
+        let a = &stats[0];
+        assert_eq!((a.total_requests, a.error_count), (20, 4));
+        assert_eq!(a.p95_latency_ms, Some(1900));
+        assert_eq!(a.avg_latency_ms, Some(1050.0));
+        assert_eq!((a.input_tokens, a.output_tokens), (200, 100));
+        assert!((a.estimated_cost_usd - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_quota_history() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        let models = vec![("gemini-3-pro".to_string(), 80), ("claude-sonnet-4-5".to_string(), 50)];
+        let now = 100 * DAY_MS;
+
+        assert_eq!(insert_quota_snapshots(&conn, "acc", &models, now - 40 * DAY_MS).unwrap(), 2);
+        assert_eq!(insert_quota_snapshots(&conn, "acc", &models, now - DAY_MS).unwrap(), 2);
+        // the same quota fetch written twice is ignored
+        assert_eq!(insert_quota_snapshots(&conn, "acc", &models, now - DAY_MS).unwrap(), 0);
+        assert_eq!(insert_quota_snapshots(&conn, "acc", &[("gemini-3-pro".to_string(), 60)], now).unwrap(), 1);
+
+        let all = query_quota_history(&conn, "acc", None, 0).unwrap();
+        assert_eq!(all.len(), 3, "snapshots older than 30 days are pruned");
+
+        let gemini = query_quota_history(&conn, "acc", Some("gemini-3-pro"), 0).unwrap();
+        assert_eq!(gemini.iter().map(|s| s.percentage).collect::<Vec<_>>(), vec![80, 60]);
+        assert!(query_quota_history(&conn, "other", None, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_log_lookup_by_request_id() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        for (id, ts) in [("first", 1_000), ("retry", 2_000)] {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, method, url, status, duration, request_body, request_id)
+                 VALUES (?1, ?2, 'POST', '/v1/messages', 200, 10, 'hello', 'req-1')",
+                params![id, ts],
+            ).unwrap();
+        }
+
+        let log = query_log_detail(&conn, "request_id", "req-1").unwrap();
+        assert_eq!(log.id, "retry");
+        assert_eq!(log.request_id.as_deref(), Some("req-1"));
+        assert_eq!(log.request_body.as_deref(), Some("hello"));
+        assert!(query_log_detail(&conn, "request_id", "missing").is_err());
+    }
+
+    #[test]
+    fn test_account_logs_export_and_delete() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        for (i, email) in ["a@example.com", "b@example.com", "a@example.com"].iter().enumerate() {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, status, duration, account_email, request_body)
+                 VALUES (?1, ?2, 200, 10, ?3, 'hello')",
+                params![i.to_string(), 1_000 + i as i64, email],
+            ).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO token_refresh_events (timestamp, account_email, success) VALUES (1000, 'a@example.com', 1)",
+            [],
+        ).unwrap();
+
+        let logs = query_logs_by_account(&conn, "a@example.com").unwrap();
+        assert_eq!(logs.iter().map(|l| l.id.as_str()).collect::<Vec<_>>(), vec!["0", "2"]);
+        assert_eq!(logs[0].request_body.as_deref(), Some("hello"));
+
+        assert_eq!(delete_account_rows(&conn, "a@example.com").unwrap(), (2, 1));
+        assert!(query_logs_by_account(&conn, "a@example.com").unwrap().is_empty());
+        assert_eq!(count_logs_fts(&conn, "hello", false).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_purge_account_from_archives() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::BufRead;
+
+        let dir = std::env::temp_dir().join(format!("archive-purge-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("proxy_logs_2024-01.jsonl.gz");
+        let mut encoder = GzEncoder::new(std::fs::File::create(&path).unwrap(), Compression::default());
+        for (id, email) in [("a", "a@example.com"), ("b", "b@example.com"), ("c", "a@example.com")] {
+            writeln!(encoder, r#"{{"id":"{}","account_email":"{}"}}"#, id, email).unwrap();
+        }
+        encoder.finish().unwrap();
+
+        assert_eq!(purge_account_from_archives("a@example.com", &dir).unwrap(), 2);
+        let reader = std::io::BufReader::new(flate2::read::MultiGzDecoder::new(std::fs::File::open(&path).unwrap()));
+        let lines: Vec<String> = reader.lines().map(|line| line.unwrap()).collect();
+        assert_eq!(lines, vec![r#"{"id":"b","account_email":"b@example.com"}"#.to_string()]);
+
+        assert_eq!(purge_account_from_archives("a@example.com", &dir).unwrap(), 0);
+        assert_eq!(purge_account_from_archives("a@example.com", &dir.join("missing")).unwrap(), 0);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fts_search_respects_errors_only() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        for (id, status) in [("ok", 200), ("failed", 500)] {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, status, duration, request_body)
+                 VALUES (?1, 1000, ?2, 10, 'tool_use payload')",
+                params![id, status],
+            ).unwrap();
+        }
+
+        assert_eq!(count_logs_fts(&conn, "tool_use", false).unwrap(), 2);
+        assert_eq!(count_logs_fts(&conn, "tool_use", true).unwrap(), 1);
+        let errors = query_logs_fts(&conn, "tool_use", true, 10, 0).unwrap();
+        assert_eq!(errors.iter().map(|l| l.id.as_str()).collect::<Vec<_>>(), vec!["failed"]);
+
+        // Index stays consistent after deletes and VACUUM renumbering rowids
+        conn.execute("DELETE FROM request_logs WHERE id = 'ok'", []).unwrap();
+        vacuum(&conn).unwrap();
+        assert_eq!(count_logs_fts(&conn, "tool_use", false).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_aggregates_accumulate_past_deleted_logs() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        let insert = |id: &str, ts: i64, status: u16| {
+            conn.execute(
+                "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, input_tokens)
+                 VALUES (?1, ?2, 'POST', '/v1/messages', ?3, 10, 'claude', 5)",
+                params![id, ts, status],
+            ).unwrap();
+        };
+        let hour_totals = |conn: &Connection| -> (i64, i64, i64) {
+            conn.query_row(
+                "SELECT requests, errors, input_tokens FROM request_aggregates WHERE bucket_size = 'hour' AND bucket_start = ?1",
+                [HOUR_MS],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            ).unwrap()
+        };
+
+        insert("a", HOUR_MS + 1_000, 200);
+        insert("b", HOUR_MS + 2_000, 500);
+        flush_aggregates_until(&conn, HOUR_MS + 10_000).unwrap();
+        assert_eq!(hour_totals(&conn), (2, 1, 10));
+
+        // Already-flushed logs are not counted twice
+        assert_eq!(flush_aggregates_until(&conn, HOUR_MS + 10_000).unwrap(), 0);
+
+        // Deleting raw logs no longer shrinks the bucket; new logs in the same hour are added
+        conn.execute("DELETE FROM request_logs", []).unwrap();
+        insert("c", HOUR_MS + 20_000, 200);
+        flush_aggregates_until(&conn, HOUR_MS + 30_000).unwrap();
+        assert_eq!(hour_totals(&conn), (3, 1, 15));
+
+        let day_requests: i64 = conn.query_row(
+            "SELECT requests FROM request_aggregates WHERE bucket_size = 'day' AND bucket_start = 0",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(day_requests, 3);
+    }
+
+    #[test]
+    fn test_migration_versions_are_ordered() {
+        assert!(MIGRATIONS.windows(2).all(|w| w[0].version < w[1].version));
+    }
+
+    #[test]
+    fn test_sanitize_fts_query() {
+        assert_eq!(sanitize_fts_query("tool_use error"), "\"tool_use\" \"error\"");
+        assert_eq!(sanitize_fts_query("a\" OR request_body:x"), "\"a\" \"OR\" \"request_body:x\"");
+        assert_eq!(sanitize_fts_query("   "), "");
+    }
+
+    #[test]
+    fn test_validate_export_columns() {
+        assert!(validate_export_columns(&["id", "status"]).is_ok());
+        assert!(validate_export_columns(&[]).is_err());
+        assert!(validate_export_columns(&["id; DROP TABLE request_logs"]).is_err());
+    }
+}
@@ -53,9 +53,9 @@ struct Tier {
     slug: Option<String>,
 }
 
-/// 创建配置好的 HTTP Client
-fn create_client() -> reqwest::Client {
-    crate::utils::http::create_client(15)
+/// 创建配置好的 HTTP Client (请求结果需回报给上游代理池)
+fn create_client() -> crate::proxy::upstream_proxy::PooledClient {
+    crate::utils::http::create_pooled_client(15)
 }
 
 fn create_warmup_client() -> reqwest::Client {
@@ -70,6 +70,7 @@ async fn fetch_project_id(access_token: &str, email: &str) -> (Option<String>, O
     let meta = json!({"metadata": {"ideType": "ANTIGRAVITY"}});
 
     let res = client
+        .client
         .post(format!("{}/v1internal:loadCodeAssist", CLOUD_CODE_BASE_URL))
         .header(reqwest::header::AUTHORIZATION, format!("Bearer {}", access_token))
         .header(reqwest::header::CONTENT_TYPE, "application/json")
@@ -77,6 +78,7 @@ async fn fetch_project_id(access_token: &str, email: &str) -> (Option<String>, O
         .json(&meta)
         .send()
         .await;
+    client.report(&res);
 
     match res {
         Ok(res) => {
@@ -133,7 +135,7 @@ pub async fn fetch_quota_with_cache(
     
     let final_project_id = project_id.as_deref().unwrap_or("bamboo-precept-lgxtn");
     
-    let client = create_client();
+    let mut client = create_client();
     let payload = json!({
         "project": final_project_id
     });
@@ -143,14 +145,16 @@ pub async fn fetch_quota_with_cache(
     let mut last_error: Option<AppError> = None;
 
     for attempt in 1..=max_retries {
-        match client
+        let result = client
+            .client
             .post(url)
             .bearer_auth(access_token)
             .header("User-Agent", USER_AGENT)
             .json(&json!(payload))
             .send()
-            .await
-        {
+            .await;
+        client.report(&result);
+        match result {
             Ok(response) => {
                 // 将 HTTP 错误状态转换为 AppError
                 if let Err(_) = response.error_for_status_ref() {
@@ -215,6 +219,8 @@ pub async fn fetch_quota_with_cache(
                 last_error = Some(AppError::Network(e));
                 if attempt < max_retries {
                     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    // 重新创建 Client，从代理池重新选择代理
+                    client = create_client();
                 }
             }
         }
@@ -0,0 +1,359 @@
+// 月度用量报告
+// 请求量、错误率与模型排行来自 request_aggregates (日志清理后仍保留)，
+// 账号排行与费用来自 request_logs (仅覆盖尚未清理的日志)
+use base64::{engine::general_purpose, Engine as _};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use super::proxy_db;
+
+const DAY_MS: i64 = 24 * 3600 * 1000;
+
+/// 排行榜条目数
+const TOP_N: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Html,
+    /// HTML 报告编码为 base64 data URI，由前端 WebView 打开后打印为 PDF
+    Pdf,
+}
+
+impl ReportFormat {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "html" => Ok(Self::Html),
+            "pdf" => Ok(Self::Pdf),
+            other => Err(format!("不支持的报告格式: {} (可选 html / pdf)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UsageRow {
+    name: String,
+    requests: u64,
+    tokens: u64,
+}
+
+#[derive(Debug, Clone)]
+struct DailyVolume {
+    day: u32,
+    requests: u64,
+    errors: u64,
+}
+
+#[derive(Debug, Clone)]
+struct ReportData {
+    year: i32,
+    month: u32,
+    total_requests: u64,
+    total_errors: u64,
+    error_rate_percent: f64,
+    top_models: Vec<UsageRow>,
+    top_accounts: Vec<UsageRow>,
+    estimated_cost_usd: Option<f64>,
+    daily: Vec<DailyVolume>,
+}
+
+/// 月份起止时间 (UTC 毫秒，左闭右开) 与天数
+fn month_range(year: i32, month: u32) -> Result<(i64, i64, u32), String> {
+    let start = chrono::NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| format!("无效的月份: {}-{}", year, month))?;
+    let end = if month == 12 {
+        chrono::NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        chrono::NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }
+    .ok_or_else(|| format!("无效的月份: {}-{}", year, month))?;
+    let to_ms = |d: chrono::NaiveDate| d.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis();
+    let days = (end - start).num_days() as u32;
+    Ok((to_ms(start), to_ms(end), days))
+}
+
+fn query_report_data(conn: &Connection, year: i32, month: u32) -> Result<ReportData, String> {
+    let (start_ms, end_ms, days) = month_range(year, month)?;
+
+    let mut daily: Vec<DailyVolume> = (1..=days)
+        .map(|day| DailyVolume { day, requests: 0, errors: 0 })
+        .collect();
+    let mut stmt = conn.prepare(
+        "SELECT bucket_start, SUM(requests), SUM(errors)
+         FROM request_aggregates
+         WHERE bucket_size = 'day' AND bucket_start >= ?1 AND bucket_start < ?2
+         GROUP BY bucket_start"
+    ).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start_ms, end_ms], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, u64>(1)?, row.get::<_, u64>(2)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let (bucket_start, requests, errors) = row.map_err(|e| e.to_string())?;
+        if let Some(entry) = daily.get_mut(((bucket_start - start_ms) / DAY_MS) as usize) {
+            entry.requests += requests;
+            entry.errors += errors;
+        }
+    }
+    let total_requests: u64 = daily.iter().map(|d| d.requests).sum();
+    let total_errors: u64 = daily.iter().map(|d| d.errors).sum();
+
+    let top_models = query_usage_rows(
+        conn,
+        "SELECT CASE WHEN model = '' THEN 'unknown' ELSE model END, SUM(requests), SUM(input_tokens + output_tokens)
+         FROM request_aggregates
+         WHERE bucket_size = 'day' AND bucket_start >= ?1 AND bucket_start < ?2
+         GROUP BY 1 ORDER BY 2 DESC LIMIT ?3",
+        start_ms,
+        end_ms,
+    )?;
+    let mut top_accounts = query_usage_rows(
+        conn,
+        "SELECT account_email, COUNT(*), COALESCE(SUM(input_tokens), 0) + COALESCE(SUM(output_tokens), 0)
+         FROM request_logs
+         WHERE account_email IS NOT NULL AND timestamp >= ?1 AND timestamp < ?2
+         GROUP BY 1 ORDER BY 2 DESC LIMIT ?3",
+        start_ms,
+        end_ms,
+    )?;
+    // 报表会被导出分享，账号邮箱脱敏后再写入
+    for row in &mut top_accounts {
+        row.name = crate::utils::privacy::mask_email(&row.name);
+    }
+
+    // 未配置单价或没有可估算的请求时为 NULL
+    let estimated_cost_usd: Option<f64> = conn
+        .query_row(
+            "SELECT SUM(estimated_cost_usd) FROM request_logs WHERE timestamp >= ?1 AND timestamp < ?2",
+            params![start_ms, end_ms],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let error_rate_percent = if total_requests == 0 {
+        0.0
+    } else {
+        total_errors as f64 * 100.0 / total_requests as f64
+    };
+
+    Ok(ReportData {
+        year,
+        month,
+        total_requests,
+        total_errors,
+        error_rate_percent,
+        top_models,
+        top_accounts,
+        estimated_cost_usd,
+        daily,
+    })
+}
+
+fn query_usage_rows(conn: &Connection, sql: &str, start_ms: i64, end_ms: i64) -> Result<Vec<UsageRow>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start_ms, end_ms, TOP_N as i64], |row| {
+            Ok(UsageRow {
+                name: row.get(0)?,
+                requests: row.get(1)?,
+                tokens: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 每日请求量柱状图 (错误请求叠加为红色)
+fn render_daily_chart(daily: &[DailyVolume]) -> String {
+    const BAR_WIDTH: u64 = 20;
+    const CHART_HEIGHT: u64 = 160;
+    const LABEL_HEIGHT: u64 = 16;
+
+    let max = daily.iter().map(|d| d.requests).max().unwrap_or(0).max(1);
+    let width = BAR_WIDTH * daily.len() as u64;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"9\">",
+        w = width,
+        h = CHART_HEIGHT + LABEL_HEIGHT
+    );
+    for (i, d) in daily.iter().enumerate() {
+        let x = i as u64 * BAR_WIDTH;
+        let height = d.requests * CHART_HEIGHT / max;
+        let error_height = d.errors.min(d.requests) * CHART_HEIGHT / max;
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#6366f1\"><title>{}: {} requests, {} errors</title></rect>",
+            x + 2,
+            CHART_HEIGHT - height,
+            BAR_WIDTH - 4,
+            height,
+            d.day,
+            d.requests,
+            d.errors
+        ));
+        if error_height > 0 {
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#ef4444\"/>",
+                x + 2,
+                CHART_HEIGHT - error_height,
+                BAR_WIDTH - 4,
+                error_height
+            ));
+        }
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            x + BAR_WIDTH / 2,
+            CHART_HEIGHT + LABEL_HEIGHT - 4,
+            d.day
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+const REPORT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Antigravity Usage Report {{ period }}</title>
+<style>
+body { font-family: -apple-system, "Segoe UI", sans-serif; color: #111827; margin: 32px; }
+h1 { font-size: 22px; }
+h2 { font-size: 16px; margin-top: 28px; }
+.cards { display: flex; gap: 16px; }
+.card { border: 1px solid #e5e7eb; border-radius: 8px; padding: 12px 16px; min-width: 140px; }
+.card .value { font-size: 20px; font-weight: bold; }
+table { border-collapse: collapse; width: 100%; font-size: 13px; }
+th, td { border-bottom: 1px solid #e5e7eb; padding: 6px 8px; text-align: left; }
+td.num { text-align: right; }
+</style>
+</head>
+<body>
+<h1>Usage Report {{ period }}</h1>
+<div class="cards">
+  <div class="card"><div>Total requests</div><div class="value">{{ total_requests }}</div></div>
+  <div class="card"><div>Error rate</div><div class="value">{{ error_rate }}%</div></div>
+  {% if estimated_cost %}
+  <div class="card"><div>Estimated cost</div><div class="value">${{ estimated_cost }}</div></div>
+  {% endif %}
+</div>
+
+<h2>Daily request volume</h2>
+{{ chart_svg|safe }}
+
+<h2>Top models</h2>
+<table>
+<tr><th>Model</th><th>Requests</th><th>Tokens</th></tr>
+{% for row in top_models %}<tr><td>{{ row.name }}</td><td class="num">{{ row.requests }}</td><td class="num">{{ row.tokens }}</td></tr>
+{% else %}<tr><td colspan="3">No data</td></tr>{% endfor %}
+</table>
+
+<h2>Top accounts</h2>
+<table>
+<tr><th>Account</th><th>Requests</th><th>Tokens</th></tr>
+{% for row in top_accounts %}<tr><td>{{ row.name }}</td><td class="num">{{ row.requests }}</td><td class="num">{{ row.tokens }}</td></tr>
+{% else %}<tr><td colspan="3">No data</td></tr>{% endfor %}
+</table>
+</body>
+</html>
+"#;
+
+fn render_html(data: &ReportData) -> Result<String, String> {
+    let mut env = minijinja::Environment::new();
+    env.add_template("report.html", REPORT_TEMPLATE)
+        .map_err(|e| e.to_string())?;
+    let template = env.get_template("report.html").map_err(|e| e.to_string())?;
+    template
+        .render(minijinja::context! {
+            period => format!("{}-{:02}", data.year, data.month),
+            total_requests => data.total_requests,
+            error_rate => format!("{:.2}", data.error_rate_percent),
+            estimated_cost => data.estimated_cost_usd.map(|c| format!("{:.2}", c)),
+            chart_svg => render_daily_chart(&data.daily),
+            top_models => &data.top_models,
+            top_accounts => &data.top_accounts,
+        })
+        .map_err(|e| format!("渲染报告失败: {}", e))
+}
+
+/// 生成指定月份 (UTC) 的用量报告
+pub fn generate_monthly_report(year: i32, month: u32, format: ReportFormat) -> Result<Vec<u8>, String> {
+    let conn = proxy_db::connect_db()?;
+    // 先将尚未汇总的近期日志写入聚合表
+    proxy_db::flush_aggregates(&conn)?;
+    let data = query_report_data(&conn, year, month)?;
+    let html = render_html(&data)?;
+
+    Ok(match format {
+        ReportFormat::Html => html.into_bytes(),
+        ReportFormat::Pdf => format!(
+            "data:text/html;base64,{}",
+            general_purpose::STANDARD.encode(html.as_bytes())
+        )
+        .into_bytes(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE request_aggregates (bucket_start INTEGER, bucket_size TEXT, model TEXT, provider TEXT,
+                requests INTEGER, errors INTEGER, input_tokens INTEGER, output_tokens INTEGER);
+             CREATE TABLE request_logs (id TEXT, timestamp INTEGER, account_email TEXT,
+                input_tokens INTEGER, output_tokens INTEGER, estimated_cost_usd REAL);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_month_range() {
+        let (start, end, days) = month_range(2024, 2).unwrap();
+        assert_eq!(days, 29);
+        assert_eq!(end - start, 29 * DAY_MS);
+        assert_eq!(month_range(2025, 12).unwrap().2, 31);
+        assert!(month_range(2025, 13).is_err());
+    }
+
+    #[test]
+    fn test_report_data_and_html() {
+        let conn = setup();
+        let (start, _, _) = month_range(2025, 3).unwrap();
+        for (day, model, requests, errors) in [(0, "gemini-3-flash", 10, 1), (0, "claude-sonnet-4-5", 5, 0), (2, "gemini-3-flash", 4, 3)] {
+            conn.execute(
+                "INSERT INTO request_aggregates VALUES (?1, 'day', ?2, 'gemini', ?3, ?4, 100, 50)",
+                params![start + day * DAY_MS, model, requests, errors],
+            )
+            .unwrap();
+        }
+        // 下个月的数据不计入
+        conn.execute(
+            "INSERT INTO request_aggregates VALUES (?1, 'day', 'gemini-3-flash', 'gemini', 99, 0, 0, 0)",
+            [start + 31 * DAY_MS],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO request_logs VALUES ('a', ?1, 'a@example.com', 10, 5, 0.5)",
+            [start + 1000],
+        )
+        .unwrap();
+
+        let data = query_report_data(&conn, 2025, 3).unwrap();
+        assert_eq!(data.total_requests, 19);
+        assert_eq!(data.total_errors, 4);
+        assert_eq!(data.daily.len(), 31);
+        assert_eq!(data.daily[2].requests, 4);
+        assert_eq!(data.top_models[0].name, "gemini-3-flash");
+        assert_eq!(data.top_models[0].requests, 14);
+        assert_eq!(data.top_accounts[0].name, "a***@example.com");
+        assert_eq!(data.estimated_cost_usd, Some(0.5));
+
+        let html = render_html(&data).unwrap();
+        assert!(html.contains("Usage Report 2025-03"));
+        assert!(html.contains("<svg"));
+        assert!(html.contains("$0.50"));
+    }
+}
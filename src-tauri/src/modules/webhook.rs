@@ -0,0 +1,190 @@
+//! Webhook 通知模块
+//! 在关键事件发生时向外部地址推送 JSON (可选 HMAC-SHA256 签名)
+//!
+//! 支持的事件：
+//! - account.disabled         账号被禁用
+//! - account.quota_low        账号模型配额低于保护阈值
+//! - rate_limit.triggered     账号触发上游限流
+//! - proxy.error_rate_spike   反代错误率突增
+//! - account.usage_anomaly    账号每分钟请求数异常突增
+
+use hmac::{Hmac, Mac};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+
+use crate::modules::logger;
+
+pub const EVENT_ACCOUNT_DISABLED: &str = "account.disabled";
+pub const EVENT_ACCOUNT_QUOTA_LOW: &str = "account.quota_low";
+pub const EVENT_RATE_LIMIT_TRIGGERED: &str = "rate_limit.triggered";
+pub const EVENT_PROXY_ERROR_RATE_SPIKE: &str = "proxy.error_rate_spike";
+pub const EVENT_ACCOUNT_USAGE_ANOMALY: &str = "account.usage_anomaly";
+
+/// 事件队列容量，队列满时丢弃新事件 (不阻塞业务流程)
+const QUEUE_CAPACITY: usize = 512;
+
+/// Webhook 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// 订阅的事件列表，`*` 表示全部
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// 设置后请求头携带 `X-Webhook-Signature: sha256=<hex>`
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+impl WebhookConfig {
+    fn subscribes(&self, event: &str) -> bool {
+        self.events.iter().any(|e| e == "*" || e == event)
+    }
+}
+
+/// Webhook 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookEvent {
+    pub event: String,
+    pub timestamp: i64,
+    pub data: serde_json::Value,
+}
+
+static DISPATCHER: OnceCell<WebhookDispatcher> = OnceCell::new();
+
+/// Webhook 分发器：事件入队后由后台任务逐个投递
+pub struct WebhookDispatcher {
+    tx: mpsc::Sender<WebhookEvent>,
+    /// 当前订阅列表 (保存设置或重载配置时替换)
+    webhooks: Arc<RwLock<Vec<WebhookConfig>>>,
+}
+
+impl WebhookDispatcher {
+    /// 启动分发器 (进程内只启动一次，重复调用仅更新订阅列表)
+    pub fn start(webhooks: Vec<WebhookConfig>) {
+        if let Some(dispatcher) = DISPATCHER.get() {
+            dispatcher.update(webhooks);
+            return;
+        }
+        DISPATCHER.get_or_init(|| {
+            let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+            let webhooks = Arc::new(RwLock::new(webhooks));
+            tauri::async_runtime::spawn(run_dispatcher(webhooks.clone(), rx));
+            WebhookDispatcher { tx, webhooks }
+        });
+    }
+
+    /// 替换订阅列表，对之后出队的事件生效
+    pub fn update(&self, webhooks: Vec<WebhookConfig>) {
+        if let Ok(mut current) = self.webhooks.write() {
+            *current = webhooks;
+        }
+    }
+
+    /// 事件入队 (队列满或已关闭时丢弃)
+    pub fn dispatch(&self, event: &str, data: serde_json::Value) {
+        let event = WebhookEvent {
+            event: event.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            data,
+        };
+        if let Err(e) = self.tx.try_send(event) {
+            logger::log_warn(&format!("[Webhook] 事件入队失败: {}", e));
+        }
+    }
+}
+
+/// 发送事件通知 (分发器未启动时为空操作)
+pub fn emit(event: &str, data: serde_json::Value) {
+    if let Some(dispatcher) = DISPATCHER.get() {
+        dispatcher.dispatch(event, data);
+    }
+}
+
+/// 热更新订阅列表 (分发器未启动时为空操作)
+pub fn reload(webhooks: Vec<WebhookConfig>) {
+    if let Some(dispatcher) = DISPATCHER.get() {
+        dispatcher.update(webhooks);
+    }
+}
+
+async fn run_dispatcher(webhooks: Arc<RwLock<Vec<WebhookConfig>>>, mut rx: mpsc::Receiver<WebhookEvent>) {
+    let client = crate::utils::http::create_client(10);
+    // 无订阅时同样消费队列，避免 try_send 持续报满
+    while let Some(event) = rx.recv().await {
+        let hooks: Vec<WebhookConfig> = match webhooks.read() {
+            Ok(list) => list.iter().filter(|h| h.subscribes(&event.event)).cloned().collect(),
+            Err(_) => continue,
+        };
+        if hooks.is_empty() {
+            continue;
+        }
+        let body = match serde_json::to_vec(&event) {
+            Ok(b) => b,
+            Err(_) => continue,
+        };
+
+        for hook in &hooks {
+            let mut request = client
+                .post(&hook.url)
+                .header("Content-Type", "application/json")
+                .header("X-Webhook-Event", &event.event)
+                .body(body.clone());
+            if let Some(secret) = hook.secret.as_deref().filter(|s| !s.is_empty()) {
+                request = request.header("X-Webhook-Signature", format!("sha256={}", sign(secret, &body)));
+            }
+
+            match request.send().await {
+                Ok(resp) if resp.status().is_success() => {}
+                Ok(resp) => logger::log_warn(&format!(
+                    "[Webhook] {} 返回 {} (事件: {})",
+                    hook.url,
+                    resp.status(),
+                    event.event
+                )),
+                Err(e) => logger::log_warn(&format!("[Webhook] 投递到 {} 失败: {}", hook.url, e)),
+            }
+        }
+    }
+}
+
+/// HMAC-SHA256 签名 (小写十六进制)
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_matches_rfc4231() {
+        // RFC 4231 Test Case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_subscribes() {
+        let hook = WebhookConfig {
+            url: "http://localhost".to_string(),
+            events: vec![EVENT_ACCOUNT_DISABLED.to_string()],
+            secret: None,
+        };
+        assert!(hook.subscribes(EVENT_ACCOUNT_DISABLED));
+        assert!(!hook.subscribes(EVENT_RATE_LIMIT_TRIGGERED));
+
+        let all = WebhookConfig { events: vec!["*".to_string()], ..hook };
+        assert!(all.subscribes(EVENT_PROXY_ERROR_RATE_SPIKE));
+    }
+}
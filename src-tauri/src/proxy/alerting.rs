@@ -0,0 +1,150 @@
+// 错误率告警
+// 定期从 ProxyMonitor 的内存日志计算滚动错误率，超过阈值时推送 Tauri 事件与 Webhook (带冷却)
+// 注意：仅在监控日志开启时有数据可供统计
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+use crate::models::config::AlertConfig;
+use crate::proxy::monitor::{ProxyMonitor, ProxyRequestLog};
+
+/// 窗口内最少请求数，样本过少时不告警
+const MIN_REQUESTS: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+struct AlertPayload {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    value: f64,
+    threshold: f64,
+}
+
+/// 统计 `now_ms` 之前 `window_ms` 内的请求数与错误数 (日志按时间倒序)
+fn window_error_counts(logs: &VecDeque<ProxyRequestLog>, now_ms: i64, window_ms: i64) -> (usize, usize) {
+    let recent = logs.iter().take_while(|l| now_ms - l.timestamp <= window_ms);
+    recent.fold((0, 0), |(total, errors), l| {
+        let is_error = l.status < 200 || l.status >= 400;
+        (total + 1, errors + is_error as usize)
+    })
+}
+
+pub struct AlertWatcher {
+    monitor: Arc<ProxyMonitor>,
+    config: AlertConfig,
+    app_handle: Option<tauri::AppHandle>,
+    last_alert: Option<Instant>,
+}
+
+impl AlertWatcher {
+    pub fn new(monitor: Arc<ProxyMonitor>, config: AlertConfig, app_handle: Option<tauri::AppHandle>) -> Self {
+        Self {
+            monitor,
+            config,
+            app_handle,
+            last_alert: None,
+        }
+    }
+
+    /// 启动后台检查任务 (每 window_secs / 10 秒检查一次)，返回的句柄在停止服务时 abort
+    pub fn start(mut self) -> tokio::task::JoinHandle<()> {
+        let period = Duration::from_secs((self.config.window_secs / 10).max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                self.check().await;
+            }
+        })
+    }
+
+    async fn check(&mut self) {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let window_ms = (self.config.window_secs * 1000) as i64;
+        let (total, errors) = {
+            let logs = self.monitor.logs.read().await;
+            window_error_counts(&logs, now_ms, window_ms)
+        };
+        if total < MIN_REQUESTS {
+            return;
+        }
+
+        let rate = errors as f64 / total as f64;
+        if rate < self.config.error_rate_threshold {
+            return;
+        }
+        if let Some(last) = self.last_alert {
+            if last.elapsed() < Duration::from_secs(self.config.cooldown_secs) {
+                return;
+            }
+        }
+        self.last_alert = Some(Instant::now());
+
+        tracing::warn!(
+            "[Alert] Error rate {:.1}% ({}/{}) over the last {}s exceeds threshold {:.1}%",
+            rate * 100.0,
+            errors,
+            total,
+            self.config.window_secs,
+            self.config.error_rate_threshold * 100.0
+        );
+
+        if let Some(app) = &self.app_handle {
+            let _ = app.emit(
+                "proxy://alert",
+                AlertPayload {
+                    kind: "error_rate",
+                    value: rate,
+                    threshold: self.config.error_rate_threshold,
+                },
+            );
+        }
+        crate::modules::webhook::emit(
+            crate::modules::webhook::EVENT_PROXY_ERROR_RATE_SPIKE,
+            serde_json::json!({
+                "window_secs": self.config.window_secs,
+                "total_requests": total,
+                "error_count": errors,
+                "error_rate": rate,
+                "threshold": self.config.error_rate_threshold,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(timestamp: i64, status: u16) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: String::new(),
+            timestamp,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status,
+            duration: 0,
+            model: None,
+            mapped_model: None,
+            account_email: None,
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: None,
+            output_tokens: None,
+            conversation_id: None,
+            estimated_cost_usd: None,
+            request_id: None,
+        }
+    }
+
+    #[test]
+    fn test_window_error_counts_ignores_old_logs() {
+        // 最新的日志在前
+        let logs: VecDeque<ProxyRequestLog> =
+            vec![log(10_000, 500), log(9_000, 200), log(8_000, 429), log(1_000, 500)].into();
+        assert_eq!(window_error_counts(&logs, 10_000, 5_000), (3, 2));
+        assert_eq!(window_error_counts(&logs, 10_000, 60_000), (4, 3));
+    }
+}
@@ -0,0 +1,219 @@
+// 账号用量异常检测
+// 按分钟统计各账号请求数，用 Welford 在线算法维护每分钟请求数的均值与标准差
+// 新一分钟的请求数超过 mean + z * stddev (且已有足够样本) 时推送 Tauri 事件与 Webhook
+// 单个账号请求量突增通常是客户端死循环等 bug，而非正常使用
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Emitter;
+
+/// 基线样本 (分钟) 少于该值时不判定异常
+const MIN_SAMPLES: u64 = 10;
+
+/// 标准差下限：请求量长期稳定时标准差接近 0，避免 +1 个请求就被判定为异常
+const MIN_STDDEV: f64 = 1.0;
+
+/// Welford 在线均值/方差
+#[derive(Debug, Clone, Default)]
+struct RunningStats {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn stddev(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            (self.m2 / (self.count - 1) as f64).sqrt()
+        }
+    }
+}
+
+/// proxy://anomaly_detected 事件负载
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageAnomaly {
+    pub account_id: String,
+    pub requests_per_minute: u32,
+    pub mean: f64,
+    pub stddev: f64,
+    pub threshold: f64,
+}
+
+#[derive(Default)]
+pub struct AnomalyDetector {
+    /// 当前分钟的请求数 (AccountID -> Count)
+    current: DashMap<String, AtomicU32>,
+    /// 历史每分钟请求数基线 (AccountID -> Stats)
+    baselines: DashMap<String, RunningStats>,
+}
+
+impl AnomalyDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, account_id: &str) {
+        if let Some(counter) = self.current.get(account_id) {
+            counter.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.current
+            .entry(account_id.to_string())
+            .or_insert_with(|| AtomicU32::new(0))
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 清空基线 (账号池重新加载后重新学习)
+    pub fn reset(&self) {
+        self.current.clear();
+        self.baselines.clear();
+    }
+
+    /// 结束当前分钟：返回超出阈值的账号，并将本分钟请求数计入基线
+    /// 已有基线但本分钟没有请求的账号记为 0
+    fn close_minute(&self, z_score_threshold: f64) -> Vec<UsageAnomaly> {
+        let mut counts: Vec<(String, u32)> = self
+            .current
+            .iter()
+            .map(|e| (e.key().clone(), e.value().swap(0, Ordering::Relaxed)))
+            .collect();
+        self.current.retain(|_, c| c.load(Ordering::Relaxed) > 0);
+        for entry in self.baselines.iter() {
+            if !counts.iter().any(|(id, _)| id == entry.key()) {
+                counts.push((entry.key().clone(), 0));
+            }
+        }
+
+        let mut anomalies = Vec::new();
+        for (account_id, count) in counts {
+            let mut stats = self.baselines.entry(account_id.clone()).or_default();
+            if stats.count >= MIN_SAMPLES {
+                let stddev = stats.stddev();
+                let threshold = stats.mean + z_score_threshold * stddev.max(MIN_STDDEV);
+                if count as f64 > threshold {
+                    anomalies.push(UsageAnomaly {
+                        account_id,
+                        requests_per_minute: count,
+                        mean: stats.mean,
+                        stddev,
+                        threshold,
+                    });
+                }
+            }
+            stats.push(count as f64);
+        }
+        anomalies
+    }
+}
+
+pub struct AnomalyWatcher {
+    detector: Arc<AnomalyDetector>,
+    z_score_threshold: f64,
+    app_handle: Option<tauri::AppHandle>,
+}
+
+impl AnomalyWatcher {
+    pub fn new(detector: Arc<AnomalyDetector>, z_score_threshold: f64, app_handle: Option<tauri::AppHandle>) -> Self {
+        Self {
+            detector,
+            z_score_threshold,
+            app_handle,
+        }
+    }
+
+    /// 启动后台任务 (每分钟结算一次)，返回的句柄在停止服务时 abort
+    pub fn start(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            // 第一次 tick 立即返回，跳过以保证每个样本都是完整的一分钟
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                for anomaly in self.detector.close_minute(self.z_score_threshold) {
+                    self.report(anomaly);
+                }
+            }
+        })
+    }
+
+    fn report(&self, anomaly: UsageAnomaly) {
+        tracing::warn!(
+            "[Anomaly] Account {} made {} requests in the last minute (mean {:.1}, stddev {:.1}, threshold {:.1})",
+            anomaly.account_id,
+            anomaly.requests_per_minute,
+            anomaly.mean,
+            anomaly.stddev,
+            anomaly.threshold
+        );
+        if let Some(app) = &self.app_handle {
+            let _ = app.emit("proxy://anomaly_detected", &anomaly);
+        }
+        crate::modules::webhook::emit(
+            crate::modules::webhook::EVENT_ACCOUNT_USAGE_ANOMALY,
+            serde_json::to_value(&anomaly).unwrap_or_default(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_minute(detector: &AnomalyDetector, account_id: &str, requests: u32) -> Vec<UsageAnomaly> {
+        for _ in 0..requests {
+            detector.record(account_id);
+        }
+        detector.close_minute(3.0)
+    }
+
+    #[test]
+    fn test_running_stats() {
+        let mut stats = RunningStats::default();
+        for v in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            stats.push(v);
+        }
+        assert!((stats.mean - 5.0).abs() < 1e-9);
+        assert!((stats.stddev() - (32.0f64 / 7.0).sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_spike_detected_after_baseline() {
+        let detector = AnomalyDetector::new();
+        // 样本不足时不告警
+        assert!(run_minute(&detector, "acc-1", 100).is_empty());
+        detector.reset();
+
+        for requests in [10, 12, 9, 11, 10, 10, 12, 8, 11, 10] {
+            assert!(run_minute(&detector, "acc-1", requests).is_empty());
+        }
+        assert!(run_minute(&detector, "acc-1", 13).is_empty());
+
+        let anomalies = run_minute(&detector, "acc-1", 100);
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].account_id, "acc-1");
+        assert_eq!(anomalies[0].requests_per_minute, 100);
+    }
+
+    #[test]
+    fn test_idle_minutes_count_as_zero() {
+        let detector = AnomalyDetector::new();
+        run_minute(&detector, "acc-1", 1);
+        for _ in 0..12 {
+            assert!(detector.close_minute(3.0).is_empty());
+        }
+        // 基线约为 0 时仍需超过标准差下限才告警
+        assert!(run_minute(&detector, "acc-1", 3).is_empty());
+        assert_eq!(run_minute(&detector, "acc-1", 20).len(), 1);
+    }
+}
@@ -1,6 +1,8 @@
 // 模型名称映射
 use std::collections::HashMap;
 use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
 
 static CLAUDE_TO_GEMINI: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
     let mut m = HashMap::new();
@@ -76,6 +78,115 @@ pub fn map_claude_model_to_gemini(input: &str) -> String {
     "claude-sonnet-4-5".to_string()
 }
 
+/// 模型能力描述 (上下文窗口与特性开关)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ModelCapability {
+    pub context_tokens: u32,
+    pub supports_vision: bool,
+    pub supports_tools: bool,
+    pub supports_thinking: bool,
+    pub max_output_tokens: u32,
+}
+
+impl ModelCapability {
+    const fn new(context_tokens: u32, vision: bool, tools: bool, thinking: bool, max_output_tokens: u32) -> Self {
+        Self {
+            context_tokens,
+            supports_vision: vision,
+            supports_tools: tools,
+            supports_thinking: thinking,
+            max_output_tokens,
+        }
+    }
+
+    /// 单次工具结果允许的最大字符数
+    /// 以 1M 上下文对应 200,000 字符为基准按比例缩放，最低 50,000
+    pub fn max_tool_result_chars(&self) -> usize {
+        let scaled = (self.context_tokens as u64
+            * crate::proxy::mappers::tool_result_compressor::MAX_TOOL_RESULT_CHARS as u64
+            / 1_048_576) as usize;
+        scaled.max(50_000)
+    }
+}
+
+/// 未登记模型的默认能力 (与 Gemini Flash 系列一致)
+const DEFAULT_CAPABILITY: ModelCapability = ModelCapability::new(1_048_576, true, true, false, 65_536);
+
+/// 模型能力注册表 (key 为上游实际使用的模型 ID)
+pub static CAPABILITY_REGISTRY: Lazy<HashMap<&'static str, ModelCapability>> = Lazy::new(|| {
+    let mut m = HashMap::new();
+
+    // Claude 系列
+    m.insert("claude-sonnet-4-5", ModelCapability::new(200_000, true, true, false, 64_000));
+    m.insert("claude-sonnet-4-5-thinking", ModelCapability::new(200_000, true, true, true, 64_000));
+    m.insert("claude-opus-4-5-thinking", ModelCapability::new(200_000, true, true, true, 64_000));
+
+    // Gemini 2.5 系列
+    m.insert("gemini-2.5-pro", ModelCapability::new(1_048_576, true, true, true, 65_536));
+    m.insert("gemini-2.5-flash", ModelCapability::new(1_048_576, true, true, true, 65_536));
+    m.insert("gemini-2.5-flash-thinking", ModelCapability::new(1_048_576, true, true, true, 65_536));
+    m.insert("gemini-2.5-flash-lite", ModelCapability::new(1_048_576, true, true, false, 65_536));
+    m.insert("gemini-2.0-flash-exp", ModelCapability::new(1_048_576, true, true, false, 8_192));
+
+    // Gemini 3 系列
+    m.insert("gemini-3-pro-preview", ModelCapability::new(1_048_576, true, true, true, 65_536));
+    m.insert("gemini-3-pro-high", ModelCapability::new(1_048_576, true, true, true, 65_536));
+    m.insert("gemini-3-pro-low", ModelCapability::new(1_048_576, true, true, true, 65_536));
+    m.insert("gemini-3-flash", ModelCapability::new(1_048_576, true, true, true, 65_536));
+    m.insert("gemini-3-pro-image", ModelCapability::new(65_536, true, false, false, 32_768));
+
+    m
+});
+
+/// 查询模型能力
+/// 顺序：精确匹配 > 系统映射后精确匹配 > 最长前缀匹配 (处理日期/分辨率后缀) > 默认值
+pub fn get_model_capability(model: &str) -> ModelCapability {
+    if let Some(cap) = CAPABILITY_REGISTRY.get(model) {
+        return *cap;
+    }
+
+    let mapped = map_claude_model_to_gemini(model);
+    if let Some(cap) = CAPABILITY_REGISTRY.get(mapped.as_str()) {
+        return *cap;
+    }
+
+    CAPABILITY_REGISTRY
+        .iter()
+        .filter(|(key, _)| model.starts_with(*key))
+        .max_by_key(|(key, _)| key.len())
+        .map(|(_, cap)| *cap)
+        .unwrap_or(DEFAULT_CAPABILITY)
+}
+
+/// 模型是否已登记在能力注册表中 (允许日期/分辨率等后缀)
+pub fn is_known_model(model: &str) -> bool {
+    CAPABILITY_REGISTRY.contains_key(model) || CAPABILITY_REGISTRY.keys().any(|key| model.starts_with(key))
+}
+
+/// 按编辑距离查找最接近的已登记模型 (用于配置纠错提示)
+pub fn closest_known_model(model: &str) -> Option<&'static str> {
+    fn edit_distance(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut prev: Vec<usize> = (0..=b.len()).collect();
+        for (i, ca) in a.chars().enumerate() {
+            let mut cur = vec![i + 1; b.len() + 1];
+            for (j, cb) in b.iter().enumerate() {
+                let cost = if ca == *cb { 0 } else { 1 };
+                cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+            }
+            prev = cur;
+        }
+        prev[b.len()]
+    }
+
+    CAPABILITY_REGISTRY
+        .keys()
+        .map(|key| (*key, edit_distance(model, key)))
+        .filter(|(_, d)| *d <= 3)
+        .min_by_key(|(key, d)| (*d, *key))
+        .map(|(key, _)| key)
+}
+
 /// 获取所有内置支持的模型列表关键字
 pub fn get_supported_models() -> Vec<String> {
     CLAUDE_TO_GEMINI.keys().map(|s| s.to_string()).collect()
@@ -84,6 +195,7 @@ pub fn get_supported_models() -> Vec<String> {
 /// 动态获取所有可用模型列表 (包含内置与用户自定义)
 pub async fn get_all_dynamic_models(
     custom_mapping: &tokio::sync::RwLock<std::collections::HashMap<String, String>>,
+    model_discovery: &crate::proxy::model_discovery::ModelDiscovery,
 ) -> Vec<String> {
     use std::collections::HashSet;
     let mut model_ids = HashSet::new();
@@ -101,6 +213,11 @@ pub async fn get_all_dynamic_models(
         }
     }
 
+    // 3. 合并上游发现的模型 ID
+    for id in model_discovery.cached_models().await {
+        model_ids.insert(id);
+    }
+
     // 5. 确保包含常用的 Gemini/画画模型 ID
     model_ids.insert("gemini-3-pro-low".to_string());
     
@@ -131,6 +248,52 @@ pub async fn get_all_dynamic_models(
     sorted_ids
 }
 
+/// 已编译的正则映射规则
+#[derive(Debug, Clone)]
+pub struct RegexMappingRule {
+    pub pattern: Regex,
+    pub target: String,
+    pub priority: i32,
+}
+
+/// 正则映射规则集合 (配置加载时编译一次，按优先级降序排列)
+#[derive(Debug, Clone, Default)]
+pub struct RegexMappingStore {
+    rules: Vec<RegexMappingRule>,
+}
+
+impl RegexMappingStore {
+    /// 从配置编译规则，无效的正则会被跳过并记录警告
+    pub fn from_config(configs: &[crate::proxy::config::RegexMappingConfig]) -> Self {
+        let mut rules: Vec<RegexMappingRule> = configs
+            .iter()
+            .filter_map(|c| match Regex::new(&c.pattern) {
+                Ok(pattern) => Some(RegexMappingRule {
+                    pattern,
+                    target: c.target.clone(),
+                    priority: c.priority,
+                }),
+                Err(e) => {
+                    tracing::warn!("[Router] 忽略无效的正则映射规则 '{}': {}", c.pattern, e);
+                    None
+                }
+            })
+            .collect();
+        // 稳定排序：同优先级保持配置顺序
+        rules.sort_by(|a, b| b.priority.cmp(&a.priority));
+        Self { rules }
+    }
+
+    /// 按优先级返回第一个匹配规则的目标模型
+    pub fn resolve(&self, model: &str) -> Option<&RegexMappingRule> {
+        self.rules.iter().find(|r| r.pattern.is_match(model))
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+}
+
 /// 通配符匹配辅助函数
 /// 支持简单的 * 通配符匹配
 /// 
@@ -149,17 +312,19 @@ fn wildcard_match(pattern: &str, text: &str) -> bool {
 }
 
 /// 核心模型路由解析引擎
-/// 优先级：精确匹配 > 通配符匹配 > 系统默认映射
+/// 优先级：精确匹配 > 正则匹配 > 通配符匹配 > 系统默认映射
 /// 
 /// # 参数
 /// - `original_model`: 原始模型名称
 /// - `custom_mapping`: 用户自定义映射表
+/// - `regex_mapping`: 已编译的正则映射规则
 /// 
 /// # 返回
 /// 映射后的目标模型名称
 pub fn resolve_model_route(
     original_model: &str,
     custom_mapping: &std::collections::HashMap<String, String>,
+    regex_mapping: &RegexMappingStore,
 ) -> String {
     // 1. 精确匹配 (最高优先级)
     if let Some(target) = custom_mapping.get(original_model) {
@@ -167,7 +332,13 @@ pub fn resolve_model_route(
         return target.clone();
     }
     
-    // 2. 通配符匹配
+    // 2. 正则匹配 (按优先级)
+    if let Some(rule) = regex_mapping.resolve(original_model) {
+        crate::modules::logger::log_info(&format!("[Router] 正则映射: {} -> {} (规则: {})", original_model, rule.target, rule.pattern.as_str()));
+        return rule.target.clone();
+    }
+    
+    // 3. 通配符匹配
     for (pattern, target) in custom_mapping.iter() {
         if pattern.contains('*') && wildcard_match(pattern, original_model) {
             crate::modules::logger::log_info(&format!("[Router] 通配符映射: {} -> {} (规则: {})", original_model, target, pattern));
@@ -175,7 +346,7 @@ pub fn resolve_model_route(
         }
     }
     
-    // 3. 系统默认映射
+    // 4. 系统默认映射
     let result = map_claude_model_to_gemini(original_model);
     if result != original_model {
         crate::modules::logger::log_info(&format!("[Router] 系统默认映射: {} -> {}", original_model, result));
@@ -183,6 +354,53 @@ pub fn resolve_model_route(
     result
 }
 
+/// 降级链判断模型可用时，至少需要一个账号剩余该模型的配额百分比
+const FALLBACK_MIN_QUOTA_PERCENT: i32 = 1;
+
+/// 带可用性检查的模型路由解析
+/// 先按 `resolve_model_route` 解析；若目标模型没有可用账号 (全部限流、被配额保护或配额耗尽)，
+/// 则沿原始模型 (其次为映射结果) 的降级链选择第一个有可用账号的模型
+pub async fn resolve_model_route_with_availability(
+    original_model: &str,
+    custom_mapping: &HashMap<String, String>,
+    regex_mapping: &RegexMappingStore,
+    fallback_chains: &HashMap<String, Vec<String>>,
+    token_manager: &crate::proxy::TokenManager,
+) -> String {
+    let resolved = resolve_model_route(original_model, custom_mapping, regex_mapping);
+
+    let chain = match fallback_chains
+        .get(original_model)
+        .or_else(|| fallback_chains.get(&resolved))
+    {
+        Some(chain) if !chain.is_empty() => chain,
+        _ => return resolved,
+    };
+
+    if token_manager.is_model_available_with_min_percent(&resolved, FALLBACK_MIN_QUOTA_PERCENT) {
+        return resolved;
+    }
+
+    for candidate in chain {
+        if candidate == &resolved {
+            continue;
+        }
+        if token_manager.is_model_available_with_min_percent(candidate, FALLBACK_MIN_QUOTA_PERCENT) {
+            crate::modules::logger::log_info(&format!(
+                "[Router] 降级链: {} -> {} ({} 无可用账号)",
+                original_model, candidate, resolved
+            ));
+            return candidate.clone();
+        }
+    }
+
+    tracing::warn!(
+        "[Router] 降级链中没有可用模型，保持原映射: {} -> {}",
+        original_model, resolved
+    );
+    resolved
+}
+
 /// Normalize any physical model name to one of the 3 standard protection IDs.
 /// This ensures quota protection works consistently regardless of API versioning or request variations.
 /// 
@@ -237,4 +455,43 @@ mod tests {
             "claude-sonnet-4-5"
         );
     }
+
+    #[test]
+    fn test_model_capability_lookup() {
+        let sonnet = get_model_capability("claude-sonnet-4-5");
+        assert_eq!(sonnet.context_tokens, 200_000);
+        assert!(!sonnet.supports_thinking);
+
+        // 别名经系统映射后命中
+        assert!(get_model_capability("claude-opus-4-5-20251101").supports_thinking);
+
+        // 图像模型的分辨率/比例后缀走前缀匹配
+        assert!(!get_model_capability("gemini-3-pro-image-4k-16x9").supports_tools);
+
+        // 未知模型回退默认值
+        assert_eq!(get_model_capability("unknown-model").context_tokens, 1_048_576);
+
+        assert_eq!(DEFAULT_CAPABILITY.max_tool_result_chars(), 200_000);
+        assert_eq!(sonnet.max_tool_result_chars(), 50_000);
+    }
+
+    #[test]
+    fn test_regex_mapping_priority() {
+        use crate::proxy::config::RegexMappingConfig;
+        let store = RegexMappingStore::from_config(&[
+            RegexMappingConfig { pattern: "^gpt-4.*".to_string(), target: "gemini-2.5-pro".to_string(), priority: 0 },
+            RegexMappingConfig { pattern: "^gpt-4o-mini".to_string(), target: "gemini-3-flash".to_string(), priority: 10 },
+            RegexMappingConfig { pattern: "([invalid".to_string(), target: "x".to_string(), priority: 99 },
+        ]);
+        assert_eq!(store.len(), 2);
+
+        let empty = HashMap::new();
+        assert_eq!(resolve_model_route("gpt-4o-mini-2024-07-18", &empty, &store), "gemini-3-flash");
+        assert_eq!(resolve_model_route("gpt-4-turbo", &empty, &store), "gemini-2.5-pro");
+
+        // 精确匹配优先于正则
+        let mut exact = HashMap::new();
+        exact.insert("gpt-4-turbo".to_string(), "claude-sonnet-4-5".to_string());
+        assert_eq!(resolve_model_route("gpt-4-turbo", &exact, &store), "claude-sonnet-4-5");
+    }
 }
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
-// use std::path::PathBuf;
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -9,6 +9,8 @@ pub enum ProxyAuthMode {
     Strict,
     AllExceptHealth,
     Auto,
+    /// 使用外部 IdP 签发的 JWT 认证 (通过 JWKS 验签并校验 aud)
+    Jwt { jwks_url: String, audience: String },
 }
 
 impl Default for ProxyAuthMode {
@@ -17,6 +19,56 @@ impl Default for ProxyAuthMode {
     }
 }
 
+/// API Key 权限
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct KeyPermissions {
+    /// 允许调用会产生上游请求的接口 (chat / messages / generate 等)
+    #[serde(default = "default_true")]
+    pub can_write: bool,
+    #[serde(default)]
+    pub can_read_logs: bool,
+    #[serde(default)]
+    pub can_manage_accounts: bool,
+}
+
+impl KeyPermissions {
+    /// 全部权限 (旧版单一 api_key 使用)
+    pub fn full() -> Self {
+        Self {
+            can_write: true,
+            can_read_logs: true,
+            can_manage_accounts: true,
+        }
+    }
+}
+
+impl Default for KeyPermissions {
+    fn default() -> Self {
+        Self {
+            can_write: true,
+            can_read_logs: false,
+            can_manage_accounts: false,
+        }
+    }
+}
+
+/// API Key 条目 (明文只在生成时返回一次，配置中只保存哈希)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyEntry {
+    /// 密钥的 SHA-256 (小写十六进制)
+    pub key_hash: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub permissions: KeyPermissions,
+    /// 过期时间 (Unix 秒)，None 表示永不过期
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// 每分钟请求数上限，None 表示不限制
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum ZaiDispatchMode {
@@ -100,6 +152,16 @@ pub struct ZaiConfig {
     pub models: ZaiModelDefaults,
     #[serde(default)]
     pub mcp: ZaiMcpConfig,
+    /// Web Reader result cache TTL in seconds (0 disables caching).
+    #[serde(default = "default_web_reader_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    /// Per-domain MCP web tool timeouts in seconds (`example.com` also matches its subdomains).
+    #[serde(default)]
+    pub domain_timeouts: HashMap<String, u64>,
+}
+
+fn default_web_reader_cache_ttl_secs() -> u64 {
+    300
 }
 
 impl Default for ZaiConfig {
@@ -112,6 +174,68 @@ impl Default for ZaiConfig {
             model_mapping: HashMap::new(),
             models: ZaiModelDefaults::default(),
             mcp: ZaiMcpConfig::default(),
+            cache_ttl_secs: default_web_reader_cache_ttl_secs(),
+            domain_timeouts: HashMap::new(),
+        }
+    }
+}
+
+/// 正则模型映射规则 (持久化形式)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegexMappingConfig {
+    /// 正则表达式 (匹配原始模型名)
+    pub pattern: String,
+    /// 目标模型名
+    pub target: String,
+    /// 优先级，数值越大越先匹配
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// 上游瞬时错误重试配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// 最大请求次数 (含首次)，1 表示不重试
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+    /// 触发重试的上游状态码
+    #[serde(default = "default_retry_statuses")]
+    pub retry_statuses: Vec<u16>,
+    /// 首次重试前的等待时间 (毫秒)
+    #[serde(default = "default_retry_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+    /// 每次重试等待时间的倍数
+    #[serde(default = "default_retry_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    /// 等待时间随机浮动比例 (0.2 表示 ±20%)
+    #[serde(default = "default_retry_jitter_ratio")]
+    pub jitter_ratio: f64,
+    /// 流式响应中途断线时的最大重连次数，0 表示不重连
+    #[serde(default = "default_max_reconnect_attempts")]
+    pub max_reconnect_attempts: u32,
+    /// 断线后重连前的等待时间 (毫秒)
+    #[serde(default = "default_reconnect_delay_ms")]
+    pub reconnect_delay_ms: u64,
+}
+
+fn default_retry_max_attempts() -> u32 { 3 }
+fn default_retry_statuses() -> Vec<u16> { vec![502, 503] }
+fn default_retry_initial_delay_ms() -> u64 { 500 }
+fn default_retry_backoff_multiplier() -> f64 { 2.0 }
+fn default_retry_jitter_ratio() -> f64 { 0.2 }
+fn default_max_reconnect_attempts() -> u32 { 2 }
+fn default_reconnect_delay_ms() -> u64 { 1000 }
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_max_attempts(),
+            retry_statuses: default_retry_statuses(),
+            initial_delay_ms: default_retry_initial_delay_ms(),
+            backoff_multiplier: default_retry_backoff_multiplier(),
+            jitter_ratio: default_retry_jitter_ratio(),
+            max_reconnect_attempts: default_max_reconnect_attempts(),
+            reconnect_delay_ms: default_reconnect_delay_ms(),
         }
     }
 }
@@ -135,6 +259,10 @@ pub struct ExperimentalConfig {
     /// 用于解决客户端因 Gemini 上下文过大而错误触发压缩的问题
     #[serde(default = "default_true")]
     pub enable_usage_scaling: bool,
+
+    /// 合并并发的相同非流式请求 (In-flight Request Coalescing)，默认关闭
+    #[serde(default)]
+    pub enable_request_dedup: bool,
 }
 
 impl Default for ExperimentalConfig {
@@ -144,6 +272,7 @@ impl Default for ExperimentalConfig {
             enable_tool_loop_recovery: true,
             enable_cross_model_checks: true,
             enable_usage_scaling: true,
+            enable_request_dedup: false,
         }
     }
 }
@@ -169,12 +298,32 @@ pub struct ProxyConfig {
     /// - auto: recommended defaults (currently: allow_lan_access => all_except_health, else off)
     #[serde(default)]
     pub auth_mode: ProxyAuthMode,
+
+    /// 客户端 IP 白名单 (CIDR，如 `192.168.1.0/24`)；为空时不限制
+    #[serde(default)]
+    pub ip_allowlist: Vec<String>,
+
+    /// 受信任的反向代理 (CIDR)；仅当连接对端在此列表中时才采用 X-Forwarded-For 识别客户端 IP
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+
+    /// 始终免认证的路径 (精确匹配，或以 `*` 结尾表示前缀匹配，如 `/healthz`、`/metrics*`)
+    #[serde(default = "default_bypass_paths")]
+    pub bypass_paths: Vec<String>,
+
+    /// 允许跨域访问的来源 (如 `https://app.example.com`、`*.example.com`)；为空时允许任意来源
+    #[serde(default)]
+    pub cors_allowed_origins: Vec<String>,
     
     /// 监听端口
     pub port: u16,
     
-    /// API 密钥
+    /// API 密钥 (兼容旧版的单一密钥，拥有全部权限)
     pub api_key: String,
+
+    /// 多 API Key (仅保存 SHA-256 哈希)，每个 Key 可单独设置权限与过期时间
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyEntry>,
     
 
     /// 是否自动启动
@@ -184,6 +333,15 @@ pub struct ProxyConfig {
     #[serde(default)]
     pub custom_mapping: std::collections::HashMap<String, String>,
 
+    /// 正则模型映射规则 (在精确匹配之后、系统默认映射之前生效)
+    #[serde(default)]
+    pub regex_mapping: Vec<RegexMappingConfig>,
+
+    /// 模型降级链 (key: 原始模型名, value: 按顺序尝试的备选模型)
+    /// 当映射结果没有可用账号时，选择链上第一个有可用账号的模型
+    #[serde(default)]
+    pub fallback_chains: std::collections::HashMap<String, Vec<String>>,
+
     /// API 请求超时时间(秒)
     #[serde(default = "default_request_timeout")]
     pub request_timeout: u64,
@@ -192,10 +350,47 @@ pub struct ProxyConfig {
     #[serde(default)]
     pub enable_logging: bool,
 
+    /// 日志数据库大小上限 (MB)，超过后自动删除最旧的 10% 日志；None 表示不限制
+    #[serde(default)]
+    pub max_db_size_mb: Option<u64>,
+
+    /// 请求体大小上限 (MB)，超过时返回 413
+    #[serde(default = "default_max_request_body_mb")]
+    pub max_request_body_mb: u64,
+
+    /// 流式响应空闲心跳间隔 (秒)：超过该时间未向客户端发送数据时发送 SSE 注释保活；0 表示关闭
+    #[serde(default = "default_stream_heartbeat_interval_secs")]
+    pub stream_heartbeat_interval_secs: u64,
+
+    /// 客户端支持时以 gzip 压缩非流式响应 (模型列表、错误信息等)
+    #[serde(default = "default_response_compression")]
+    pub response_compression: bool,
+
+    /// 注入到所有响应中的自定义响应头 (Header -> Value)
+    #[serde(default)]
+    pub response_headers: HashMap<String, String>,
+
+    /// 按模型覆盖上游请求超时 (模型名 -> 秒)，未配置的模型使用 request_timeout
+    /// 超时只限制等待响应头的时间，不截断流式输出；思考模型首包通常较慢，建议 300 秒以上
+    #[serde(default)]
+    pub model_timeouts: HashMap<String, u64>,
+
+    /// gRPC 推理服务端口 (需以 `--features grpc` 构建)；None 表示不启用
+    #[serde(default)]
+    pub grpc_port: Option<u16>,
+
     /// 上游代理配置
     #[serde(default)]
     pub upstream_proxy: UpstreamProxyConfig,
 
+    /// 访问 Google API 时使用的客户端证书 (mTLS)；None 表示不启用
+    #[serde(default)]
+    pub upstream_mtls: Option<MtlsConfig>,
+
+    /// 非流式响应签名 (X-Response-Signature)；None 表示不启用
+    #[serde(default)]
+    pub response_signing: Option<ResponseSigningConfig>,
+
     /// z.ai provider configuration (Anthropic-compatible).
     #[serde(default)]
     pub zai: ZaiConfig,
@@ -207,6 +402,22 @@ pub struct ProxyConfig {
     /// 实验性功能配置
     #[serde(default)]
     pub experimental: ExperimentalConfig,
+
+    /// 上游瞬时错误 (502/503) 重试配置
+    #[serde(default)]
+    pub retry: RetryConfig,
+
+    /// 启用 OpenTelemetry 链路追踪导出 (需以 `otel` feature 编译，修改后重启生效)
+    #[serde(default)]
+    pub enable_otel: bool,
+
+    /// OTLP gRPC 导出地址，未设置时使用 http://localhost:4317
+    #[serde(default)]
+    pub otel_endpoint: Option<String>,
+
+    /// 账号健康探测间隔(秒)，0 表示关闭
+    #[serde(default = "default_health_probe_interval")]
+    pub health_probe_interval_secs: u64,
 }
 
 /// 上游代理配置
@@ -215,7 +426,102 @@ pub struct UpstreamProxyConfig {
     /// 是否启用
     pub enabled: bool,
     /// 代理地址 (http://, https://, socks5://)
+    /// 已弃用：保留用于兼容旧配置，与 urls 合并使用
+    #[serde(default)]
     pub url: String,
+    /// 代理地址列表，按轮询使用并在连续失败时自动切换
+    #[serde(default)]
+    pub urls: Vec<String>,
+}
+
+impl UpstreamProxyConfig {
+    /// 生效的代理地址 (去重，url 排在 urls 之后)；未启用时为空
+    pub fn proxy_urls(&self) -> Vec<String> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let mut urls: Vec<String> = Vec::new();
+        for url in self.urls.iter().chain(std::iter::once(&self.url)) {
+            let url = url.trim();
+            if !url.is_empty() && !urls.iter().any(|u| u == url) {
+                urls.push(url.to_string());
+            }
+        }
+        urls
+    }
+}
+
+/// 上游 mTLS 客户端证书配置 (PEM 格式，私钥需为 PKCS#8)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MtlsConfig {
+    /// 客户端证书 (可包含中间证书链)
+    pub cert_path: PathBuf,
+    /// 客户端私钥
+    pub key_path: PathBuf,
+    /// 自定义 CA 证书；None 时使用系统根证书
+    #[serde(default)]
+    pub ca_path: Option<PathBuf>,
+}
+
+impl MtlsConfig {
+    fn read_pem(kind: &str, path: &Path) -> Result<Vec<u8>, String> {
+        if !path.is_file() {
+            return Err(format!("{} 文件不存在: {}", kind, path.display()));
+        }
+        std::fs::read(path).map_err(|e| format!("读取{}失败 ({}): {}", kind, path.display(), e))
+    }
+
+    /// 加载并解析证书与私钥 (启动反代服务时用于校验配置)
+    pub fn load(&self) -> Result<(reqwest::Identity, Option<reqwest::Certificate>), String> {
+        let cert = Self::read_pem("客户端证书", &self.cert_path)?;
+        let key = Self::read_pem("客户端私钥", &self.key_path)?;
+        let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key).map_err(|e| {
+            format!(
+                "解析客户端证书/私钥失败 ({} / {}): {}",
+                self.cert_path.display(),
+                self.key_path.display(),
+                e
+            )
+        })?;
+
+        let ca = match &self.ca_path {
+            Some(path) => {
+                let pem = Self::read_pem("CA 证书", path)?;
+                Some(
+                    reqwest::Certificate::from_pem(&pem)
+                        .map_err(|e| format!("解析 CA 证书失败 ({}): {}", path.display(), e))?,
+                )
+            }
+            None => None,
+        };
+        Ok((identity, ca))
+    }
+
+    /// 为 Client 配置客户端证书与自定义 CA
+    pub fn apply(&self, builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, String> {
+        let (identity, ca) = self.load()?;
+        let builder = builder.identity(identity);
+        Ok(match ca {
+            Some(ca) => builder.add_root_certificate(ca),
+            None => builder,
+        })
+    }
+}
+
+/// 响应签名算法
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SigningAlgorithm {
+    #[default]
+    Ed25519,
+}
+
+/// 响应签名配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseSigningConfig {
+    /// PKCS#8 PEM 格式的私钥
+    pub private_key_path: PathBuf,
+    #[serde(default)]
+    pub algorithm: SigningAlgorithm,
 }
 
 impl Default for ProxyConfig {
@@ -224,16 +530,36 @@ impl Default for ProxyConfig {
             enabled: false,
             allow_lan_access: false, // 默认仅本机访问，隐私优先
             auth_mode: ProxyAuthMode::default(),
+            ip_allowlist: Vec::new(),
+            trusted_proxies: Vec::new(),
+            bypass_paths: default_bypass_paths(),
+            cors_allowed_origins: Vec::new(),
             port: 8045,
             api_key: format!("sk-{}", uuid::Uuid::new_v4().simple()),
+            api_keys: Vec::new(),
             auto_start: false,
             custom_mapping: std::collections::HashMap::new(),
+            regex_mapping: Vec::new(),
+            fallback_chains: std::collections::HashMap::new(),
             request_timeout: default_request_timeout(),
             enable_logging: false, // 默认关闭，节省性能
+            max_db_size_mb: None,
+            max_request_body_mb: default_max_request_body_mb(),
+            stream_heartbeat_interval_secs: default_stream_heartbeat_interval_secs(),
+            response_compression: default_response_compression(),
+            response_headers: HashMap::new(),
+            model_timeouts: HashMap::new(),
+            grpc_port: None,
             upstream_proxy: UpstreamProxyConfig::default(),
+            upstream_mtls: None,
+            response_signing: None,
             zai: ZaiConfig::default(),
             scheduling: crate::proxy::sticky_config::StickySessionConfig::default(),
             experimental: ExperimentalConfig::default(),
+            retry: RetryConfig::default(),
+            enable_otel: false,
+            otel_endpoint: None,
+            health_probe_interval_secs: default_health_probe_interval(),
         }
     }
 }
@@ -242,6 +568,26 @@ fn default_request_timeout() -> u64 {
     120  // 默认 120 秒,原来 60 秒太短
 }
 
+fn default_max_request_body_mb() -> u64 {
+    10
+}
+
+fn default_stream_heartbeat_interval_secs() -> u64 {
+    30
+}
+
+fn default_response_compression() -> bool {
+    true
+}
+
+fn default_health_probe_interval() -> u64 {
+    600
+}
+
+fn default_bypass_paths() -> Vec<String> {
+    vec!["/metrics".to_string()]
+}
+
 fn default_zai_base_url() -> String {
     "https://api.z.ai/api/anthropic".to_string()
 }
@@ -0,0 +1,119 @@
+// 按账号隔离的上游 HTTP 连接池
+// 每个账号复用独立的 reqwest::Client，避免不同账号的连接互相复用，同时保留同账号的 keep-alive
+use dashmap::DashMap;
+use reqwest::Client;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::proxy::config::{MtlsConfig, UpstreamProxyConfig};
+use crate::proxy::upstream_proxy::PooledClient;
+
+/// 每主机最多保留的空闲连接数
+const POOL_MAX_IDLE_PER_HOST: usize = 4;
+/// TCP 保活探测间隔
+const TCP_KEEPALIVE: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+pub struct ConnectionPoolManager {
+    clients: DashMap<String, Arc<PooledClient>>,
+}
+
+impl ConnectionPoolManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 获取账号专属的 Client (不存在时创建并缓存；所用代理进入冷却时重新选择代理并重建)
+    /// 创建失败时返回 None，由调用方回退到共享 Client
+    pub fn build_client_for_account(
+        &self,
+        account_id: &str,
+        proxy_config: Option<&UpstreamProxyConfig>,
+        mtls_config: Option<&MtlsConfig>,
+    ) -> Option<Arc<PooledClient>> {
+        let stale = match self.clients.get(account_id) {
+            Some(client) if !client.needs_reselect() => return Some(client.clone()),
+            Some(client) => Some(client.clone()),
+            None => None,
+        };
+
+        let mut builder = Client::builder()
+            .connection_verbose(false)
+            .connect_timeout(Duration::from_secs(20))
+            .pool_max_idle_per_host(POOL_MAX_IDLE_PER_HOST)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(TCP_KEEPALIVE)
+            .timeout(Duration::from_secs(600))
+            .user_agent("antigravity/1.11.9 windows/amd64");
+
+        let mut proxy_url = proxy_config
+            .and_then(|c| crate::proxy::upstream_proxy::UpstreamProxyPool::global().next_url(c));
+        if let Some(url) = &proxy_url {
+            match reqwest::Proxy::all(url) {
+                Ok(proxy) => builder = builder.proxy(proxy),
+                Err(_) => proxy_url = None,
+            }
+        }
+        if let Some(mtls) = mtls_config {
+            builder = match mtls.apply(builder) {
+                Ok(builder) => builder,
+                Err(e) => {
+                    tracing::warn!("账号 {} 的 HTTP Client 配置 mTLS 失败: {}", account_id, e);
+                    return None;
+                }
+            };
+        }
+
+        match builder.build() {
+            Ok(client) => {
+                let client = Arc::new(PooledClient { client, proxy_url });
+                let mut entry = self.clients.entry(account_id.to_string()).or_insert_with(|| client.clone());
+                // 并发创建时以先插入者为准；替换代理冷却中的旧 Client
+                if stale.as_ref().map(|s| Arc::ptr_eq(s, &*entry)).unwrap_or(false) {
+                    *entry = client;
+                }
+                Some(entry.clone())
+            }
+            Err(e) => {
+                tracing::warn!("创建账号 {} 的 HTTP Client 失败: {}", account_id, e);
+                None
+            }
+        }
+    }
+
+    /// 移除不在活跃账号列表中的 Client，返回移除数量
+    pub fn retain_accounts(&self, active_ids: &HashSet<String>) -> usize {
+        let before = self.clients.len();
+        self.clients.retain(|id, _| active_ids.contains(id));
+        before.saturating_sub(self.clients.len())
+    }
+
+    /// 清空所有 Client (上游代理配置变更时调用)
+    pub fn clear(&self) {
+        self.clients.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clients_cached_per_account_and_evicted() {
+        let pool = ConnectionPoolManager::new();
+        let a1 = pool.build_client_for_account("acc-1", None, None).unwrap();
+        let a2 = pool.build_client_for_account("acc-1", None, None).unwrap();
+        assert!(Arc::ptr_eq(&a1, &a2));
+        pool.build_client_for_account("acc-2", None, None).unwrap();
+        assert_eq!(pool.len(), 2);
+
+        let active: HashSet<String> = ["acc-2".to_string()].into_iter().collect();
+        assert_eq!(pool.retain_accounts(&active), 1);
+        assert_eq!(pool.len(), 1);
+    }
+}
@@ -0,0 +1,220 @@
+// 相同请求合并 (In-flight Request Coalescing)
+// 多个会话并发发送完全相同的非流式请求时，仅向上游发送一次，其余请求等待并复用同一响应 (含响应头)
+// 由 experimental.enable_request_dedup 控制，默认关闭
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, StatusCode},
+    response::Response,
+};
+use bytes::Bytes;
+use dashmap::DashMap;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::proxy::monitor::ProxyMonitor;
+
+const MAX_COALESCED_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
+
+/// 首个请求的响应 (成功时为响应头与响应体)
+type SharedResponse = Result<(HeaderMap, Bytes), String>;
+
+/// 进行中请求表 (请求指纹 -> 响应广播通道)
+pub type InFlightMap = DashMap<String, Arc<broadcast::Sender<SharedResponse>>>;
+
+/// 不影响响应内容、每次请求都可能不同的字段，不计入指纹
+const VOLATILE_FIELDS: &[&str] = &["metadata"];
+
+/// 按键排序写出 JSON，保证字段顺序不同的相同请求得到相同指纹
+pub(crate) fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// 计算请求指纹：完整请求体 (去除易变字段) 的规范化 SHA-256；流式请求返回 None 不参与合并
+/// 覆盖所有影响输出的参数 (temperature、max_tokens、thinking 等)，避免参数不同的请求被错误合并
+pub fn dedup_key(body: &Value) -> Option<String> {
+    if body.get("stream").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return None;
+    }
+    let mut map = body.as_object()?.clone();
+    map.get("model")?.as_str()?;
+    map.get("messages")?;
+    for field in VOLATILE_FIELDS {
+        map.remove(*field);
+    }
+
+    let mut canonical = String::new();
+    write_canonical(&Value::Object(map), &mut canonical);
+    Some(format!("{:x}", Sha256::digest(canonical.as_bytes())))
+}
+
+/// 复用首个请求的响应头 (长度与传输编码由新响应体重新确定)
+fn replay_response(headers: &HeaderMap, bytes: Bytes) -> Response {
+    let mut builder = Response::builder().status(StatusCode::OK);
+    for (name, value) in headers {
+        if name != header::CONTENT_LENGTH && name != header::TRANSFER_ENCODING {
+            builder = builder.header(name, value);
+        }
+    }
+    builder
+        .body(Body::from(bytes))
+        .unwrap_or_else(|_| Response::new(Body::empty()))
+}
+
+/// 首个请求退出时 (包括客户端断开导致 future 被丢弃) 移除进行中记录，
+/// 等待者随即收到 Closed 并自行发起请求
+struct InFlightGuard<'a> {
+    map: &'a InFlightMap,
+    key: String,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.map.remove(&self.key);
+    }
+}
+
+/// 执行请求，相同指纹的并发请求只执行一次
+pub async fn coalesce<F, Fut>(
+    in_flight: &InFlightMap,
+    monitor: &ProxyMonitor,
+    key: Option<String>,
+    f: F,
+) -> Response
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let Some(key) = key else {
+        return f().await;
+    };
+
+    // 在持有表项引用时订阅，保证不会错过首个请求的广播
+    let (waiter, leader_tx) = match in_flight.entry(key.clone()) {
+        dashmap::mapref::entry::Entry::Occupied(e) => (Some(e.get().subscribe()), None),
+        dashmap::mapref::entry::Entry::Vacant(e) => {
+            let (tx, _) = broadcast::channel(1);
+            let tx = Arc::new(tx);
+            e.insert(tx.clone());
+            (None, Some(tx))
+        }
+    };
+
+    if let Some(mut rx) = waiter {
+        if let Ok(Ok((headers, bytes))) = rx.recv().await {
+            tracing::debug!("[Dedup] Reusing in-flight response for identical request");
+            monitor.record_dedup_hit();
+            return replay_response(&headers, bytes);
+        }
+        // 首个请求失败或被取消，自行请求
+        return f().await;
+    }
+
+    let guard = InFlightGuard { map: in_flight, key };
+    let response = f().await;
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_COALESCED_BODY_SIZE).await {
+        Ok(b) => b,
+        Err(e) => {
+            drop(guard);
+            return Response::from_parts(parts, Body::from(format!("Failed to read response body: {}", e)));
+        }
+    };
+
+    // 先移除表项再广播：之后到达的相同请求会重新发起，而不是订阅已结束的通道
+    drop(guard);
+    if let Some(tx) = leader_tx {
+        let message = if parts.status.is_success() {
+            Ok((parts.headers.clone(), bytes.clone()))
+        } else {
+            Err(format!("upstream returned {}", parts.status))
+        };
+        let _ = tx.send(message);
+    }
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_dedup_key() {
+        let body = json!({
+            "model": "claude-sonnet-4-5",
+            "system": "You are a test fixture",
+            "messages": [{ "role": "user", "content": "hello" }]
+        });
+        let key = dedup_key(&body).unwrap();
+        assert_eq!(dedup_key(&body.clone()).unwrap(), key);
+
+        let mut multi_turn = body.clone();
+        multi_turn["messages"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "role": "assistant", "content": "hi" }));
+        assert_ne!(dedup_key(&multi_turn).unwrap(), key);
+
+        // 采样参数不同的请求不合并
+        let mut hotter = body.clone();
+        hotter["temperature"] = json!(1.0);
+        assert_ne!(dedup_key(&hotter).unwrap(), key);
+
+        // 字段顺序与易变字段不影响指纹
+        let reordered = json!({
+            "metadata": { "user_id": "user-123" },
+            "messages": [{ "content": "hello", "role": "user" }],
+            "system": "You are a test fixture",
+            "model": "claude-sonnet-4-5"
+        });
+        assert_eq!(dedup_key(&reordered).unwrap(), key);
+
+        let mut streaming = body.clone();
+        streaming["stream"] = json!(true);
+        assert!(dedup_key(&streaming).is_none());
+    }
+
+    #[test]
+    fn test_replay_response_keeps_leader_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+        headers.insert("x-account-email", "a***@example.com".parse().unwrap());
+        headers.insert(header::CONTENT_LENGTH, "999".parse().unwrap());
+
+        let response = replay_response(&headers, Bytes::from_static(b"{}"));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-account-email"], "a***@example.com");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        assert!(response.headers().get(header::CONTENT_LENGTH).is_none());
+    }
+}
@@ -0,0 +1,140 @@
+// 账号错误预算
+// 按账号维护最近 1 小时的上游请求结果 (成功/失败) 滑动窗口，计算 1 小时与 5 分钟错误率
+// 比单纯的 "是否被限流" 更能反映账号的整体健康状况
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const LONG_WINDOW: Duration = Duration::from_secs(3600);
+const SHORT_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// 5 分钟错误率超过该值时视为错误预算耗尽
+pub const ERROR_BUDGET_TRIP_RATE: f64 = 0.5;
+
+/// 5 分钟窗口内样本少于该值时不判定错误预算耗尽
+const MIN_SHORT_WINDOW_SAMPLES: usize = 10;
+
+/// 账号错误预算快照
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorBudgetSnapshot {
+    pub account_id: String,
+    pub error_rate_1h: f64,
+    pub error_rate_5m: f64,
+}
+
+#[derive(Default)]
+pub struct ErrorBudgetTracker {
+    outcomes: DashMap<String, VecDeque<(Instant, bool)>>,
+}
+
+fn error_rate_since(window: &VecDeque<(Instant, bool)>, since: Option<Instant>) -> (usize, f64) {
+    let (total, failures) = window
+        .iter()
+        .filter(|(at, _)| since.map(|s| *at >= s).unwrap_or(true))
+        .fold((0usize, 0usize), |(total, failures), (_, success)| {
+            (total + 1, failures + usize::from(!success))
+        });
+    if total == 0 {
+        (0, 0.0)
+    } else {
+        (total, failures as f64 / total as f64)
+    }
+}
+
+impl ErrorBudgetTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次上游响应结果，返回错误预算是否已耗尽 (5 分钟错误率 > 50%)
+    pub fn record(&self, account_id: &str, is_success: bool) -> bool {
+        self.record_at(account_id, is_success, Instant::now())
+    }
+
+    fn record_at(&self, account_id: &str, is_success: bool, now: Instant) -> bool {
+        let mut window = self.outcomes.entry(account_id.to_string()).or_default();
+        while window
+            .front()
+            .map(|(at, _)| now.duration_since(*at) > LONG_WINDOW)
+            .unwrap_or(false)
+        {
+            window.pop_front();
+        }
+        window.push_back((now, is_success));
+
+        let (samples, rate) = error_rate_since(&window, now.checked_sub(SHORT_WINDOW));
+        samples >= MIN_SHORT_WINDOW_SAMPLES && rate > ERROR_BUDGET_TRIP_RATE
+    }
+
+    /// 最近 1 小时错误率 (无样本时为 0)
+    pub fn error_rate(&self, account_id: &str) -> f64 {
+        self.rates_at(account_id, Instant::now()).0
+    }
+
+    /// (1 小时错误率, 5 分钟错误率)
+    fn rates_at(&self, account_id: &str, now: Instant) -> (f64, f64) {
+        match self.outcomes.get(account_id) {
+            Some(window) => (
+                error_rate_since(&window, now.checked_sub(LONG_WINDOW)).1,
+                error_rate_since(&window, now.checked_sub(SHORT_WINDOW)).1,
+            ),
+            None => (0.0, 0.0),
+        }
+    }
+
+    /// 所有有记录账号的错误率快照 (按 1 小时错误率降序)
+    pub fn snapshot(&self) -> Vec<ErrorBudgetSnapshot> {
+        let now = Instant::now();
+        let mut snapshots: Vec<ErrorBudgetSnapshot> = self
+            .outcomes
+            .iter()
+            .map(|entry| {
+                let (error_rate_1h, error_rate_5m) = self.rates_at(entry.key(), now);
+                ErrorBudgetSnapshot {
+                    account_id: entry.key().clone(),
+                    error_rate_1h,
+                    error_rate_5m,
+                }
+            })
+            .collect();
+        snapshots.sort_by(|a, b| b.error_rate_1h.total_cmp(&a.error_rate_1h));
+        snapshots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliding_windows_and_budget_exhaustion() {
+        let tracker = ErrorBudgetTracker::new();
+        let start = Instant::now();
+
+        // 一小时前半段：全部失败，但超出 5 分钟窗口
+        for i in 0..10 {
+            tracker.record_at("acc", false, start + Duration::from_secs(i));
+        }
+        let now = start + Duration::from_secs(30 * 60);
+        for i in 0..10 {
+            assert!(!tracker.record_at("acc", true, now + Duration::from_millis(i)));
+        }
+        let (rate_1h, rate_5m) = tracker.rates_at("acc", now + Duration::from_secs(1));
+        assert!((rate_1h - 0.5).abs() < 1e-9);
+        assert_eq!(rate_5m, 0.0);
+
+        // 5 分钟内失败占多数后错误预算耗尽
+        let mut exhausted = false;
+        for i in 0..11 {
+            exhausted = tracker.record_at("acc", false, now + Duration::from_secs(10 + i));
+        }
+        assert!(exhausted);
+
+        // 超过 1 小时的样本被淘汰
+        let later = start + Duration::from_secs(2 * 3600);
+        assert!(!tracker.record_at("acc", true, later));
+        assert_eq!(tracker.rates_at("acc", later), (0.0, 0.0));
+        assert_eq!(tracker.rates_at("unknown", later), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,177 @@
+// 错误响应格式协商
+// 默认返回 Anthropic 风格错误；客户端声明 X-Target-Format: openai、使用 OpenAI SDK (User-Agent)
+// 或请求 OpenAI 端点时，改为 OpenAI 风格 { error: { message, type, param, code } }
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+pub const TARGET_FORMAT_HEADER: &str = "X-Target-Format";
+
+/// OpenAI 官方 SDK 的 User-Agent 前缀 (openai-python / openai-node / openai-go 等)
+const OPENAI_SDK_UA_PATTERNS: &[&str] = &["openai/", "openai-python", "openai-node", "openai-go", "asyncopenai"];
+
+/// OpenAI 兼容端点
+const OPENAI_PATHS: &[&str] = &["/v1/chat/completions", "/v1/completions", "/v1/responses"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientFormat {
+    Anthropic,
+    OpenAI,
+}
+
+/// 根据请求路径与请求头判断客户端期望的错误格式
+/// 优先级: X-Target-Format > OpenAI 兼容端点 > User-Agent
+pub fn detect_client_format(path: &str, headers: &HeaderMap) -> ClientFormat {
+    if let Some(target) = headers.get(TARGET_FORMAT_HEADER).and_then(|v| v.to_str().ok()) {
+        match target.trim().to_ascii_lowercase().as_str() {
+            "openai" => return ClientFormat::OpenAI,
+            "anthropic" | "claude" => return ClientFormat::Anthropic,
+            _ => {}
+        }
+    }
+
+    if OPENAI_PATHS.iter().any(|p| path.starts_with(p)) {
+        return ClientFormat::OpenAI;
+    }
+
+    let is_openai_sdk = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|ua| {
+            let ua = ua.to_ascii_lowercase();
+            OPENAI_SDK_UA_PATTERNS.iter().any(|p| ua.contains(p))
+        })
+        .unwrap_or(false);
+    if is_openai_sdk {
+        ClientFormat::OpenAI
+    } else {
+        ClientFormat::Anthropic
+    }
+}
+
+pub fn anthropic_error(status: StatusCode, error_type: &str, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "type": "error",
+            "error": {
+                "type": error_type,
+                "message": message
+            }
+        })),
+    )
+        .into_response()
+}
+
+pub fn openai_error(status: StatusCode, error_type: &str, message: &str, code: Option<&str>) -> Response {
+    (
+        status,
+        Json(json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "param": null,
+                "code": code
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 获取账号失败 (账号池耗尽 / Token 刷新失败) 时的错误响应
+pub fn map_token_manager_error_to_anthropic(format: ClientFormat, error: &str) -> Response {
+    let safe_message = if error.contains("invalid_grant") {
+        "OAuth refresh failed (invalid_grant): refresh_token likely revoked/expired; reauthorize account(s) to restore service."
+    } else {
+        error
+    };
+    let message = format!("No available accounts: {}", safe_message);
+    match format {
+        ClientFormat::Anthropic => anthropic_error(StatusCode::SERVICE_UNAVAILABLE, "overloaded_error", &message),
+        ClientFormat::OpenAI => openai_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "server_error",
+            &message,
+            Some("service_unavailable"),
+        ),
+    }
+}
+
+/// 所有重试均失败后，根据最后一次上游 (Google) 错误构造响应
+pub fn map_google_upstream_error_to_anthropic(format: ClientFormat, max_attempts: usize, last_error: &str) -> Response {
+    let message = format!("All {} attempts failed. Last error: {}", max_attempts, last_error);
+    match format {
+        ClientFormat::Anthropic => anthropic_error(StatusCode::TOO_MANY_REQUESTS, "overloaded_error", &message),
+        ClientFormat::OpenAI => openai_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limit_error",
+            &message,
+            Some("rate_limit_exceeded"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (k, v) in pairs {
+            map.insert(*k, HeaderValue::from_static(v));
+        }
+        map
+    }
+
+    #[test]
+    fn test_detect_client_format() {
+        let messages = "/v1/messages";
+        assert_eq!(detect_client_format(messages, &headers(&[])), ClientFormat::Anthropic);
+        assert_eq!(detect_client_format(messages, &headers(&[("x-target-format", "OpenAI")])), ClientFormat::OpenAI);
+        assert_eq!(
+            detect_client_format(messages, &headers(&[("user-agent", "OpenAI/Python 1.51.0")])),
+            ClientFormat::OpenAI
+        );
+        assert_eq!(
+            detect_client_format(messages, &headers(&[("user-agent", "claude-cli/1.0.0 (external, cli)")])),
+            ClientFormat::Anthropic
+        );
+        // 显式声明优先于 User-Agent 与请求路径
+        assert_eq!(
+            detect_client_format(
+                messages,
+                &headers(&[("x-target-format", "anthropic"), ("user-agent", "OpenAI/Python 1.51.0")])
+            ),
+            ClientFormat::Anthropic
+        );
+        assert_eq!(
+            detect_client_format("/v1/chat/completions", &headers(&[("x-target-format", "anthropic")])),
+            ClientFormat::Anthropic
+        );
+        assert_eq!(detect_client_format("/v1/chat/completions", &headers(&[])), ClientFormat::OpenAI);
+    }
+
+    #[tokio::test]
+    async fn test_error_shapes() {
+        let response = map_token_manager_error_to_anthropic(ClientFormat::OpenAI, "invalid_grant");
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["type"], "server_error");
+        assert_eq!(json["error"]["code"], "service_unavailable");
+        assert!(json["error"]["param"].is_null());
+        assert!(json.get("type").is_none());
+
+        let response = map_google_upstream_error_to_anthropic(ClientFormat::Anthropic, 3, "HTTP 429");
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["type"], "error");
+        assert_eq!(json["error"]["type"], "overloaded_error");
+        assert_eq!(json["error"]["message"], "All 3 attempts failed. Last error: HTTP 429");
+    }
+}
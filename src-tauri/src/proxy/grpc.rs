@@ -0,0 +1,210 @@
+// gRPC 推理服务 (可选，`--features grpc`)
+// 请求转换为 Gemini 原生 HTTP 请求后交给 Axum 路由处理，
+// 与 HTTP 入口经过同一套中间件 (认证 / 按 Key 限流 / 幂等缓存 / 监控日志 / 指标)
+use axum::{body::Body, extract::ConnectInfo, http::header, Router};
+use futures::{Stream, StreamExt};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use tonic::{Code, Request, Response, Status};
+use tower::ServiceExt;
+
+pub mod pb {
+    tonic::include_proto!("antigravity.inference.v1");
+}
+
+use pb::model_inference_server::{ModelInference, ModelInferenceServer};
+use pb::{GenerateContentRequest, GenerateContentResponse};
+
+const MAX_RESPONSE_SIZE: usize = 100 * 1024 * 1024; // 100MB
+
+pub struct InferenceService {
+    /// 已挂载全部中间件的 HTTP 路由
+    app: Router,
+}
+
+impl InferenceService {
+    pub fn new(app: Router) -> Self {
+        Self { app }
+    }
+
+    /// 经 HTTP 路由转发到 Gemini 处理器，非 2xx 响应 (含认证失败、限流) 转换为 gRPC 错误
+    async fn forward(
+        &self,
+        request: Request<GenerateContentRequest>,
+        method: &str,
+    ) -> Result<axum::response::Response, Status> {
+        let remote_addr = request.remote_addr();
+        let (metadata, _, request) = request.into_parts();
+        if request.model.is_empty() {
+            return Err(Status::invalid_argument("model is required"));
+        }
+        serde_json::from_str::<serde_json::Value>(&request.body_json)
+            .map_err(|e| Status::invalid_argument(format!("invalid body_json: {}", e)))?;
+
+        let mut builder = axum::http::Request::post(format!("/v1beta/models/{}:{}", request.model, method))
+            .header(header::CONTENT_TYPE, "application/json");
+        // 认证、幂等键、会话标识等元数据按同名请求头透传
+        for (name, value) in metadata.into_headers().iter() {
+            if is_forwarded_metadata(name.as_str()) {
+                builder = builder.header(name, value);
+            }
+        }
+        let mut http_request = builder
+            .body(Body::from(request.body_json))
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        if let Some(addr) = remote_addr {
+            http_request.extensions_mut().insert(ConnectInfo(addr));
+        }
+
+        let response = match self.app.clone().oneshot(http_request).await {
+            Ok(response) => response,
+            Err(never) => match never {},
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let message = axum::body::to_bytes(response.into_body(), MAX_RESPONSE_SIZE)
+            .await
+            .map(|b| String::from_utf8_lossy(&b).into_owned())
+            .unwrap_or_default();
+        Err(Status::new(code_from_http(status.as_u16()), message))
+    }
+}
+
+#[tonic::async_trait]
+impl ModelInference for InferenceService {
+    async fn generate_content(
+        &self,
+        request: Request<GenerateContentRequest>,
+    ) -> Result<Response<GenerateContentResponse>, Status> {
+        let response = self.forward(request, "generateContent").await?;
+        let bytes = axum::body::to_bytes(response.into_body(), MAX_RESPONSE_SIZE)
+            .await
+            .map_err(|e| Status::internal(format!("failed to read response: {}", e)))?;
+        Ok(Response::new(GenerateContentResponse {
+            body_json: String::from_utf8_lossy(&bytes).into_owned(),
+        }))
+    }
+
+    type StreamGenerateContentStream =
+        Pin<Box<dyn Stream<Item = Result<GenerateContentResponse, Status>> + Send + 'static>>;
+
+    async fn stream_generate_content(
+        &self,
+        request: Request<GenerateContentRequest>,
+    ) -> Result<Response<Self::StreamGenerateContentStream>, Status> {
+        let response = self.forward(request, "streamGenerateContent").await?;
+
+        let mut decoder = SseDataDecoder::default();
+        let stream = response
+            .into_body()
+            .into_data_stream()
+            .map(move |chunk| match chunk {
+                Ok(bytes) => decoder
+                    .push(&bytes)
+                    .into_iter()
+                    .map(|body_json| Ok(GenerateContentResponse { body_json }))
+                    .collect::<Vec<_>>(),
+                Err(e) => vec![Err(Status::internal(e.to_string()))],
+            })
+            .flat_map(futures::stream::iter);
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// gRPC 协议自身的元数据 (content-type / te / grpc-*) 不作为 HTTP 请求头转发
+fn is_forwarded_metadata(name: &str) -> bool {
+    !matches!(name, "content-type" | "content-length" | "te" | "host") && !name.starts_with("grpc-")
+}
+
+/// HTTP 状态码到 gRPC 状态码的映射
+fn code_from_http(status: u16) -> Code {
+    match status {
+        400 => Code::InvalidArgument,
+        401 => Code::Unauthenticated,
+        403 => Code::PermissionDenied,
+        404 => Code::NotFound,
+        429 => Code::ResourceExhausted,
+        500..=599 => Code::Unavailable,
+        _ => Code::Unknown,
+    }
+}
+
+/// 将 SSE 字节流拆分为 `data:` 负载 (跳过 [DONE])，可跨数据块拼接不完整的行
+#[derive(Default)]
+struct SseDataDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDataDecoder {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                let data = data.trim();
+                if !data.is_empty() && data != "[DONE]" {
+                    events.push(data.to_string());
+                }
+            }
+        }
+        events
+    }
+}
+
+/// 在独立端口启动 gRPC 服务，返回的句柄在停止反代服务时 abort
+pub async fn start(host: &str, port: u16, app: Router) -> Result<tokio::task::JoinHandle<()>, String> {
+    let addr: SocketAddr = format!("{}:{}", host, port)
+        .parse()
+        .map_err(|e| format!("gRPC 地址无效: {}", e))?;
+    // 先绑定端口，便于将占用错误返回给调用方
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("gRPC 地址 {} 绑定失败: {}", addr, e))?;
+    let incoming = tonic::transport::server::TcpIncoming::from_listener(listener, true, None)
+        .map_err(|e| format!("gRPC 监听失败: {}", e))?;
+
+    tracing::info!("gRPC 推理服务启动在 {}", addr);
+    let service = ModelInferenceServer::new(InferenceService::new(app));
+    Ok(tokio::spawn(async move {
+        if let Err(e) = tonic::transport::Server::builder()
+            .add_service(service)
+            .serve_with_incoming(incoming)
+            .await
+        {
+            tracing::error!("gRPC 服务异常退出: {}", e);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_decoder_handles_split_lines() {
+        let mut decoder = SseDataDecoder::default();
+        assert!(decoder.push(b"data: {\"a\":").is_empty());
+        assert_eq!(decoder.push(b"1}\n\ndata: {\"b\":2}\n"), vec!["{\"a\":1}", "{\"b\":2}"]);
+        assert!(decoder.push(b"data: [DONE]\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_forwarded_metadata() {
+        assert!(is_forwarded_metadata("authorization"));
+        assert!(is_forwarded_metadata("x-idempotency-key"));
+        assert!(!is_forwarded_metadata("grpc-timeout"));
+        assert!(!is_forwarded_metadata("content-type"));
+    }
+
+    #[test]
+    fn test_code_from_http() {
+        assert_eq!(code_from_http(429), Code::ResourceExhausted);
+        assert_eq!(code_from_http(503), Code::Unavailable);
+        assert_eq!(code_from_http(401), Code::Unauthenticated);
+    }
+}
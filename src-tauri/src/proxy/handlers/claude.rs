@@ -302,12 +302,35 @@ fn should_rotate_account(status_code: u16) -> bool {
 // ===== 退避策略模块结束 =====
 
 /// 处理 Claude messages 请求
-/// 
-/// 处理 Chat 消息请求流程
+///
+/// 开启 experimental.enable_request_dedup 时，非流式的相同请求在进行中时合并为一次上游调用
 pub async fn handle_messages(
     State(state): State<AppState>,
+    uri: axum::http::Uri,
     headers: HeaderMap,
     Json(body): Json<Value>,
+) -> Response {
+    let client_format = crate::proxy::errors::detect_client_format(uri.path(), &headers);
+    let key = if state.experimental.read().await.enable_request_dedup {
+        crate::proxy::dedup::dedup_key(&body)
+    } else {
+        None
+    };
+    let inflight = state.inflight.clone();
+    let monitor = state.monitor.clone();
+    crate::proxy::dedup::coalesce(&inflight, &monitor, key, || {
+        handle_messages_inner(state, headers, body, client_format)
+    })
+    .await
+}
+
+/// 处理 Chat 消息请求流程
+/// `client_format` 决定错误响应使用 Anthropic 还是 OpenAI 格式
+async fn handle_messages_inner(
+    state: AppState,
+    headers: HeaderMap,
+    body: Value,
+    client_format: crate::proxy::errors::ClientFormat,
 ) -> Response {
     tracing::debug!("handle_messages called. Body JSON len: {}", body.to_string().len());
     
@@ -520,6 +543,7 @@ pub async fn handle_messages(
 
     // 2. 获取 UpstreamClient
     let upstream = state.upstream.clone();
+    let app_state = state.clone();
     
     // 3. 准备闭包
     let mut request_for_body = request.clone();
@@ -534,10 +558,13 @@ pub async fn handle_messages(
     
     for attempt in 0..max_attempts {
         // 2. 模型路由解析
-        let mut mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        let mut mapped_model = crate::proxy::common::model_mapping::resolve_model_route_with_availability(
             &request_for_body.model,
             &*state.custom_mapping.read().await,
-        );
+            &*state.regex_mapping.read().await,
+            &*state.fallback_chains.read().await,
+            &state.token_manager,
+        ).await;
         
         // 将 Claude 工具转为 Value 数组以便探测联网
         let tools_val: Option<Vec<Value>> = request_for_body.tools.as_ref().map(|list| {
@@ -552,24 +579,10 @@ pub async fn handle_messages(
         let session_id = Some(session_id_str.as_str());
 
         let force_rotate_token = attempt > 0;
-        let (access_token, project_id, email) = match token_manager.get_token(&config.request_type, force_rotate_token, session_id, &config.final_model).await {
+        let (access_token, project_id, email, in_flight) = match token_manager.get_token(&config.request_type, force_rotate_token, session_id, &config.final_model).await {
             Ok(t) => t,
             Err(e) => {
-                let safe_message = if e.contains("invalid_grant") {
-                    "OAuth refresh failed (invalid_grant): refresh_token likely revoked/expired; reauthorize account(s) to restore service.".to_string()
-                } else {
-                    e
-                };
-                 return (
-                    StatusCode::SERVICE_UNAVAILABLE,
-                    Json(json!({
-                        "type": "error",
-                        "error": {
-                            "type": "overloaded_error",
-                            "message": format!("No available accounts: {}", safe_message)
-                        }
-                    }))
-                ).into_response();
+                return crate::proxy::errors::map_token_manager_error_to_anthropic(client_format, &e);
             }
         };
 
@@ -658,6 +671,14 @@ pub async fn handle_messages(
                 ).into_response();
             }
         };
+        let gemini_body = crate::proxy::plugins::apply(
+            &crate::proxy::plugins::TransformContext {
+                model: config.final_model.clone(),
+                account_id: token_manager.email_to_account_id(&email),
+                session_id: session_id.map(str::to_string),
+            },
+            gemini_body,
+        );
         
     // 4. 上游调用 - 自动转换逻辑
     let client_wants_stream = request.stream;
@@ -672,34 +693,48 @@ pub async fn handle_messages(
     let method = if actual_stream { "streamGenerateContent" } else { "generateContent" };
     let query = if actual_stream { Some("alt=sse") } else { None };
 
-    let response = match upstream.call_v1_internal(
-        method,
-        &access_token,
-        gemini_body,
-        query
-    ).await {
+    // 流式请求保留请求体，供中途断线时续传
+    let reconnect_body = if actual_stream { Some(gemini_body.clone()) } else { None };
+    let account_client = app_state.account_client(&email).await;
+    let upstream_start = std::time::Instant::now();
+    let upstream_result = match &account_client {
+        Some(client) => upstream.call_v1_internal_with_client(client, method, &access_token, gemini_body, query).await,
+        None => upstream.call_v1_internal(method, &access_token, gemini_body, query).await,
+    };
+    let response = match upstream_result {
             Ok(r) => r,
             Err(e) => {
                 last_error = e.clone();
                 debug!("Request failed on attempt {}/{}: {}", attempt + 1, max_attempts, e);
+                token_manager.record_upstream_outcome(&email, None);
                 continue;
             }
         };
         
         let status = response.status();
+        token_manager.record_upstream_outcome(&email, Some(status.as_u16()));
         
         // 成功
         if status.is_success() {
             // [智能限流] 请求成功，重置该账号的连续失败计数
             token_manager.mark_account_success(&email);
+            token_manager.record_latency(&email, upstream_start.elapsed().as_millis() as u64);
             
                 // Determine context limit based on model
                 let context_limit = crate::proxy::mappers::claude::utils::get_context_limit_for_model(&request_with_mapped.model);
 
             // 处理流式响应
             if actual_stream {
-                let stream = response.bytes_stream();
-                let gemini_stream = Box::pin(stream);
+                let retry_config = app_state.retry.read().await.clone();
+                let gemini_stream = crate::proxy::upstream::reconnect::StreamReconnector::new(
+                    upstream.clone(),
+                    account_client.clone(),
+                    access_token.clone(),
+                    reconnect_body.unwrap_or_default(),
+                    &retry_config,
+                    trace_id.clone(),
+                )
+                .wrap(Box::pin(response.bytes_stream()));
                 // [v3.3.17] Pass session_id for signature caching
                 let mut claude_stream = create_claude_sse_stream(
                     gemini_stream, 
@@ -742,7 +777,7 @@ pub async fn handle_messages(
                                 .header(header::CONNECTION, "keep-alive")
                                 .header("X-Account-Email", &email)
                                 .header("X-Mapped-Model", &request_with_mapped.model)
-                                .body(Body::from_stream(combined_stream))
+                                .body(Body::from_stream(in_flight.attach(combined_stream)))
                                 .unwrap();
                         } else {
                             // 客户端要非 Stream，需要收集完整响应并转换为 JSON
@@ -941,23 +976,11 @@ pub async fn handle_messages(
         }
     }
     
-    if let Some(email) = last_email {
-        (StatusCode::TOO_MANY_REQUESTS, [("X-Account-Email", email)], Json(json!({
-            "type": "error",
-            "error": {
-                "type": "overloaded_error",
-                "message": format!("All {} attempts failed. Last error: {}", max_attempts, last_error)
-            }
-        }))).into_response()
-    } else {
-        (StatusCode::TOO_MANY_REQUESTS, Json(json!({
-            "type": "error",
-            "error": {
-                "type": "overloaded_error",
-                "message": format!("All {} attempts failed. Last error: {}", max_attempts, last_error)
-            }
-        }))).into_response()
+    let mut response = crate::proxy::errors::map_google_upstream_error_to_anthropic(client_format, max_attempts, &last_error);
+    if let Some(value) = last_email.and_then(|email| axum::http::HeaderValue::from_str(&email).ok()) {
+        response.headers_mut().insert("X-Account-Email", value);
     }
+    response
 }
 
 /// 列出可用模型
@@ -966,6 +989,7 @@ pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoRespo
 
     let model_ids = get_all_dynamic_models(
         &state.custom_mapping,
+        &state.model_discovery,
     ).await;
 
     let data: Vec<_> = model_ids.into_iter().map(|id| {
@@ -1010,6 +1034,205 @@ pub async fn handle_count_tokens(
     .into_response()
 }
 
+// ===== 批量请求 =====
+
+/// 批量请求默认并发数
+const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+/// 批量请求并发上限，避免单个调用占满账号池
+const MAX_BATCH_CONCURRENCY: usize = 16;
+/// 单个批量请求最多包含的子请求数
+const MAX_BATCH_ITEMS: usize = 100;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct BatchRequest {
+    pub requests: Vec<ClaudeRequest>,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct BatchResult {
+    pub index: usize,
+    pub status: u16,
+    pub body: Value,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct BatchResponse {
+    pub results: Vec<BatchResult>,
+}
+
+/// 批量处理多个 Claude 请求 (仅非流式)
+/// 每个子请求独立走 handle_messages_inner，分别获取账号并遵循限流与账号并发限制
+/// 每个子请求计入 API Key 限流、指标与监控日志 (含费用估算与用量统计)；批量请求本身已计一次，从第二个子请求开始计数
+pub async fn handle_batch(
+    State(state): State<AppState>,
+    uri: axum::http::Uri,
+    headers: HeaderMap,
+    key_limit: Option<axum::Extension<crate::proxy::middleware::auth::KeyRateLimit>>,
+    request_id: Option<axum::Extension<crate::proxy::middleware::request_id::RequestId>>,
+    Json(batch): Json<BatchRequest>,
+) -> Response {
+    let total = batch.requests.len();
+    if total > MAX_BATCH_ITEMS {
+        return crate::proxy::errors::anthropic_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request_error",
+            &format!("Batch contains {} requests; the maximum is {}", total, MAX_BATCH_ITEMS),
+        );
+    }
+    let request_id = request_id.map(|axum::Extension(id)| id.0);
+    let client_format = crate::proxy::errors::detect_client_format(uri.path(), &headers);
+    let concurrency = batch
+        .concurrency
+        .unwrap_or(DEFAULT_BATCH_CONCURRENCY)
+        .clamp(1, MAX_BATCH_CONCURRENCY);
+    info!("[Batch] Processing {} requests with concurrency {}", total, concurrency);
+
+    let mut pending = batch.requests.into_iter().enumerate();
+    let mut results: Vec<Option<BatchResult>> = (0..total).map(|_| None).collect();
+    let mut set = tokio::task::JoinSet::new();
+    loop {
+        while set.len() < concurrency {
+            let Some((index, mut request)) = pending.next() else {
+                break;
+            };
+            if let (true, Some(axum::Extension(limit))) = (index > 0, key_limit.as_ref()) {
+                let limiter = crate::proxy::middleware::key_rate_limit::KeyRateLimiter::global();
+                if let Err(retry_after) = limiter.check(&limit.key_hash, limit.requests_per_minute) {
+                    results[index] = Some(BatchResult {
+                        index,
+                        status: StatusCode::TOO_MANY_REQUESTS.as_u16(),
+                        body: json!({
+                            "type": "error",
+                            "error": {
+                                "type": "rate_limit_error",
+                                "message": format!("API key rate limit exceeded, retry after {}s", retry_after)
+                            }
+                        }),
+                    });
+                    continue;
+                }
+            }
+            request.stream = false;
+            let (state, headers) = (state.clone(), headers.clone());
+            let request_id = request_id.as_ref().map(|id| format!("{}-{}", id, index));
+            set.spawn(run_batch_item(state, headers, client_format, index, request, request_id));
+        }
+        match set.join_next().await {
+            Some(Ok(result)) => {
+                let index = result.index;
+                results[index] = Some(result);
+            }
+            Some(Err(e)) => error!("[Batch] Batch item task failed: {}", e),
+            None => break,
+        }
+    }
+
+    // 任务异常退出的子请求以 500 填充
+    let results = results
+        .into_iter()
+        .enumerate()
+        .map(|(index, result)| {
+            result.unwrap_or_else(|| BatchResult {
+                index,
+                status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                body: json!({
+                    "type": "error",
+                    "error": { "type": "api_error", "message": "Batch item task failed" }
+                }),
+            })
+        })
+        .collect();
+    Json(BatchResponse { results }).into_response()
+}
+
+async fn run_batch_item(
+    state: AppState,
+    headers: HeaderMap,
+    client_format: crate::proxy::errors::ClientFormat,
+    index: usize,
+    request: ClaudeRequest,
+    request_id: Option<String>,
+) -> BatchResult {
+    let start = std::time::Instant::now();
+    let body = match serde_json::to_value(&request) {
+        Ok(body) => body,
+        Err(e) => {
+            return BatchResult {
+                index,
+                status: StatusCode::BAD_REQUEST.as_u16(),
+                body: json!({
+                    "type": "error",
+                    "error": { "type": "invalid_request_error", "message": e.to_string() }
+                }),
+            }
+        }
+    };
+
+    let request_body = state.monitor.is_enabled().then(|| body.to_string());
+    let response = handle_messages_inner(state.clone(), headers, body, client_format).await;
+    let status = response.status().as_u16();
+    let header_value = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+    };
+    let account_email = header_value("X-Account-Email");
+    let mapped_model = header_value("X-Mapped-Model");
+    let provider = header_value("X-Provider").unwrap_or_else(|| "google".to_string());
+
+    let body = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned())),
+        Err(e) => json!({
+            "type": "error",
+            "error": { "type": "api_error", "message": format!("Failed to read response: {}", e) }
+        }),
+    };
+
+    // 子请求不经过 metrics / monitor 中间件，在此单独记录
+    let duration = start.elapsed();
+    state.metrics.record_request(
+        status,
+        mapped_model.as_deref().unwrap_or(&request.model),
+        &provider,
+        duration,
+    );
+    if state.monitor.is_enabled() {
+        let usage = |field: &str| {
+            body.get("usage")
+                .and_then(|u| u.get(field))
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32)
+        };
+        let response_body = body.to_string();
+        let log = crate::proxy::monitor::ProxyRequestLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            method: "POST".to_string(),
+            url: format!("/v1/batch#{}", index),
+            status,
+            duration: duration.as_millis() as u64,
+            model: Some(request.model.clone()),
+            mapped_model,
+            account_email,
+            error: (status >= 400).then(|| response_body.clone()),
+            request_body,
+            response_body: Some(response_body),
+            input_tokens: usage("input_tokens"),
+            output_tokens: usage("output_tokens"),
+            conversation_id: None,
+            estimated_cost_usd: None,
+            request_id,
+        };
+        state.monitor.log_request(log).await;
+    }
+    BatchResult { index, status, body }
+}
+
 // 移除已失效的简单单元测试，后续将补全完整的集成测试
 /*
 #[cfg(test)]
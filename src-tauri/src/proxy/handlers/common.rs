@@ -15,10 +15,13 @@ pub async fn handle_detect_model(
     }
 
     // 1. Resolve mapping
-    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route_with_availability(
         model_name,
         &*state.custom_mapping.read().await,
-    );
+        &*state.regex_mapping.read().await,
+        &*state.fallback_chains.read().await,
+        &state.token_manager,
+    ).await;
 
     // 2. Resolve capabilities
     let config = crate::proxy::mappers::common_utils::resolve_request_config(
@@ -42,10 +42,13 @@ pub async fn handle_generate(
 
     for attempt in 0..max_attempts {
         // 3. 模型路由解析
-        let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        let mapped_model = crate::proxy::common::model_mapping::resolve_model_route_with_availability(
             &model_name,
             &*state.custom_mapping.read().await,
-        );
+            &*state.regex_mapping.read().await,
+            &*state.fallback_chains.read().await,
+            &state.token_manager,
+        ).await;
         // 提取 tools 列表以进行联网探测 (Gemini 风格可能是嵌套的)
         let tools_val: Option<Vec<Value>> = body.get("tools").and_then(|t| t.as_array()).map(|arr| {
             let mut flattened = Vec::new();
@@ -66,7 +69,7 @@ pub async fn handle_generate(
         let session_id = SessionManager::extract_gemini_session_id(&body, &model_name);
 
         // 关键：在重试尝试 (attempt > 0) 时强制轮换账号
-        let (access_token, project_id, email) = match token_manager.get_token(&config.request_type, attempt > 0, Some(&session_id), &config.final_model).await {
+        let (access_token, project_id, email, in_flight) = match token_manager.get_token(&config.request_type, attempt > 0, Some(&session_id), &config.final_model).await {
             Ok(t) => t,
             Err(e) => {
                 return Err((StatusCode::SERVICE_UNAVAILABLE, format!("Token error: {}", e)));
@@ -78,24 +81,46 @@ pub async fn handle_generate(
 
         // 5. 包装请求 (project injection)
         let wrapped_body = wrap_request(&body, &project_id, &mapped_model);
+        let wrapped_body = crate::proxy::plugins::apply(
+            &crate::proxy::plugins::TransformContext {
+                model: config.final_model.clone(),
+                account_id: token_manager.email_to_account_id(&email),
+                session_id: Some(session_id.clone()),
+            },
+            wrapped_body,
+        );
 
         // 5. 上游调用
         let query_string = if is_stream { Some("alt=sse") } else { None };
         let upstream_method = if is_stream { "streamGenerateContent" } else { "generateContent" };
 
-        let response = match upstream
-            .call_v1_internal(upstream_method, &access_token, wrapped_body, query_string)
-            .await {
+        let retry_config = state.retry.read().await.clone();
+        let account_client = state.account_client(&email).await;
+        let upstream_start = std::time::Instant::now();
+        let response = match crate::proxy::retry::retry_with_backoff(&retry_config, "Gemini upstream", || async {
+            match &account_client {
+                Some(client) => {
+                    upstream
+                        .call_v1_internal_with_client(client, upstream_method, &access_token, wrapped_body.clone(), query_string)
+                        .await
+                }
+                None => upstream.call_v1_internal(upstream_method, &access_token, wrapped_body.clone(), query_string).await,
+            }
+        })
+        .await {
                 Ok(r) => r,
                 Err(e) => {
                     last_error = e.clone();
                     debug!("Gemini Request failed on attempt {}/{}: {}", attempt + 1, max_attempts, e);
+                    token_manager.record_upstream_outcome(&email, None);
                     continue;
                 }
             };
 
         let status = response.status();
+        token_manager.record_upstream_outcome(&email, Some(status.as_u16()));
         if status.is_success() {
+            token_manager.record_latency(&email, upstream_start.elapsed().as_millis() as u64);
             // 6. 响应处理
             if is_stream {
                 use axum::body::Body;
@@ -103,15 +128,46 @@ pub async fn handle_generate(
                 use bytes::{Bytes, BytesMut};
                 use futures::StreamExt;
                 
-                let mut response_stream = response.bytes_stream();
+                let mut response_stream = crate::proxy::upstream::reconnect::StreamReconnector::new(
+                    upstream.clone(),
+                    account_client.clone(),
+                    access_token.clone(),
+                    wrapped_body.clone(),
+                    &retry_config,
+                    format!("Gemini {}", email),
+                )
+                .wrap(Box::pin(response.bytes_stream()));
                 let mut buffer = BytesMut::new();
+                let heartbeat_interval = std::time::Duration::from_secs(state.stream_heartbeat_interval_secs);
 
                 let stream = async_stream::stream! {
-                    while let Some(item) = response_stream.next().await {
+                    // 最近一次向客户端发送数据的时间；首个数据发出前为 None，此阶段不发送心跳
+                    let mut last_sent: Option<tokio::time::Instant> = None;
+                    loop {
+                        let item = match last_sent {
+                            Some(last) if !heartbeat_interval.is_zero() => {
+                                match tokio::time::timeout_at(last + heartbeat_interval, response_stream.next()).await {
+                                    Ok(item) => item,
+                                    Err(_) => {
+                                        // 上游长时间无输出 (如深度思考)，发送 SSE 注释保活，防止反向代理断开空闲连接
+                                        debug!("[Gemini-SSE] Sending heartbeat");
+                                        last_sent = Some(tokio::time::Instant::now());
+                                        yield Ok::<Bytes, String>(Bytes::from_static(b":\n\n"));
+                                        continue;
+                                    }
+                                }
+                            }
+                            _ => response_stream.next().await,
+                        };
+                        let Some(item) = item else {
+                            break;
+                        };
+
                         match item {
                             Ok(bytes) => {
                                 debug!("[Gemini-SSE] Received chunk: {} bytes", bytes.len());
                                 buffer.extend_from_slice(&bytes);
+                                let mut out: Vec<Bytes> = Vec::new();
                                 while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
                                     let line_raw = buffer.split_to(pos + 1);
                                     if let Ok(line_str) = std::str::from_utf8(&line_raw) {
@@ -121,7 +177,7 @@ pub async fn handle_generate(
                                         if line.starts_with("data: ") {
                                             let json_part = line.trim_start_matches("data: ").trim();
                                             if json_part == "[DONE]" {
-                                                yield Ok::<Bytes, String>(Bytes::from("data: [DONE]\n\n"));
+                                                out.push(Bytes::from("data: [DONE]\n\n"));
                                                 continue;
                                             }
                                             
@@ -130,26 +186,32 @@ pub async fn handle_generate(
                                                     // Unwrap v1internal response wrapper
                                                     if let Some(inner) = json.get_mut("response").map(|v| v.take()) {
                                                         let new_line = format!("data: {}\n\n", serde_json::to_string(&inner).unwrap_or_default());
-                                                        yield Ok::<Bytes, String>(Bytes::from(new_line));
+                                                        out.push(Bytes::from(new_line));
                                                     } else {
-                                                        yield Ok::<Bytes, String>(Bytes::from(format!("data: {}\n\n", serde_json::to_string(&json).unwrap_or_default())));
+                                                        out.push(Bytes::from(format!("data: {}\n\n", serde_json::to_string(&json).unwrap_or_default())));
                                                     }
                                                 }
                                                 Err(e) => {
                                                     debug!("[Gemini-SSE] JSON parse error: {}, passing raw line", e);
-                                                    yield Ok::<Bytes, String>(Bytes::from(format!("{}\n\n", line)));
+                                                    out.push(Bytes::from(format!("{}\n\n", line)));
                                                 }
                                             }
                                         } else {
                                             // Non-data lines (comments, etc.)
-                                            yield Ok::<Bytes, String>(Bytes::from(format!("{}\n\n", line)));
+                                            out.push(Bytes::from(format!("{}\n\n", line)));
                                         }
                                     } else {
                                         // Non-UTF8 data? Just pass it through or skip
                                         debug!("[Gemini-SSE] Non-UTF8 line encountered");
-                                        yield Ok::<Bytes, String>(line_raw.freeze());
+                                        out.push(line_raw.freeze());
                                     }
                                 }
+                                if !out.is_empty() {
+                                    last_sent = Some(tokio::time::Instant::now());
+                                }
+                                for chunk in out {
+                                    yield Ok::<Bytes, String>(chunk);
+                                }
                             }
                             Err(e) => {
                                 error!("[Gemini-SSE] Connection error: {}", e);
@@ -159,7 +221,7 @@ pub async fn handle_generate(
                     }
                 };
                 
-                let body = Body::from_stream(stream);
+                let body = Body::from_stream(in_flight.attach(stream));
                 return Ok(Response::builder()
                     .header("Content-Type", "text/event-stream")
                     .header("Cache-Control", "no-cache")
@@ -219,6 +281,7 @@ pub async fn handle_list_models(State(state): State<AppState>) -> Result<impl In
     // 获取所有动态模型列表（与 /v1/models 一致）
     let model_ids = get_all_dynamic_models(
         &state.custom_mapping,
+        &state.model_discovery,
     ).await;
 
     // 转换为 Gemini API 格式
@@ -249,7 +312,7 @@ pub async fn handle_get_model(Path(model_name): Path<String>) -> impl IntoRespon
 
 pub async fn handle_count_tokens(State(state): State<AppState>, Path(_model_name): Path<String>, Json(_body): Json<Value>) -> Result<impl IntoResponse, (StatusCode, String)> {
     let model_group = "gemini";
-    let (_access_token, _project_id, _) = state.token_manager.get_token(model_group, false, None, "gemini").await
+    let (_access_token, _project_id, _, _in_flight) = state.token_manager.get_token(model_group, false, None, "gemini").await
         .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, format!("Token error: {}", e)))?;
     
     Ok(Json(json!({"totalTokens": 0})))
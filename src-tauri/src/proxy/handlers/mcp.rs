@@ -15,17 +15,11 @@ use crate::proxy::server::AppState;
 fn build_client(
     upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
     timeout_secs: u64,
-) -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder()
+) -> Result<crate::proxy::upstream_proxy::PooledClient, String> {
+    let builder = reqwest::Client::builder()
         .timeout(Duration::from_secs(timeout_secs.max(5)));
 
-    if upstream_proxy.enabled && !upstream_proxy.url.is_empty() {
-        let proxy = reqwest::Proxy::all(&upstream_proxy.url)
-            .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
-        builder = builder.proxy(proxy);
-    }
-
-    builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))
+    crate::proxy::upstream_proxy::PooledClient::build(builder, &upstream_proxy)
 }
 
 fn copy_passthrough_headers(incoming: &HeaderMap) -> HeaderMap {
@@ -58,12 +52,6 @@ async fn forward_mcp(
         return StatusCode::NOT_FOUND.into_response();
     }
 
-    let upstream_proxy = state.upstream_proxy.read().await.clone();
-    let client = match build_client(upstream_proxy, state.request_timeout) {
-        Ok(c) => c,
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
-    };
-
     let collected = match to_bytes(body, 100 * 1024 * 1024).await {
         Ok(b) => b,
         Err(e) => {
@@ -75,17 +63,32 @@ async fn forward_mcp(
         }
     };
 
+    // 按目标域名选择超时 (需先解析工具参数)
+    let timeout_secs = crate::proxy::zai_web_tools::timeout_for_request(
+        &zai.domain_timeouts,
+        &collected,
+        state.request_timeout,
+    );
+    let upstream_proxy = state.upstream_proxy.read().await.clone();
+    let client = match build_client(upstream_proxy, timeout_secs) {
+        Ok(c) => c,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
     let mut headers = copy_passthrough_headers(&incoming_headers);
     if let Ok(v) = HeaderValue::from_str(&format!("Bearer {}", zai.api_key)) {
         headers.insert(header::AUTHORIZATION, v);
     }
 
     let req = client
+        .client
         .request(method, upstream_url)
         .headers(headers)
         .body(collected);
 
-    let resp = match req.send().await {
+    let result = req.send().await;
+    client.report(&result);
+    let resp = match result {
         Ok(r) => r,
         Err(e) => {
             return (
@@ -124,14 +127,41 @@ pub async fn handle_web_search_prime(
     }
     drop(zai);
 
-    forward_mcp(
+    let session_id = mcp_session_id(&headers).filter(|_| method == Method::POST);
+    let response = forward_mcp(
         &state,
         headers,
         method,
         "https://api.z.ai/api/mcp/web_search_prime/mcp",
         body,
     )
-    .await
+    .await;
+
+    // 同一会话内多次搜索时过滤已返回过的 URL
+    let Some(session_id) = session_id else {
+        return response;
+    };
+    if !response.status().is_success() {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, 100 * 1024 * 1024).await {
+        Ok(b) => b,
+        Err(e) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to read upstream response: {}", e),
+            )
+                .into_response();
+        }
+    };
+    match state.search_seen_urls.dedup_response(session_id, &bytes) {
+        Some(rewritten) => {
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(rewritten))
+        }
+        None => Response::from_parts(parts, Body::from(bytes)),
+    }
 }
 
 pub async fn handle_web_reader(
@@ -144,16 +174,58 @@ pub async fn handle_web_reader(
     if !zai.mcp.web_reader_enabled {
         return StatusCode::NOT_FOUND.into_response();
     }
+    let cache_ttl = Duration::from_secs(zai.cache_ttl_secs);
     drop(zai);
 
-    forward_mcp(
-        &state,
-        headers,
-        method,
-        "https://api.z.ai/api/mcp/web_reader/mcp",
-        body,
-    )
-    .await
+    const WEB_READER_URL: &str = "https://api.z.ai/api/mcp/web_reader/mcp";
+    if method != Method::POST || cache_ttl.is_zero() {
+        return forward_mcp(&state, headers, method, WEB_READER_URL, body).await;
+    }
+
+    let collected = match to_bytes(body, 100 * 1024 * 1024).await {
+        Ok(b) => b,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Failed to read request body: {}", e),
+            )
+                .into_response();
+        }
+    };
+    let Some(call) = crate::proxy::zai_web_tools::parse_web_reader_call(&collected) else {
+        return forward_mcp(&state, headers, method, WEB_READER_URL, Body::from(collected)).await;
+    };
+
+    if call.no_cache {
+        state.web_reader_cache.invalidate(&call.cache_key);
+    } else if let Some(cached) = state.web_reader_cache.get(&call.cache_key) {
+        if let Ok(result) = serde_json::from_str::<Value>(&cached) {
+            state.cache_stats.record_hit();
+            tracing::debug!("[MCP] Web Reader cache hit: {}", call.url);
+            return axum::Json(jsonrpc_result(call.id, result)).into_response();
+        }
+    }
+    state.cache_stats.record_miss();
+
+    let response = forward_mcp(&state, headers, method, WEB_READER_URL, Body::from(collected)).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, 100 * 1024 * 1024).await {
+        Ok(b) => b,
+        Err(e) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to read upstream response: {}", e),
+            )
+                .into_response();
+        }
+    };
+    if let Some(result) = crate::proxy::zai_web_tools::extract_result(&bytes) {
+        state.web_reader_cache.insert(call.cache_key, result.to_string(), cache_ttl);
+    }
+    Response::from_parts(parts, Body::from(bytes))
 }
 
 fn mcp_session_id(headers: &HeaderMap) -> Option<String> {
@@ -100,10 +100,13 @@ pub async fn handle_chat_completions(
 
     for attempt in 0..max_attempts {
         // 2. 模型路由解析
-        let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        let mapped_model = crate::proxy::common::model_mapping::resolve_model_route_with_availability(
             &openai_req.model,
             &*state.custom_mapping.read().await,
-        );
+            &*state.regex_mapping.read().await,
+            &*state.fallback_chains.read().await,
+            &state.token_manager,
+        ).await;
         // 将 OpenAI 工具转为 Value 数组以便探测联网
         let tools_val: Option<Vec<Value>> = openai_req
             .tools
@@ -120,7 +123,7 @@ pub async fn handle_chat_completions(
 
         // 4. 获取 Token (使用准确的 request_type)
         // 关键：在重试尝试 (attempt > 0) 时强制轮换账号
-        let (access_token, project_id, email) = match token_manager
+        let (access_token, project_id, email, in_flight) = match token_manager
             .get_token(&config.request_type, attempt > 0, Some(&session_id), &config.final_model)
             .await
         {
@@ -138,6 +141,14 @@ pub async fn handle_chat_completions(
 
         // 4. 转换请求
         let gemini_body = transform_openai_request(&openai_req, &project_id, &mapped_model);
+        let gemini_body = crate::proxy::plugins::apply(
+            &crate::proxy::plugins::TransformContext {
+                model: config.final_model.clone(),
+                account_id: token_manager.email_to_account_id(&email),
+                session_id: Some(session_id.clone()),
+            },
+            gemini_body,
+        );
 
         // [New] 打印转换后的报文 (Gemini Body) 供调试
         if let Ok(body_json) = serde_json::to_string_pretty(&gemini_body) {
@@ -161,6 +172,7 @@ pub async fn handle_chat_completions(
         };
         let query_string = if actual_stream { Some("alt=sse") } else { None };
 
+        let upstream_start = std::time::Instant::now();
         let response = match upstream
             .call_v1_internal(method, &access_token, gemini_body, query_string)
             .await
@@ -174,12 +186,15 @@ pub async fn handle_chat_completions(
                     max_attempts,
                     e
                 );
+                token_manager.record_upstream_outcome(&email, None);
                 continue;
             }
         };
 
         let status = response.status();
+        token_manager.record_upstream_outcome(&email, Some(status.as_u16()));
         if status.is_success() {
+            token_manager.record_latency(&email, upstream_start.elapsed().as_millis() as u64);
             // 5. 处理流式 vs 非流式
             if actual_stream {
                 use crate::proxy::mappers::openai::streaming::create_openai_sse_stream;
@@ -193,7 +208,7 @@ pub async fn handle_chat_completions(
                 // 判断客户端期望的格式
                 if client_wants_stream {
                     // 客户端本就要 Stream，直接返回 SSE
-                    let body = Body::from_stream(openai_stream);
+                    let body = Body::from_stream(in_flight.attach(openai_stream));
                     return Ok(Response::builder()
                         .header("Content-Type", "text/event-stream")
                         .header("Cache-Control", "no-cache")
@@ -615,10 +630,13 @@ pub async fn handle_completions(
 
     for _attempt in 0..max_attempts {
         // 1. 模型路由解析
-        let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        let mapped_model = crate::proxy::common::model_mapping::resolve_model_route_with_availability(
             &openai_req.model,
             &*state.custom_mapping.read().await,
-        );
+            &*state.regex_mapping.read().await,
+            &*state.fallback_chains.read().await,
+            &state.token_manager,
+        ).await;
         // 将 OpenAI 工具转为 Value 数组以便探测联网
         let tools_val: Option<Vec<Value>> = openai_req
             .tools
@@ -630,7 +648,7 @@ pub async fn handle_completions(
             &tools_val,
         );
 
-        let (access_token, project_id, email) =
+        let (access_token, project_id, email, in_flight) =
             match token_manager.get_token(&config.request_type, false, None, &config.final_model).await {
                 Ok(t) => t,
                 Err(e) => {
@@ -644,6 +662,14 @@ pub async fn handle_completions(
         info!("✓ Using account: {} (type: {})", email, config.request_type);
 
         let gemini_body = transform_openai_request(&openai_req, &project_id, &mapped_model);
+        let gemini_body = crate::proxy::plugins::apply(
+            &crate::proxy::plugins::TransformContext {
+                model: config.final_model.clone(),
+                account_id: token_manager.email_to_account_id(&email),
+                session_id: None,
+            },
+            gemini_body,
+        );
 
         // [New] 打印转换后的报文 (Gemini Body) 供调试 (Codex 路径)
         if let Ok(body_json) = serde_json::to_string_pretty(&gemini_body) {
@@ -658,6 +684,7 @@ pub async fn handle_completions(
         };
         let query_string = if list_response { Some("alt=sse") } else { None };
 
+        let upstream_start = std::time::Instant::now();
         let response = match upstream
             .call_v1_internal(method, &access_token, gemini_body, query_string)
             .await
@@ -665,12 +692,15 @@ pub async fn handle_completions(
             Ok(r) => r,
             Err(e) => {
                 last_error = e.clone();
+                token_manager.record_upstream_outcome(&email, None);
                 continue;
             }
         };
 
         let status = response.status();
+        token_manager.record_upstream_outcome(&email, Some(status.as_u16()));
         if status.is_success() {
+            token_manager.record_latency(&email, upstream_start.elapsed().as_millis() as u64);
             if list_response {
                 use axum::body::Body;
                 use axum::response::Response;
@@ -680,12 +710,12 @@ pub async fn handle_completions(
                     use crate::proxy::mappers::openai::streaming::create_codex_sse_stream;
                     let s =
                         create_codex_sse_stream(Box::pin(gemini_stream), openai_req.model.clone());
-                    Body::from_stream(s)
+                    Body::from_stream(in_flight.attach(s))
                 } else {
                     use crate::proxy::mappers::openai::streaming::create_legacy_sse_stream;
                     let s =
                         create_legacy_sse_stream(Box::pin(gemini_stream), openai_req.model.clone());
-                    Body::from_stream(s)
+                    Body::from_stream(in_flight.attach(s))
                 };
 
                 return Ok(Response::builder()
@@ -732,9 +762,17 @@ pub async fn handle_completions(
 
         // Handle errors and retry
         let status_code = status.as_u16();
+        let retry_after = response
+            .headers()
+            .get("Retry-After")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string());
         let error_text = response.text().await.unwrap_or_default();
         last_error = format!("HTTP {}: {}", status_code, error_text);
 
+        if status_code == 429 || status_code == 529 || status_code == 503 || status_code == 500 {
+            token_manager.mark_rate_limited(&email, status_code, retry_after.as_deref(), &error_text);
+        }
         if status_code == 429 || status_code == 403 || status_code == 401 {
             continue;
         }
@@ -752,6 +790,7 @@ pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoRespo
 
     let model_ids = get_all_dynamic_models(
         &state.custom_mapping,
+        &state.model_discovery,
     ).await;
 
     let data: Vec<_> = model_ids.into_iter().map(|id| {
@@ -842,7 +881,7 @@ pub async fn handle_images_generations(
     let upstream = state.upstream.clone();
     let token_manager = state.token_manager;
 
-    let (access_token, project_id, email) = match token_manager.get_token("image_gen", false, None, "dall-e-3").await
+    let (access_token, project_id, email, _in_flight) = match token_manager.get_token("image_gen", false, None, "dall-e-3").await
     {
         Ok(t) => t,
         Err(e) => {
@@ -1096,7 +1135,7 @@ pub async fn handle_images_edits(
     let upstream = state.upstream.clone();
     let token_manager = state.token_manager;
     // Fix: Proper get_token call with correct signature and unwrap (using image_gen quota)
-    let (access_token, project_id, email) = match token_manager.get_token("image_gen", false, None, "dall-e-3").await
+    let (access_token, project_id, email, _in_flight) = match token_manager.get_token("image_gen", false, None, "dall-e-3").await
     {
         Ok(t) => t,
         Err(e) => {
@@ -1041,20 +1041,21 @@ fn build_contents(
                                 .cloned()
                                 .unwrap_or_else(|| tool_use_id.clone());
 
-                            // [FIX #593] 工具输出压缩: 处理超大工具输出
-                            // 使用智能压缩策略(浏览器快照、大文件提示等)
-                            let mut compacted_content = content.clone();
-                            if let Some(blocks) = compacted_content.as_array_mut() {
-                                tool_result_compressor::sanitize_tool_result_blocks(blocks);
-                            }
+                            // Smart Truncation: max chars limit (按目标模型上下文窗口缩放)
+                            let max_tool_result_chars = crate::proxy::common::model_mapping::get_model_capability(mapped_model).max_tool_result_chars();
 
+                            // [FIX #593] 工具输出压缩: 处理超大工具输出
+                            // 使用智能压缩策略(浏览器快照、大文件提示等)，流式逐块压缩，预算耗尽后不再处理剩余 block
+                            //
                             // Smart Truncation: strict image removal
                             // Remove all Base64 images from historical tool results to save context.
                             // Only allow text.
-                            let mut merged_content = match &compacted_content {
+                            let mut merged_content = match content {
                                 serde_json::Value::String(s) => s.clone(),
-                                serde_json::Value::Array(arr) => arr
-                                    .iter()
+                                serde_json::Value::Array(arr) => tool_result_compressor::compact_tool_result_stream(
+                                    arr.iter().cloned(),
+                                    max_tool_result_chars,
+                                )
                                     .filter_map(|block| {
                                         if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
                                             Some(text.to_string())
@@ -1074,11 +1075,9 @@ fn build_contents(
                                 _ => content.to_string(),
                             };
                             
-                            // Smart Truncation: max chars limit
-                            const MAX_TOOL_RESULT_CHARS: usize = 200_000;
-                            if merged_content.len() > MAX_TOOL_RESULT_CHARS {
-                                tracing::warn!("Truncating tool result from {} chars to {}", merged_content.len(), MAX_TOOL_RESULT_CHARS);
-                                let mut truncated = merged_content.chars().take(MAX_TOOL_RESULT_CHARS).collect::<String>();
+                            if merged_content.len() > max_tool_result_chars {
+                                tracing::warn!("Truncating tool result from {} chars to {}", merged_content.len(), max_tool_result_chars);
+                                let mut truncated = merged_content.chars().take(max_tool_result_chars).collect::<String>();
                                 truncated.push_str("\n...[truncated output]");
                                 merged_content = truncated;
                             }
@@ -8,12 +8,27 @@ use reqwest::Error;
 /// - 英文消息: fallback 消息,供非浏览器客户端使用
 /// - i18n_key: 前端翻译键,供浏览器客户端本地化
 pub fn classify_stream_error(error: &Error) -> (&'static str, &'static str, &'static str) {
+    // reqwest 的 Display 只包含顶层信息，SSL/DNS 细节在 source 链中
+    let chain = error_chain_text(error);
+
     if error.is_timeout() {
         (
             "timeout_error",
             "Request timeout, please check your network connection",
             "errors.stream.timeout_error"
         )
+    } else if is_ssl_error(&chain) {
+        (
+            "ssl_error",
+            "SSL/TLS handshake failed, please check your proxy certificate settings or system time",
+            "errors.stream.ssl_error"
+        )
+    } else if error.is_connect() && is_dns_error(&chain) {
+        (
+            "dns_error",
+            "DNS resolution failed, please check your DNS settings or proxy configuration",
+            "errors.stream.dns_error"
+        )
     } else if error.is_connect() {
         (
             "connection_error",
@@ -41,10 +56,82 @@ pub fn classify_stream_error(error: &Error) -> (&'static str, &'static str, &'st
     }
 }
 
+/// 拼接错误及其 source 链的描述 (小写)
+fn error_chain_text(error: &dyn std::error::Error) -> String {
+    let mut parts = vec![error.to_string()];
+    let mut source = error.source();
+    while let Some(err) = source {
+        parts.push(err.to_string());
+        source = err.source();
+    }
+    parts.join(": ").to_lowercase()
+}
+
+/// SSL/TLS 握手或证书错误 (rustls / native-tls)
+fn is_ssl_error(chain: &str) -> bool {
+    ["ssl", "tls", "certificate", "handshake"]
+        .iter()
+        .any(|k| chain.contains(k))
+}
+
+/// DNS 解析错误 (hyper-util 的 "dns error: failed to lookup address information")
+fn is_dns_error(chain: &str) -> bool {
+    chain.contains("dns") || chain.contains("failed to lookup address")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// 模拟带 source 链的底层错误
+    #[derive(Debug)]
+    struct MockError {
+        message: &'static str,
+        source: Option<Box<MockError>>,
+    }
+
+    impl std::fmt::Display for MockError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.message)
+        }
+    }
+
+    impl std::error::Error for MockError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.source.as_deref().map(|e| e as &(dyn std::error::Error + 'static))
+        }
+    }
+
+    fn mock_chain(outer: &'static str, inner: &'static str) -> MockError {
+        MockError {
+            message: outer,
+            source: Some(Box::new(MockError { message: inner, source: None })),
+        }
+    }
+
+    #[test]
+    fn test_classify_ssl_error_chain() {
+        let rustls = mock_chain("error sending request", "invalid peer certificate: UnknownIssuer");
+        assert!(is_ssl_error(&error_chain_text(&rustls)));
+
+        let native_tls = mock_chain("client error (Connect)", "SSL routines: wrong version number");
+        assert!(is_ssl_error(&error_chain_text(&native_tls)));
+
+        let refused = mock_chain("client error (Connect)", "Connection refused (os error 111)");
+        assert!(!is_ssl_error(&error_chain_text(&refused)));
+    }
+
+    #[test]
+    fn test_classify_dns_error_chain() {
+        let dns = mock_chain("client error (Connect)", "dns error: failed to lookup address information");
+        let chain = error_chain_text(&dns);
+        assert!(is_dns_error(&chain));
+        assert!(!is_ssl_error(&chain));
+
+        let refused = mock_chain("client error (Connect)", "Connection refused (os error 111)");
+        assert!(!is_dns_error(&error_chain_text(&refused)));
+    }
+
     #[test]
     fn test_classify_timeout_error() {
         // 创建一个模拟的超时错误
@@ -83,6 +170,8 @@ mod tests {
         // 错误类型应该是已知的类型之一
         assert!(
             error_type == "timeout_error" ||
+            error_type == "ssl_error" ||
+            error_type == "dns_error" ||
             error_type == "connection_error" ||
             error_type == "decode_error" ||
             error_type == "stream_error" ||
@@ -101,6 +190,8 @@ mod tests {
         // 验证所有错误类型都有正确的 i18n_key 格式
         let test_cases = vec![
             ("timeout_error", "errors.stream.timeout_error"),
+            ("ssl_error", "errors.stream.ssl_error"),
+            ("dns_error", "errors.stream.dns_error"),
             ("connection_error", "errors.stream.connection_error"),
             ("decode_error", "errors.stream.decode_error"),
             ("stream_error", "errors.stream.stream_error"),
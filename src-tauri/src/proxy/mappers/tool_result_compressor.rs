@@ -1,16 +1,34 @@
 //! 工具结果输出压缩模块
 //! 
 //! 提供智能压缩功能:
+//! - 文档文本提取 (PDF / DOCX，含 base64 编码)
+//! - JSON 结果压缩 (剔除噪声字段)
 //! - 浏览器快照压缩 (头+尾保留)
 //! - 大文件提示压缩 (提取关键信息)
 //! - 通用截断 (200,000 字符限制)
 
+use once_cell::sync::Lazy;
 use regex::Regex;
 use serde_json::Value;
-use tracing::{debug, info};
+use std::sync::RwLock;
+use tracing::debug;
 
 /// 最大工具结果字符数 (约 20 万,防止 prompt 超长)
-const MAX_TOOL_RESULT_CHARS: usize = 200_000;
+pub(crate) const MAX_TOOL_RESULT_CHARS: usize = 200_000;
+
+/// JSON 工具结果默认剔除的噪声字段
+pub const DEFAULT_DROP_KEYS: &[&str] = &["_links", "metadata", "headers", "etag"];
+
+/// 当前生效的剔除字段 (来自 app_config.tool_compressor，可热更新)
+static DROP_KEYS: Lazy<RwLock<Vec<String>>> =
+    Lazy::new(|| RwLock::new(DEFAULT_DROP_KEYS.iter().map(|k| k.to_string()).collect()));
+
+/// 更新 JSON 压缩时剔除的字段列表
+pub fn set_drop_keys(keys: Vec<String>) {
+    if let Ok(mut guard) = DROP_KEYS.write() {
+        *guard = keys;
+    }
+}
 
 /// 浏览器快照检测阈值
 const SNAPSHOT_DETECTION_THRESHOLD: usize = 20_000;
@@ -28,13 +46,45 @@ const SNAPSHOT_TAIL_RATIO: f64 = 0.3;
 /// 压缩工具结果文本
 /// 
 /// 根据内容类型自动选择最佳压缩策略:
-/// 1. 大文件提示 → 提取关键信息
-/// 2. 浏览器快照 → 头+尾保留
-/// 3. 其他 → 简单截断
+/// 0. PDF / DOCX 文档 → 提取正文 (预处理)
+/// 1. JSON → 剔除噪声字段后紧凑序列化
+/// 2. 大文件提示 → 提取关键信息
+/// 3. 浏览器快照 → 头+尾保留
+/// 4. 其他 → 简单截断
 pub fn compact_tool_result_text(text: &str, max_chars: usize) -> String {
-    if text.is_empty() || text.len() <= max_chars {
+    if text.is_empty() {
+        return String::new();
+    }
+
+    // 0. 文档预处理: 二进制/base64 文档对模型毫无意义，替换为提取出的正文
+    let extracted;
+    let text = match detect_document_format(text) {
+        DocumentFormat::Unknown => text,
+        format => match extract_text_from_document(text, format) {
+            Some(doc_text) => {
+                debug!("[ToolCompressor] Extracted text from {:?}, {} -> {} chars", format, text.len(), doc_text.len());
+                extracted = doc_text;
+                extracted.as_str()
+            }
+            None => text,
+        },
+    };
+
+    if text.len() <= max_chars {
         return text.to_string();
     }
+
+    // JSON 结果: 剔除噪声字段后紧凑序列化
+    let trimmed = text.trim_start();
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        if let Ok(json) = serde_json::from_str::<Value>(text) {
+            let keys = DROP_KEYS.read().map(|k| k.clone()).unwrap_or_default();
+            let key_refs: Vec<&str> = keys.iter().map(|k| k.as_str()).collect();
+            let compacted = compact_json_tool_result(&json, max_chars, &key_refs);
+            debug!("[ToolCompressor] Compacted JSON result, {} -> {} chars", text.len(), compacted.len());
+            return compacted;
+        }
+    }
     
     // [NEW] 针对可能的 HTML 内容进行深度预处理
     let cleaned_text = if text.contains("<html") || text.contains("<body") || text.contains("<!DOCTYPE") {
@@ -68,6 +118,165 @@ pub fn compact_tool_result_text(text: &str, max_chars: usize) -> String {
     truncate_text_safe(&cleaned_text, max_chars)
 }
 
+/// 压缩 JSON 工具结果
+///
+/// 递归剔除 `drop_keys` 中的字段后紧凑序列化，仍超出 `max_chars` 时再做安全截断
+pub fn compact_json_tool_result(json: &Value, max_chars: usize, drop_keys: &[&str]) -> String {
+    let mut cleaned = json.clone();
+    drop_json_keys(&mut cleaned, drop_keys);
+
+    let serialized = serde_json::to_string(&cleaned).unwrap_or_else(|_| json.to_string());
+    if serialized.len() <= max_chars {
+        serialized
+    } else {
+        truncate_text_safe(&serialized, max_chars)
+    }
+}
+
+fn drop_json_keys(value: &mut Value, drop_keys: &[&str]) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|k, _| !drop_keys.contains(&k.as_str()));
+            for v in map.values_mut() {
+                drop_json_keys(v, drop_keys);
+            }
+        }
+        Value::Array(arr) => {
+            for v in arr.iter_mut() {
+                drop_json_keys(v, drop_keys);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 工具结果中可识别的文档格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Pdf,
+    Docx,
+    Base64Pdf,
+    Base64Docx,
+    Unknown,
+}
+
+/// PDF 文件头 `%PDF-` 的 base64 前缀
+const BASE64_PDF_PREFIX: &str = "JVBERi0";
+/// ZIP 本地文件头 `PK\x03\x04` 的 base64 前缀
+const BASE64_ZIP_PREFIX: &str = "UEsDB";
+
+/// word/document.xml 解压后的最大字节数 (防止 zip 炸弹)
+const MAX_DOCX_XML_BYTES: u64 = 16 * 1024 * 1024;
+
+/// DOCX 段落结束标签 / 任意 XML 标签
+static DOCX_PARAGRAPH_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"</w:p>").unwrap());
+static DOCX_TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+/// 检测工具结果文本是否为 PDF / DOCX 文档 (原始或 base64 编码)
+pub fn detect_document_format(text: &str) -> DocumentFormat {
+    if text.starts_with("%PDF-") {
+        return DocumentFormat::Pdf;
+    }
+    if text.starts_with("PK\u{3}\u{4}") {
+        return DocumentFormat::Docx;
+    }
+
+    let payload = strip_data_url_prefix(text.trim_start());
+    if payload.starts_with(BASE64_PDF_PREFIX) {
+        DocumentFormat::Base64Pdf
+    } else if payload.starts_with(BASE64_ZIP_PREFIX) {
+        DocumentFormat::Base64Docx
+    } else {
+        DocumentFormat::Unknown
+    }
+}
+
+/// 从文档中提取纯文本，解析失败或没有正文时返回 None
+pub fn extract_text_from_document(text: &str, format: DocumentFormat) -> Option<String> {
+    let bytes: Vec<u8> = match format {
+        // 原始二进制经过 UTF-8 往返可能已损坏，尽力而为
+        DocumentFormat::Pdf | DocumentFormat::Docx => text.as_bytes().to_vec(),
+        DocumentFormat::Base64Pdf | DocumentFormat::Base64Docx => {
+            use base64::Engine;
+            let payload: String = strip_data_url_prefix(text.trim())
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect();
+            base64::engine::general_purpose::STANDARD.decode(payload).ok()?
+        }
+        DocumentFormat::Unknown => return None,
+    };
+
+    let (label, extracted) = match format {
+        DocumentFormat::Pdf | DocumentFormat::Base64Pdf => ("PDF", extract_pdf_text(&bytes)?),
+        DocumentFormat::Docx | DocumentFormat::Base64Docx => ("DOCX", extract_docx_text(&bytes)?),
+        DocumentFormat::Unknown => return None,
+    };
+
+    let extracted = extracted.trim();
+    if extracted.is_empty() {
+        return None;
+    }
+    Some(format!("[extracted text from {} document]\n{}", label, extracted))
+}
+
+/// 去掉 `data:<mime>;base64,` 前缀
+fn strip_data_url_prefix(text: &str) -> &str {
+    if text.starts_with("data:") {
+        if let Some(idx) = text.find(";base64,") {
+            return &text[idx + ";base64,".len()..];
+        }
+    }
+    text
+}
+
+fn extract_pdf_text(bytes: &[u8]) -> Option<String> {
+    let doc = lopdf::Document::load_mem(bytes).ok()?;
+    let pages: Vec<u32> = doc.get_pages().keys().copied().collect();
+    if pages.is_empty() {
+        return None;
+    }
+    doc.extract_text(&pages).ok()
+}
+
+fn extract_docx_text(bytes: &[u8]) -> Option<String> {
+    let xml = read_docx_document_xml(bytes, MAX_DOCX_XML_BYTES)?;
+
+    // 段落结束 → 换行，<w:t> 内为正文，其余标签全部丢弃
+    let with_breaks = DOCX_PARAGRAPH_RE.replace_all(&xml, "\n");
+    let plain = DOCX_TAG_RE.replace_all(&with_breaks, "");
+
+    Some(
+        plain
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// 读取 word/document.xml，声明大小或实际解压大小超过 `max_bytes` 时放弃
+fn read_docx_document_xml(bytes: &[u8], max_bytes: u64) -> Option<String> {
+    use std::io::Read;
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).ok()?;
+    let entry = archive.by_name("word/document.xml").ok()?;
+    if entry.size() > max_bytes {
+        debug!("DOCX document.xml declares {} bytes, exceeding limit {}", entry.size(), max_bytes);
+        return None;
+    }
+
+    // 声明大小可能被伪造，解压时同样限制读取量
+    let mut xml = String::new();
+    entry.take(max_bytes + 1).read_to_string(&mut xml).ok()?;
+    if xml.len() as u64 > max_bytes {
+        debug!("DOCX document.xml exceeds limit {} bytes while decompressing", max_bytes);
+        return None;
+    }
+    Some(xml)
+}
+
 /// 压缩"输出已保存到文件"类型的提示
 /// 
 /// 检测模式: "result (N characters) exceeds maximum allowed tokens. Output saved to <path>"
@@ -244,79 +453,87 @@ fn deep_clean_html(html: &str) -> String {
     result
 }
 
-/// 清理工具结果 content blocks
-/// 
+/// 惰性压缩工具结果 blocks
+///
 /// 处理逻辑:
 /// 1. 移除 base64 图片 (避免体积过大)
 /// 2. 压缩文本内容 (使用智能压缩策略)
-/// 3. 限制总字符数 (默认 200,000)
-/// 
+/// 3. 限制总字符数 (`total_budget`，默认 `MAX_TOOL_RESULT_CHARS`)
+///
 /// 参考: anthropicGeminiBridgeService.js:540-597
-pub fn sanitize_tool_result_blocks(blocks: &mut Vec<Value>) {
-    let mut used_chars = 0;
-    let mut cleaned_blocks = Vec::new();
-    let mut removed_image = false;
-    
-    if !blocks.is_empty() {
-        info!(
-            "[ToolCompressor] Processing {} blocks for truncation (MAX: {} chars)",
-            blocks.len(),
-            MAX_TOOL_RESULT_CHARS
-        );
+///
+/// 按需逐个产出压缩后的 block，避免在大量工具结果的 agent 循环中一次性持有全部中间结果。
+/// 累计字符数达到 `total_budget` 后停止产出内容；若移除过 base64 图片，末尾追加一条提示。
+pub fn compact_tool_result_stream<I>(iter: I, total_budget: usize) -> ToolResultStream<I::IntoIter>
+where
+    I: IntoIterator<Item = Value>,
+{
+    ToolResultStream {
+        inner: iter.into_iter(),
+        budget: total_budget,
+        used_chars: 0,
+        removed_image: false,
+        exhausted: false,
     }
-    
-    for block in blocks.iter() {
-        // 移除 base64 图片
-        if is_base64_image(block) {
-            removed_image = true;
-            debug!("[ToolCompressor] Removed base64 image block");
-            continue;
-        }
-        
-        // 压缩文本内容
-        if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
-            let remaining = MAX_TOOL_RESULT_CHARS.saturating_sub(used_chars);
-            if remaining == 0 {
+}
+
+/// `compact_tool_result_stream` 返回的迭代器
+pub struct ToolResultStream<I> {
+    inner: I,
+    budget: usize,
+    used_chars: usize,
+    removed_image: bool,
+    exhausted: bool,
+}
+
+impl<I: Iterator<Item = Value>> Iterator for ToolResultStream<I> {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        while !self.exhausted {
+            if self.used_chars >= self.budget {
                 debug!("[ToolCompressor] Reached character limit, stopping");
+                self.exhausted = true;
                 break;
             }
-            
-            let compacted = compact_tool_result_text(text, remaining);
-            let mut new_block = block.clone();
-            new_block["text"] = Value::String(compacted.clone());
-            cleaned_blocks.push(new_block);
-            used_chars += compacted.len();
-            
-            debug!(
-                "[ToolCompressor] Compacted text block: {} → {} chars",
-                text.len(),
-                compacted.len()
-            );
-        } else {
-            cleaned_blocks.push(block.clone());
-            used_chars += 100; // 估算非文本块大小
+
+            let Some(mut block) = self.inner.next() else {
+                self.exhausted = true;
+                break;
+            };
+
+            // 移除 base64 图片
+            if is_base64_image(&block) {
+                self.removed_image = true;
+                debug!("[ToolCompressor] Removed base64 image block");
+                continue;
+            }
+
+            // 压缩文本内容
+            if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
+                let remaining = self.budget - self.used_chars;
+                let compacted = compact_tool_result_text(text, remaining);
+                debug!(
+                    "[ToolCompressor] Compacted text block: {} → {} chars",
+                    text.len(),
+                    compacted.len()
+                );
+                self.used_chars += compacted.len();
+                block["text"] = Value::String(compacted);
+            } else {
+                self.used_chars += 100; // 估算非文本块大小
+            }
+            return Some(block);
         }
-        
-        if used_chars >= MAX_TOOL_RESULT_CHARS {
-            break;
+
+        if std::mem::take(&mut self.removed_image) {
+            return Some(serde_json::json!({
+                "type": "text",
+                "text": "[image omitted to fit Antigravity prompt limits; use the file path in the previous text block]"
+            }));
         }
+        None
     }
-    
-    if removed_image {
-        cleaned_blocks.push(serde_json::json!({
-            "type": "text",
-            "text": "[image omitted to fit Antigravity prompt limits; use the file path in the previous text block]"
-        }));
-    }
-    
-    info!(
-        "[ToolCompressor] Sanitization complete: {} → {} blocks, {} chars used",
-        blocks.len(),
-        cleaned_blocks.len(),
-        used_chars
-    );
-    
-    *blocks = cleaned_blocks;
 }
 
 /// 检测是否是 base64 图片块
@@ -375,7 +592,7 @@ Please read the file locally."#;
 
     #[test]
     fn test_sanitize_tool_result_blocks() {
-        let mut blocks = vec![
+        let blocks = vec![
             serde_json::json!({
                 "type": "text",
                 "text": "a".repeat(100_000)
@@ -386,7 +603,7 @@ Please read the file locally."#;
             }),
         ];
 
-        sanitize_tool_result_blocks(&mut blocks);
+        let blocks: Vec<Value> = compact_tool_result_stream(blocks, MAX_TOOL_RESULT_CHARS).collect();
 
         assert_eq!(blocks.len(), 2);
         // 第一个块应该保持原样
@@ -397,7 +614,7 @@ Please read the file locally."#;
 
     #[test]
     fn test_sanitize_removes_base64_image() {
-        let mut blocks = vec![
+        let blocks = vec![
             serde_json::json!({
                 "type": "image",
                 "source": {
@@ -411,7 +628,7 @@ Please read the file locally."#;
             }),
         ];
 
-        sanitize_tool_result_blocks(&mut blocks);
+        let blocks: Vec<Value> = compact_tool_result_stream(blocks, MAX_TOOL_RESULT_CHARS).collect();
 
         // 图片应该被移除,添加了提示文本
         assert_eq!(blocks.len(), 2);
@@ -420,6 +637,149 @@ Please read the file locally."#;
         assert!(blocks[1]["text"].as_str().unwrap().contains("[image omitted"));
     }
 
+    fn minimal_pdf() -> Vec<u8> {
+        use lopdf::content::{Content, Operation};
+        use lopdf::{dictionary, Document, Object, Stream};
+
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Courier",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 24.into()]),
+                Operation::new("Td", vec![100.into(), 600.into()]),
+                Operation::new("Tj", vec![Object::string_literal("Hello PDF")]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+
+        let mut buf = Vec::new();
+        doc.save_to(&mut buf).unwrap();
+        buf
+    }
+
+    fn minimal_docx() -> Vec<u8> {
+        use std::io::Write;
+
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        writer
+            .start_file("word/document.xml", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .write_all(
+                br#"<?xml version="1.0"?><w:document><w:body><w:p><w:r><w:t>Hello DOCX</w:t></w:r></w:p><w:p><w:r><w:t>A &amp; B</w:t></w:r></w:p></w:body></w:document>"#,
+            )
+            .unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_compact_json_tool_result() {
+        let json = serde_json::json!({
+            "items": [
+                { "id": 1, "name": "a", "_links": { "self": "/items/1" }, "etag": "x" },
+                { "id": 2, "name": "b", "metadata": { "created": "2024" } }
+            ],
+            "headers": { "content-type": "application/json" }
+        });
+
+        let result = compact_json_tool_result(&json, 10_000, DEFAULT_DROP_KEYS);
+        assert_eq!(result, r#"{"items":[{"id":1,"name":"a"},{"id":2,"name":"b"}]}"#);
+
+        // 剔除后仍超限则截断
+        let big = serde_json::json!({ "data": "z".repeat(5_000) });
+        assert!(compact_json_tool_result(&big, 1_000, &[]).contains("[truncated"));
+    }
+
+    #[test]
+    fn test_detect_document_format() {
+        use base64::Engine;
+        let b64 = base64::engine::general_purpose::STANDARD;
+
+        assert_eq!(detect_document_format("%PDF-1.5\n..."), DocumentFormat::Pdf);
+        assert_eq!(detect_document_format("PK\u{3}\u{4}rest"), DocumentFormat::Docx);
+        assert_eq!(detect_document_format(&b64.encode(minimal_pdf())), DocumentFormat::Base64Pdf);
+        assert_eq!(
+            detect_document_format(&format!("data:application/vnd.openxmlformats-officedocument.wordprocessingml.document;base64,{}", b64.encode(minimal_docx()))),
+            DocumentFormat::Base64Docx
+        );
+        assert_eq!(detect_document_format("plain text"), DocumentFormat::Unknown);
+    }
+
+    #[test]
+    fn test_extract_text_from_base64_pdf() {
+        use base64::Engine;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(minimal_pdf());
+
+        let text = extract_text_from_document(&encoded, DocumentFormat::Base64Pdf).unwrap();
+        assert!(text.contains("Hello PDF"));
+
+        let compacted = compact_tool_result_text(&encoded, 10_000);
+        assert!(compacted.starts_with("[extracted text from PDF document]"));
+    }
+
+    #[test]
+    fn test_extract_text_from_base64_docx() {
+        use base64::Engine;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(minimal_docx());
+
+        let text = extract_text_from_document(&encoded, DocumentFormat::Base64Docx).unwrap();
+        assert!(text.contains("Hello DOCX\nA & B"));
+
+        // 损坏的文档保持原样
+        assert!(extract_text_from_document("UEsDBBroken", DocumentFormat::Base64Docx).is_none());
+    }
+
+    #[test]
+    fn test_docx_document_xml_size_limit() {
+        let docx = minimal_docx();
+        assert!(read_docx_document_xml(&docx, 10_000).is_some());
+        // 解压后超过上限时放弃提取
+        assert!(read_docx_document_xml(&docx, 64).is_none());
+    }
+
+    #[test]
+    fn test_compact_tool_result_stream_is_lazy() {
+        let consumed = std::cell::Cell::new(0usize);
+        let source = (0..1000).map(|i| {
+            consumed.set(consumed.get() + 1);
+            serde_json::json!({ "type": "text", "text": format!("{}{}", i, "x".repeat(1_000)) })
+        });
+
+        // 预算只够约 10 个 block，上游迭代器不应被完整消费
+        let out: Vec<Value> = compact_tool_result_stream(source, 10_000).collect();
+        assert!(out.len() <= 11);
+        assert!(consumed.get() < 20);
+        let total: usize = out.iter().filter_map(|b| b["text"].as_str()).map(|t| t.len()).sum();
+        assert!(total <= 10_500);
+    }
+
     #[test]
     fn test_is_base64_image() {
         let image_block = serde_json::json!({
@@ -0,0 +1,216 @@
+// Prometheus / OpenMetrics 运行指标
+use dashmap::DashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// 请求耗时直方图桶上界 (秒)
+const DURATION_BUCKETS: [f64; 11] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+/// model 标签的最大取值数；模型名来自客户端请求，超出后归入 OTHER_MODEL_LABEL，避免时间序列无限增长
+const MAX_MODEL_LABELS: usize = 64;
+const OTHER_MODEL_LABEL: &str = "other";
+
+#[derive(Default)]
+struct DurationHistogram {
+    /// 每个桶的独立计数 (渲染时累加为 Prometheus 的累计桶)
+    buckets: [AtomicU64; DURATION_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl DurationHistogram {
+    fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        if let Some(idx) = DURATION_BUCKETS.iter().position(|le| secs <= *le) {
+            self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+/// 反代运行指标 (进程内累计，服务重启后清零)
+#[derive(Default)]
+pub struct ProxyMetrics {
+    /// (status, model, provider) -> 请求数
+    requests: DashMap<(String, String, String), AtomicU64>,
+    /// (model, provider) -> 耗时分布
+    durations: DashMap<(String, String), DurationHistogram>,
+    /// 已分配独立标签的模型名
+    model_labels: DashMap<String, ()>,
+    token_refresh_success: AtomicU64,
+    token_refresh_failure: AtomicU64,
+}
+
+impl ProxyMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 模型名对应的标签值 (小写规范化；超过 MAX_MODEL_LABELS 后的新模型归入 other)
+    fn model_label(&self, model: &str) -> String {
+        let model = model.trim().to_lowercase();
+        if self.model_labels.contains_key(&model) {
+            return model;
+        }
+        if self.model_labels.len() >= MAX_MODEL_LABELS {
+            return OTHER_MODEL_LABEL.to_string();
+        }
+        self.model_labels.insert(model.clone(), ());
+        model
+    }
+
+    /// 记录一次已完成的请求
+    pub fn record_request(&self, status: u16, model: &str, provider: &str, duration: Duration) {
+        let model = self.model_label(model);
+        self.requests
+            .entry((status.to_string(), model.clone(), provider.to_string()))
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+        self.durations
+            .entry((model, provider.to_string()))
+            .or_default()
+            .observe(duration);
+    }
+
+    /// 记录一次 OAuth token 刷新结果
+    pub fn record_token_refresh(&self, success: bool) {
+        let counter = if success {
+            &self.token_refresh_success
+        } else {
+            &self.token_refresh_failure
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 渲染为 OpenMetrics 文本格式
+    pub fn render(&self, active_accounts: usize, rate_limited_accounts: usize) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# TYPE antigravity_requests counter");
+        let _ = writeln!(out, "# HELP antigravity_requests Proxied API requests.");
+        let mut requests: Vec<_> = self
+            .requests
+            .iter()
+            .map(|e| (e.key().clone(), e.value().load(Ordering::Relaxed)))
+            .collect();
+        requests.sort();
+        for ((status, model, provider), count) in requests {
+            let _ = writeln!(
+                out,
+                "antigravity_requests_total{{status=\"{}\",model=\"{}\",provider=\"{}\"}} {}",
+                escape_label(&status),
+                escape_label(&model),
+                escape_label(&provider),
+                count
+            );
+        }
+
+        let _ = writeln!(out, "# TYPE antigravity_request_duration_seconds histogram");
+        let _ = writeln!(out, "# UNIT antigravity_request_duration_seconds seconds");
+        let _ = writeln!(
+            out,
+            "# HELP antigravity_request_duration_seconds Time until upstream response headers are returned."
+        );
+        let mut keys: Vec<(String, String)> = self.durations.iter().map(|e| e.key().clone()).collect();
+        keys.sort();
+        for key in keys {
+            let Some(hist) = self.durations.get(&key) else { continue };
+            let labels = format!("model=\"{}\",provider=\"{}\"", escape_label(&key.0), escape_label(&key.1));
+            let mut cumulative = 0;
+            for (le, bucket) in DURATION_BUCKETS.iter().zip(hist.buckets.iter()) {
+                cumulative += bucket.load(Ordering::Relaxed);
+                let _ = writeln!(
+                    out,
+                    "antigravity_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, le, cumulative
+                );
+            }
+            let count = hist.count.load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "antigravity_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, count
+            );
+            let _ = writeln!(
+                out,
+                "antigravity_request_duration_seconds_sum{{{}}} {}",
+                labels,
+                hist.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+            );
+            let _ = writeln!(out, "antigravity_request_duration_seconds_count{{{}}} {}", labels, count);
+        }
+
+        let _ = writeln!(out, "# TYPE antigravity_active_accounts gauge");
+        let _ = writeln!(out, "# HELP antigravity_active_accounts Accounts loaded into the proxy pool.");
+        let _ = writeln!(out, "antigravity_active_accounts {}", active_accounts);
+
+        let _ = writeln!(out, "# TYPE antigravity_rate_limited_accounts gauge");
+        let _ = writeln!(out, "# HELP antigravity_rate_limited_accounts Accounts currently rate limited.");
+        let _ = writeln!(out, "antigravity_rate_limited_accounts {}", rate_limited_accounts);
+
+        let _ = writeln!(out, "# TYPE antigravity_token_refresh counter");
+        let _ = writeln!(out, "# HELP antigravity_token_refresh OAuth access token refresh attempts.");
+        let _ = writeln!(
+            out,
+            "antigravity_token_refresh_total{{result=\"success\"}} {}",
+            self.token_refresh_success.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            out,
+            "antigravity_token_refresh_total{{result=\"failure\"}} {}",
+            self.token_refresh_failure.load(Ordering::Relaxed)
+        );
+
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_openmetrics() {
+        let metrics = ProxyMetrics::new();
+        metrics.record_request(200, "gemini-2.5-flash", "google", Duration::from_millis(300));
+        metrics.record_request(200, "gemini-2.5-flash", "google", Duration::from_secs(3));
+        metrics.record_token_refresh(false);
+
+        let text = metrics.render(3, 1);
+        assert!(text.contains(
+            "antigravity_requests_total{status=\"200\",model=\"gemini-2.5-flash\",provider=\"google\"} 2"
+        ));
+        assert!(text.contains(
+            "antigravity_request_duration_seconds_bucket{model=\"gemini-2.5-flash\",provider=\"google\",le=\"0.5\"} 1"
+        ));
+        assert!(text.contains(
+            "antigravity_request_duration_seconds_bucket{model=\"gemini-2.5-flash\",provider=\"google\",le=\"+Inf\"} 2"
+        ));
+        assert!(text.contains("antigravity_active_accounts 3"));
+        assert!(text.contains("antigravity_rate_limited_accounts 1"));
+        assert!(text.contains("antigravity_token_refresh_total{result=\"failure\"} 1"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_model_labels_capped() {
+        let metrics = ProxyMetrics::new();
+        for i in 0..MAX_MODEL_LABELS {
+            metrics.record_request(200, &format!("model-{}", i), "google", Duration::from_millis(10));
+        }
+        metrics.record_request(200, "one-more-model", "google", Duration::from_millis(10));
+        metrics.record_request(200, "MODEL-0", "google", Duration::from_millis(10));
+
+        let text = metrics.render(0, 0);
+        assert!(text.contains("antigravity_requests_total{status=\"200\",model=\"other\",provider=\"google\"} 1"));
+        assert!(text.contains("antigravity_requests_total{status=\"200\",model=\"model-0\",provider=\"google\"} 2"));
+        assert!(!text.contains("one-more-model"));
+    }
+}
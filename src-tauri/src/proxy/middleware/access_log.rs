@@ -0,0 +1,110 @@
+// 访问日志中间件
+// 记录请求方法与路径，查询参数与请求头中的凭证在写入日志前替换为 ***
+use axum::{
+    extract::Request,
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+
+/// 需要脱敏的查询参数 (大小写不敏感)
+const SENSITIVE_QUERY_PARAMS: [&str; 3] = ["key", "api_key", "token"];
+
+/// 需要脱敏的请求头
+const SENSITIVE_HEADERS: [&str; 3] = ["authorization", "x-api-key", "x-goog-api-key"];
+
+const REDACTED: &str = "***";
+
+/// 将路径中 key / api_key / token 查询参数的值替换为 ***，其余参数原样保留
+pub fn sanitize_path_for_log(path: &str) -> String {
+    let Some((base, query)) = path.split_once('?') else {
+        return path.to_string();
+    };
+    let params: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if SENSITIVE_QUERY_PARAMS.iter().any(|p| name.eq_ignore_ascii_case(p)) => {
+                format!("{}={}", name, REDACTED)
+            }
+            _ => pair.to_string(),
+        })
+        .collect();
+    format!("{}?{}", base, params.join("&"))
+}
+
+/// 请求头的日志表示，Authorization / x-api-key / x-goog-api-key 的值替换为 ***
+pub fn sanitize_headers_for_log(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                value.to_str().unwrap_or("<binary>").to_string()
+            };
+            (name.as_str().to_string(), value)
+        })
+        .collect()
+}
+
+pub async fn access_log_middleware(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path();
+
+    // 过滤心跳和健康检查请求,避免日志噪音
+    if !path.contains("event_logging") && path != "/healthz" {
+        let target = request
+            .uri()
+            .path_and_query()
+            .map(|pq| sanitize_path_for_log(pq.as_str()))
+            .unwrap_or_else(|| path.to_string());
+        tracing::info!("Request: {} {}", method, target);
+        tracing::debug!(headers = ?sanitize_headers_for_log(request.headers()), "Request headers");
+    } else {
+        tracing::trace!("Heartbeat: {} {}", method, path);
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_sanitize_path_redacts_only_sensitive_params() {
+        assert_eq!(
+            sanitize_path_for_log("/v1beta/models/gemini:generateContent?alt=sse&key=sk-secret&foo=bar"),
+            "/v1beta/models/gemini:generateContent?alt=sse&key=***&foo=bar"
+        );
+        assert_eq!(
+            sanitize_path_for_log("/v1/messages?API_KEY=abc&token=xyz&monkey=1"),
+            "/v1/messages?API_KEY=***&token=***&monkey=1"
+        );
+        assert_eq!(sanitize_path_for_log("/v1/models?flag&key="), "/v1/models?flag&key=***");
+        assert_eq!(sanitize_path_for_log("/healthz"), "/healthz");
+    }
+
+    #[test]
+    fn test_sanitize_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer sk-secret"));
+        headers.insert("x-api-key", HeaderValue::from_static("sk-secret"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+
+        let sanitized = sanitize_headers_for_log(&headers);
+        assert!(sanitized.iter().all(|(_, v)| !v.contains("sk-secret")));
+        assert!(sanitized.contains(&("content-type".to_string(), "application/json".to_string())));
+        assert!(sanitized.contains(&("x-api-key".to_string(), "***".to_string())));
+    }
+
+    #[test]
+    fn test_sanitize_headers_redacts_goog_api_key() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-goog-api-key", HeaderValue::from_static("sk-secret"));
+
+        let sanitized = sanitize_headers_for_log(&headers);
+        assert_eq!(sanitized, vec![("x-goog-api-key".to_string(), "***".to_string())]);
+    }
+}
@@ -1,38 +1,56 @@
 // API Key 认证中间件
 use axum::{
     extract::State,
-    extract::Request,
+    extract::{ConnectInfo, Request},
     http::{header, StatusCode},
     middleware::Next,
     response::Response,
 };
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::proxy::middleware::jwt::{AuthenticatedSubject, JwksCache};
+use crate::proxy::middleware::key_rate_limit::{rate_limited_response, KeyRateLimiter};
 use crate::proxy::{ProxyAuthMode, ProxySecurityConfig};
 
 /// API Key 认证中间件
 pub async fn auth_middleware(
-    State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
-    request: Request,
+    State((security, jwks_cache)): State<(Arc<RwLock<ProxySecurityConfig>>, Arc<JwksCache>)>,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
     let method = request.method().clone();
     let path = request.uri().path().to_string();
 
-    // 过滤心跳和健康检查请求,避免日志噪音
-    if !path.contains("event_logging") && path != "/healthz" {
-        tracing::info!("Request: {} {}", method, path);
-    } else {
-        tracing::trace!("Heartbeat: {} {}", method, path);
-    }
-
     // Allow CORS preflight regardless of auth policy.
     if method == axum::http::Method::OPTIONS {
         return Ok(next.run(request).await);
     }
 
     let security = security.read().await.clone();
+
+    let ip = client_ip(&request, &security);
+    if let Some(ip) = ip {
+        request.extensions_mut().insert(ResolvedClientIp(ip));
+    }
+
+    // IP 白名单 (先于 API key 校验)
+    if !security.ip_allowlist.is_empty() {
+        match ip {
+            Some(ip) if security.is_ip_allowed(ip) => {}
+            ip => {
+                tracing::warn!("Rejected request from non-allowlisted IP: {:?}", ip);
+                return Err(StatusCode::FORBIDDEN);
+            }
+        }
+    }
+
+    // 免认证路径 (先于认证模式判断)
+    if security.is_bypass_path(&path) {
+        return Ok(next.run(request).await);
+    }
+
     let effective_mode = security.effective_auth_mode();
 
     if matches!(effective_mode, ProxyAuthMode::Off) {
@@ -42,7 +60,28 @@ pub async fn auth_middleware(
     if matches!(effective_mode, ProxyAuthMode::AllExceptHealth) && path == "/healthz" {
         return Ok(next.run(request).await);
     }
-    
+
+    // JWT 模式：验签并校验 aud，将 sub 作为请求扩展传给下游
+    if let ProxyAuthMode::Jwt { jwks_url, audience } = &effective_mode {
+        let token = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|s| s.strip_prefix("Bearer "))
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+
+        let subject = jwks_cache
+            .verify(token, jwks_url, audience)
+            .await
+            .map_err(|e| {
+                tracing::warn!("JWT auth rejected: {}", e);
+                StatusCode::UNAUTHORIZED
+            })?;
+
+        request.extensions_mut().insert(AuthenticatedSubject(subject));
+        return Ok(next.run(request).await);
+    }
+
     // 从 header 中提取 API key
     let api_key = request
         .headers()
@@ -56,19 +95,70 @@ pub async fn auth_middleware(
                 .and_then(|h| h.to_str().ok())
         });
 
-    if security.api_key.is_empty() {
-        tracing::error!("Proxy auth is enabled but api_key is empty; denying request");
+    if !security.has_api_keys() {
+        tracing::error!("Proxy auth is enabled but no api key is configured; denying request");
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    // Constant-time compare is unnecessary here, but keep strict equality and avoid leaking values.
-    let authorized = api_key.map(|k| k == security.api_key).unwrap_or(false);
+    // match_api_key 比较的是 SHA-256 摘要，耗时与密钥内容无关；日志中不记录密钥
+    let permissions = api_key.and_then(|k| security.match_api_key(k, chrono::Utc::now().timestamp()));
+
+    let Some(permissions) = permissions else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    // 按 Key 限流
+    if let Some((key_hash, limit)) = api_key.and_then(|k| security.key_rate_limit(k)) {
+        if let Err(retry_after) = KeyRateLimiter::global().check(&key_hash, limit) {
+            tracing::warn!("API key rate limit exceeded ({} rpm), retry after {}s", limit, retry_after);
+            return Ok(rate_limited_response(retry_after));
+        }
+        request.extensions_mut().insert(KeyRateLimit {
+            key_hash,
+            requests_per_minute: limit,
+        });
+    }
 
-    if authorized {
-        Ok(next.run(request).await)
-    } else {
-        Err(StatusCode::UNAUTHORIZED)
+    request.extensions_mut().insert(permissions);
+    if let Some(k) = api_key {
+        request
+            .extensions_mut()
+            .insert(AuthenticatedKeyHash(crate::proxy::security::hash_api_key(k)));
     }
+    Ok(next.run(request).await)
+}
+
+/// 通过认证的 API Key 哈希 (供幂等缓存等按调用方隔离的中间件使用)
+#[derive(Debug, Clone)]
+pub(crate) struct AuthenticatedKeyHash(pub String);
+
+/// 当前 API Key 的每分钟请求数上限 (批量请求据此为每个子请求计数)
+#[derive(Debug, Clone)]
+pub(crate) struct KeyRateLimit {
+    pub key_hash: String,
+    pub requests_per_minute: u32,
+}
+
+/// 认证中间件解析出的客户端 IP (供内层中间件复用)
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ResolvedClientIp(pub IpAddr);
+
+/// 连接的对端地址
+pub(crate) fn peer_ip(request: &Request) -> Option<IpAddr> {
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+/// 获取客户端 IP：默认为连接的对端地址，仅当对端是受信任代理时才采用 X-Forwarded-For
+pub(crate) fn client_ip(request: &Request, security: &ProxySecurityConfig) -> Option<IpAddr> {
+    let peer = peer_ip(request)?;
+    let forwarded_for = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|h| h.to_str().ok());
+    Some(security.resolve_client_ip(peer, forwarded_for))
 }
 
 #[cfg(test)]
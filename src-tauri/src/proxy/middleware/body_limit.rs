@@ -0,0 +1,99 @@
+// 请求体大小限制
+// RequestBodyLimitLayer 负责截断 (Content-Length 超限时直接拒绝，分块传输时读取超限报错)，
+// 本中间件将其返回的纯文本 413 改写为 Anthropic 风格的 JSON 错误
+// 只限制请求体；SSE 等流式响应不受影响
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tower_http::limit::RequestBodyLimitLayer;
+
+pub fn body_limit_layer(max_request_body_mb: u64) -> RequestBodyLimitLayer {
+    let bytes = max_request_body_mb.max(1).saturating_mul(1024 * 1024);
+    RequestBodyLimitLayer::new(usize::try_from(bytes).unwrap_or(usize::MAX))
+}
+
+pub fn payload_too_large_response() -> Response {
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": "invalid_request_error",
+                "message": "Request body too large"
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 读取请求体失败是否由大小限制导致
+pub fn is_length_limit_error(error: &axum::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(e) = source {
+        if e.is::<http_body_util::LengthLimitError>() {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+pub async fn payload_too_large_middleware(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.contains("application/json"))
+        .unwrap_or(false);
+    // 上游或处理器已返回 JSON 错误时保持原样
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return payload_too_large_response();
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::post, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/v1/messages", post(|body: axum::body::Bytes| async move { body.len().to_string() }))
+            .layer(axum::extract::DefaultBodyLimit::disable())
+            .layer(body_limit_layer(1))
+            .layer(axum::middleware::from_fn(payload_too_large_middleware))
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_rejected_with_json_error() {
+        let response = app()
+            .oneshot(
+                Request::post("/v1/messages")
+                    .body(Body::from(vec![b'a'; 2 * 1024 * 1024]))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["type"], "invalid_request_error");
+        assert_eq!(json["error"]["message"], "Request body too large");
+    }
+
+    #[tokio::test]
+    async fn test_body_within_limit_passes() {
+        let response = app()
+            .oneshot(Request::post("/v1/messages").body(Body::from("{}")).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
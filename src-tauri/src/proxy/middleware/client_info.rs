@@ -0,0 +1,51 @@
+// 请求来源信息 (客户端 IP / User-Agent / 路由标签)
+// 通过 task-local 在请求处理期间暴露给 TokenManager，用于记录会话来源与按标签选择账号
+use axum::{
+    extract::Request,
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub client_ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    /// X-Antigravity-Tag 请求头 (逗号分隔的账号标签)
+    pub routing_tag: Option<String>,
+}
+
+/// 按账号标签路由的请求头
+pub const ROUTING_TAG_HEADER: &str = "X-Antigravity-Tag";
+
+tokio::task_local! {
+    static CLIENT_INFO: ClientInfo;
+}
+
+/// 获取当前请求的来源信息 (不在请求上下文中时返回默认值)
+pub fn current() -> ClientInfo {
+    CLIENT_INFO.try_with(|info| info.clone()).unwrap_or_default()
+}
+
+pub async fn client_info_middleware(request: Request, next: Next) -> Response {
+    let info = ClientInfo {
+        client_ip: request
+            .extensions()
+            .get::<super::auth::ResolvedClientIp>()
+            .map(|ip| ip.0)
+            .or_else(|| super::auth::peer_ip(&request)),
+        user_agent: request
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string()),
+        routing_tag: request
+            .headers()
+            .get(ROUTING_TAG_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty()),
+    };
+    CLIENT_INFO.scope(info, next.run(request)).await
+}
@@ -0,0 +1,71 @@
+// 响应压缩 (gzip)
+// 仅压缩非流式响应：SSE 逐块发送，压缩会导致客户端在缓冲区填满前收不到数据
+use tower_http::compression::{
+    predicate::{NotForContentType, Predicate},
+    CompressionLayer, DefaultPredicate,
+};
+
+pub fn compression_layer(enabled: bool) -> CompressionLayer<impl Predicate> {
+    let predicate = DefaultPredicate::new()
+        .and(NotForContentType::const_new("text/event-stream"))
+        .and(move |_: axum::http::StatusCode,
+                   _: axum::http::Version,
+                   _: &axum::http::HeaderMap,
+                   _: &axum::http::Extensions| enabled);
+    CompressionLayer::new().gzip(true).compress_when(predicate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        extract::Request,
+        http::header,
+        response::{IntoResponse, Response},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    fn large_body() -> String {
+        "x".repeat(4096)
+    }
+
+    fn app(enabled: bool) -> Router {
+        Router::new()
+            .route("/v1/models", get(|| async { large_body() }))
+            .route(
+                "/stream",
+                get(|| async { ([(header::CONTENT_TYPE, "text/event-stream")], large_body()).into_response() }),
+            )
+            .layer(compression_layer(enabled))
+    }
+
+    async fn send(enabled: bool, path: &str) -> Response {
+        app(enabled)
+            .oneshot(
+                Request::get(path)
+                    .header(header::ACCEPT_ENCODING, "gzip")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+
+    fn encoding(response: &Response) -> Option<&str> {
+        response.headers().get(header::CONTENT_ENCODING).and_then(|v| v.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn test_non_streaming_response_is_gzipped() {
+        assert_eq!(encoding(&send(true, "/v1/models").await), Some("gzip"));
+        assert_eq!(encoding(&send(false, "/v1/models").await), None);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_is_not_compressed() {
+        assert_eq!(encoding(&send(true, "/stream").await), None);
+    }
+}
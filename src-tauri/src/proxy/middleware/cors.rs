@@ -1,11 +1,62 @@
 // CORS 中间件
-use tower_http::cors::{CorsLayer, Any};
+// 允许的来源由 ProxySecurityConfig::cors_allowed_origins 控制，随安全配置热更新；
+// 列表为空时允许任意来源
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use axum::http::Method;
 
+use crate::proxy::ProxySecurityConfig;
+
+/// 来源是否匹配配置项
+/// 支持 `*`、完整来源 (`https://app.example.com`)、主机名 (`app.example.com`) 以及
+/// 子域名通配 (`*.example.com` / `https://*.example.com`，不含 example.com 本身)
+pub fn origin_matches(pattern: &str, origin: &str) -> bool {
+    let pattern = pattern.trim().trim_end_matches('/').to_ascii_lowercase();
+    let origin = origin.trim().trim_end_matches('/').to_ascii_lowercase();
+    if pattern.is_empty() {
+        return false;
+    }
+    if pattern == "*" {
+        return true;
+    }
+
+    let (origin_scheme, origin_host) = match origin.split_once("://") {
+        Some((scheme, host)) => (Some(scheme), host),
+        None => (None, origin.as_str()),
+    };
+    let host_pattern = match pattern.split_once("://") {
+        Some((scheme, host)) => {
+            if origin_scheme != Some(scheme) {
+                return false;
+            }
+            host
+        }
+        None => pattern.as_str(),
+    };
+
+    match host_pattern.strip_prefix("*.") {
+        Some(suffix) => origin_host
+            .strip_suffix(suffix)
+            .map(|sub| sub.len() > 1 && sub.ends_with('.'))
+            .unwrap_or(false),
+        None => origin_host == host_pattern,
+    }
+}
+
 /// 创建 CORS layer
-pub fn cors_layer() -> CorsLayer {
+pub fn cors_layer(security: Arc<RwLock<ProxySecurityConfig>>) -> CorsLayer {
     CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(AllowOrigin::predicate(move |origin, _| {
+            // 安全配置仅在热更新时短暂持有写锁，此时拒绝跨域请求
+            let Ok(origin) = origin.to_str() else {
+                return false;
+            };
+            security
+                .try_read()
+                .map(|sec| sec.is_cors_origin_allowed(origin))
+                .unwrap_or(false)
+        }))
         .allow_methods([
             Method::GET,
             Method::POST,
@@ -23,11 +74,69 @@ pub fn cors_layer() -> CorsLayer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::{body::Body, extract::Request, http::header, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn security(origins: &[&str]) -> Arc<RwLock<ProxySecurityConfig>> {
+        let mut config = crate::proxy::config::ProxyConfig::default();
+        config.cors_allowed_origins = origins.iter().map(|s| s.to_string()).collect();
+        Arc::new(RwLock::new(ProxySecurityConfig::from_proxy_config(&config)))
+    }
 
     #[test]
     fn test_cors_layer_creation() {
-        let _layer = cors_layer();
+        let _layer = cors_layer(security(&[]));
         // Layer 创建成功
         assert!(true);
     }
+
+    #[test]
+    fn test_origin_matches() {
+        assert!(origin_matches("*", "https://anything.dev"));
+        assert!(origin_matches("https://app.example.com/", "https://APP.example.com"));
+        assert!(!origin_matches("https://app.example.com", "http://app.example.com"));
+        assert!(origin_matches("localhost:3000", "http://localhost:3000"));
+
+        assert!(origin_matches("*.example.com", "https://a.example.com"));
+        assert!(origin_matches("*.example.com", "http://a.b.example.com"));
+        assert!(!origin_matches("*.example.com", "https://example.com"));
+        assert!(!origin_matches("*.example.com", "https://evilexample.com"));
+        assert!(origin_matches("https://*.example.com", "https://a.example.com"));
+        assert!(!origin_matches("https://*.example.com", "http://a.example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_origin_restricted_and_hot_updated() {
+        let security = security(&["*.example.com"]);
+        let app = Router::new()
+            .route("/v1/models", get(|| async { "ok" }))
+            .layer(cors_layer(security.clone()));
+        let allow_origin = |app: Router, origin: &'static str| async move {
+            let response = app
+                .oneshot(
+                    Request::get("/v1/models")
+                        .header(header::ORIGIN, origin)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            response
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .map(|v| v.to_str().unwrap().to_string())
+        };
+
+        assert_eq!(
+            allow_origin(app.clone(), "https://app.example.com").await.as_deref(),
+            Some("https://app.example.com")
+        );
+        assert_eq!(allow_origin(app.clone(), "https://evil.dev").await, None);
+
+        security.write().await.cors_allowed_origins.clear();
+        assert_eq!(
+            allow_origin(app, "https://evil.dev").await.as_deref(),
+            Some("https://evil.dev")
+        );
+    }
 }
@@ -0,0 +1,274 @@
+// 幂等请求缓存
+// 客户端在网络失败后重试时携带相同的 X-Idempotency-Key，60 秒内直接返回缓存的响应，避免重复消耗配额
+// 仅缓存成功的非流式响应
+// 缓存按调用方隔离 (API Key 哈希 / JWT subject / 客户端 IP)；同一 key 携带不同请求体时返回 422
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use crate::proxy::middleware::auth::{AuthenticatedKeyHash, ResolvedClientIp};
+use crate::proxy::middleware::jwt::AuthenticatedSubject;
+use crate::proxy::server::AppState;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "x-idempotency-key";
+pub const IDEMPOTENCY_CACHE_HEADER: &str = "x-idempotency-cache";
+
+const IDEMPOTENCY_TTL: Duration = Duration::from_secs(60);
+const MAX_KEY_LEN: usize = 256;
+const MAX_CACHED_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
+const MAX_REQUEST_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB (实际上限由外层 body_limit 层控制)
+const MAX_ENTRIES: usize = 10_000;
+
+/// 缓存的响应 (写入时间, 请求体 SHA-256, 响应体)
+struct CachedResponse {
+    created: Instant,
+    request_hash: String,
+    body: Bytes,
+}
+
+/// 查询结果
+#[derive(Debug, PartialEq)]
+pub enum Lookup {
+    Hit(Bytes),
+    /// 同一 key 但请求体不同
+    Mismatch,
+    Miss,
+}
+
+/// 幂等响应缓存 (调用方 + 路径 + key -> 缓存响应)，最多保留 MAX_ENTRIES 条
+#[derive(Default)]
+pub struct IdempotencyStore {
+    entries: DashMap<String, CachedResponse>,
+}
+
+impl IdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 查询未过期的缓存响应，并校验请求体是否与首次请求一致
+    pub fn lookup(&self, key: &str, request_hash: &str) -> Lookup {
+        match self.entries.get(key).filter(|e| e.created.elapsed() < IDEMPOTENCY_TTL) {
+            Some(e) if e.request_hash == request_hash => Lookup::Hit(e.body.clone()),
+            Some(_) => Lookup::Mismatch,
+            None => Lookup::Miss,
+        }
+    }
+
+    pub fn insert(&self, key: String, request_hash: String, body: Bytes) {
+        if self.entries.len() >= MAX_ENTRIES && !self.entries.contains_key(&key) {
+            self.cleanup_expired();
+            // 仍已满时淘汰最早的条目
+            if self.entries.len() >= MAX_ENTRIES {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|e| e.created)
+                    .map(|e| e.key().clone());
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        self.entries.insert(
+            key,
+            CachedResponse {
+                created: Instant::now(),
+                request_hash,
+                body,
+            },
+        );
+    }
+
+    /// 清理过期条目，返回清理数量
+    pub fn cleanup_expired(&self) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, e| e.created.elapsed() < IDEMPOTENCY_TTL);
+        before.saturating_sub(self.entries.len())
+    }
+
+    /// 启动后台清理任务 (每 60 秒)，缓存被释放 (反代停止) 后任务自动退出
+    pub fn start_cleanup_task(self: &Arc<Self>) {
+        let store: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(IDEMPOTENCY_TTL);
+            loop {
+                interval.tick().await;
+                let Some(store) = store.upgrade() else {
+                    break;
+                };
+                let removed = store.cleanup_expired();
+                if removed > 0 {
+                    tracing::debug!("[Idempotency] Removed {} expired entries", removed);
+                }
+            }
+        });
+    }
+}
+
+fn cache_header(value: &'static str) -> (header::HeaderName, HeaderValue) {
+    (header::HeaderName::from_static(IDEMPOTENCY_CACHE_HEADER), HeaderValue::from_static(value))
+}
+
+pub async fn idempotency_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = request
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+    else {
+        return next.run(request).await;
+    };
+
+    if key.len() > MAX_KEY_LEN {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": {
+                    "type": "invalid_request_error",
+                    "message": format!("X-Idempotency-Key must be at most {} bytes", MAX_KEY_LEN)
+                }
+            })),
+        )
+            .into_response();
+    }
+
+    // 同一 key 在不同调用方、不同端点上互不影响
+    let key = format!("{} {} {}", caller_scope(&request), request.uri().path(), key);
+
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_REQUEST_BODY_SIZE).await {
+        Ok(b) => b,
+        Err(e) if crate::proxy::middleware::body_limit::is_length_limit_error(&e) => {
+            return crate::proxy::middleware::body_limit::payload_too_large_response();
+        }
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, format!("Failed to read request body: {}", e)).into_response();
+        }
+    };
+    let request_hash = format!("{:x}", Sha256::digest(&body));
+    let request = Request::from_parts(parts, Body::from(body));
+
+    match state.idempotency.lookup(&key, &request_hash) {
+        Lookup::Hit(body) => {
+            tracing::debug!("[Idempotency] Cache hit");
+            return (
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+                    cache_header("hit"),
+                ],
+                body,
+            )
+                .into_response();
+        }
+        Lookup::Mismatch => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(serde_json::json!({
+                    "error": {
+                        "type": "invalid_request_error",
+                        "message": "X-Idempotency-Key was already used with a different request body"
+                    }
+                })),
+            )
+                .into_response();
+        }
+        Lookup::Miss => {}
+    }
+
+    let response = next.run(request).await;
+    let is_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.contains("text/event-stream"))
+        .unwrap_or(false);
+    if !response.status().is_success() || is_stream {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    match axum::body::to_bytes(body, MAX_CACHED_BODY_SIZE).await {
+        Ok(bytes) => {
+            state.idempotency.insert(key, request_hash, bytes.clone());
+            let (name, value) = cache_header("miss");
+            parts.headers.insert(name, value);
+            Response::from_parts(parts, Body::from(bytes))
+        }
+        Err(e) => Response::from_parts(parts, Body::from(format!("Failed to read response body: {}", e))),
+    }
+}
+
+/// 调用方标识：API Key 哈希 > JWT subject > 客户端 IP
+fn caller_scope(request: &Request) -> String {
+    let extensions = request.extensions();
+    if let Some(AuthenticatedKeyHash(hash)) = extensions.get::<AuthenticatedKeyHash>() {
+        return format!("key:{}", hash);
+    }
+    if let Some(AuthenticatedSubject(subject)) = extensions.get::<AuthenticatedSubject>() {
+        return format!("sub:{}", subject);
+    }
+    match extensions.get::<ResolvedClientIp>() {
+        Some(ResolvedClientIp(ip)) => format!("ip:{}", ip),
+        None => "anonymous".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_expiry() {
+        let store = IdempotencyStore::new();
+        store.insert("fresh".to_string(), "h".to_string(), Bytes::from_static(b"{}"));
+        store.entries.insert(
+            "stale".to_string(),
+            CachedResponse {
+                created: Instant::now() - Duration::from_secs(61),
+                request_hash: "h".to_string(),
+                body: Bytes::from_static(b"{}"),
+            },
+        );
+
+        assert_eq!(store.lookup("fresh", "h"), Lookup::Hit(Bytes::from_static(b"{}")));
+        assert_eq!(store.lookup("fresh", "other"), Lookup::Mismatch);
+        assert_eq!(store.lookup("stale", "h"), Lookup::Miss);
+        assert_eq!(store.lookup("missing", "h"), Lookup::Miss);
+
+        assert_eq!(store.cleanup_expired(), 1);
+        assert_eq!(store.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_store_is_bounded_and_scoped_by_caller() {
+        let store = IdempotencyStore::new();
+        for i in 0..MAX_ENTRIES + 5 {
+            store.insert(format!("k{}", i), "h".to_string(), Bytes::new());
+        }
+        assert_eq!(store.entries.len(), MAX_ENTRIES);
+
+        let mut a = Request::new(Body::empty());
+        a.extensions_mut().insert(AuthenticatedKeyHash("aaa".to_string()));
+        let mut b = Request::new(Body::empty());
+        b.extensions_mut().insert(AuthenticatedKeyHash("bbb".to_string()));
+        assert_ne!(caller_scope(&a), caller_scope(&b));
+        assert_eq!(caller_scope(&Request::new(Body::empty())), "anonymous");
+    }
+}
@@ -0,0 +1,89 @@
+// JWT 认证 (JWKS 验签)
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// JWKS 缓存有效期
+const JWKS_TTL: Duration = Duration::from_secs(3600);
+/// 找不到 kid 时强制刷新的最小间隔，防止伪造 kid 的请求反复拉取 JWKS
+const JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 已通过验证的调用方身份 (JWT `sub`)，作为请求扩展传递给下游中间件
+#[derive(Debug, Clone)]
+pub struct AuthenticatedSubject(pub String);
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+/// JWKS 缓存 (按 URL 缓存，过期或找不到 kid 时重新拉取，后者有最小间隔限制)
+#[derive(Default)]
+pub struct JwksCache {
+    entry: RwLock<Option<(String, Instant, JwkSet)>>,
+}
+
+impl JwksCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `force_refresh` 时忽略 TTL，但距上次拉取不足 `JWKS_MIN_REFRESH_INTERVAL` 仍使用缓存
+    async fn get(&self, jwks_url: &str, force_refresh: bool) -> Result<JwkSet, String> {
+        if let Some((url, fetched_at, set)) = self.entry.read().await.as_ref() {
+            let max_age = if force_refresh { JWKS_MIN_REFRESH_INTERVAL } else { JWKS_TTL };
+            if url == jwks_url && fetched_at.elapsed() < max_age {
+                return Ok(set.clone());
+            }
+        }
+
+        let client = crate::utils::http::create_client(10);
+        let set: JwkSet = client
+            .get(jwks_url)
+            .send()
+            .await
+            .map_err(|e| format!("JWKS 请求失败: {}", e))?
+            .error_for_status()
+            .map_err(|e| format!("JWKS 返回错误: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("解析 JWKS 失败: {}", e))?;
+
+        *self.entry.write().await = Some((jwks_url.to_string(), Instant::now(), set.clone()));
+        Ok(set)
+    }
+
+    /// 验证 JWT 签名与 `aud`，成功时返回 `sub`
+    pub async fn verify(&self, token: &str, jwks_url: &str, audience: &str) -> Result<String, String> {
+        let header = decode_header(token).map_err(|e| format!("无效的 JWT header: {}", e))?;
+
+        // 仅接受非对称算法，防止使用公钥作为 HMAC 密钥的算法混淆攻击
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(format!("不支持的 JWT 算法: {:?}", header.alg));
+        }
+
+        let mut jwks = self.get(jwks_url, false).await?;
+        let find_key = |set: &JwkSet| match header.kid.as_deref() {
+            Some(kid) => set.find(kid).cloned(),
+            None => set.keys.first().cloned(),
+        };
+
+        // kid 不在缓存中时可能发生了密钥轮换，强制刷新一次
+        let jwk = match find_key(&jwks) {
+            Some(jwk) => jwk,
+            None => {
+                jwks = self.get(jwks_url, true).await?;
+                find_key(&jwks).ok_or_else(|| "JWKS 中找不到匹配的密钥".to_string())?
+            }
+        };
+
+        let key = DecodingKey::from_jwk(&jwk).map_err(|e| format!("无效的 JWK: {}", e))?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[audience]);
+
+        let data = decode::<Claims>(token, &key, &validation).map_err(|e| format!("JWT 验证失败: {}", e))?;
+        Ok(data.claims.sub)
+    }
+}
@@ -0,0 +1,90 @@
+// 按 API Key 限流 (ApiKeyEntry::requests_per_minute)
+// 每个 Key 哈希维护一个 60 秒固定窗口计数，超出时返回 429 + Retry-After
+// 过期窗口由 TokenManager::start_auto_cleanup 的后台任务定期清理
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+static GLOBAL_KEY_RATE_LIMITER: Lazy<KeyRateLimiter> = Lazy::new(KeyRateLimiter::default);
+
+#[derive(Default)]
+pub struct KeyRateLimiter {
+    windows: DashMap<String, (Instant, AtomicU32)>,
+}
+
+impl KeyRateLimiter {
+    pub fn global() -> &'static KeyRateLimiter {
+        &GLOBAL_KEY_RATE_LIMITER
+    }
+
+    /// 计数并检查是否超限；超限时返回距窗口重置的秒数
+    pub fn check(&self, key_hash: &str, requests_per_minute: u32) -> Result<(), u64> {
+        self.check_at(key_hash, requests_per_minute, Instant::now())
+    }
+
+    fn check_at(&self, key_hash: &str, requests_per_minute: u32, now: Instant) -> Result<(), u64> {
+        let mut window = self
+            .windows
+            .entry(key_hash.to_string())
+            .or_insert_with(|| (now, AtomicU32::new(0)));
+        if now.duration_since(window.0) >= WINDOW {
+            *window = (now, AtomicU32::new(0));
+        }
+
+        let count = window.1.fetch_add(1, Ordering::Relaxed) + 1;
+        if count <= requests_per_minute {
+            return Ok(());
+        }
+        let remaining = WINDOW.saturating_sub(now.duration_since(window.0));
+        Err(remaining.as_secs().max(1))
+    }
+
+    /// 清除已过期的计数窗口，返回清除数量
+    pub fn reset_expired(&self) -> usize {
+        let before = self.windows.len();
+        self.windows.retain(|_, (started, _)| started.elapsed() < WINDOW);
+        before.saturating_sub(self.windows.len())
+    }
+}
+
+pub fn rate_limited_response(retry_after_secs: u64) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after_secs.to_string())],
+        Json(serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": "rate_limit_error",
+                "message": "API key rate limit exceeded"
+            }
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_per_key_and_window_reset() {
+        let limiter = KeyRateLimiter::default();
+        let now = Instant::now();
+
+        assert!(limiter.check_at("a", 2, now).is_ok());
+        assert!(limiter.check_at("a", 2, now).is_ok());
+        assert_eq!(limiter.check_at("a", 2, now + Duration::from_secs(15)), Err(45));
+        // 其他 Key 不受影响
+        assert!(limiter.check_at("b", 2, now).is_ok());
+
+        assert!(limiter.check_at("a", 2, now + WINDOW).is_ok());
+    }
+}
@@ -0,0 +1,49 @@
+// 请求指标采集 (Prometheus)
+// 与监控日志开关无关，始终记录；流式响应的耗时以响应头返回为准
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+
+use crate::proxy::server::AppState;
+
+pub async fn metrics_middleware(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    if !is_api_path(&path) {
+        return next.run(request).await;
+    }
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+
+    // 优先使用映射后的模型名；未映射时 (如错误响应) 回退到 Gemini 路径中的模型名
+    let model = response
+        .headers()
+        .get("X-Mapped-Model")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .or_else(|| {
+            path.split("/v1beta/models/")
+                .nth(1)
+                .and_then(|s| s.split(':').next())
+                .map(|s| s.to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    let provider = response
+        .headers()
+        .get("X-Provider")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("google");
+
+    state
+        .metrics
+        .record_request(response.status().as_u16(), &model, provider, start.elapsed());
+    response
+}
+
+/// 仅统计模型调用类端点，排除健康检查、指标抓取与遥测
+fn is_api_path(path: &str) -> bool {
+    (path.starts_with("/v1") || path.starts_with("/mcp")) && !path.contains("event_logging")
+}
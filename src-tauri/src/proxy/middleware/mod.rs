@@ -1,9 +1,21 @@
 // Middleware 模块 - Axum 中间件
 
+pub mod access_log;
 pub mod auth;
+pub mod body_limit;
+pub mod client_info;
+pub mod compression;
 pub mod cors;
+pub mod idempotency;
+pub mod jwt;
+pub mod key_rate_limit;
 pub mod logging;
+pub mod metrics;
 pub mod monitor;
+pub mod request_id;
+pub mod response_signing;
+pub mod static_headers;
 
+pub use access_log::access_log_middleware;
 pub use auth::auth_middleware;
 pub use cors::cors_layer;
@@ -13,6 +13,29 @@ use futures::StreamExt;
 const MAX_REQUEST_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB
 const MAX_RESPONSE_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB for image responses
 
+/// 客户端显式指定多轮对话标识的请求头
+const CONVERSATION_ID_HEADER: &str = "X-Conversation-ID";
+
+/// 未携带对话标识时，按协议计算会话指纹 (与粘性会话使用同一算法)
+fn conversation_fingerprint(uri: &str, model: Option<&str>, body: &[u8]) -> Option<String> {
+    use crate::proxy::session_manager::SessionManager;
+    if uri.starts_with("/v1/messages") && !uri.contains("count_tokens") {
+        serde_json::from_slice::<crate::proxy::mappers::claude::models::ClaudeRequest>(body)
+            .ok()
+            .map(|req| SessionManager::extract_session_id(&req))
+    } else if uri.starts_with("/v1/chat/completions") {
+        serde_json::from_slice::<crate::proxy::mappers::openai::models::OpenAIRequest>(body)
+            .ok()
+            .map(|req| SessionManager::extract_openai_session_id(&req))
+    } else if uri.contains("/v1beta/models/") {
+        serde_json::from_slice::<Value>(body)
+            .ok()
+            .map(|req| SessionManager::extract_gemini_session_id(&req, model.unwrap_or("")))
+    } else {
+        None
+    }
+}
+
 pub async fn monitor_middleware(
     State(state): State<AppState>,
     request: Request,
@@ -24,11 +47,22 @@ pub async fn monitor_middleware(
 
     let start = Instant::now();
     let method = request.method().to_string();
-    let uri = request.uri().to_string();
+    // 查询参数中的凭证 (?key=...) 不写入日志库
+    let uri = crate::proxy::middleware::access_log::sanitize_path_for_log(&request.uri().to_string());
     
-    if uri.contains("event_logging") {
+    if uri.contains("event_logging") || uri.starts_with("/metrics") {
         return next.run(request).await;
     }
+
+    // JWT 认证模式下记录调用方身份 (脱敏)
+    if let Some(subject) = request.extensions().get::<crate::proxy::middleware::jwt::AuthenticatedSubject>() {
+        tracing::info!(
+            account_email_masked = %crate::utils::privacy::mask_email(&subject.0),
+            "{} {}",
+            method,
+            uri
+        );
+    }
     
     let mut model = if uri.contains("/v1beta/models/") {
         uri.split("/v1beta/models/")
@@ -39,6 +73,18 @@ pub async fn monitor_middleware(
         None
     };
 
+    let request_id = request
+        .extensions()
+        .get::<crate::proxy::middleware::request_id::RequestId>()
+        .map(|id| id.0.clone());
+
+    let mut conversation_id = request
+        .headers()
+        .get(CONVERSATION_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+
     let request_body_str;
     let request = if method == "POST" {
         let (parts, body) = request.into_parts();
@@ -49,6 +95,9 @@ pub async fn monitor_middleware(
                         v.get("model").and_then(|m| m.as_str()).map(|s| s.to_string())
                     );
                 }
+                if conversation_id.is_none() {
+                    conversation_id = conversation_fingerprint(&uri, model.as_deref(), &bytes);
+                }
                 request_body_str = if let Ok(s) = std::str::from_utf8(&bytes) {
                     Some(s.to_string())
                 } else {
@@ -56,6 +105,9 @@ pub async fn monitor_middleware(
                 };
                 Request::from_parts(parts, Body::from(bytes))
             }
+            Err(e) if crate::proxy::middleware::body_limit::is_length_limit_error(&e) => {
+                return crate::proxy::middleware::body_limit::payload_too_large_response();
+            }
             Err(_) => {
                 request_body_str = None;
                 Request::from_parts(parts, Body::empty())
@@ -106,6 +158,9 @@ pub async fn monitor_middleware(
         response_body: None,
         input_tokens: None,
         output_tokens: None,
+        conversation_id,
+        estimated_cost_usd: None,
+        request_id,
     };
 
     if content_type.contains("text/event-stream") {
@@ -0,0 +1,104 @@
+// 请求追踪标识 (X-Request-ID)
+// 优先沿用客户端提供的 X-Request-ID，缺失或格式不合法时生成 UUID
+// 标识写入请求扩展 (供监控日志记录)、task-local (供上游客户端转发) 并回显在响应头中
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-ID";
+
+/// 客户端提供的标识最大长度，超出时重新生成
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// 获取当前请求的标识 (不在请求上下文中时返回 None)
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// 将当前请求的标识写入上游请求头
+pub fn inject_header(headers: &mut reqwest::header::HeaderMap) {
+    if let Some(value) = current().and_then(|id| reqwest::header::HeaderValue::from_str(&id).ok()) {
+        headers.insert(REQUEST_ID_HEADER, value);
+    }
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+pub async fn request_id_middleware(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.trim())
+        .filter(|s| is_valid_request_id(s))
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    request.extensions_mut().insert(RequestId(request_id.clone()));
+    let mut response = REQUEST_ID.scope(request_id.clone(), next.run(request)).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Extension, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/",
+                get(|Extension(id): Extension<RequestId>| async move {
+                    assert_eq!(current().as_deref(), Some(id.0.as_str()));
+                    id.0
+                }),
+            )
+            .layer(axum::middleware::from_fn(request_id_middleware))
+    }
+
+    async fn send(header: Option<&str>) -> (String, String) {
+        let mut request = Request::get("/");
+        if let Some(value) = header {
+            request = request.header(REQUEST_ID_HEADER, value);
+        }
+        let response = app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let echoed = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (echoed, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_client_request_id_is_propagated() {
+        let (echoed, seen) = send(Some("req-abc-123")).await;
+        assert_eq!(echoed, "req-abc-123");
+        assert_eq!(seen, "req-abc-123");
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_invalid_request_id_is_generated() {
+        let (echoed, seen) = send(None).await;
+        assert_eq!(echoed, seen);
+        assert!(uuid::Uuid::parse_str(&echoed).is_ok());
+
+        let (echoed, _) = send(Some(&"x".repeat(MAX_REQUEST_ID_LEN + 1))).await;
+        assert!(uuid::Uuid::parse_str(&echoed).is_ok());
+    }
+}
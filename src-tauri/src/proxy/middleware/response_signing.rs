@@ -0,0 +1,189 @@
+// 响应签名 (X-Response-Signature)
+// 对完整的非流式响应体计算 Ed25519(SHA-256(body))，以 base64 写入响应头，客户端可用对应公钥校验响应未被篡改
+// 签名的是未压缩的响应体 (位于 gzip 压缩层之内)
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, EncodePrivateKey, EncodePublicKey, LineEnding};
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::proxy::config::ResponseSigningConfig;
+
+pub const RESPONSE_SIGNATURE_HEADER: &str = "X-Response-Signature";
+
+const MAX_SIGNED_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
+
+pub struct ResponseSigner {
+    key: SigningKey,
+}
+
+impl ResponseSigner {
+    /// 加载私钥 (启动反代服务时调用，配置错误时返回可读的错误信息)
+    pub fn load(config: &ResponseSigningConfig) -> Result<Self, String> {
+        let path = &config.private_key_path;
+        if !path.is_file() {
+            return Err(format!("签名私钥文件不存在: {}", path.display()));
+        }
+        let pem = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取签名私钥失败 ({}): {}", path.display(), e))?;
+        let key = SigningKey::from_pkcs8_pem(&pem)
+            .map_err(|e| format!("解析签名私钥失败 ({})，需为 PKCS#8 PEM 格式的 Ed25519 私钥: {}", path.display(), e))?;
+        Ok(Self { key })
+    }
+
+    /// base64(Ed25519(SHA-256(body)))
+    pub fn sign(&self, body: &[u8]) -> String {
+        let signature = self.key.sign(&Sha256::digest(body));
+        base64::engine::general_purpose::STANDARD.encode(signature.to_bytes())
+    }
+}
+
+/// 公钥文件路径 (<私钥路径>.pub)
+pub fn public_key_path(private_key_path: &Path) -> PathBuf {
+    let mut path = private_key_path.as_os_str().to_owned();
+    path.push(".pub");
+    PathBuf::from(path)
+}
+
+/// 生成 Ed25519 密钥对：私钥写入 path，公钥写入 <path>.pub (均为 PEM 格式)；已存在时不覆盖
+pub fn generate_keypair(path: &Path) -> Result<(), String> {
+    let public_path = public_key_path(path);
+    for p in [path, public_path.as_path()] {
+        if p.exists() {
+            return Err(format!("文件已存在，拒绝覆盖: {}", p.display()));
+        }
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+
+    let key = SigningKey::generate(&mut rand::rngs::OsRng);
+    let private_pem = key
+        .to_pkcs8_pem(LineEnding::LF)
+        .map_err(|e| format!("编码私钥失败: {}", e))?;
+    let public_pem = key
+        .verifying_key()
+        .to_public_key_pem(LineEnding::LF)
+        .map_err(|e| format!("编码公钥失败: {}", e))?;
+
+    // 私钥创建时即为 0600，避免写入后再收紧权限之间的窗口；create_new 防止与并发创建竞争
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| format!("创建私钥文件失败: {}", e))?;
+    file.write_all(private_pem.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("写入私钥失败: {}", e))?;
+    std::fs::write(&public_path, public_pem).map_err(|e| format!("写入公钥失败: {}", e))?;
+    Ok(())
+}
+
+pub async fn response_signing_middleware(
+    State(signer): State<Option<Arc<ResponseSigner>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+    let Some(signer) = signer else {
+        return response;
+    };
+    let is_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.contains("text/event-stream"))
+        .unwrap_or(false);
+    if is_stream {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    match axum::body::to_bytes(body, MAX_SIGNED_BODY_SIZE).await {
+        Ok(bytes) => {
+            if let Ok(value) = HeaderValue::from_str(&signer.sign(&bytes)) {
+                parts.headers.insert(RESPONSE_SIGNATURE_HEADER, value);
+            }
+            Response::from_parts(parts, Body::from(bytes))
+        }
+        // 无法读取完整响应体时不能签名，也不能把截断的内容以原状态码返回
+        Err(e) => (
+            StatusCode::BAD_GATEWAY,
+            format!("Failed to read response body: {}", e),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::SigningAlgorithm;
+    use axum::{response::IntoResponse, routing::get, Router};
+    use ed25519_dalek::pkcs8::DecodePublicKey;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_generated_keypair_signs_verifiable_responses() {
+        let dir = std::env::temp_dir().join(format!("response-signing-{}", uuid::Uuid::new_v4()));
+        let key_path = dir.join("signing.pem");
+        generate_keypair(&key_path).unwrap();
+        assert!(generate_keypair(&key_path).is_err());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&key_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let signer = ResponseSigner::load(&ResponseSigningConfig {
+            private_key_path: key_path.clone(),
+            algorithm: SigningAlgorithm::Ed25519,
+        })
+        .unwrap();
+        let app = Router::new()
+            .route("/v1/models", get(|| async { r#"{"data":[]}"# }))
+            .route(
+                "/stream",
+                get(|| async { ([(header::CONTENT_TYPE, "text/event-stream")], "data: {}\n\n").into_response() }),
+            )
+            .layer(axum::middleware::from_fn_with_state(Some(Arc::new(signer)), response_signing_middleware));
+
+        let response = app
+            .clone()
+            .oneshot(Request::get("/v1/models").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let signature = response.headers()[RESPONSE_SIGNATURE_HEADER].to_str().unwrap().to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        let public_pem = std::fs::read_to_string(public_key_path(&key_path)).unwrap();
+        let verifying_key = VerifyingKey::from_public_key_pem(&public_pem).unwrap();
+        let signature_bytes: [u8; 64] = base64::engine::general_purpose::STANDARD
+            .decode(signature)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let signature = Signature::from_bytes(&signature_bytes);
+        assert!(verifying_key.verify(&Sha256::digest(&body), &signature).is_ok());
+        assert!(verifying_key.verify(&Sha256::digest(b"tampered"), &signature).is_err());
+
+        let response = app.oneshot(Request::get("/stream").body(Body::empty()).unwrap()).await.unwrap();
+        assert!(response.headers().get(RESPONSE_SIGNATURE_HEADER).is_none());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,134 @@
+// 自定义响应头注入
+// 部分反向代理/网关依赖特定响应头 (如 X-Content-Type-Options: nosniff、Cache-Control: no-store)，
+// 按 proxy.response_headers 配置为所有响应添加 (同名时覆盖)，支持热更新
+use axum::http::{HeaderName, HeaderValue, Request, Response};
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// 解析并校验配置中的响应头，返回第一个非法的名称或值
+pub fn parse_response_headers(
+    headers: &HashMap<String, String>,
+) -> Result<Vec<(HeaderName, HeaderValue)>, String> {
+    let mut entries: Vec<_> = headers.iter().collect();
+    entries.sort();
+    entries
+        .into_iter()
+        .map(|(name, value)| {
+            let header_name = HeaderName::from_bytes(name.trim().as_bytes())
+                .map_err(|_| format!("响应头名称 '{}' 不合法", name))?;
+            let header_value = HeaderValue::from_str(value)
+                .map_err(|_| format!("响应头 '{}' 的值不合法", name))?;
+            Ok((header_name, header_value))
+        })
+        .collect()
+}
+
+#[derive(Clone, Default)]
+pub struct StaticResponseHeadersLayer {
+    headers: Arc<RwLock<Vec<(HeaderName, HeaderValue)>>>,
+}
+
+impl StaticResponseHeadersLayer {
+    pub fn new(headers: &HashMap<String, String>) -> Self {
+        let layer = Self::default();
+        layer.update(headers);
+        layer
+    }
+
+    /// 热更新响应头；配置非法时保留原有响应头
+    pub fn update(&self, headers: &HashMap<String, String>) {
+        match parse_response_headers(headers) {
+            Ok(parsed) => {
+                if let Ok(mut current) = self.headers.write() {
+                    *current = parsed;
+                }
+                tracing::debug!("自定义响应头已更新 ({} 项)", headers.len());
+            }
+            Err(e) => tracing::warn!("[StaticHeaders] {}，保留原有响应头", e),
+        }
+    }
+}
+
+impl<S> Layer<S> for StaticResponseHeadersLayer {
+    type Service = StaticResponseHeaders<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        StaticResponseHeaders {
+            inner,
+            headers: self.headers.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct StaticResponseHeaders<S> {
+    inner: S,
+    headers: Arc<RwLock<Vec<(HeaderName, HeaderValue)>>>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for StaticResponseHeaders<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let future = self.inner.call(request);
+        let headers = self.headers.clone();
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Ok(headers) = headers.read() {
+                for (name, value) in headers.iter() {
+                    response.headers_mut().insert(name.clone(), value.clone());
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn config(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_headers() {
+        assert_eq!(parse_response_headers(&config(&[("Cache-Control", "no-store")])).unwrap().len(), 1);
+        assert!(parse_response_headers(&config(&[("Bad Header", "x")])).is_err());
+        assert!(parse_response_headers(&config(&[("X-Test", "line\nbreak")])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_headers_injected_and_hot_updated() {
+        let layer = StaticResponseHeadersLayer::new(&config(&[("X-Content-Type-Options", "nosniff")]));
+        let app = Router::new().route("/", get(|| async { "ok" })).layer(layer.clone());
+
+        let response = app.clone().oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+
+        layer.update(&config(&[("Cache-Control", "no-store")]));
+        // 非法配置不覆盖已生效的响应头
+        layer.update(&config(&[("Bad Header", "x")]));
+        let response = app.oneshot(Request::get("/").body(Body::empty()).unwrap()).await.unwrap();
+        assert!(response.headers().get("x-content-type-options").is_none());
+        assert_eq!(response.headers()["cache-control"], "no-store");
+    }
+}
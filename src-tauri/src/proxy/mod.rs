@@ -13,15 +13,33 @@ pub mod handlers;          // API 端点处理器
 pub mod middleware;        // Axum 中间件
 pub mod upstream;          // 上游客户端
 pub mod common;            // 公共工具
+pub mod errors;            // 错误响应格式协商
 pub mod providers;         // Extra upstream providers (z.ai, etc.)
 pub mod zai_vision_mcp;    // Built-in Vision MCP server state
 pub mod zai_vision_tools;  // Built-in Vision MCP tools (z.ai vision API)
+pub mod zai_web_tools;     // z.ai Web Reader result cache
 pub mod monitor;           // 监控
 pub mod rate_limit;        // 限流跟踪
 pub mod sticky_config;     // 粘性调度配置
 pub mod session_manager;   // 会话指纹管理
 pub mod audio;             // 音频处理模块 (PR #311)
 pub mod signature_cache;   // Signature Cache (v3.3.16)
+pub mod model_discovery;   // 上游模型发现
+pub mod retry;             // 上游瞬时错误重试
+pub mod connection_pool;   // 按账号隔离的上游连接池
+pub mod dedup;             // 相同请求合并
+pub mod metrics;           // Prometheus 运行指标
+pub mod otel;              // OpenTelemetry 链路追踪
+pub mod alerting;          // 错误率告警
+pub mod time_sync;         // 服务器时间同步
+pub mod quota_groups;      // 配额共享组
+pub mod upstream_health;   // 上游健康检查
+pub mod upstream_proxy;    // 上游代理池
+pub mod plugins;           // 请求转换插件
+pub mod anomaly;           // 账号用量异常检测
+pub mod error_budget;      // 账号错误预算
+#[cfg(feature = "grpc")]
+pub mod grpc;              // gRPC 推理服务 (可选)
 
 
 pub use config::ProxyConfig;
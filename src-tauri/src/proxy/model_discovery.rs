@@ -0,0 +1,118 @@
+// 上游模型发现
+// 定期通过 Cloud Code v1internal:fetchAvailableModels 拉取可用模型 ID，合并到 /v1/models 列表中，
+// 避免 Google 发布新模型后必须等待版本更新才能在客户端中选择
+// (账号 token 为 Cloud Code 授权，不能直接调用 generativelanguage API)
+
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+use crate::proxy::upstream::client::UpstreamClient;
+use crate::proxy::TokenManager;
+
+/// 发现结果缓存有效期 (1 小时)
+const DISCOVERY_TTL: Duration = Duration::from_secs(3600);
+
+/// 上游模型发现器
+pub struct ModelDiscovery {
+    token_manager: Arc<TokenManager>,
+    upstream: Arc<UpstreamClient>,
+    models: Arc<RwLock<Vec<String>>>,
+    last_refresh: Arc<RwLock<Option<Instant>>>,
+}
+
+impl ModelDiscovery {
+    pub fn new(token_manager: Arc<TokenManager>, upstream: Arc<UpstreamClient>) -> Self {
+        Self {
+            token_manager,
+            upstream,
+            models: Arc::new(RwLock::new(Vec::new())),
+            last_refresh: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// 启动发现任务：立即拉取一次，此后每小时刷新
+    /// 任务持有 TokenManager，返回的句柄需在停止反代服务时 abort
+    pub fn start_refresh_task(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let discovery = self.clone();
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(DISCOVERY_TTL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = discovery.refresh().await {
+                    tracing::warn!("[ModelDiscovery] 模型列表刷新失败: {}", e);
+                }
+            }
+        });
+        tracing::info!("✅ Model discovery task started (interval: {}s)", DISCOVERY_TTL.as_secs());
+        handle
+    }
+
+    /// 获取已发现的模型 ID (缓存过期时仍返回上一次结果，由后台任务负责刷新)
+    pub async fn cached_models(&self) -> Vec<String> {
+        let fresh = self
+            .last_refresh
+            .read()
+            .await
+            .map(|t| t.elapsed() < DISCOVERY_TTL)
+            .unwrap_or(false);
+        if !fresh {
+            tracing::debug!("[ModelDiscovery] 模型缓存已过期或尚未拉取，返回上次结果");
+        }
+        self.models.read().await.clone()
+    }
+
+    /// 立即从上游拉取模型列表并更新缓存
+    pub async fn refresh(&self) -> Result<Vec<String>, String> {
+        let access_token = self
+            .token_manager
+            .first_valid_access_token()
+            .ok_or_else(|| "没有可用的 access_token".to_string())?;
+
+        let data = self.upstream.fetch_available_models(&access_token).await?;
+        let models = parse_model_ids(&data);
+        tracing::info!("[ModelDiscovery] 发现 {} 个上游模型", models.len());
+
+        *self.models.write().await = models.clone();
+        *self.last_refresh.write().await = Some(Instant::now());
+        Ok(models)
+    }
+}
+
+/// 从 v1internal:fetchAvailableModels 响应中提取模型 ID (`models` 为以模型 ID 为键的对象)
+fn parse_model_ids(data: &Value) -> Vec<String> {
+    let mut models: Vec<String> = data
+        .get("models")
+        .and_then(|v| v.as_object())
+        .map(|map| map.keys().cloned().collect())
+        .unwrap_or_default();
+    models.sort();
+    models
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_model_ids() {
+        let data = json!({
+            "models": {
+                "gemini-3-pro-preview": { "quotaInfo": { "remainingFraction": 1.0 } },
+                "claude-sonnet-4-5": { "quotaInfo": { "remainingFraction": 0.5 } },
+                "gemini-2.5-flash": {}
+            }
+        });
+        assert_eq!(
+            parse_model_ids(&data),
+            vec![
+                "claude-sonnet-4-5".to_string(),
+                "gemini-2.5-flash".to_string(),
+                "gemini-3-pro-preview".to_string()
+            ]
+        );
+        assert!(parse_model_ids(&json!({})).is_empty());
+    }
+}
@@ -1,8 +1,12 @@
 use serde::{Serialize, Deserialize};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use tokio::sync::RwLock;
 use tauri::Emitter;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use once_cell::sync::Lazy;
+use tokio::sync::broadcast;
+use std::sync::{Arc, Mutex};
+use crate::modules::privacy::{PiiScrubber, ScrubStats};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyRequestLog {
@@ -20,6 +24,15 @@ pub struct ProxyRequestLog {
     pub response_body: Option<String>,
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
+    /// 多轮对话标识 (X-Conversation-ID 请求头或会话指纹)
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+    /// 按模型单价估算的费用
+    #[serde(default)]
+    pub estimated_cost_usd: Option<f64>,
+    /// X-Request-ID (客户端提供或代理生成)，与上游请求关联
+    #[serde(default)]
+    pub request_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -27,6 +40,48 @@ pub struct ProxyStats {
     pub total_requests: u64,
     pub success_count: u64,
     pub error_count: u64,
+    /// 按模型统计的延迟分布 (仅由 get_proxy_stats 填充)
+    #[serde(default)]
+    pub latency_by_model: Vec<crate::modules::proxy_db::ModelLatencyStats>,
+    /// 复用进行中相同请求响应的次数 (进程内计数，不持久化)
+    #[serde(default)]
+    pub dedup_hits: u64,
+    /// PII 脱敏命中统计 (进程内计数，不持久化)
+    #[serde(default)]
+    pub scrub_stats: ScrubStats,
+}
+
+/// 实时日志广播通道容量 (订阅者落后超过该数量时会丢弃最旧的日志)
+const LOG_BROADCAST_CAPACITY: usize = 256;
+
+static LOG_BROADCASTER: Lazy<LogBroadcaster> = Lazy::new(LogBroadcaster::new);
+
+/// 实时日志广播器
+/// 反代服务与 HTTP API 生命周期独立，因此使用进程级单例连接两者
+#[derive(Clone)]
+pub struct LogBroadcaster {
+    tx: broadcast::Sender<ProxyRequestLog>,
+}
+
+impl LogBroadcaster {
+    fn new() -> Self {
+        let (tx, _) = broadcast::channel(LOG_BROADCAST_CAPACITY);
+        Self { tx }
+    }
+
+    /// 获取全局广播器
+    pub fn global() -> &'static LogBroadcaster {
+        &LOG_BROADCASTER
+    }
+
+    /// 广播一条日志 (无订阅者时直接丢弃)
+    pub fn send(&self, log: ProxyRequestLog) {
+        let _ = self.tx.send(log);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProxyRequestLog> {
+        self.tx.subscribe()
+    }
 }
 
 pub struct ProxyMonitor {
@@ -35,6 +90,76 @@ pub struct ProxyMonitor {
     pub max_logs: usize,
     pub enabled: AtomicBool,
     app_handle: Option<tauri::AppHandle>,
+    dedup_hits: AtomicU64,
+    /// 写入日志时使用的配置，随配置热更新 (见 `update_settings`)
+    settings: std::sync::RwLock<Arc<LogSettings>>,
+    scrub_stats: Mutex<ScrubStats>,
+}
+
+/// 写入日志时需要的配置快照，避免每个请求读取磁盘配置
+#[derive(Default)]
+struct LogSettings {
+    model_pricing: HashMap<String, crate::models::config::ModelPricing>,
+    /// 开启 scrub_pii 时按当前自定义正则编译的脱敏器
+    scrubber: Option<Arc<PiiScrubber>>,
+}
+
+/// 归档 (AppConfig.auto_archive_days) 后按 AppConfig.log_retention 清理旧日志
+/// 归档阈值不晚于最短的保留天数，保证开启归档时被清理的日志都已归档
+async fn run_log_maintenance() {
+    let config = crate::modules::config::load_app_config().ok();
+    let policy = config.as_ref().map(|c| c.log_retention.clone()).unwrap_or_default();
+
+    if let Some(days) = config.and_then(|c| c.auto_archive_days).filter(|d| *d > 0) {
+        let archive_days = [policy.success_days, policy.error_days, policy.large_body_days]
+            .into_iter()
+            .filter(|d| *d > 0)
+            .fold(days, u32::min);
+        let result = tokio::task::spawn_blocking(move || {
+            let dir = crate::modules::proxy_db::get_archive_dir()?;
+            crate::modules::proxy_db::archive_logs_older_than(archive_days as i64, &dir)
+        })
+        .await;
+        match result {
+            Ok(Ok(stats)) if stats.rows_archived > 0 => {
+                tracing::info!(
+                    "Auto archive: archived {} logs (>{} days), {} bytes written",
+                    stats.rows_archived,
+                    archive_days,
+                    stats.bytes_written
+                );
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                // 归档失败时跳过本轮清理，避免删除未归档的日志
+                tracing::error!("Failed to archive old logs, skipping cleanup: {}", e);
+                return;
+            }
+            Err(e) => {
+                tracing::error!("Log archive task panicked, skipping cleanup: {}", e);
+                return;
+            }
+        }
+    }
+
+    let result = tokio::task::spawn_blocking(move || {
+        crate::modules::proxy_db::cleanup_with_policy(&policy).map(|deleted| (deleted, policy))
+    })
+    .await;
+    match result {
+        Ok(Ok((deleted, policy))) if deleted > 0 => {
+            tracing::info!(
+                "Auto cleanup: removed {} old logs (success {}d / error {}d / large body {}d)",
+                deleted,
+                policy.success_days,
+                policy.error_days,
+                policy.large_body_days
+            );
+        }
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => tracing::error!("Failed to cleanup old logs: {}", e),
+        Err(e) => tracing::error!("Log cleanup task panicked: {}", e),
+    }
 }
 
 impl ProxyMonitor {
@@ -44,17 +169,16 @@ impl ProxyMonitor {
             tracing::error!("Failed to initialize proxy DB: {}", e);
         }
 
-        // Auto cleanup old logs (keep last 30 days)
+        crate::modules::proxy_db::schedule_wal_checkpoint();
+        crate::modules::proxy_db::schedule_db_size_check();
+
+        // 日志归档与清理在同一任务中顺序执行 (启动时一次，之后每天一次)：先归档再按保留策略清理，
+        // 避免清理任务抢先删除尚未归档的日志
         tokio::spawn(async {
-            match crate::modules::proxy_db::cleanup_old_logs(30) {
-                Ok(deleted) => {
-                    if deleted > 0 {
-                        tracing::info!("Auto cleanup: removed {} old logs (>30 days)", deleted);
-                    }
-                }
-                Err(e) => {
-                    tracing::error!("Failed to cleanup old logs: {}", e);
-                }
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(24 * 3600));
+            loop {
+                interval.tick().await;
+                run_log_maintenance().await;
             }
         });
 
@@ -64,6 +188,9 @@ impl ProxyMonitor {
             max_logs,
             enabled: AtomicBool::new(false), // Default to disabled
             app_handle,
+            dedup_hits: AtomicU64::new(0),
+            settings: std::sync::RwLock::new(Arc::new(LogSettings::default())),
+            scrub_stats: Mutex::new(ScrubStats::default()),
         }
     }
 
@@ -75,10 +202,42 @@ impl ProxyMonitor {
         self.enabled.load(Ordering::Relaxed)
     }
 
-    pub async fn log_request(&self, log: ProxyRequestLog) {
+    /// 同步费用估算与脱敏配置 (创建监控器与配置热更新时调用)
+    /// 自定义正则未变化时沿用已编译的脱敏器
+    pub fn update_settings(&self, config: &crate::models::AppConfig) {
+        let current = self.settings.read().unwrap().clone();
+        let scrubber = if config.scrub_pii {
+            match &current.scrubber {
+                Some(s) if s.extra_patterns() == config.pii_patterns.as_slice() => Some(s.clone()),
+                _ => Some(Arc::new(PiiScrubber::new(&config.pii_patterns))),
+            }
+        } else {
+            None
+        };
+        *self.settings.write().unwrap() = Arc::new(LogSettings {
+            model_pricing: config.model_pricing.clone(),
+            scrubber,
+        });
+    }
+
+    pub async fn log_request(&self, mut log: ProxyRequestLog) {
         if !self.is_enabled() {
             return;
         }
+        let settings = self.settings.read().unwrap().clone();
+        if log.input_tokens.is_some() || log.output_tokens.is_some() {
+            if let Some(model) = log.mapped_model.clone().or_else(|| log.model.clone()) {
+                log.estimated_cost_usd = crate::models::config::estimate_cost(
+                    &settings.model_pricing,
+                    &model,
+                    log.input_tokens.unwrap_or(0) as u64,
+                    log.output_tokens.unwrap_or(0) as u64,
+                );
+            }
+        }
+        if let Some(scrubber) = &settings.scrubber {
+            self.scrub_log(&mut log, scrubber);
+        }
         tracing::info!("[Monitor] Logging request: {} {}", log.method, log.url);
         // Update stats
         {
@@ -100,32 +259,39 @@ impl ProxyMonitor {
             logs.push_front(log.clone());
         }
 
-        // Save to DB
+        // Summary without body to reduce memory (used by event and live stream)
+        let log_summary = ProxyRequestLog {
+            id: log.id.clone(),
+            timestamp: log.timestamp,
+            method: log.method.clone(),
+            url: log.url.clone(),
+            status: log.status,
+            duration: log.duration,
+            model: log.model.clone(),
+            mapped_model: log.mapped_model.clone(),
+            account_email: log.account_email.clone(),
+            error: log.error.clone(),
+            request_body: None,  // Don't send body in event
+            response_body: None, // Don't send body in event
+            input_tokens: log.input_tokens,
+            output_tokens: log.output_tokens,
+            conversation_id: log.conversation_id.clone(),
+            estimated_cost_usd: log.estimated_cost_usd,
+            request_id: log.request_id.clone(),
+        };
+
+        // Save to DB, then push to live stream subscribers (HTTP API /logs/stream)
         let log_to_save = log.clone();
+        let log_to_broadcast = log_summary.clone();
         tokio::spawn(async move {
             if let Err(e) = crate::modules::proxy_db::save_log(&log_to_save) {
                 tracing::error!("Failed to save proxy log to DB: {}", e);
             }
+            LogBroadcaster::global().send(log_to_broadcast);
         });
 
         // Emit event (send summary only, without body to reduce memory)
         if let Some(app) = &self.app_handle {
-            let log_summary = ProxyRequestLog {
-                id: log.id.clone(),
-                timestamp: log.timestamp,
-                method: log.method.clone(),
-                url: log.url.clone(),
-                status: log.status,
-                duration: log.duration,
-                model: log.model.clone(),
-                mapped_model: log.mapped_model.clone(),
-                account_email: log.account_email.clone(),
-                error: log.error.clone(),
-                request_body: None,  // Don't send body in event
-                response_body: None, // Don't send body in event
-                input_tokens: log.input_tokens,
-                output_tokens: log.output_tokens,
-            };
             let _ = app.emit("proxy://request", &log_summary);
         }
     }
@@ -143,14 +309,38 @@ impl ProxyMonitor {
         }
     }
 
+    /// 脱敏请求/响应体与错误信息 (写入内存与 SQLite 之前调用)
+    /// 正则替换在锁外进行，锁只用于合并统计
+    fn scrub_log(&self, log: &mut ProxyRequestLog, scrubber: &PiiScrubber) {
+        let mut matches = Vec::new();
+        for field in [&mut log.request_body, &mut log.response_body, &mut log.error].into_iter().flatten() {
+            let (scrubbed, matched) = scrubber.scrub(field);
+            *field = scrubbed;
+            matches.push(matched);
+        }
+
+        let mut stats = self.scrub_stats.lock().unwrap();
+        for matched in &matches {
+            stats.merge(matched);
+        }
+    }
+
+    /// 记录一次相同请求合并命中
+    pub fn record_dedup_hit(&self) {
+        self.dedup_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub async fn get_stats(&self) -> ProxyStats {
-        match crate::modules::proxy_db::get_stats() {
+        let mut stats = match crate::modules::proxy_db::get_stats() {
             Ok(stats) => stats,
             Err(e) => {
                 tracing::error!("Failed to get stats from DB: {}", e);
                 self.stats.read().await.clone()
             }
-        }
+        };
+        stats.dedup_hits = self.dedup_hits.load(Ordering::Relaxed);
+        stats.scrub_stats = self.scrub_stats.lock().unwrap().clone();
+        stats
     }
     
     pub async fn clear(&self) {
@@ -158,6 +348,8 @@ impl ProxyMonitor {
         logs.clear();
         let mut stats = self.stats.write().await;
         *stats = ProxyStats::default();
+        self.dedup_hits.store(0, Ordering::Relaxed);
+        *self.scrub_stats.lock().unwrap() = ScrubStats::default();
 
         if let Err(e) = crate::modules::proxy_db::clear_logs() {
             tracing::error!("Failed to clear logs in DB: {}", e);
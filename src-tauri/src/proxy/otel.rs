@@ -0,0 +1,169 @@
+// OpenTelemetry 链路追踪
+// 从入站请求提取 W3C trace context (traceparent / tracestate)，并透传给上游，
+// 使代理 span 与调用方的分布式追踪 (Jaeger / Tempo 等) 串联。
+// 导出与上下文传播仅在启用 `otel` feature 时生效，否则只保留本地 tracing span。
+use axum::http::{HeaderMap, Request};
+use axum::response::Response;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::trace::{DefaultOnResponse, OnResponse};
+use tracing::Span;
+
+use crate::proxy::TokenManager;
+
+/// 未配置 `otel_endpoint` 时使用的 OTLP gRPC 地址
+#[cfg_attr(not(feature = "otel"), allow(dead_code))]
+const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4317";
+
+/// 为入站请求创建 span；携带 traceparent 时作为调用方 span 的子 span
+pub fn make_span<B>(request: &Request<B>) -> Span {
+    let span = tracing::info_span!(
+        "proxy_request",
+        http.method = %request.method(),
+        http.target = %request.uri().path(),
+        model = tracing::field::Empty,
+        provider = tracing::field::Empty,
+        account_id_hash = tracing::field::Empty,
+        http.status_code = tracing::field::Empty,
+    );
+
+    #[cfg(feature = "otel")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        span.set_parent(parent);
+    }
+
+    span
+}
+
+/// 响应返回时补充 span 属性 (模型、提供商、账号哈希、状态码)
+pub fn record_response(
+    token_manager: &Arc<TokenManager>,
+    response: &Response,
+    latency: Duration,
+    span: &Span,
+) {
+    let header = |name: &str| response.headers().get(name).and_then(|v| v.to_str().ok());
+
+    span.record("http.status_code", response.status().as_u16());
+    if let Some(model) = header("X-Mapped-Model") {
+        span.record("model", model);
+    }
+    span.record("provider", header("X-Provider").unwrap_or("google"));
+    if let Some(email) = header("X-Account-Email") {
+        let account_id = token_manager
+            .email_to_account_id(email)
+            .unwrap_or_else(|| email.to_string());
+        span.record("account_id_hash", hash_account_id(&account_id).as_str());
+    }
+
+    DefaultOnResponse::default().on_response(response, latency, span);
+}
+
+/// 账号 ID 的 SHA-256 (避免在追踪后端暴露账号标识)
+fn hash_account_id(account_id: &str) -> String {
+    format!("{:x}", Sha256::digest(account_id.as_bytes()))
+}
+
+/// 将当前 span 的 trace context 写入上游请求头
+pub fn inject_trace_headers(headers: &mut HeaderMap) {
+    #[cfg(feature = "otel")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        let context = Span::current().context();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut HeaderInjector(headers))
+        });
+    }
+    #[cfg(not(feature = "otel"))]
+    let _ = headers;
+}
+
+/// 根据反代配置构建 OTLP 导出层 (未开启 `enable_otel` 时返回 Ok(None))
+/// 在全局 tracing 订阅器安装之前调用，结果由调用方在初始化后记录日志
+#[cfg(feature = "otel")]
+pub fn build_layer<S>(
+    config: &crate::proxy::ProxyConfig,
+) -> Result<Option<tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>, String>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+
+    if !config.enable_otel {
+        return Ok(None);
+    }
+    let endpoint = config
+        .otel_endpoint
+        .clone()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_OTLP_ENDPOINT.to_string());
+
+    // 批量导出器需要在 tokio 运行时上下文中创建
+    let runtime = tauri::async_runtime::handle();
+    let _enter = runtime.inner().enter();
+
+    opentelemetry::global::set_text_map_propagator(
+        opentelemetry_sdk::propagation::TraceContextPropagator::new(),
+    );
+
+    let provider = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint.clone()),
+        )
+        .with_trace_config(opentelemetry_sdk::trace::Config::default().with_resource(
+            opentelemetry_sdk::Resource::new(vec![opentelemetry::KeyValue::new(
+                "service.name",
+                "antigravity-proxy",
+            )]),
+        ))
+        .install_batch(opentelemetry_sdk::runtime::Tokio);
+
+    let provider = provider.map_err(|e| format!("{} (OTLP endpoint: {})", e, endpoint))?;
+    let tracer = provider.tracer("antigravity-proxy");
+    opentelemetry::global::set_tracer_provider(provider);
+    Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer)))
+}
+
+/// 进程退出前刷新尚未导出的 span
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+#[cfg(feature = "otel")]
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+#[cfg(feature = "otel")]
+impl opentelemetry::propagation::Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+#[cfg(feature = "otel")]
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+#[cfg(feature = "otel")]
+impl opentelemetry::propagation::Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            axum::http::HeaderName::from_bytes(key.as_bytes()),
+            axum::http::HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
@@ -0,0 +1,396 @@
+// 请求转换插件
+// 在请求发往上游前按优先级依次修改请求体 (v1internal 包装后的 Gemini 请求)
+// 插件目录: <数据目录>/plugins/<插件>/plugin.json，动态库通过 C ABI 交换 JSON：
+//   char* ag_transform(const char* ctx_json, const char* body_json);
+//     返回 {"ok": <新请求体>} 或 {"error": "<原因>"}
+//   void ag_free_string(char* s);  释放 ag_transform 返回的字符串
+// 插件默认不加载，需在设置中启用 (AppConfig.plugins.enabled)
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::models::config::PluginsConfig;
+
+pub const PLUGINS_DIR: &str = "plugins";
+const MANIFEST_FILE: &str = "plugin.json";
+pub const SYSTEM_PROMPT_INJECTOR: &str = "system-prompt-injector";
+
+/// 转换上下文
+#[derive(Debug, Clone, Serialize)]
+pub struct TransformContext {
+    pub model: String,
+    pub account_id: Option<String>,
+    pub session_id: Option<String>,
+}
+
+pub trait RequestTransformer: Send + Sync {
+    fn name(&self) -> &str;
+    fn transform(&self, ctx: &TransformContext, body: Value) -> Result<Value, String>;
+}
+
+/// 插件清单 (plugin.json)
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// 数值越小越先执行
+    #[serde(default)]
+    pub priority: i32,
+    /// 动态库文件名 (相对于插件目录)
+    pub library: String,
+}
+
+/// 插件列表项 (用于前端展示)
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub name: String,
+    pub description: String,
+    pub priority: i32,
+    pub builtin: bool,
+    pub enabled: bool,
+    /// 清单解析失败时的错误
+    pub error: Option<String>,
+}
+
+// ===== 内置插件 =====
+
+/// 内置参考实现：在系统指令开头注入固定提示词
+pub struct SystemPromptInjector {
+    prompt: String,
+}
+
+impl SystemPromptInjector {
+    pub fn new(prompt: String) -> Self {
+        Self { prompt }
+    }
+}
+
+impl RequestTransformer for SystemPromptInjector {
+    fn name(&self) -> &str {
+        SYSTEM_PROMPT_INJECTOR
+    }
+
+    fn transform(&self, _ctx: &TransformContext, mut body: Value) -> Result<Value, String> {
+        let request = body
+            .get_mut("request")
+            .and_then(|r| r.as_object_mut())
+            .ok_or("request body has no `request` object")?;
+        let instruction = request
+            .entry("systemInstruction")
+            .or_insert_with(|| json!({ "role": "user", "parts": [] }));
+        let parts = instruction
+            .as_object_mut()
+            .ok_or("`systemInstruction` is not an object")?
+            .entry("parts")
+            .or_insert_with(|| json!([]));
+        parts
+            .as_array_mut()
+            .ok_or("`systemInstruction.parts` is not an array")?
+            .insert(0, json!({ "text": self.prompt }));
+        Ok(body)
+    }
+}
+
+// ===== 动态库插件 =====
+
+type TransformFn = unsafe extern "C" fn(*const c_char, *const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+struct DylibTransformer {
+    name: String,
+    transform_fn: TransformFn,
+    free_fn: FreeFn,
+    // 函数指针依赖库保持加载，必须在其之后释放
+    _library: libloading::Library,
+}
+
+impl DylibTransformer {
+    fn load(name: String, path: &Path) -> Result<Self, String> {
+        // SAFETY: 加载用户显式启用的插件库；导出函数签名由插件 ABI 约定
+        unsafe {
+            let library = libloading::Library::new(path).map_err(|e| format!("加载插件库失败: {}", e))?;
+            let transform_fn = *library
+                .get::<TransformFn>(b"ag_transform\0")
+                .map_err(|e| format!("插件缺少 ag_transform: {}", e))?;
+            let free_fn = *library
+                .get::<FreeFn>(b"ag_free_string\0")
+                .map_err(|e| format!("插件缺少 ag_free_string: {}", e))?;
+            Ok(Self {
+                name,
+                transform_fn,
+                free_fn,
+                _library: library,
+            })
+        }
+    }
+}
+
+impl RequestTransformer for DylibTransformer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn transform(&self, ctx: &TransformContext, body: Value) -> Result<Value, String> {
+        let ctx_json = CString::new(serde_json::to_string(ctx).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+        let body_json = CString::new(body.to_string()).map_err(|e| e.to_string())?;
+
+        // SAFETY: 输入为有效的 C 字符串；返回值由插件分配，读取后交还插件释放
+        let output = unsafe {
+            let ptr = (self.transform_fn)(ctx_json.as_ptr(), body_json.as_ptr());
+            if ptr.is_null() {
+                return Err("plugin returned null".to_string());
+            }
+            let output = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+            (self.free_fn)(ptr);
+            output
+        };
+        parse_plugin_output(&output)
+    }
+}
+
+fn parse_plugin_output(output: &str) -> Result<Value, String> {
+    let mut value: Value = serde_json::from_str(output).map_err(|e| format!("invalid plugin output: {}", e))?;
+    if let Some(error) = value.get("error").and_then(|e| e.as_str()) {
+        return Err(error.to_string());
+    }
+    value
+        .get_mut("ok")
+        .map(Value::take)
+        .ok_or_else(|| "plugin output has neither `ok` nor `error`".to_string())
+}
+
+// ===== 插件注册表 =====
+
+static REGISTRY: Lazy<RwLock<Vec<(i32, Arc<dyn RequestTransformer>)>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+fn plugins_dir() -> Result<PathBuf, String> {
+    Ok(crate::modules::account::get_data_dir()?.join(PLUGINS_DIR))
+}
+
+/// 扫描插件目录，返回 (插件目录, 清单解析结果)
+fn scan_manifests(dir: &Path) -> Vec<(PathBuf, Result<PluginManifest, String>)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut manifests: Vec<_> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.join(MANIFEST_FILE).is_file())
+        .map(|p| {
+            let manifest = std::fs::read_to_string(p.join(MANIFEST_FILE))
+                .map_err(|e| e.to_string())
+                .and_then(|s| serde_json::from_str::<PluginManifest>(&s).map_err(|e| e.to_string()));
+            (p, manifest)
+        })
+        .collect();
+    manifests.sort_by(|a, b| a.0.cmp(&b.0));
+    manifests
+}
+
+/// 解析清单中的 library 路径：规范化后必须位于插件目录内 (拒绝绝对路径、`..` 与指向目录外的符号链接)
+fn resolve_library_path(plugin_dir: &Path, library: &str) -> Result<PathBuf, String> {
+    let dir = plugin_dir
+        .canonicalize()
+        .map_err(|e| format!("无法解析插件目录 {}: {}", plugin_dir.display(), e))?;
+    let path = dir
+        .join(library)
+        .canonicalize()
+        .map_err(|e| format!("无法解析插件库 {}: {}", library, e))?;
+    if !path.starts_with(&dir) {
+        return Err(format!("插件库 {} 不在插件目录内", library));
+    }
+    Ok(path)
+}
+
+/// 按配置重新加载已启用的插件 (反代启动及启用/禁用插件时调用)
+pub fn reload(config: &PluginsConfig) {
+    let mut transformers: Vec<(i32, Arc<dyn RequestTransformer>)> = Vec::new();
+
+    if config.enabled.iter().any(|n| n == SYSTEM_PROMPT_INJECTOR) && !config.system_prompt.trim().is_empty() {
+        transformers.push((0, Arc::new(SystemPromptInjector::new(config.system_prompt.clone()))));
+    }
+
+    if let Ok(dir) = plugins_dir() {
+        for (path, manifest) in scan_manifests(&dir) {
+            let Ok(manifest) = manifest else {
+                continue;
+            };
+            if !config.enabled.contains(&manifest.name) {
+                continue;
+            }
+            let loaded = resolve_library_path(&path, &manifest.library)
+                .and_then(|library| DylibTransformer::load(manifest.name.clone(), &library));
+            match loaded {
+                Ok(t) => transformers.push((manifest.priority, Arc::new(t))),
+                Err(e) => tracing::error!("[Plugins] Failed to load {}: {}", manifest.name, e),
+            }
+        }
+    }
+
+    // 稳定排序：同优先级保持加载顺序
+    transformers.sort_by_key(|(priority, _)| *priority);
+    tracing::info!("[Plugins] {} request transformer(s) active", transformers.len());
+    if let Ok(mut registry) = REGISTRY.write() {
+        *registry = transformers;
+    }
+}
+
+/// 依次应用已启用的转换插件；单个插件失败时跳过该插件并保留上一步结果
+pub fn apply(ctx: &TransformContext, body: Value) -> Value {
+    let transformers: Vec<Arc<dyn RequestTransformer>> = match REGISTRY.read() {
+        Ok(registry) if !registry.is_empty() => registry.iter().map(|(_, t)| t.clone()).collect(),
+        _ => return body,
+    };
+    apply_transformers(&transformers, ctx, body)
+}
+
+fn apply_transformers(transformers: &[Arc<dyn RequestTransformer>], ctx: &TransformContext, mut body: Value) -> Value {
+    for transformer in transformers {
+        match transformer.transform(ctx, body.clone()) {
+            Ok(transformed) => body = transformed,
+            Err(e) => tracing::warn!("[Plugins] {} failed, skipped: {}", transformer.name(), e),
+        }
+    }
+    body
+}
+
+/// 列出内置与插件目录中的全部插件
+pub fn list_plugins(config: &PluginsConfig) -> Vec<PluginInfo> {
+    let mut plugins = vec![PluginInfo {
+        name: SYSTEM_PROMPT_INJECTOR.to_string(),
+        description: "Prepend a fixed system prompt to every request".to_string(),
+        priority: 0,
+        builtin: true,
+        enabled: config.enabled.iter().any(|n| n == SYSTEM_PROMPT_INJECTOR),
+        error: None,
+    }];
+
+    if let Ok(dir) = plugins_dir() {
+        for (path, manifest) in scan_manifests(&dir) {
+            plugins.push(match manifest {
+                Ok(m) => PluginInfo {
+                    enabled: config.enabled.contains(&m.name),
+                    name: m.name,
+                    description: m.description,
+                    priority: m.priority,
+                    builtin: false,
+                    error: None,
+                },
+                Err(e) => PluginInfo {
+                    name: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+                    description: String::new(),
+                    priority: 0,
+                    builtin: false,
+                    enabled: false,
+                    error: Some(e),
+                },
+            });
+        }
+    }
+    plugins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Tagger(&'static str);
+
+    impl RequestTransformer for Tagger {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn transform(&self, _ctx: &TransformContext, mut body: Value) -> Result<Value, String> {
+            if self.0 == "broken" {
+                return Err("boom".to_string());
+            }
+            body["tags"].as_array_mut().unwrap().push(json!(self.0));
+            Ok(body)
+        }
+    }
+
+    fn ctx() -> TransformContext {
+        TransformContext {
+            model: "gemini-3-flash".to_string(),
+            account_id: Some("acc-1".to_string()),
+            session_id: None,
+        }
+    }
+
+    #[test]
+    fn test_system_prompt_injector() {
+        let injector = SystemPromptInjector::new("Be concise".to_string());
+        let body = json!({
+            "model": "gemini-3-flash",
+            "request": { "systemInstruction": { "parts": [{ "text": "existing" }] } }
+        });
+        let out = injector.transform(&ctx(), body).unwrap();
+        assert_eq!(out["request"]["systemInstruction"]["parts"][0]["text"], "Be concise");
+        assert_eq!(out["request"]["systemInstruction"]["parts"][1]["text"], "existing");
+
+        let out = injector.transform(&ctx(), json!({ "request": {} })).unwrap();
+        assert_eq!(out["request"]["systemInstruction"]["parts"][0]["text"], "Be concise");
+        assert!(injector.transform(&ctx(), json!({})).is_err());
+    }
+
+    #[test]
+    fn test_apply_in_order_and_skip_failures() {
+        let transformers: Vec<Arc<dyn RequestTransformer>> =
+            vec![Arc::new(Tagger("first")), Arc::new(Tagger("broken")), Arc::new(Tagger("second"))];
+        let out = apply_transformers(&transformers, &ctx(), json!({ "tags": [] }));
+        assert_eq!(out["tags"], json!(["first", "second"]));
+    }
+
+    #[test]
+    fn test_parse_plugin_output() {
+        assert_eq!(parse_plugin_output(r#"{"ok": {"a": 1}}"#).unwrap(), json!({"a": 1}));
+        assert_eq!(parse_plugin_output(r#"{"error": "denied"}"#).unwrap_err(), "denied");
+        assert!(parse_plugin_output("{}").is_err());
+        assert!(parse_plugin_output("not json").is_err());
+    }
+
+    #[test]
+    fn test_scan_manifests() {
+        let dir = std::env::temp_dir().join(format!("plugins-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("good")).unwrap();
+        std::fs::create_dir_all(dir.join("bad")).unwrap();
+        std::fs::create_dir_all(dir.join("empty")).unwrap();
+        std::fs::write(
+            dir.join("good").join(MANIFEST_FILE),
+            r#"{"name": "rewriter", "priority": 5, "library": "librewriter.so"}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("bad").join(MANIFEST_FILE), "{").unwrap();
+
+        let manifests = scan_manifests(&dir);
+        assert_eq!(manifests.len(), 2);
+        assert!(manifests[0].1.is_err());
+        let good = manifests[1].1.as_ref().unwrap();
+        assert_eq!((good.name.as_str(), good.priority), ("rewriter", 5));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_resolve_library_path_stays_in_plugin_dir() {
+        let dir = std::env::temp_dir().join(format!("plugins-{}", uuid::Uuid::new_v4()));
+        let plugin_dir = dir.join("rewriter");
+        std::fs::create_dir_all(&plugin_dir).unwrap();
+        std::fs::write(plugin_dir.join("librewriter.so"), b"").unwrap();
+        std::fs::write(dir.join("outside.so"), b"").unwrap();
+
+        let resolved = resolve_library_path(&plugin_dir, "librewriter.so").unwrap();
+        assert!(resolved.ends_with("librewriter.so"));
+        assert!(resolve_library_path(&plugin_dir, "../outside.so").is_err());
+        let outside = dir.join("outside.so");
+        assert!(resolve_library_path(&plugin_dir, outside.to_str().unwrap()).is_err());
+        assert!(resolve_library_path(&plugin_dir, "missing.so").is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,8 +1,41 @@
 use serde_json::Value;
 
+/// loadCodeAssist 调用失败原因
+#[derive(Debug)]
+pub enum LoadCodeAssistError {
+    /// 上游返回非 2xx 状态码
+    Status { status: u16, body: String },
+    /// 网络错误或响应解析失败
+    Transport(String),
+}
+
+impl LoadCodeAssistError {
+    /// access_token 已失效 (401) 或授权已被撤销 (invalid_grant)
+    pub fn is_auth_failure(&self) -> bool {
+        match self {
+            Self::Status { status, body } => *status == 401 || body.contains("invalid_grant"),
+            Self::Transport(_) => false,
+        }
+    }
+}
+
+impl std::fmt::Display for LoadCodeAssistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Status { status, body } => write!(f, "loadCodeAssist 返回错误 {}: {}", status, body),
+            Self::Transport(message) => f.write_str(message),
+        }
+    }
+}
+
 /// 使用 Antigravity 的 loadCodeAssist API 获取 project_id
 /// 这是获取 cloudaicompanionProject 的正确方式
 pub async fn fetch_project_id(access_token: &str) -> Result<String, String> {
+    load_code_assist(access_token).await.map_err(|e| e.to_string())
+}
+
+/// 同 `fetch_project_id`，但返回可区分认证失败的错误类型
+pub async fn load_code_assist(access_token: &str) -> Result<String, LoadCodeAssistError> {
     let url = "https://cloudcode-pa.googleapis.com/v1internal:loadCodeAssist";
     
     let request_body = serde_json::json!({
@@ -11,8 +44,9 @@ pub async fn fetch_project_id(access_token: &str) -> Result<String, String> {
         }
     });
     
-    let client = crate::utils::http::create_client(30);
+    let client = crate::utils::http::create_pooled_client(30);
     let response = client
+        .client
         .post(url)
         .bearer_auth(access_token)
         .header("Host", "cloudcode-pa.googleapis.com")
@@ -20,18 +54,19 @@ pub async fn fetch_project_id(access_token: &str) -> Result<String, String> {
         .header("Content-Type", "application/json")
         .json(&request_body)
         .send()
-        .await
-        .map_err(|e| format!("loadCodeAssist 请求失败: {}", e))?;
+        .await;
+    client.report(&response);
+    let response = response.map_err(|e| LoadCodeAssistError::Transport(format!("loadCodeAssist 请求失败: {}", e)))?;
     
     if !response.status().is_success() {
-        let status = response.status();
+        let status = response.status().as_u16();
         let body = response.text().await.unwrap_or_default();
-        return Err(format!("loadCodeAssist 返回错误 {}: {}", status, body));
+        return Err(LoadCodeAssistError::Status { status, body });
     }
     
     let data: Value = response.json()
         .await
-        .map_err(|e| format!("解析响应失败: {}", e))?;
+        .map_err(|e| LoadCodeAssistError::Transport(format!("解析响应失败: {}", e)))?;
     
     // 提取 cloudaicompanionProject
     if let Some(project_id) = data.get("cloudaicompanionProject")
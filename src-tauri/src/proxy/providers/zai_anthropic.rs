@@ -9,6 +9,7 @@ use serde_json::Value;
 use tokio::time::Duration;
 
 use crate::proxy::server::AppState;
+use crate::proxy::upstream_proxy::PooledClient;
 
 fn map_model_for_zai(original: &str, state: &crate::proxy::ZaiConfig) -> String {
     let m = original.to_lowercase();
@@ -48,23 +49,13 @@ fn join_base_url(base: &str, path: &str) -> Result<String, String> {
 
 fn build_client(
     upstream_proxy: Option<crate::proxy::config::UpstreamProxyConfig>,
-    timeout_secs: u64,
-) -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder()
-        .timeout(Duration::from_secs(timeout_secs.max(5)));
-
-    if let Some(config) = upstream_proxy {
-        if config.enabled && !config.url.is_empty() {
-            let proxy = reqwest::Proxy::all(&config.url)
-                .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
-            builder = builder.proxy(proxy);
-        }
-    }
+    effective_timeout: u64,
+) -> Result<PooledClient, String> {
+    let builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(effective_timeout.max(5)))
+        .tcp_nodelay(true); // [FIX #307] Disable Nagle's algorithm to improve latency for small requests
 
-    builder
-        .tcp_nodelay(true) // [FIX #307] Disable Nagle's algorithm to improve latency for small requests
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+    PooledClient::build(builder, &upstream_proxy.unwrap_or_default())
 }
 
 fn copy_passthrough_headers(incoming: &HeaderMap) -> HeaderMap {
@@ -154,7 +145,8 @@ pub async fn forward_anthropic_json(
         Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
     };
 
-    let timeout_secs = state.request_timeout.max(5);
+    let model = body.get("model").and_then(|v| v.as_str()).unwrap_or_default();
+    let timeout_secs = state.effective_timeout(model).await;
     let upstream_proxy = state.upstream_proxy.read().await.clone();
     let client = match build_client(Some(upstream_proxy), timeout_secs) {
         Ok(c) => c,
@@ -163,6 +155,7 @@ pub async fn forward_anthropic_json(
 
     let mut headers = copy_passthrough_headers(incoming_headers);
     set_zai_auth(&mut headers, incoming_headers, &zai.api_key);
+    crate::proxy::otel::inject_trace_headers(&mut headers);
 
     // Ensure JSON content type.
     headers
@@ -180,11 +173,22 @@ pub async fn forward_anthropic_json(
     
     tracing::debug!("Forwarding request to z.ai (len: {} bytes): {}", body_len, url);
 
-    let req = client.request(method, &url)
-        .headers(headers)
-        .body(body_bytes); // Use .body(Vec<u8>) instead of .json()
-
-    let resp = match req.send().await {
+    let retry_config = state.retry.read().await.clone();
+    let send = || {
+        client
+            .client
+            .request(method.clone(), &url)
+            .headers(headers.clone())
+            .body(body_bytes.clone()) // Use .body(Vec<u8>) instead of .json()
+            .send()
+    };
+    let resp = match crate::proxy::retry::retry_with_backoff(&retry_config, "z.ai upstream", || async {
+        let result = send().await;
+        client.report(&result);
+        result.map_err(|e| e.to_string())
+    })
+    .await
+    {
         Ok(r) => r,
         Err(e) => {
             return (
@@ -197,7 +201,7 @@ pub async fn forward_anthropic_json(
 
     let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
 
-    let mut out = Response::builder().status(status);
+    let mut out = Response::builder().status(status).header("X-Provider", "zai");
     if let Some(ct) = resp.headers().get(header::CONTENT_TYPE) {
         out = out.header(header::CONTENT_TYPE, ct.clone());
     }
@@ -0,0 +1,185 @@
+// 配额共享组
+// 订阅 TokenManager 的 token 消耗事件，按组累计账号消耗的 token；组总量超过共享预算时，
+// 将组内所有账号锁定到本配额窗口结束 (组内最早的模型 reset_time)，窗口结束后计数清零
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::models::config::QuotaGroup;
+use crate::proxy::token_manager::TokenUsageEvent;
+use crate::proxy::TokenManager;
+
+/// 组内账号均无配额刷新时间时使用的默认窗口 (秒)
+const DEFAULT_WINDOW_SECS: i64 = 3600;
+
+pub struct QuotaGroupWatcher {
+    token_manager: Arc<TokenManager>,
+    groups: Vec<QuotaGroup>,
+    /// 各组当前窗口内已消耗的 token 数 (key: 组名)
+    used_tokens: DashMap<String, AtomicU64>,
+    /// 各组当前窗口的结束时间 (服务器时间 Unix 秒)
+    window_end: DashMap<String, i64>,
+}
+
+impl QuotaGroupWatcher {
+    pub fn new(token_manager: Arc<TokenManager>, groups: Vec<QuotaGroup>) -> Self {
+        Self {
+            token_manager,
+            groups,
+            used_tokens: DashMap::new(),
+            window_end: DashMap::new(),
+        }
+    }
+
+    /// 启动后台统计任务，返回的句柄在停止服务时 abort
+    pub fn start(self) -> tokio::task::JoinHandle<()> {
+        let mut rx = self.token_manager.subscribe_token_usage();
+        tokio::spawn(async move {
+            if self.groups.is_empty() {
+                return;
+            }
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        self.reset_expired(now_timestamp());
+                    }
+                    received = rx.recv() => match received {
+                        Ok(event) => self.handle_usage(&event),
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            tracing::warn!("[QuotaGroup] Dropped {} usage events, group usage may be undercounted", skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+            }
+        })
+    }
+
+    fn handle_usage(&self, event: &TokenUsageEvent) {
+        let tokens = event.input_tokens + event.output_tokens;
+        if tokens == 0 {
+            return;
+        }
+
+        let now = now_timestamp();
+        self.reset_expired(now);
+        for group in self.groups.iter().filter(|g| g.account_ids.contains(&event.account_id)) {
+            let window_end = *self
+                .window_end
+                .entry(group.name.clone())
+                .or_insert_with(|| self.earliest_reset(group).unwrap_or(now + DEFAULT_WINDOW_SECS));
+            if self.add_usage(group, tokens) {
+                self.lock_group(group, window_end);
+            }
+        }
+    }
+
+    /// 累加组消耗，返回本次是否首次超出共享预算
+    fn add_usage(&self, group: &QuotaGroup, tokens: u64) -> bool {
+        let previous = self
+            .used_tokens
+            .entry(group.name.clone())
+            .or_insert_with(|| AtomicU64::new(0))
+            .fetch_add(tokens, Ordering::Relaxed);
+        match group.shared_budget_tokens {
+            Some(budget) => previous < budget && previous + tokens >= budget,
+            None => false,
+        }
+    }
+
+    /// 窗口结束的组计数清零
+    fn reset_expired(&self, now: i64) {
+        self.window_end.retain(|name, end| {
+            if *end > now {
+                return true;
+            }
+            if let Some(used) = self.used_tokens.get(name) {
+                used.store(0, Ordering::Relaxed);
+            }
+            tracing::info!("[QuotaGroup] Quota window of group {} ended, usage counter reset", name);
+            false
+        });
+    }
+
+    /// 组内账号中最早的模型配额刷新时间
+    fn earliest_reset(&self, group: &QuotaGroup) -> Option<i64> {
+        group
+            .account_ids
+            .iter()
+            .filter_map(|id| self.token_manager.email_for_account_id(id))
+            .filter_map(|email| self.token_manager.get_quota_reset_time(&email))
+            .filter_map(|reset| chrono::DateTime::parse_from_rfc3339(&reset).ok())
+            .map(|dt| dt.timestamp())
+            .min()
+    }
+
+    fn lock_group(&self, group: &QuotaGroup, window_end: i64) {
+        let Some(reset) = chrono::DateTime::from_timestamp(window_end, 0) else {
+            return;
+        };
+        let reset = reset.to_rfc3339();
+        tracing::warn!(
+            "[QuotaGroup] Group {} exceeded its shared budget of {} tokens, locking {} accounts until {}",
+            group.name,
+            group.shared_budget_tokens.unwrap_or(0),
+            group.account_ids.len(),
+            reset
+        );
+        for account_id in &group.account_ids {
+            self.token_manager.lock_account_until_iso(account_id, &reset);
+        }
+    }
+}
+
+fn now_timestamp() -> i64 {
+    crate::proxy::time_sync::TimeSyncManager::global().now_timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watcher(budget: Option<u64>) -> QuotaGroupWatcher {
+        let data_dir = std::env::temp_dir().join(format!("quota-group-{}", uuid::Uuid::new_v4()));
+        QuotaGroupWatcher::new(
+            Arc::new(TokenManager::new(data_dir)),
+            vec![QuotaGroup {
+                name: "team".to_string(),
+                account_ids: vec!["a".to_string(), "b".to_string()],
+                shared_budget_tokens: budget,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_add_usage_reports_first_budget_crossing() {
+        let w = watcher(Some(1000));
+        let group = w.groups[0].clone();
+        assert!(!w.add_usage(&group, 600));
+        assert!(w.add_usage(&group, 400));
+        // 已超出预算后不再重复触发
+        assert!(!w.add_usage(&group, 100));
+
+        let unlimited = watcher(None);
+        let group = unlimited.groups[0].clone();
+        assert!(!unlimited.add_usage(&group, 1_000_000));
+    }
+
+    #[test]
+    fn test_reset_expired_clears_usage() {
+        let w = watcher(Some(1000));
+        let group = w.groups[0].clone();
+        w.add_usage(&group, 800);
+        w.window_end.insert("team".to_string(), 100);
+
+        w.reset_expired(99);
+        assert_eq!(w.used_tokens.get("team").unwrap().load(Ordering::Relaxed), 800);
+
+        w.reset_expired(100);
+        assert_eq!(w.used_tokens.get("team").unwrap().load(Ordering::Relaxed), 0);
+        assert!(w.window_end.is_empty());
+    }
+}
@@ -1,9 +1,13 @@
 use dashmap::DashMap;
-use std::time::{SystemTime, Duration};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::time::{SystemTime, Duration, Instant, UNIX_EPOCH};
 use regex::Regex;
+use std::path::Path;
 
 /// 限流原因类型
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RateLimitReason {
     /// 配额耗尽 (QUOTA_EXHAUSTED)
     QuotaExhausted,
@@ -41,11 +45,237 @@ pub struct RateLimitInfo {
 /// 失败计数过期时间：1小时（超过此时间未失败则重置计数）
 const FAILURE_COUNT_EXPIRY_SECONDS: u64 = 3600;
 
+/// 熔断阈值：60 秒内连续 5 次失败 (相邻失败间隔超过 60 秒则重新计数)
+const BREAKER_FAILURE_THRESHOLD: u32 = 5;
+const BREAKER_FAILURE_WINDOW_SECS: u64 = 60;
+/// 熔断打开时长上限 (秒)
+const BREAKER_MAX_OPEN_SECS: u64 = 3600;
+/// 半开状态下探测请求的超时时间，超时未回报结果则允许下一个探测
+const BREAKER_PROBE_TIMEOUT_SECS: u64 = 60;
+
+const BREAKER_CLOSED: u8 = 0;
+const BREAKER_OPEN: u8 = 1;
+const BREAKER_HALF_OPEN: u8 = 2;
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// 熔断器状态快照
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BreakerSnapshot {
+    pub state: BreakerState,
+    pub failure_count: u32,
+    /// 距熔断结束的秒数 (仅 Open 状态有效)
+    pub open_remaining_secs: u64,
+}
+
+/// 单账号熔断器 (CLOSED -> OPEN -> HALF-OPEN -> CLOSED/OPEN)
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    state: AtomicU8,
+    failure_count: AtomicU32,
+    /// 最近一次失败时间 (Unix 秒)
+    last_failure: AtomicU64,
+    /// Open: 熔断结束时间；HalfOpen: 探测超时时间 (Unix 秒)
+    open_until: AtomicU64,
+}
+
+impl CircuitBreaker {
+    fn state(&self) -> BreakerState {
+        match self.state.load(Ordering::SeqCst) {
+            BREAKER_OPEN => BreakerState::Open,
+            BREAKER_HALF_OPEN => BreakerState::HalfOpen,
+            _ => BreakerState::Closed,
+        }
+    }
+
+    /// 熔断打开时长：2^failure_count * 30 秒，上限 1 小时
+    fn open_duration_secs(failure_count: u32) -> u64 {
+        2u64.saturating_pow(failure_count)
+            .saturating_mul(30)
+            .min(BREAKER_MAX_OPEN_SECS)
+    }
+
+    fn trip(&self, failure_count: u32, now: u64) {
+        self.open_until.store(now + Self::open_duration_secs(failure_count), Ordering::SeqCst);
+        self.state.store(BREAKER_OPEN, Ordering::SeqCst);
+    }
+
+    fn record_failure(&self, now: u64) -> BreakerState {
+        let last = self.last_failure.swap(now, Ordering::SeqCst);
+        match self.state.load(Ordering::SeqCst) {
+            BREAKER_HALF_OPEN => {
+                // 探测失败，重新打开并延长熔断时间
+                let count = self.failure_count.fetch_add(1, Ordering::SeqCst) + 1;
+                self.trip(count, now);
+            }
+            BREAKER_OPEN => {}
+            _ => {
+                if now.saturating_sub(last) > BREAKER_FAILURE_WINDOW_SECS {
+                    self.failure_count.store(0, Ordering::SeqCst);
+                }
+                let count = self.failure_count.fetch_add(1, Ordering::SeqCst) + 1;
+                if count >= BREAKER_FAILURE_THRESHOLD {
+                    self.trip(count, now);
+                }
+            }
+        }
+        self.state()
+    }
+
+    fn record_success(&self) {
+        self.failure_count.store(0, Ordering::SeqCst);
+        self.open_until.store(0, Ordering::SeqCst);
+        self.state.store(BREAKER_CLOSED, Ordering::SeqCst);
+    }
+
+    /// 只读判断熔断器是否拦截请求 (不占用探测名额)
+    /// Open 窗口未结束，或 HalfOpen 下已有探测请求在途时返回 true
+    fn is_open(&self, now: u64) -> bool {
+        match self.state.load(Ordering::SeqCst) {
+            BREAKER_OPEN | BREAKER_HALF_OPEN => now < self.open_until.load(Ordering::SeqCst),
+            _ => false,
+        }
+    }
+
+    /// 是否放行请求。Open 窗口结束后转为 HalfOpen 并只放行一个探测请求
+    /// 会占用探测名额，只应在实际派发请求时调用
+    fn allow_request(&self, now: u64) -> bool {
+        match self.state.load(Ordering::SeqCst) {
+            BREAKER_OPEN => {
+                if now < self.open_until.load(Ordering::SeqCst) {
+                    return false;
+                }
+                let acquired = self
+                    .state
+                    .compare_exchange(BREAKER_OPEN, BREAKER_HALF_OPEN, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok();
+                if acquired {
+                    self.open_until.store(now + BREAKER_PROBE_TIMEOUT_SECS, Ordering::SeqCst);
+                }
+                acquired
+            }
+            BREAKER_HALF_OPEN => {
+                // 探测请求未回报结果且已超时，允许新的探测
+                let deadline = self.open_until.load(Ordering::SeqCst);
+                now >= deadline
+                    && self
+                        .open_until
+                        .compare_exchange(deadline, now + BREAKER_PROBE_TIMEOUT_SECS, Ordering::SeqCst, Ordering::SeqCst)
+                        .is_ok()
+            }
+            _ => true,
+        }
+    }
+
+    fn snapshot(&self, now: u64) -> BreakerSnapshot {
+        let state = self.state();
+        BreakerSnapshot {
+            state,
+            failure_count: self.failure_count.load(Ordering::SeqCst),
+            open_remaining_secs: if state == BreakerState::Open {
+                self.open_until.load(Ordering::SeqCst).saturating_sub(now)
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// 每个账号保留的最近请求耗时样本数
+const LATENCY_WINDOW_SIZE: usize = 100;
+
+/// 延迟样本有效期：超过该时长的样本不再参与统计
+/// 被判定为慢账号后不再获得新请求，样本过期后账号自动恢复参与分配 (相当于定期探测)
+const LATENCY_SAMPLE_MAX_AGE: Duration = Duration::from_secs(600);
+
+/// 延迟分位数统计
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+/// 单账号最近 100 次成功请求的耗时 (记录时间, 毫秒)，仅统计 10 分钟内的样本
+#[derive(Debug, Default)]
+pub struct LatencyWindow {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl LatencyWindow {
+    fn record(&mut self, duration_ms: u64, now: Instant) {
+        while self.samples.len() >= LATENCY_WINDOW_SIZE
+            || self
+                .samples
+                .front()
+                .map(|(at, _)| now.duration_since(*at) > LATENCY_SAMPLE_MAX_AGE)
+                .unwrap_or(false)
+        {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, duration_ms));
+    }
+
+    /// 未过期的样本
+    fn recent(&self, now: Instant) -> Vec<u64> {
+        self.samples
+            .iter()
+            .filter(|(at, _)| now.duration_since(*at) <= LATENCY_SAMPLE_MAX_AGE)
+            .map(|(_, ms)| *ms)
+            .collect()
+    }
+
+    /// 平均耗时 (毫秒)，返回 (样本数, 平均值)
+    fn average(&self, now: Instant) -> Option<(usize, u64)> {
+        let recent = self.recent(now);
+        if recent.is_empty() {
+            return None;
+        }
+        let sum: u64 = recent.iter().sum();
+        Some((recent.len(), sum / recent.len() as u64))
+    }
+
+    fn stats(&self, now: Instant) -> Option<LatencyStats> {
+        let mut sorted = self.recent(now);
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_unstable();
+        // Nearest-rank 分位数
+        let percentile = |p: usize| sorted[((sorted.len() * p + 99) / 100).max(1) - 1];
+        Some(LatencyStats {
+            samples: sorted.len(),
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+            p99_ms: percentile(99),
+        })
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 /// 限流跟踪器
 pub struct RateLimitTracker {
     limits: DashMap<String, RateLimitInfo>,
     /// 连续失败计数（用于智能指数退避），带时间戳用于自动过期
     failure_counts: DashMap<String, (u32, SystemTime)>,
+    /// 账号熔断器 (仅统计 5xx 后端故障)
+    breakers: DashMap<String, CircuitBreaker>,
+    /// 最近成功请求的耗时窗口 (用于识别慢账号)
+    latencies: DashMap<String, LatencyWindow>,
 }
 
 impl RateLimitTracker {
@@ -53,6 +283,51 @@ impl RateLimitTracker {
         Self {
             limits: DashMap::new(),
             failure_counts: DashMap::new(),
+            breakers: DashMap::new(),
+            latencies: DashMap::new(),
+        }
+    }
+
+    /// 记录一次成功请求的耗时 (毫秒)
+    pub fn record_latency(&self, account_id: &str, duration_ms: u64) {
+        self.latencies
+            .entry(account_id.to_string())
+            .or_default()
+            .record(duration_ms, Instant::now());
+    }
+
+    /// 获取账号最近请求的延迟分位数 (无未过期样本时返回 None)
+    pub fn latency_stats(&self, account_id: &str) -> Option<LatencyStats> {
+        self.latencies.get(account_id).and_then(|w| w.stats(Instant::now()))
+    }
+
+    /// 获取账号最近请求的平均延迟，返回 (样本数, 平均毫秒数)
+    pub fn average_latency(&self, account_id: &str) -> Option<(usize, u64)> {
+        self.latencies.get(account_id).and_then(|w| w.average(Instant::now()))
+    }
+
+    /// 记录一次后端故障，返回熔断器当前状态
+    pub fn record_breaker_failure(&self, account_id: &str) -> BreakerState {
+        let state = self
+            .breakers
+            .entry(account_id.to_string())
+            .or_default()
+            .record_failure(unix_now());
+        if state == BreakerState::Open {
+            tracing::warn!("账号 {} 熔断器已打开 (连续后端故障)", account_id);
+        }
+        state
+    }
+
+    /// 获取账号熔断器状态快照
+    pub fn breaker_snapshot(&self, account_id: &str) -> BreakerSnapshot {
+        match self.breakers.get(account_id) {
+            Some(breaker) => breaker.snapshot(unix_now()),
+            None => BreakerSnapshot {
+                state: BreakerState::Closed,
+                failure_count: 0,
+                open_remaining_secs: 0,
+            },
         }
     }
     
@@ -75,6 +350,12 @@ impl RateLimitTracker {
         if self.failure_counts.remove(account_id).is_some() {
             tracing::debug!("账号 {} 请求成功，已重置失败计数", account_id);
         }
+        if let Some(breaker) = self.breakers.get(account_id) {
+            if breaker.state() != BreakerState::Closed {
+                tracing::info!("账号 {} 探测请求成功，熔断器已关闭", account_id);
+            }
+            breaker.record_success();
+        }
         // 同时清除限流记录（如果有）
         self.limits.remove(account_id);
     }
@@ -129,8 +410,9 @@ impl RateLimitTracker {
         // 尝试解析 ISO 8601 格式
         match chrono::DateTime::parse_from_rfc3339(reset_time_str) {
             Ok(dt) => {
-                let reset_time = SystemTime::UNIX_EPOCH + 
-                    std::time::Duration::from_secs(dt.timestamp() as u64);
+                // reset_time 为服务端时间，按校正后的时钟换算剩余时长，再映射回本机时间
+                let remaining = dt.timestamp() - crate::proxy::time_sync::TimeSyncManager::global().now_timestamp();
+                let reset_time = SystemTime::now() + Duration::from_secs(remaining.max(0) as u64);
                 self.set_lockout_until(account_id, reset_time, reason, model);
                 true
             },
@@ -169,6 +451,7 @@ impl RateLimitTracker {
             tracing::warn!("Google 429 Error Body: {}", body);
             self.parse_rate_limit_reason(body)
         } else {
+            self.record_breaker_failure(account_id);
             RateLimitReason::ServerError
         };
         
@@ -454,8 +737,14 @@ impl RateLimitTracker {
         self.limits.get(account_id).map(|r| r.clone())
     }
     
-    /// 检查账号是否仍在限流中
+    /// 检查账号是否仍在限流中 (只读，可用于筛选与统计)
+    /// 熔断器打开或半开探测在途时返回 true
     pub fn is_rate_limited(&self, account_id: &str) -> bool {
+        if let Some(breaker) = self.breakers.get(account_id) {
+            if breaker.is_open(unix_now()) {
+                return true;
+            }
+        }
         if let Some(info) = self.get(account_id) {
             info.reset_time > SystemTime::now()
         } else {
@@ -463,6 +752,23 @@ impl RateLimitTracker {
         }
     }
     
+    /// 派发请求前占用熔断器放行名额；半开状态下只有一个请求能成功占用探测名额
+    pub fn try_acquire_breaker(&self, account_id: &str) -> bool {
+        match self.breakers.get(account_id) {
+            Some(breaker) => breaker.allow_request(unix_now()),
+            None => true,
+        }
+    }
+
+    /// 测试用：将账号熔断器直接置为半开 (探测请求在途)
+    #[cfg(test)]
+    pub(crate) fn force_breaker_half_open(&self, account_id: &str) {
+        let breaker = self.breakers.entry(account_id.to_string()).or_default();
+        breaker.failure_count.store(BREAKER_FAILURE_THRESHOLD, Ordering::SeqCst);
+        breaker.open_until.store(unix_now() + BREAKER_PROBE_TIMEOUT_SECS, Ordering::SeqCst);
+        breaker.state.store(BREAKER_HALF_OPEN, Ordering::SeqCst);
+    }
+
     /// 获取距离限流重置还有多少秒
     pub fn get_reset_seconds(&self, account_id: &str) -> Option<u64> {
         if let Some(info) = self.get(account_id) {
@@ -498,7 +804,6 @@ impl RateLimitTracker {
     }
     
     /// 清除指定账号的限流记录
-    #[allow(dead_code)]
     pub fn clear(&self, account_id: &str) -> bool {
         self.limits.remove(account_id).is_some()
     }
@@ -514,6 +819,80 @@ impl RateLimitTracker {
     }
 }
 
+/// 限流记录持久化文件名 (位于数据目录下)
+pub const RATE_LIMITS_FILE: &str = "rate_limits.json";
+
+/// 限流记录持久化格式 (SystemTime 改用 Unix 秒)
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct PersistedRateLimit {
+    account_id: String,
+    reset_time: u64,
+    retry_after_sec: u64,
+    detected_at: u64,
+    reason: RateLimitReason,
+    model: Option<String>,
+}
+
+fn to_unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// 将尚未过期的限流记录保存到文件 (反代停止时调用)
+/// 失败计数与熔断器状态只在进程内有效，不做持久化
+pub fn save_rate_limits(path: &Path, tracker: &RateLimitTracker) -> Result<(), String> {
+    let now = SystemTime::now();
+    let records: Vec<PersistedRateLimit> = tracker
+        .limits
+        .iter()
+        .filter(|e| e.value().reset_time > now)
+        .map(|e| {
+            let info = e.value();
+            PersistedRateLimit {
+                account_id: e.key().clone(),
+                reset_time: to_unix_secs(info.reset_time),
+                retry_after_sec: info.retry_after_sec,
+                detected_at: to_unix_secs(info.detected_at),
+                reason: info.reason,
+                model: info.model.clone(),
+            }
+        })
+        .collect();
+
+    let content = serde_json::to_string(&records).map_err(|e| format!("序列化限流记录失败: {}", e))?;
+
+    // 先写临时文件再原子重命名，避免进程中断时留下半截文件
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, content).map_err(|e| format!("写入临时限流记录文件失败: {}", e))?;
+    std::fs::rename(&temp_path, path).map_err(|e| format!("替换限流记录文件失败: {}", e))
+}
+
+/// 从文件恢复限流记录，跳过 reset_time 已过的条目 (文件不存在时返回空跟踪器)
+pub fn load_rate_limits(path: &Path) -> Result<RateLimitTracker, String> {
+    let tracker = RateLimitTracker::new();
+    if !path.exists() {
+        return Ok(tracker);
+    }
+
+    let content = std::fs::read_to_string(path).map_err(|e| format!("读取限流记录文件失败: {}", e))?;
+    let records: Vec<PersistedRateLimit> =
+        serde_json::from_str(&content).map_err(|e| format!("解析限流记录文件失败: {}", e))?;
+
+    let now = unix_now();
+    for record in records.into_iter().filter(|r| r.reset_time > now) {
+        tracker.limits.insert(
+            record.account_id,
+            RateLimitInfo {
+                reset_time: UNIX_EPOCH + Duration::from_secs(record.reset_time),
+                retry_after_sec: record.retry_after_sec,
+                detected_at: UNIX_EPOCH + Duration::from_secs(record.detected_at),
+                reason: record.reason,
+                model: record.model,
+            },
+        );
+    }
+    Ok(tracker)
+}
+
 impl Default for RateLimitTracker {
     fn default() -> Self {
         Self::new()
@@ -524,6 +903,141 @@ impl Default for RateLimitTracker {
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_circuit_breaker_transitions() {
+        let breaker = CircuitBreaker::default();
+        let now = 1_000_000;
+
+        // 4 次失败仍保持关闭
+        for i in 0..4 {
+            assert_eq!(breaker.record_failure(now + i), BreakerState::Closed);
+        }
+        assert!(breaker.allow_request(now + 4));
+
+        // 第 5 次失败打开熔断：2^5 * 30 = 960 秒
+        assert_eq!(breaker.record_failure(now + 4), BreakerState::Open);
+        assert!(!breaker.allow_request(now + 100));
+        assert_eq!(breaker.snapshot(now + 4).open_remaining_secs, 960);
+
+        // 窗口结束后只放行一个探测请求
+        // 查询不占用探测名额
+        assert!(breaker.is_open(now + 963));
+        assert!(!breaker.is_open(now + 964));
+        assert!(!breaker.is_open(now + 964));
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(breaker.allow_request(now + 964));
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(breaker.is_open(now + 965));
+        assert!(!breaker.allow_request(now + 965));
+
+        // 探测失败重新打开，时长翻倍
+        assert_eq!(breaker.record_failure(now + 966), BreakerState::Open);
+        assert_eq!(breaker.snapshot(now + 966).open_remaining_secs, 1920);
+
+        // 探测成功关闭熔断
+        assert!(breaker.allow_request(now + 966 + 1920));
+        breaker.record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.snapshot(now).failure_count, 0);
+    }
+
+    #[test]
+    fn test_circuit_breaker_window_and_cap() {
+        let breaker = CircuitBreaker::default();
+        // 相邻失败间隔超过 60 秒时重新计数
+        for i in 0..10 {
+            assert_eq!(breaker.record_failure(i * 61), BreakerState::Closed);
+        }
+        assert_eq!(CircuitBreaker::open_duration_secs(7), 3600);
+        assert_eq!(CircuitBreaker::open_duration_secs(40), 3600);
+    }
+
+    #[test]
+    fn test_tracker_open_breaker_reports_rate_limited() {
+        let tracker = RateLimitTracker::new();
+        for _ in 0..5 {
+            tracker.parse_from_error("acc", 503, Some("1"), "", None);
+        }
+        tracker.clear("acc"); // 清除软避让计时，仅依赖熔断器
+        assert!(tracker.is_rate_limited("acc"));
+        assert_eq!(tracker.breaker_snapshot("acc").state, BreakerState::Open);
+
+        tracker.mark_success("acc");
+        assert!(!tracker.is_rate_limited("acc"));
+    }
+
+    #[test]
+    fn test_rate_limits_roundtrip_skips_expired() {
+        let path = std::env::temp_dir().join(format!("rate-limits-{}.json", uuid::Uuid::new_v4()));
+        let tracker = RateLimitTracker::new();
+        tracker.set_lockout_until(
+            "acc-active",
+            SystemTime::now() + Duration::from_secs(1800),
+            RateLimitReason::QuotaExhausted,
+            Some("gemini-3-pro-high".to_string()),
+        );
+        tracker.set_lockout_until("acc-expired", SystemTime::now() - Duration::from_secs(10), RateLimitReason::Unknown, None);
+
+        save_rate_limits(&path, &tracker).unwrap();
+        assert!(!path.with_extension("json.tmp").exists());
+        let loaded = load_rate_limits(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let info = loaded.get("acc-active").unwrap();
+        assert_eq!(info.reason, RateLimitReason::QuotaExhausted);
+        assert_eq!(info.model.as_deref(), Some("gemini-3-pro-high"));
+        assert!(loaded.is_rate_limited("acc-active"));
+        assert!((1795..=1800).contains(&loaded.get_remaining_wait("acc-active")));
+        assert!(loaded.get("acc-expired").is_none());
+    }
+
+    #[test]
+    fn test_load_rate_limits_missing_file() {
+        let path = std::env::temp_dir().join(format!("missing-{}.json", uuid::Uuid::new_v4()));
+        assert!(load_rate_limits(&path).unwrap().get("any").is_none());
+    }
+
+    #[test]
+    fn test_latency_window_percentiles() {
+        let tracker = RateLimitTracker::new();
+        assert!(tracker.latency_stats("acc").is_none());
+
+        for ms in 1..=100 {
+            tracker.record_latency("acc", ms * 10);
+        }
+        let stats = tracker.latency_stats("acc").unwrap();
+        assert_eq!(stats.samples, 100);
+        assert_eq!((stats.p50_ms, stats.p95_ms, stats.p99_ms), (500, 950, 990));
+        assert_eq!(tracker.average_latency("acc"), Some((100, 505)));
+
+        // 窗口只保留最近 100 个样本
+        for _ in 0..100 {
+            tracker.record_latency("acc", 40_000);
+        }
+        let stats = tracker.latency_stats("acc").unwrap();
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.p50_ms, 40_000);
+    }
+
+    #[test]
+    fn test_latency_samples_age_out() {
+        let mut window = LatencyWindow::default();
+        let start = Instant::now();
+        for _ in 0..20 {
+            window.record(40_000, start);
+        }
+        assert_eq!(window.stats(start).unwrap().p95_ms, 40_000);
+
+        // 慢账号不再获得新样本，过期后恢复参与分配
+        let later = start + LATENCY_SAMPLE_MAX_AGE + Duration::from_secs(1);
+        assert!(window.stats(later).is_none());
+        assert!(window.average(later).is_none());
+
+        window.record(500, later);
+        assert_eq!(window.average(later), Some((1, 500)));
+        assert_eq!(window.samples.len(), 1);
+    }
+
     #[test]
     fn test_parse_retry_time_minutes_seconds() {
         let tracker = RateLimitTracker::new();
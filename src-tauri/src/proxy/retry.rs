@@ -0,0 +1,72 @@
+// 上游瞬时错误重试 (按状态码 + 抖动指数退避)
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::proxy::config::RetryConfig;
+
+/// 计算第 `attempt` 次重试前的等待时间 (attempt 从 1 开始)
+/// `jitter_sample` 取值 [-1.0, 1.0]，按 `jitter_ratio` 比例浮动
+fn backoff_delay(config: &RetryConfig, attempt: u32, jitter_sample: f64) -> Duration {
+    let base = config.initial_delay_ms as f64
+        * config.backoff_multiplier.max(1.0).powi(attempt.saturating_sub(1) as i32);
+    let jitter = config.jitter_ratio.clamp(0.0, 1.0) * jitter_sample.clamp(-1.0, 1.0);
+    Duration::from_millis((base * (1.0 + jitter)).max(0.0) as u64)
+}
+
+/// 执行上游请求，响应状态码在 `retry_statuses` 中时按抖动指数退避重试，
+/// 最多执行 `max_attempts` 次；最后一次的响应原样返回给调用方。
+/// 网络错误 (Err) 不在此处重试，由调用方的账号轮换逻辑处理。
+pub async fn retry_with_backoff<F, Fut>(
+    config: &RetryConfig,
+    label: &str,
+    mut f: F,
+) -> Result<reqwest::Response, String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<reqwest::Response, String>>,
+{
+    let max_attempts = config.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let response = f().await?;
+        let status = response.status().as_u16();
+        if attempt >= max_attempts || !config.retry_statuses.contains(&status) {
+            return Ok(response);
+        }
+
+        let delay = backoff_delay(config, attempt, rand::thread_rng().gen_range(-1.0..=1.0));
+        tracing::warn!(
+            "[Retry] {} returned {}, retrying in {}ms (attempt {}/{})",
+            label,
+            status,
+            delay.as_millis(),
+            attempt + 1,
+            max_attempts
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay_exponential_with_jitter() {
+        let config = RetryConfig {
+            max_attempts: 3,
+            retry_statuses: vec![503],
+            initial_delay_ms: 500,
+            backoff_multiplier: 2.0,
+            jitter_ratio: 0.2,
+            ..Default::default()
+        };
+        assert_eq!(backoff_delay(&config, 1, 0.0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(&config, 2, 0.0), Duration::from_millis(1000));
+        assert_eq!(backoff_delay(&config, 3, 0.0), Duration::from_millis(2000));
+        assert_eq!(backoff_delay(&config, 2, 1.0), Duration::from_millis(1200));
+        assert_eq!(backoff_delay(&config, 2, -1.0), Duration::from_millis(800));
+    }
+}
@@ -1,10 +1,27 @@
-use crate::proxy::config::{ProxyAuthMode, ProxyConfig};
+use crate::proxy::config::{ApiKeyEntry, KeyPermissions, ProxyAuthMode, ProxyConfig};
+use ipnetwork::IpNetwork;
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+
+/// 计算 API Key 的 SHA-256 (小写十六进制)
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
 
 #[derive(Debug, Clone)]
 pub struct ProxySecurityConfig {
     pub auth_mode: ProxyAuthMode,
     pub api_key: String,
     pub allow_lan_access: bool,
+    /// 客户端 IP 白名单 (CIDR)，为空表示不限制
+    pub ip_allowlist: Vec<String>,
+    /// 受信任的反向代理 (CIDR)
+    pub trusted_proxies: Vec<String>,
+    pub api_keys: Vec<ApiKeyEntry>,
+    /// 免认证路径 (精确匹配，或 `*` 结尾的前缀匹配)
+    pub bypass_paths: Vec<String>,
+    /// 允许跨域访问的来源，为空表示不限制
+    pub cors_allowed_origins: Vec<String>,
 }
 
 impl ProxySecurityConfig {
@@ -13,7 +30,109 @@ impl ProxySecurityConfig {
             auth_mode: config.auth_mode.clone(),
             api_key: config.api_key.clone(),
             allow_lan_access: config.allow_lan_access,
+            ip_allowlist: config.ip_allowlist.clone(),
+            trusted_proxies: config.trusted_proxies.clone(),
+            api_keys: config.api_keys.clone(),
+            bypass_paths: config.bypass_paths.clone(),
+            cors_allowed_origins: config.cors_allowed_origins.clone(),
+        }
+    }
+
+    /// 路径是否命中免认证列表
+    pub fn is_bypass_path(&self, path: &str) -> bool {
+        self.bypass_paths.iter().any(|pattern| {
+            let pattern = pattern.trim();
+            match pattern.strip_suffix('*') {
+                Some(prefix) => path.starts_with(prefix),
+                None => !pattern.is_empty() && path == pattern,
+            }
+        })
+    }
+
+    /// 跨域请求来源是否允许
+    pub fn is_cors_origin_allowed(&self, origin: &str) -> bool {
+        self.cors_allowed_origins.is_empty()
+            || self
+                .cors_allowed_origins
+                .iter()
+                .any(|pattern| crate::proxy::middleware::cors::origin_matches(pattern, origin))
+    }
+
+    /// 是否配置了任意可用的密钥
+    pub fn has_api_keys(&self) -> bool {
+        !self.api_key.is_empty() || !self.api_keys.is_empty()
+    }
+
+    /// 校验请求携带的密钥，返回对应权限 (旧版 api_key 拥有全部权限；过期的 Key 视为无效)
+    pub fn match_api_key(&self, key: &str, now: i64) -> Option<KeyPermissions> {
+        if key.is_empty() {
+            return None;
+        }
+        // 比较摘要而非原文，避免逐字节比较的耗时泄露密钥前缀
+        let hash = hash_api_key(key);
+        if !self.api_key.is_empty() && hash == hash_api_key(&self.api_key) {
+            return Some(KeyPermissions::full());
+        }
+
+        self.api_keys
+            .iter()
+            .find(|entry| entry.key_hash == hash)
+            .filter(|entry| entry.expires_at.map(|exp| exp > now).unwrap_or(true))
+            .map(|entry| entry.permissions)
+    }
+
+    /// 密钥的限流设置 (Key 哈希, 每分钟请求数)；旧版 api_key 与未设置上限的 Key 返回 None
+    pub fn key_rate_limit(&self, key: &str) -> Option<(String, u32)> {
+        let hash = hash_api_key(key);
+        let limit = self
+            .api_keys
+            .iter()
+            .find(|entry| entry.key_hash == hash)
+            .and_then(|entry| entry.requests_per_minute)?;
+        Some((hash, limit))
+    }
+
+    /// 检查客户端 IP 是否在白名单内 (白名单为空时全部放行，无效条目被忽略)
+    pub fn is_ip_allowed(&self, ip: IpAddr) -> bool {
+        if self.ip_allowlist.is_empty() {
+            return true;
         }
+        self.ip_allowlist
+            .iter()
+            .filter_map(|entry| entry.trim().parse::<IpNetwork>().ok())
+            .any(|net| net.contains(ip))
+    }
+
+    /// 对端地址是否为受信任的反向代理
+    pub fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies
+            .iter()
+            .filter_map(|entry| entry.trim().parse::<IpNetwork>().ok())
+            .any(|net| net.contains(ip))
+    }
+
+    /// 解析客户端 IP：对端不是受信任代理时直接使用对端地址 (忽略可伪造的 X-Forwarded-For)；
+    /// 否则从右向左跳过受信任代理，取第一个不受信任的地址
+    pub fn resolve_client_ip(&self, peer: IpAddr, forwarded_for: Option<&str>) -> IpAddr {
+        if !self.is_trusted_proxy(peer) {
+            return peer;
+        }
+        let Some(forwarded_for) = forwarded_for else {
+            return peer;
+        };
+        let mut client = peer;
+        for hop in forwarded_for.rsplit(',') {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(ip) => {
+                    client = ip;
+                    if !self.is_trusted_proxy(ip) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        client
     }
 
     pub fn effective_auth_mode(&self) -> ProxyAuthMode {
@@ -40,6 +159,11 @@ mod tests {
             auth_mode: ProxyAuthMode::Auto,
             api_key: "sk-test".to_string(),
             allow_lan_access: false,
+            ip_allowlist: Vec::new(),
+            trusted_proxies: Vec::new(),
+            api_keys: Vec::new(),
+            bypass_paths: Vec::new(),
+            cors_allowed_origins: Vec::new(),
         };
         assert!(matches!(s.effective_auth_mode(), ProxyAuthMode::Off));
     }
@@ -50,11 +174,135 @@ mod tests {
             auth_mode: ProxyAuthMode::Auto,
             api_key: "sk-test".to_string(),
             allow_lan_access: true,
+            ip_allowlist: Vec::new(),
+            trusted_proxies: Vec::new(),
+            api_keys: Vec::new(),
+            bypass_paths: Vec::new(),
+            cors_allowed_origins: Vec::new(),
         };
         assert!(matches!(
             s.effective_auth_mode(),
             ProxyAuthMode::AllExceptHealth
         ));
     }
-}
 
+    #[test]
+    fn ip_allowlist_matches_cidr() {
+        let mut s = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Off,
+            api_key: String::new(),
+            allow_lan_access: true,
+            ip_allowlist: Vec::new(),
+            trusted_proxies: Vec::new(),
+            api_keys: Vec::new(),
+            bypass_paths: Vec::new(),
+            cors_allowed_origins: Vec::new(),
+        };
+        assert!(s.is_ip_allowed("10.0.0.8".parse().unwrap()));
+
+        s.ip_allowlist = vec!["192.168.1.0/24".to_string(), "10.0.0.5".to_string(), "bad".to_string()];
+        assert!(s.is_ip_allowed("192.168.1.42".parse().unwrap()));
+        assert!(s.is_ip_allowed("10.0.0.5".parse().unwrap()));
+        assert!(!s.is_ip_allowed("10.0.0.8".parse().unwrap()));
+    }
+
+    #[test]
+    fn forwarded_for_only_honoured_from_trusted_proxies() {
+        let mut s = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Off,
+            api_key: String::new(),
+            allow_lan_access: true,
+            ip_allowlist: Vec::new(),
+            trusted_proxies: Vec::new(),
+            api_keys: Vec::new(),
+            bypass_paths: Vec::new(),
+            cors_allowed_origins: Vec::new(),
+        };
+        let peer: IpAddr = "10.0.0.1".parse().unwrap();
+        // 未配置受信任代理时，伪造的 X-Forwarded-For 被忽略
+        assert_eq!(s.resolve_client_ip(peer, Some("192.168.1.42")), peer);
+
+        s.trusted_proxies = vec!["10.0.0.0/24".to_string()];
+        assert_eq!(
+            s.resolve_client_ip(peer, Some("1.2.3.4, 203.0.113.7, 10.0.0.9")),
+            "203.0.113.7".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(s.resolve_client_ip(peer, None), peer);
+        assert_eq!(s.resolve_client_ip(peer, Some("garbage")), peer);
+
+        let untrusted: IpAddr = "172.16.0.1".parse().unwrap();
+        assert_eq!(s.resolve_client_ip(untrusted, Some("192.168.1.42")), untrusted);
+    }
+
+    #[test]
+    fn api_keys_match_by_hash_and_expiry() {
+        let read_only = KeyPermissions {
+            can_write: false,
+            can_read_logs: true,
+            can_manage_accounts: false,
+        };
+        let s = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Strict,
+            api_key: "sk-legacy".to_string(),
+            allow_lan_access: true,
+            ip_allowlist: Vec::new(),
+            trusted_proxies: Vec::new(),
+            api_keys: vec![
+                ApiKeyEntry {
+                    key_hash: hash_api_key("sk-reader"),
+                    label: "reader".to_string(),
+                    permissions: read_only,
+                    expires_at: None,
+                    requests_per_minute: Some(60),
+                },
+                ApiKeyEntry {
+                    key_hash: hash_api_key("sk-expired"),
+                    label: "expired".to_string(),
+                    permissions: KeyPermissions::default(),
+                    expires_at: Some(1_000),
+                    requests_per_minute: None,
+                },
+            ],
+            bypass_paths: Vec::new(),
+            cors_allowed_origins: Vec::new(),
+        };
+
+        assert_eq!(s.match_api_key("sk-legacy", 2_000), Some(KeyPermissions::full()));
+        assert_eq!(s.match_api_key("sk-reader", 2_000), Some(read_only));
+        assert_eq!(s.match_api_key("sk-expired", 500), Some(KeyPermissions::default()));
+        assert_eq!(s.match_api_key("sk-expired", 2_000), None);
+        assert_eq!(s.match_api_key("sk-unknown", 2_000), None);
+
+        assert_eq!(s.key_rate_limit("sk-reader"), Some((hash_api_key("sk-reader"), 60)));
+        assert_eq!(s.key_rate_limit("sk-expired"), None);
+        assert_eq!(s.key_rate_limit("sk-legacy"), None);
+    }
+
+    #[test]
+    fn bypass_paths_support_exact_and_wildcard_suffix() {
+        let s = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Strict,
+            api_key: "sk-test".to_string(),
+            allow_lan_access: true,
+            ip_allowlist: Vec::new(),
+            trusted_proxies: Vec::new(),
+            api_keys: Vec::new(),
+            bypass_paths: vec![
+                "/healthz".to_string(),
+                "/metrics*".to_string(),
+                "/v1/models".to_string(),
+                " ".to_string(),
+            ],
+            cors_allowed_origins: Vec::new(),
+        };
+
+        assert!(s.is_bypass_path("/healthz"));
+        assert!(s.is_bypass_path("/metrics"));
+        assert!(s.is_bypass_path("/metrics/prometheus"));
+        assert!(s.is_bypass_path("/v1/models"));
+        assert!(!s.is_bypass_path("/v1/models/claude"));
+        assert!(!s.is_bypass_path("/healthz/extra"));
+        assert!(!s.is_bypass_path("/v1/messages"));
+        assert!(!s.is_bypass_path(""));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::proxy::TokenManager;
 use axum::{
-    extract::DefaultBodyLimit,
+    extract::{DefaultBodyLimit, State},
     http::StatusCode,
     response::{IntoResponse, Json, Response},
     routing::{any, get, post},
@@ -18,8 +18,11 @@ use std::sync::atomic::AtomicUsize;
 pub struct AppState {
     pub token_manager: Arc<TokenManager>,
     pub custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
+    pub regex_mapping: Arc<RwLock<crate::proxy::common::model_mapping::RegexMappingStore>>,
+    pub fallback_chains: Arc<RwLock<std::collections::HashMap<String, Vec<String>>>>,
     #[allow(dead_code)]
     pub request_timeout: u64, // API 请求超时(秒)
+    pub model_timeouts: Arc<RwLock<std::collections::HashMap<String, u64>>>, // 按模型覆盖的请求超时(秒)
     #[allow(dead_code)]
     pub thought_signature_map: Arc<tokio::sync::Mutex<std::collections::HashMap<String, String>>>, // 思维链签名映射 (ID -> Signature)
     #[allow(dead_code)]
@@ -28,33 +31,112 @@ pub struct AppState {
     pub zai: Arc<RwLock<crate::proxy::ZaiConfig>>,
     pub provider_rr: Arc<AtomicUsize>,
     pub zai_vision_mcp: Arc<crate::proxy::zai_vision_mcp::ZaiVisionMcpState>,
+    pub web_reader_cache: Arc<crate::proxy::zai_web_tools::WebReaderCache>,
+    pub cache_stats: Arc<crate::proxy::zai_web_tools::CacheStats>, // Web Reader 缓存命中统计
+    pub search_seen_urls: Arc<crate::proxy::zai_web_tools::SearchSeenUrls>, // webSearchPrime 会话内 URL 去重
     pub monitor: Arc<crate::proxy::monitor::ProxyMonitor>,
     pub experimental: Arc<RwLock<crate::proxy::config::ExperimentalConfig>>,
+    pub model_discovery: Arc<crate::proxy::model_discovery::ModelDiscovery>,
+    pub jwks_cache: Arc<crate::proxy::middleware::jwt::JwksCache>,
+    pub retry: Arc<RwLock<crate::proxy::config::RetryConfig>>,
+    pub connection_pool: Arc<crate::proxy::connection_pool::ConnectionPoolManager>,
+    pub inflight: Arc<crate::proxy::dedup::InFlightMap>,
+    pub metrics: Arc<crate::proxy::metrics::ProxyMetrics>,
+    pub upstream_health: Arc<RwLock<crate::proxy::upstream_health::UpstreamHealth>>,
+    pub idempotency: Arc<crate::proxy::middleware::idempotency::IdempotencyStore>,
+    pub stream_heartbeat_interval_secs: u64, // SSE 空闲心跳间隔(秒)，0 表示关闭
+    pub upstream_mtls: Option<crate::proxy::config::MtlsConfig>, // 上游 mTLS 客户端证书 (需重启生效)
+    pub rate_limit_events: tokio::sync::broadcast::Sender<crate::proxy::token_manager::RateLimitEvent>,
+}
+
+impl AppState {
+    /// 模型的有效请求超时 (秒)：优先使用 model_timeouts，否则使用 request_timeout
+    pub async fn effective_timeout(&self, model: &str) -> u64 {
+        self.model_timeouts
+            .read()
+            .await
+            .get(model)
+            .copied()
+            .unwrap_or(self.request_timeout)
+    }
+
+    /// 获取账号专属的上游 Client (按 email 定位账号；创建失败时返回 None，调用方使用共享 Client)
+    pub async fn account_client(&self, email: &str) -> Option<Arc<crate::proxy::upstream_proxy::PooledClient>> {
+        let key = self.token_manager.email_to_account_id(email).unwrap_or_else(|| email.to_string());
+        let proxy_config = self.upstream_proxy.read().await.clone();
+        self.connection_pool
+            .build_client_for_account(&key, Some(&proxy_config), self.upstream_mtls.as_ref())
+    }
 }
 
 /// Axum 服务器实例
 pub struct AxumServer {
     shutdown_tx: Option<oneshot::Sender<()>>,
     custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
+    regex_mapping: Arc<RwLock<crate::proxy::common::model_mapping::RegexMappingStore>>,
+    fallback_chains: Arc<RwLock<std::collections::HashMap<String, Vec<String>>>>,
+    model_timeouts: Arc<RwLock<std::collections::HashMap<String, u64>>>,
     proxy_state: Arc<tokio::sync::RwLock<crate::proxy::config::UpstreamProxyConfig>>,
     security_state: Arc<RwLock<crate::proxy::ProxySecurityConfig>>,
     zai_state: Arc<RwLock<crate::proxy::ZaiConfig>>,
     experimental: Arc<RwLock<crate::proxy::config::ExperimentalConfig>>,
+    retry: Arc<RwLock<crate::proxy::config::RetryConfig>>,
+    connection_pool: Arc<crate::proxy::connection_pool::ConnectionPoolManager>,
+    upstream_health: Arc<RwLock<crate::proxy::upstream_health::UpstreamHealth>>,
+    web_reader_cache_stats: Arc<crate::proxy::zai_web_tools::CacheStats>,
+    search_seen_urls: Arc<crate::proxy::zai_web_tools::SearchSeenUrls>,
+    grpc_handle: Option<tokio::task::JoinHandle<()>>,
+    response_headers: crate::proxy::middleware::static_headers::StaticResponseHeadersLayer,
 }
 
 impl AxumServer {
+    /// 上游健康状态 (由 UpstreamHealthChecker 更新)
+    pub fn upstream_health(&self) -> Arc<RwLock<crate::proxy::upstream_health::UpstreamHealth>> {
+        self.upstream_health.clone()
+    }
+
+    /// Web Reader 缓存命中统计
+    pub fn web_reader_cache_stats(&self) -> crate::proxy::zai_web_tools::CacheStatsSnapshot {
+        self.web_reader_cache_stats.snapshot()
+    }
+
+    /// 清除 webSearchPrime 各会话已返回的 URL 记录
+    pub fn clear_search_seen_urls(&self) {
+        self.search_seen_urls.clear();
+    }
+
     pub async fn update_mapping(&self, config: &crate::proxy::config::ProxyConfig) {
         {
             let mut m = self.custom_mapping.write().await;
             *m = config.custom_mapping.clone();
         }
+        self.update_regex_mapping(&config.regex_mapping).await;
+        self.update_fallback_chains(config.fallback_chains.clone()).await;
+        *self.model_timeouts.write().await = config.model_timeouts.clone();
+        self.response_headers.update(&config.response_headers);
         tracing::debug!("模型映射 (Custom) 已全量热更新");
     }
 
+    /// 热更新模型降级链
+    pub async fn update_fallback_chains(&self, chains: std::collections::HashMap<String, Vec<String>>) {
+        *self.fallback_chains.write().await = chains;
+        tracing::debug!("模型降级链已热更新");
+    }
+
+    /// 重新编译并热更新正则映射规则
+    pub async fn update_regex_mapping(&self, rules: &[crate::proxy::config::RegexMappingConfig]) {
+        let store = crate::proxy::common::model_mapping::RegexMappingStore::from_config(rules);
+        let count = store.len();
+        *self.regex_mapping.write().await = store;
+        tracing::debug!("正则模型映射已热更新 ({} 条规则)", count);
+    }
+
     /// 更新代理配置
     pub async fn update_proxy(&self, new_config: crate::proxy::config::UpstreamProxyConfig) {
         let mut proxy = self.proxy_state.write().await;
         *proxy = new_config;
+        // 账号连接池中的 Client 绑定了旧的代理设置，需重建
+        self.connection_pool.clear();
         tracing::info!("上游代理配置已热更新");
     }
 
@@ -64,6 +146,24 @@ impl AxumServer {
         tracing::info!("反代服务安全配置已热更新");
     }
 
+    /// 仅热更新多 API Key 列表
+    pub async fn update_api_keys(&self, api_keys: Vec<crate::proxy::config::ApiKeyEntry>) {
+        self.security_state.write().await.api_keys = api_keys;
+        tracing::info!("反代服务 API Key 列表已热更新");
+    }
+
+    /// 仅热更新 IP 白名单
+    pub async fn update_ip_allowlist(&self, ip_allowlist: Vec<String>) {
+        self.security_state.write().await.ip_allowlist = ip_allowlist;
+        tracing::info!("反代服务 IP 白名单已热更新");
+    }
+
+    /// 仅热更新免认证路径
+    pub async fn update_bypass_paths(&self, bypass_paths: Vec<String>) {
+        self.security_state.write().await.bypass_paths = bypass_paths;
+        tracing::info!("反代服务免认证路径已热更新");
+    }
+
     pub async fn update_zai(&self, config: &crate::proxy::config::ProxyConfig) {
         let mut zai = self.zai_state.write().await;
         *zai = config.zai.clone();
@@ -75,21 +175,44 @@ impl AxumServer {
         *exp = config.experimental.clone();
         tracing::info!("实验性配置已热更新");
     }
+
+    pub async fn update_retry(&self, config: &crate::proxy::config::ProxyConfig) {
+        *self.retry.write().await = config.retry.clone();
+        tracing::info!("上游重试配置已热更新");
+    }
     /// 启动 Axum 服务器
     pub async fn start(
         host: String,
         port: u16,
         token_manager: Arc<TokenManager>,
         custom_mapping: std::collections::HashMap<String, String>,
+        regex_mapping: &[crate::proxy::config::RegexMappingConfig],
+        fallback_chains: std::collections::HashMap<String, Vec<String>>,
         _request_timeout: u64,
+        model_timeouts: std::collections::HashMap<String, u64>,
         upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
+        upstream_mtls: Option<crate::proxy::config::MtlsConfig>,
         security_config: crate::proxy::ProxySecurityConfig,
         zai_config: crate::proxy::ZaiConfig,
         monitor: Arc<crate::proxy::monitor::ProxyMonitor>,
         experimental_config: crate::proxy::config::ExperimentalConfig,
-
+        model_discovery: Arc<crate::proxy::model_discovery::ModelDiscovery>,
+        retry_config: crate::proxy::config::RetryConfig,
+        grpc_port: Option<u16>,
+        max_request_body_mb: u64,
+        stream_heartbeat_interval_secs: u64,
+        response_compression: bool,
+        response_headers: &std::collections::HashMap<String, String>,
+        response_signer: Option<Arc<crate::proxy::middleware::response_signing::ResponseSigner>>,
     ) -> Result<(Self, tokio::task::JoinHandle<()>), String> {
         let custom_mapping_state = Arc::new(tokio::sync::RwLock::new(custom_mapping));
+        let regex_mapping_state = Arc::new(RwLock::new(
+            crate::proxy::common::model_mapping::RegexMappingStore::from_config(regex_mapping),
+        ));
+        let fallback_chains_state = Arc::new(RwLock::new(fallback_chains));
+        let model_timeouts_state = Arc::new(RwLock::new(model_timeouts));
+        let response_headers_layer =
+            crate::proxy::middleware::static_headers::StaticResponseHeadersLayer::new(response_headers);
 	        let proxy_state = Arc::new(tokio::sync::RwLock::new(upstream_proxy.clone()));
 	        let security_state = Arc::new(RwLock::new(security_config));
 	        let zai_state = Arc::new(RwLock::new(zai_config));
@@ -97,29 +220,61 @@ impl AxumServer {
 	        let zai_vision_mcp_state =
 	            Arc::new(crate::proxy::zai_vision_mcp::ZaiVisionMcpState::new());
 	        let experimental_state = Arc::new(RwLock::new(experimental_config));
+	        let jwks_cache = Arc::new(crate::proxy::middleware::jwt::JwksCache::new());
+	        let retry_state = Arc::new(RwLock::new(retry_config));
+	        let upstream_health_state = Arc::new(RwLock::new(crate::proxy::upstream_health::UpstreamHealth::default()));
+	        let web_reader_cache = Arc::new(crate::proxy::zai_web_tools::WebReaderCache::new());
+	        web_reader_cache.start_sweep_task();
+	        let web_reader_cache_stats = Arc::new(crate::proxy::zai_web_tools::CacheStats::default());
+	        let search_seen_urls = Arc::new(crate::proxy::zai_web_tools::SearchSeenUrls::new());
+	        search_seen_urls.start_sweep_task();
+	        let idempotency_store = Arc::new(crate::proxy::middleware::idempotency::IdempotencyStore::new());
+	        idempotency_store.start_cleanup_task();
 
 	        let state = AppState {
 	            token_manager: token_manager.clone(),
 	            custom_mapping: custom_mapping_state.clone(),
+	            regex_mapping: regex_mapping_state.clone(),
+	            fallback_chains: fallback_chains_state.clone(),
 	            request_timeout: 300, // 5分钟超时
+            model_timeouts: model_timeouts_state.clone(),
             thought_signature_map: Arc::new(tokio::sync::Mutex::new(
                 std::collections::HashMap::new(),
             )),
             upstream_proxy: proxy_state.clone(),
-            upstream: Arc::new(crate::proxy::upstream::client::UpstreamClient::new(Some(
-                upstream_proxy.clone(),
-            ))),
+            upstream: Arc::new(
+                crate::proxy::upstream::client::UpstreamClient::new(
+                    Some(upstream_proxy.clone()),
+                    upstream_mtls.as_ref(),
+                )
+                .with_model_timeouts(model_timeouts_state.clone()),
+            ),
             zai: zai_state.clone(),
             provider_rr: provider_rr.clone(),
             zai_vision_mcp: zai_vision_mcp_state,
+            web_reader_cache,
+            cache_stats: web_reader_cache_stats.clone(),
+            search_seen_urls: search_seen_urls.clone(),
             monitor: monitor.clone(),
             experimental: experimental_state.clone(),
+            model_discovery,
+            jwks_cache: jwks_cache.clone(),
+            retry: retry_state.clone(),
+            connection_pool: token_manager.connection_pool(),
+            inflight: Arc::new(crate::proxy::dedup::InFlightMap::new()),
+            metrics: token_manager.metrics(),
+            upstream_health: upstream_health_state.clone(),
+            idempotency: idempotency_store,
+            stream_heartbeat_interval_secs,
+            upstream_mtls,
+            rate_limit_events: token_manager.rate_limit_events(),
         };
 
 
         // 构建路由 - 使用新架构的 handlers！
         use crate::proxy::handlers;
         // 构建路由
+        let trace_token_manager = token_manager.clone();
         let app = Router::new()
             // OpenAI Protocol
             .route("/v1/models", get(handlers::openai::handle_list_models))
@@ -146,6 +301,7 @@ impl AxumServer {
             ) // 音频转录 API (PR #311)
             // Claude Protocol
             .route("/v1/messages", post(handlers::claude::handle_messages))
+            .route("/v1/batch", post(handlers::claude::handle_batch)) // 批量请求 (非流式)
             .route(
                 "/v1/messages/count_tokens",
                 post(handlers::claude::handle_count_tokens),
@@ -183,14 +339,41 @@ impl AxumServer {
             .route("/v1/api/event_logging/batch", post(silent_ok_handler))
             .route("/v1/api/event_logging", post(silent_ok_handler))
             .route("/healthz", get(health_check_handler))
-            .layer(DefaultBodyLimit::max(100 * 1024 * 1024))
+            .route("/metrics", get(metrics_handler))
+            .route("/api/rate-limits/stream", get(rate_limits_stream_handler))
+            // 请求体上限由外层 RequestBodyLimitLayer 统一控制
+            .layer(DefaultBodyLimit::disable())
             .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::monitor::monitor_middleware))
-            .layer(TraceLayer::new_for_http())
+            // 幂等缓存位于监控之外：命中时不重复记录用量
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::idempotency::idempotency_middleware))
+            // 位于监控与幂等缓存之外：超限请求在缓冲请求体之前即被拒绝
+            .layer(crate::proxy::middleware::body_limit::body_limit_layer(max_request_body_mb))
+            .layer(axum::middleware::from_fn(crate::proxy::middleware::body_limit::payload_too_large_middleware))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::proxy::middleware::metrics::metrics_middleware))
+            .layer(axum::middleware::from_fn(crate::proxy::middleware::client_info::client_info_middleware))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(crate::proxy::otel::make_span)
+                    .on_response(move |response: &Response, latency, span: &tracing::Span| {
+                        crate::proxy::otel::record_response(&trace_token_manager, response, latency, span)
+                    }),
+            )
             .layer(axum::middleware::from_fn_with_state(
-                security_state.clone(),
+                (security_state.clone(), jwks_cache),
                 crate::proxy::middleware::auth_middleware,
             ))
-            .layer(crate::proxy::middleware::cors_layer())
+            .layer(axum::middleware::from_fn(crate::proxy::middleware::access_log_middleware))
+            // 位于压缩之内：签名的是未压缩的响应体
+            .layer(axum::middleware::from_fn_with_state(
+                response_signer,
+                crate::proxy::middleware::response_signing::response_signing_middleware,
+            ))
+            // 位于监控与幂等缓存之外：二者始终处理未压缩的响应体
+            .layer(crate::proxy::middleware::compression::compression_layer(response_compression))
+            .layer(response_headers_layer.clone())
+            // 最外层生成请求标识：鉴权失败等提前返回的响应同样带有 X-Request-ID
+            .layer(axum::middleware::from_fn(crate::proxy::middleware::request_id::request_id_middleware))
+            .layer(crate::proxy::middleware::cors_layer(security_state.clone()))
             .with_state(state);
 
         // 绑定地址
@@ -201,16 +384,40 @@ impl AxumServer {
 
         tracing::info!("反代服务器启动在 http://{}", addr);
 
+        // gRPC 推理服务 (独立端口，请求经同一路由及其中间件处理)
+        #[cfg(feature = "grpc")]
+        let grpc_handle = match grpc_port {
+            Some(grpc_port) => Some(crate::proxy::grpc::start(&host, grpc_port, app.clone()).await?),
+            None => None,
+        };
+        #[cfg(not(feature = "grpc"))]
+        let grpc_handle = {
+            if grpc_port.is_some() {
+                tracing::warn!("已配置 grpc_port，但当前构建未启用 grpc 功能，gRPC 服务不会启动");
+            }
+            None
+        };
+
         // 创建关闭通道
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
 
         let server_instance = Self {
             shutdown_tx: Some(shutdown_tx),
             custom_mapping: custom_mapping_state.clone(),
+            regex_mapping: regex_mapping_state,
+            fallback_chains: fallback_chains_state,
+            model_timeouts: model_timeouts_state,
             proxy_state,
             security_state,
             zai_state,
             experimental: experimental_state.clone(),
+            retry: retry_state,
+            connection_pool: token_manager.connection_pool(),
+            upstream_health: upstream_health_state,
+            web_reader_cache_stats,
+            search_seen_urls,
+            grpc_handle,
+            response_headers: response_headers_layer,
         };
 
         // 在新任务中启动服务器
@@ -223,9 +430,13 @@ impl AxumServer {
                 tokio::select! {
                     res = listener.accept() => {
                         match res {
-                            Ok((stream, _)) => {
+                            Ok((stream, peer_addr)) => {
                                 let io = TokioIo::new(stream);
-                                let service = TowerToHyperService::new(app.clone());
+                                // 注入对端地址，供 IP 白名单校验使用
+                                let service = TowerToHyperService::new(
+                                    app.clone()
+                                        .layer(axum::Extension(axum::extract::ConnectInfo(peer_addr))),
+                                );
 
                                 tokio::task::spawn(async move {
                                     if let Err(err) = http1::Builder::new()
@@ -258,19 +469,73 @@ impl AxumServer {
         if let Some(tx) = self.shutdown_tx.take() {
             let _ = tx.send(());
         }
+        if let Some(handle) = self.grpc_handle.take() {
+            handle.abort();
+        }
     }
 }
 
 // ===== API 处理器 (旧代码已移除，由 src/proxy/handlers/* 接管) =====
 
 /// 健康检查处理器
-async fn health_check_handler() -> Response {
+async fn health_check_handler(State(state): State<AppState>) -> Response {
+    let upstream = state.upstream_health.read().await.snapshot();
     Json(serde_json::json!({
-        "status": "ok"
+        "status": "ok",
+        "upstream": upstream
     }))
     .into_response()
 }
 
+/// Prometheus 指标端点 (OpenMetrics 文本格式)
+async fn metrics_handler(State(state): State<AppState>) -> Response {
+    let body = state.metrics.render(
+        state.token_manager.len(),
+        state.token_manager.rate_limited_count(),
+    );
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        )],
+        body,
+    )
+        .into_response()
+}
+
+/// 限流状态实时推送 (SSE)：连接时先发送全量快照，之后推送变更事件，每 30 秒发送保活注释
+async fn rate_limits_stream_handler(State(state): State<AppState>) -> Response {
+    use axum::response::sse::{Event, KeepAlive, Sse};
+    use crate::proxy::token_manager::RateLimitEvent;
+
+    // 先订阅再取快照，避免遗漏两者之间发生的变更
+    let mut rx = state.rate_limit_events.subscribe();
+    let snapshot = RateLimitEvent::Snapshot {
+        statuses: state.token_manager.rate_limit_statuses(),
+    };
+    let stream = async_stream::stream! {
+        if let Ok(event) = Event::default().json_data(&snapshot) {
+            yield Ok::<_, std::convert::Infallible>(event);
+        }
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    if let Ok(event) = Event::default().json_data(&event) {
+                        yield Ok(event);
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Rate limit stream lagged, skipped {} events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    Sse::new(stream)
+        .keep_alive(KeepAlive::new().interval(std::time::Duration::from_secs(30)))
+        .into_response()
+}
+
 /// 静默成功处理器 (用于拦截遥测日志等)
 async fn silent_ok_handler() -> Response {
     StatusCode::OK.into_response()
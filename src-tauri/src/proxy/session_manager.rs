@@ -1,7 +1,14 @@
 use sha2::{Sha256, Digest};
 use crate::proxy::mappers::claude::models::{ClaudeRequest, MessageContent};
 use crate::proxy::mappers::openai::models::{OpenAIRequest, OpenAIContent};
+use crate::proxy::token_manager::SessionMeta;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
 
 /// 会话管理器工具
 pub struct SessionManager;
@@ -157,3 +164,103 @@ impl SessionManager {
         sid
     }
 }
+
+/// 会话绑定持久化记录 (Instant 无法序列化，改用 Unix 秒)
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedSession {
+    session_id: String,
+    account_id: String,
+    bound_at: i64,
+    client_ip: Option<IpAddr>,
+    user_agent: Option<String>,
+    request_count: u32,
+}
+
+/// 将会话绑定保存到文件 (反代停止时调用)
+pub fn save_sessions(path: &Path, sessions: &DashMap<String, SessionMeta>) -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+    let records: Vec<PersistedSession> = sessions
+        .iter()
+        .map(|e| {
+            let meta = e.value();
+            PersistedSession {
+                session_id: e.key().clone(),
+                account_id: meta.account_id.clone(),
+                bound_at: now - meta.bound_at.elapsed().as_secs() as i64,
+                client_ip: meta.client_ip,
+                user_agent: meta.user_agent.clone(),
+                request_count: meta.request_count.load(Ordering::Relaxed),
+            }
+        })
+        .collect();
+
+    let content = serde_json::to_string(&records).map_err(|e| format!("序列化会话失败: {}", e))?;
+    std::fs::write(path, content).map_err(|e| format!("写入会话文件失败: {}", e))
+}
+
+/// 从文件加载会话绑定 (文件不存在时返回空表；过期与失效账号的过滤由调用方完成)
+pub fn load_sessions(path: &Path) -> Result<DashMap<String, SessionMeta>, String> {
+    let sessions = DashMap::new();
+    if !path.exists() {
+        return Ok(sessions);
+    }
+
+    let content = std::fs::read_to_string(path).map_err(|e| format!("读取会话文件失败: {}", e))?;
+    let records: Vec<PersistedSession> =
+        serde_json::from_str(&content).map_err(|e| format!("解析会话文件失败: {}", e))?;
+
+    let now = chrono::Utc::now().timestamp();
+    for record in records {
+        let age = Duration::from_secs((now - record.bound_at).max(0) as u64);
+        let bound_at = Instant::now().checked_sub(age).unwrap_or_else(Instant::now);
+        sessions.insert(
+            record.session_id,
+            SessionMeta {
+                account_id: record.account_id,
+                bound_at,
+                client_ip: record.client_ip,
+                user_agent: record.user_agent,
+                request_count: AtomicU32::new(record.request_count),
+            },
+        );
+    }
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions_roundtrip() {
+        let path = std::env::temp_dir().join(format!("sessions-{}.json", uuid::Uuid::new_v4()));
+        let sessions = DashMap::new();
+        sessions.insert(
+            "sid-abc".to_string(),
+            SessionMeta {
+                account_id: "acc-1".to_string(),
+                bound_at: Instant::now() - Duration::from_secs(120),
+                client_ip: Some("10.0.0.2".parse().unwrap()),
+                user_agent: Some("claude-cli/1.0".to_string()),
+                request_count: AtomicU32::new(7),
+            },
+        );
+
+        save_sessions(&path, &sessions).unwrap();
+        let loaded = load_sessions(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let meta = loaded.get("sid-abc").unwrap();
+        assert_eq!(meta.account_id, "acc-1");
+        assert_eq!(meta.user_agent.as_deref(), Some("claude-cli/1.0"));
+        assert_eq!(meta.request_count.load(Ordering::Relaxed), 7);
+        let age = meta.bound_at.elapsed().as_secs();
+        assert!((119..=122).contains(&age));
+    }
+
+    #[test]
+    fn test_load_sessions_missing_file() {
+        let path = std::env::temp_dir().join(format!("missing-{}.json", uuid::Uuid::new_v4()));
+        assert!(load_sessions(&path).unwrap().is_empty());
+    }
+}
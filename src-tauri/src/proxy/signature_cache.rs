@@ -1,4 +1,7 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, SystemTime};
 
@@ -12,7 +15,7 @@ const FAMILY_CACHE_LIMIT: usize = 200;    // Layer 2: Model family mappings
 const SESSION_CACHE_LIMIT: usize = 1000;  // Layer 3: Session-based signatures (largest)
 
 /// Cache entry with timestamp for TTL
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct CacheEntry<T> {
     data: T,
     timestamp: SystemTime,
@@ -31,6 +34,69 @@ impl<T> CacheEntry<T> {
     }
 }
 
+/// Per-layer hit/miss/eviction counters
+#[derive(Debug, Default)]
+struct CacheStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl CacheStats {
+    fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_evictions(&self, count: usize) {
+        self.evictions.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.evictions.store(0, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, current_size: usize) -> CacheLayerStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        CacheLayerStats {
+            hits,
+            misses,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            current_size,
+            hit_rate: if lookups == 0 { 0.0 } else { hits as f64 / lookups as f64 },
+        }
+    }
+}
+
+/// Point-in-time statistics for one cache layer
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheLayerStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub current_size: usize,
+    pub hit_rate: f64,
+}
+
+/// Statistics for all three cache layers
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureCacheReport {
+    pub tool_signatures: CacheLayerStats,
+    pub thinking_families: CacheLayerStats,
+    pub session_signatures: CacheLayerStats,
+}
+
+/// On-disk snapshot of the persistable layers (Layer 2 is cheap to rebuild and is skipped)
+#[derive(Serialize, Deserialize)]
+struct PersistedSignatures {
+    tool_signatures: HashMap<String, CacheEntry<String>>,
+    session_signatures: HashMap<String, CacheEntry<String>>,
+}
+
 /// Triple-layer signature cache to handle:
 /// 1. Signature recovery for tool calls (when clients strip them)
 /// 2. Cross-model compatibility checks (preventing Claude signatures on Gemini models)
@@ -51,6 +117,10 @@ pub struct SignatureCache {
     /// Value: The most recent valid thought signature for this session
     /// This prevents signature pollution between different conversations
     session_signatures: Mutex<HashMap<String, CacheEntry<String>>>,
+
+    tool_stats: CacheStats,
+    family_stats: CacheStats,
+    session_stats: CacheStats,
 }
 
 impl SignatureCache {
@@ -59,6 +129,9 @@ impl SignatureCache {
             tool_signatures: Mutex::new(HashMap::new()),
             thinking_families: Mutex::new(HashMap::new()),
             session_signatures: Mutex::new(HashMap::new()),
+            tool_stats: CacheStats::default(),
+            family_stats: CacheStats::default(),
+            session_stats: CacheStats::default(),
         }
     }
 
@@ -83,6 +156,7 @@ impl SignatureCache {
                 let before = cache.len();
                 cache.retain(|_, v| !v.is_expired());
                 let after = cache.len();
+                self.tool_stats.record_evictions(before - after);
                 if before != after {
                     tracing::debug!("[SignatureCache] Tool cache cleanup: {} -> {} entries", before, after);
                 }
@@ -96,10 +170,12 @@ impl SignatureCache {
             if let Some(entry) = cache.get(tool_use_id) {
                 if !entry.is_expired() {
                     tracing::debug!("[SignatureCache] Hit tool signature for id: {}", tool_use_id);
+                    self.tool_stats.record(true);
                     return Some(entry.data.clone());
                 }
             }
         }
+        self.tool_stats.record(false);
         None
     }
 
@@ -117,6 +193,7 @@ impl SignatureCache {
                 let before = cache.len();
                 cache.retain(|_, v| !v.is_expired());
                 let after = cache.len();
+                self.family_stats.record_evictions(before - after);
                 if before != after {
                     tracing::debug!("[SignatureCache] Family cache cleanup: {} -> {} entries", before, after);
                 }
@@ -129,12 +206,14 @@ impl SignatureCache {
         if let Ok(cache) = self.thinking_families.lock() {
             if let Some(entry) = cache.get(signature) {
                 if !entry.is_expired() {
+                    self.family_stats.record(true);
                     return Some(entry.data.clone());
                 } else {
                     tracing::debug!("[SignatureCache] Signature family entry expired");
                 }
             }
         }
+        self.family_stats.record(false);
         None
     }
 
@@ -175,6 +254,7 @@ impl SignatureCache {
                 let before = cache.len();
                 cache.retain(|_, v| !v.is_expired());
                 let after = cache.len();
+                self.session_stats.record_evictions(before - after);
                 if before != after {
                     tracing::info!(
                         "[SignatureCache] Session cache cleanup: {} -> {} entries (limit: {})",
@@ -198,18 +278,75 @@ impl SignatureCache {
                         session_id,
                         entry.data.len()
                     );
+                    self.session_stats.record(true);
                     return Some(entry.data.clone());
                 } else {
                     tracing::debug!("[SignatureCache] Session {} -> EXPIRED", session_id);
                 }
             }
         }
+        self.session_stats.record(false);
         None
     }
 
-    /// Clear all caches (for testing or manual reset)
+    /// Hit/miss/eviction statistics for all three layers
+    pub fn stats(&self) -> SignatureCacheReport {
+        let size = |layer: &Mutex<HashMap<String, CacheEntry<String>>>| layer.lock().map(|c| c.len()).unwrap_or(0);
+        SignatureCacheReport {
+            tool_signatures: self.tool_stats.snapshot(size(&self.tool_signatures)),
+            thinking_families: self.family_stats.snapshot(size(&self.thinking_families)),
+            session_signatures: self.session_stats.snapshot(size(&self.session_signatures)),
+        }
+    }
+
+    // ===== Persistence =====
+
+    /// Save tool and session signatures to disk (called when the proxy stops)
+    pub fn save_to_disk(&self, path: &Path) -> Result<(), String> {
+        let snapshot = PersistedSignatures {
+            tool_signatures: self.tool_signatures.lock().map(|c| c.clone()).unwrap_or_default(),
+            session_signatures: self.session_signatures.lock().map(|c| c.clone()).unwrap_or_default(),
+        };
+        let bytes = bincode::serialize(&snapshot).map_err(|e| format!("序列化签名缓存失败: {}", e))?;
+        std::fs::write(path, bytes).map_err(|e| format!("写入签名缓存失败: {}", e))
+    }
+
+    /// Load tool and session signatures from disk, skipping expired entries.
+    /// A missing file is not an error; a corrupt file is reported and left untouched.
+    /// Returns the number of restored entries.
+    pub fn load_from_disk(&self, path: &Path) -> Result<usize, String> {
+        if !path.exists() {
+            return Ok(0);
+        }
+        let bytes = std::fs::read(path).map_err(|e| format!("读取签名缓存失败: {}", e))?;
+        let snapshot: PersistedSignatures =
+            bincode::deserialize(&bytes).map_err(|e| format!("签名缓存文件已损坏: {}", e))?;
+
+        let mut restored = 0;
+        for (layer, entries) in [
+            (&self.tool_signatures, snapshot.tool_signatures),
+            (&self.session_signatures, snapshot.session_signatures),
+        ] {
+            if let Ok(mut cache) = layer.lock() {
+                for (key, entry) in entries.into_iter().filter(|(_, e)| !e.is_expired()) {
+                    // Entries cached since startup are newer than the snapshot
+                    cache.entry(key).or_insert_with(|| {
+                        restored += 1;
+                        entry
+                    });
+                }
+            }
+        }
+        tracing::debug!("[SignatureCache] Restored {} entries from disk", restored);
+        Ok(restored)
+    }
+
+    /// Clear all caches and reset statistics (for testing or manual reset)
     #[allow(dead_code)] // Used in tests
     pub fn clear(&self) {
+        self.tool_stats.reset();
+        self.family_stats.reset();
+        self.session_stats.reset();
         if let Ok(mut cache) = self.tool_signatures.lock() {
             cache.clear();
         }
@@ -283,6 +420,62 @@ mod tests {
         assert!(cache.get_session_signature("sid-other").is_none());
     }
 
+    #[test]
+    fn test_disk_roundtrip_skips_expired_and_corrupt() {
+        let path = std::env::temp_dir().join(format!("signature_cache-{}.bin", uuid::Uuid::new_v4()));
+        let sig = "s".repeat(60);
+
+        let cache = SignatureCache::new();
+        cache.cache_tool_signature("tool_1", sig.clone());
+        cache.cache_session_signature("sid-1", sig.clone());
+        if let Ok(mut tools) = cache.tool_signatures.lock() {
+            tools.insert(
+                "tool_old".to_string(),
+                CacheEntry {
+                    data: sig.clone(),
+                    timestamp: SystemTime::now() - SIGNATURE_TTL - Duration::from_secs(1),
+                },
+            );
+        }
+        cache.save_to_disk(&path).unwrap();
+
+        let restored = SignatureCache::new();
+        assert_eq!(restored.load_from_disk(&path).unwrap(), 2);
+        assert_eq!(restored.get_tool_signature("tool_1"), Some(sig.clone()));
+        assert_eq!(restored.get_session_signature("sid-1"), Some(sig));
+        assert!(restored.tool_signatures.lock().unwrap().get("tool_old").is_none());
+
+        std::fs::write(&path, b"not bincode").unwrap();
+        assert!(SignatureCache::new().load_from_disk(&path).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_stats_track_hits_misses_and_reset() {
+        let cache = SignatureCache::new();
+        let sig = "z".repeat(60);
+
+        cache.cache_tool_signature("tool_1", sig.clone());
+        assert!(cache.get_tool_signature("tool_1").is_some());
+        assert!(cache.get_tool_signature("tool_missing").is_none());
+        assert!(cache.get_signature_family(&sig).is_none());
+        assert!(cache.get_session_signature("sid-none").is_none());
+
+        let report = cache.stats();
+        assert_eq!(report.tool_signatures.hits, 1);
+        assert_eq!(report.tool_signatures.misses, 1);
+        assert_eq!(report.tool_signatures.current_size, 1);
+        assert!((report.tool_signatures.hit_rate - 0.5).abs() < f64::EPSILON);
+        assert_eq!(report.thinking_families.misses, 1);
+        assert_eq!(report.session_signatures.misses, 1);
+
+        cache.clear();
+        let report = cache.stats();
+        assert_eq!(report.tool_signatures.hits, 0);
+        assert_eq!(report.tool_signatures.misses, 0);
+        assert_eq!(report.tool_signatures.current_size, 0);
+    }
+
     #[test]
     fn test_clear_all_caches() {
         let cache = SignatureCache::new();
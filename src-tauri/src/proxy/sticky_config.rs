@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// 调度模式枚举
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -9,6 +10,8 @@ pub enum SchedulingMode {
     Balance,
     /// 性能优先 (Performance-first): 纯轮询模式 (Round-robin)，账号负载最均衡，但不利用缓存
     PerformanceFirst,
+    /// 响应时间加权 (Response-time weighted): 优先选择最近平均延迟最低的账号，会话绑定账号明显变慢时重新绑定
+    ResponseTimeWeighted,
 }
 
 impl Default for SchedulingMode {
@@ -17,6 +20,46 @@ impl Default for SchedulingMode {
     }
 }
 
+/// FREE 账号使用策略
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FreeAccountMode {
+    /// 与其它账号一同参与调度 (按等级排序)
+    Normal,
+    /// 仅在 PRO/ULTRA 账号全部限流或被配额保护时使用
+    FallbackOnly,
+    /// 从不使用 FREE 账号
+    Disabled,
+}
+
+impl Default for FreeAccountMode {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+fn default_session_ttl_secs() -> u64 {
+    3600
+}
+
+fn default_slow_account_threshold_ms() -> u64 {
+    30_000
+}
+
+fn default_rotation_cooldown_ms() -> u64 {
+    500
+}
+
+/// 标签路由策略 (key 为请求头 X-Antigravity-Tag 的取值)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagRoutingPolicy {
+    /// 允许使用的账号标签；为空时直接使用请求头中的标签
+    #[serde(default)]
+    pub preferred_tags: Vec<String>,
+    /// 没有匹配标签的可用账号时，是否回退到未打标签的账号
+    #[serde(default)]
+    pub fallback_to_untagged: bool,
+}
+
 /// 粘性会话配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StickySessionConfig {
@@ -24,6 +67,27 @@ pub struct StickySessionConfig {
     pub mode: SchedulingMode,
     /// 缓存优先模式下的最大等待时间 (秒)
     pub max_wait_seconds: u64,
+    /// 会话绑定的最大存续时间 (秒)，超过后解绑并轮换账号；0 表示不限制
+    #[serde(default)]
+    pub max_session_age_secs: u64,
+    /// 会话绑定的存活时间 (秒)，超过后由后台任务回收；0 表示不回收
+    #[serde(default = "default_session_ttl_secs")]
+    pub session_ttl_secs: u64,
+    /// 模型账号亲和规则 (key: 标准模型 ID, value: 优先使用的 account_id 列表，按顺序尝试)
+    #[serde(default)]
+    pub model_account_affinity: HashMap<String, Vec<String>>,
+    /// 标签路由策略 (key: 请求头中的标签)
+    #[serde(default)]
+    pub tag_routing: HashMap<String, TagRoutingPolicy>,
+    /// FREE 账号使用策略
+    #[serde(default)]
+    pub free_account_mode: FreeAccountMode,
+    /// 慢账号阈值 (毫秒)：最近请求 P95 延迟超过该值的账号暂不参与新分配；0 表示不检测
+    #[serde(default = "default_slow_account_threshold_ms")]
+    pub slow_account_threshold_ms: u64,
+    /// 轮换冷却 (毫秒)：会话因限流解绑后，跳过在此时间内刚被其他会话绑定的账号，错开重新绑定；0 表示不错开
+    #[serde(default = "default_rotation_cooldown_ms")]
+    pub rotation_cooldown_ms: u64,
 }
 
 impl Default for StickySessionConfig {
@@ -31,6 +95,13 @@ impl Default for StickySessionConfig {
         Self {
             mode: SchedulingMode::Balance,
             max_wait_seconds: 60,
+            max_session_age_secs: 0,
+            session_ttl_secs: default_session_ttl_secs(),
+            model_account_affinity: HashMap::new(),
+            tag_routing: HashMap::new(),
+            free_account_mode: FreeAccountMode::Normal,
+            slow_account_threshold_ms: default_slow_account_threshold_ms(),
+            rotation_cooldown_ms: default_rotation_cooldown_ms(),
         }
     }
 }
@@ -0,0 +1,113 @@
+// 服务器时间同步
+// 配额 reset_time 为服务端绝对时间，本机时钟漂移或被手动修改时精确锁定的时长会出错。
+// 通过对 googleapis 发起 HEAD 请求读取 Date 响应头，计算本机与可信时间源的偏移量 (秒)。
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Once, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 可信时间源
+const TIME_SOURCE_URL: &str = "https://www.googleapis.com";
+
+/// 后台重新校准间隔
+const RESYNC_INTERVAL_SECS: u64 = 30 * 60;
+
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+pub struct TimeSyncManager {
+    /// 可信时间 - 本机时间 (秒)
+    clock_offset: Arc<AtomicI64>,
+    started: Once,
+}
+
+impl TimeSyncManager {
+    fn new() -> Self {
+        Self {
+            clock_offset: Arc::new(AtomicI64::new(0)),
+            started: Once::new(),
+        }
+    }
+
+    /// 全局单例
+    pub fn global() -> &'static TimeSyncManager {
+        static INSTANCE: OnceLock<TimeSyncManager> = OnceLock::new();
+        INSTANCE.get_or_init(TimeSyncManager::new)
+    }
+
+    /// 当前时钟偏移 (秒)，尚未同步成功时为 0
+    pub fn offset_secs(&self) -> i64 {
+        self.clock_offset.load(Ordering::Relaxed)
+    }
+
+    /// 校正后的当前 Unix 时间戳 (秒)
+    pub fn now_timestamp(&self) -> i64 {
+        chrono::Utc::now().timestamp() + self.offset_secs()
+    }
+
+    /// 请求时间源并更新偏移量，返回新的偏移 (秒)
+    pub async fn sync_once(&self) -> Result<i64, String> {
+        let client = crate::utils::http::create_client(REQUEST_TIMEOUT_SECS);
+        let started_ms = chrono::Utc::now().timestamp_millis();
+        let started = Instant::now();
+        let response = client
+            .head(TIME_SOURCE_URL)
+            .send()
+            .await
+            .map_err(|e| format!("请求时间源失败: {}", e))?;
+        // 以请求往返的中点作为服务端生成 Date 头的本地时间
+        let midpoint_ms = started_ms + started.elapsed().as_millis() as i64 / 2;
+
+        let date = response
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|v| v.to_str().ok())
+            .ok_or("时间源响应缺少 Date 头")?;
+        let offset = compute_offset(date, midpoint_ms)?;
+
+        let previous = self.clock_offset.swap(offset, Ordering::Relaxed);
+        if previous != offset {
+            tracing::info!("[TimeSync] Clock offset updated: {}s (was {}s)", offset, previous);
+        }
+        Ok(offset)
+    }
+
+    /// 启动时同步一次，之后每 30 分钟重新校准 (重复调用无效)
+    pub fn start_background_sync(&'static self) {
+        self.started.call_once(|| {
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(RESYNC_INTERVAL_SECS));
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = self.sync_once().await {
+                        tracing::warn!("[TimeSync] {}", e);
+                    }
+                }
+            });
+        });
+    }
+}
+
+/// 根据 HTTP Date 头 (RFC 7231 IMF-fixdate) 计算偏移量 (秒)
+fn compute_offset(date_header: &str, local_ms: i64) -> Result<i64, String> {
+    let server = chrono::DateTime::parse_from_rfc2822(date_header)
+        .map_err(|e| format!("无法解析 Date 头 '{}': {}", date_header, e))?;
+    // Date 头精度为秒，四舍五入到整秒
+    let diff_ms = server.timestamp_millis() - local_ms;
+    Ok((diff_ms as f64 / 1000.0).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_offset() {
+        // Thu, 08 Jan 2026 17:00:00 GMT = 1767891600
+        let header = "Thu, 08 Jan 2026 17:00:00 GMT";
+        assert_eq!(compute_offset(header, 1_767_891_600_000).unwrap(), 0);
+        // 本机时钟快 90 秒
+        assert_eq!(compute_offset(header, 1_767_891_690_000).unwrap(), -90);
+        // 本机时钟慢 2 分钟，加上半个往返的误差
+        assert_eq!(compute_offset(header, 1_767_891_479_700).unwrap(), 120);
+        assert!(compute_offset("not a date", 0).is_err());
+    }
+}
@@ -1,13 +1,42 @@
 // 移除冗余的顶层导入，因为这些在代码中已由 full path 或局部导入处理
 use dashmap::DashMap;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use crate::proxy::rate_limit::RateLimitTracker;
 use crate::proxy::sticky_config::StickySessionConfig;
 
+/// 后台预刷新窗口：token 距过期不足该秒数时提前刷新
+const PREREFRESH_WINDOW_SECS: i64 = 600;
+
+/// 慢账号判定所需的最少延迟样本数，避免个别慢请求导致误判
+const SLOW_ACCOUNT_MIN_SAMPLES: usize = 10;
+
+/// 响应时间加权模式: 参与评分所需的最少延迟样本数 (样本不足的账号使用已采样账号的中位数)
+const RESPONSE_TIME_MIN_SAMPLES: usize = 5;
+
+/// 响应时间加权模式: 会话绑定账号延迟超过池中位数的倍数时重新绑定
+const RESPONSE_TIME_REBIND_FACTOR: u64 = 3;
+
+/// 账号目录监听去抖间隔：写入账号文件时通常会产生多次事件
+const ACCOUNT_WATCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// 账号目录变更 (accounts://changed 事件负载，均为账号 ID)
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct AccountsDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub updated: Vec<String>,
+}
+
+impl AccountsDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ProxyToken {
     pub account_id: String,
@@ -21,9 +50,283 @@ pub struct ProxyToken {
     pub subscription_tier: Option<String>, // "FREE" | "PRO" | "ULTRA"
     pub remaining_quota: Option<i32>, // [FIX #563] Remaining quota for priority sorting
     pub protected_models: HashSet<String>, // [NEW #621]
+    pub daily_request_limit: Option<u32>, // 每日请求上限 (None 表示不限制)
+    pub tags: Vec<String>, // 账号标签 (用于按标签路由)
+    pub max_concurrent: Option<u32>, // 最大并发请求数 (None 表示不限制)
+    pub model_quotas: HashMap<String, i32>, // 各模型剩余配额百分比 (来自账号文件 quota.models)
+}
+
+/// 账号并发计数守卫：选中账号时计数 +1，drop 时 -1
+/// 处理器需在上游请求期间 (流式响应需到流结束) 持有该守卫
+#[derive(Debug)]
+pub struct InFlightGuard {
+    counter: Arc<AtomicU32>,
+}
+
+impl InFlightGuard {
+    /// 以单次 CAS 检查并占用并发名额，已达上限时返回 None
+    fn try_acquire(counter: Arc<AtomicU32>, max_concurrent: Option<u32>) -> Option<Self> {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| match max_concurrent {
+                Some(max) if current >= max => None,
+                _ => Some(current + 1),
+            })
+            .ok()?;
+        Some(Self { counter })
+    }
+
+    /// 将守卫绑定到流式响应体上，直到流结束或客户端断开时释放
+    pub fn attach<S>(self, stream: S) -> impl futures::Stream<Item = S::Item>
+    where
+        S: futures::Stream,
+    {
+        use futures::StreamExt;
+        stream.map(move |item| {
+            let _hold = &self;
+            item
+        })
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 每日请求数预占：选中账号时以单次 CAS 检查上限并计数
+/// 未 commit 即 drop (选中后的刷新 token 等步骤失败换号) 时归还名额
+struct DailyRequestReservation {
+    counts: Arc<DashMap<String, AtomicU32>>,
+    account_id: String,
+    committed: bool,
+}
+
+impl DailyRequestReservation {
+    /// 已达每日上限时返回 None
+    fn try_reserve(counts: &Arc<DashMap<String, AtomicU32>>, account_id: &str, limit: Option<u32>) -> Option<Self> {
+        counts
+            .entry(account_id.to_string())
+            .or_insert_with(|| AtomicU32::new(0))
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| match limit {
+                Some(max) if current >= max => None,
+                _ => Some(current + 1),
+            })
+            .ok()?;
+        Some(Self {
+            counts: counts.clone(),
+            account_id: account_id.to_string(),
+            committed: false,
+        })
+    }
+
+    fn commit(mut self) {
+        self.committed = true;
+    }
+}
+
+impl Drop for DailyRequestReservation {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        // 期间计数已被每日重置清空时无需归还
+        if let Some(count) = self.counts.get(&self.account_id) {
+            let _ = count.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| current.checked_sub(1));
+        }
+    }
 }
 
 
+/// 账号健康探测结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProbeResult {
+    pub account_id: String,
+    pub email: String,
+    /// 最近一次探测时间 (Unix 秒)
+    pub last_checked: i64,
+    pub status: ProbeStatus,
+    pub message: Option<String>,
+}
+
+/// 账号健康探测状态
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeStatus {
+    Healthy,
+    /// 探测失败但不足以判定账号失效（网络错误、5xx 等）
+    Degraded,
+    /// 401 / invalid_grant，账号已被禁用
+    Disabled,
+}
+
+/// 账号限流与熔断状态（邮箱已脱敏）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AccountRateLimitStatus {
+    pub account_id: String,
+    pub email: String,
+    pub rate_limit_remaining_secs: u64,
+    pub reason: Option<String>,
+    pub breaker: crate::proxy::rate_limit::BreakerSnapshot,
+}
+
+/// 限流状态变更事件 (推送给 /api/rate-limits/stream 与 Tauri Channel 订阅者)
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RateLimitEvent {
+    /// 订阅时的全量快照
+    Snapshot { statuses: Vec<AccountRateLimitStatus> },
+    /// 单个账号被限流或状态变化
+    Updated { status: AccountRateLimitStatus },
+    /// 限流记录被清除；account_id 为 None 表示全部清除
+    Cleared { account_id: Option<String> },
+}
+
+/// 限流事件广播容量，订阅者落后过多时丢弃旧事件
+const RATE_LIMIT_EVENT_CAPACITY: usize = 64;
+
+/// 账号 token 消耗事件 (由会话用量统计任务发出，供配额共享组等订阅)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenUsageEvent {
+    pub account_id: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// token 消耗事件广播容量
+const TOKEN_USAGE_EVENT_CAPACITY: usize = 256;
+
+pub const EVENT_ACCOUNT_DISABLED: &str = "accounts://disabled";
+pub const EVENT_ACCOUNT_RATE_LIMITED: &str = "accounts://rate_limited";
+pub const EVENT_ACCOUNT_RATE_LIMIT_CLEARED: &str = "accounts://rate_limit_cleared";
+pub const EVENT_ACCOUNT_QUOTA_PROTECTED: &str = "accounts://quota_protected";
+
+/// 账号状态变更事件负载 (推送给前端，邮箱已脱敏)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AccountStateEvent {
+    pub account_id: String,
+    pub email_masked: String,
+    pub timestamp: i64,
+}
+
+/// 账号延迟分位数（邮箱已脱敏）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AccountLatencyStats {
+    pub account_id: String,
+    pub email: String,
+    #[serde(flatten)]
+    pub latency: crate::proxy::rate_limit::LatencyStats,
+}
+
+/// 粘性会话元数据
+pub struct SessionMeta {
+    pub account_id: String,
+    pub bound_at: std::time::Instant,
+    pub client_ip: Option<std::net::IpAddr>,
+    pub user_agent: Option<String>,
+    /// 绑定后复用该账号的请求次数 (含首次)
+    pub request_count: AtomicU32,
+}
+
+impl SessionMeta {
+    fn new(account_id: String) -> Self {
+        let client = crate::proxy::middleware::client_info::current();
+        Self {
+            account_id,
+            bound_at: std::time::Instant::now(),
+            client_ip: client.client_ip,
+            user_agent: client.user_agent,
+            request_count: AtomicU32::new(1),
+        }
+    }
+}
+
+/// 活跃会话快照（客户端 IP 已脱敏，用于调试粘性会话）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SessionSnapshot {
+    pub session_id: String,
+    pub account_id: String,
+    pub bound_secs: u64,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_count: u32,
+}
+
+/// 会话快照（按绑定时间由新到旧），也用于导出反代停止时持久化的会话
+pub(crate) fn snapshot_sessions(sessions: &DashMap<String, SessionMeta>) -> Vec<SessionSnapshot> {
+    let mut snapshots: Vec<SessionSnapshot> = sessions
+        .iter()
+        .map(|e| {
+            let meta = e.value();
+            SessionSnapshot {
+                session_id: e.key().clone(),
+                account_id: meta.account_id.clone(),
+                bound_secs: meta.bound_at.elapsed().as_secs(),
+                client_ip: meta.client_ip.map(crate::utils::privacy::mask_ip),
+                user_agent: meta.user_agent.clone(),
+                request_count: meta.request_count.load(Ordering::Relaxed),
+            }
+        })
+        .collect();
+    snapshots.sort_by_key(|s| s.bound_secs);
+    snapshots
+}
+
+/// 会话累计 token 用量 (key 为会话指纹或 X-Conversation-ID)
+pub struct SessionTokenUsage {
+    pub session_id: String,
+    pub input_tokens: AtomicU64,
+    pub output_tokens: AtomicU64,
+    pub request_count: AtomicU32,
+    pub started_at: std::time::Instant,
+}
+
+impl SessionTokenUsage {
+    fn new(session_id: String) -> Self {
+        Self {
+            session_id,
+            input_tokens: AtomicU64::new(0),
+            output_tokens: AtomicU64::new(0),
+            request_count: AtomicU32::new(0),
+            started_at: std::time::Instant::now(),
+        }
+    }
+
+    fn snapshot(&self) -> SessionTokenUsageSnapshot {
+        SessionTokenUsageSnapshot {
+            session_id: self.session_id.clone(),
+            input_tokens: self.input_tokens.load(Ordering::Relaxed),
+            output_tokens: self.output_tokens.load(Ordering::Relaxed),
+            request_count: self.request_count.load(Ordering::Relaxed),
+            age_secs: self.started_at.elapsed().as_secs(),
+        }
+    }
+}
+
+/// 会话 token 用量快照
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SessionTokenUsageSnapshot {
+    pub session_id: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub request_count: u32,
+    pub age_secs: u64,
+}
+
+/// 账号池状态快照（脱敏，用于调试路由决策）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AccountSnapshot {
+    pub account_id: String,
+    pub email: String,
+    pub subscription_tier: Option<String>,
+    pub remaining_quota: Option<i32>,
+    pub protected_models: Vec<String>,
+    /// 限流剩余秒数，0 表示未限流
+    pub rate_limit_remaining_secs: u64,
+    /// 是否处于 60s 粘性锁定窗口
+    pub sticky_window_active: bool,
+}
+
 pub struct TokenManager {
     tokens: Arc<DashMap<String, ProxyToken>>,  // account_id -> ProxyToken
     current_index: Arc<AtomicUsize>,
@@ -31,23 +334,134 @@ pub struct TokenManager {
     data_dir: PathBuf,
     rate_limit_tracker: Arc<RateLimitTracker>,  // 新增: 限流跟踪器
     sticky_config: Arc<tokio::sync::RwLock<StickySessionConfig>>, // 新增：调度配置
-    session_accounts: Arc<DashMap<String, String>>, // 新增：会话与账号映射 (SessionID -> AccountID)
+    session_accounts: Arc<DashMap<String, SessionMeta>>, // 会话与账号映射 (SessionID -> SessionMeta)
+    health_probe_results: Arc<DashMap<String, ProbeResult>>, // 账号健康探测结果 (AccountID -> ProbeResult)
+    daily_request_counts: Arc<DashMap<String, AtomicU32>>, // 每日请求计数 (AccountID -> Count)，UTC 零点重置
+    connection_pool: Arc<crate::proxy::connection_pool::ConnectionPoolManager>, // 按账号隔离的上游连接池
+    metrics: Arc<crate::proxy::metrics::ProxyMetrics>, // Prometheus 运行指标
+    in_flight: Arc<DashMap<String, Arc<AtomicU32>>>, // 各账号进行中的请求数 (AccountID -> Count)
+    last_bind_time: Arc<DashMap<String, AtomicU64>>, // 各账号最近一次被会话绑定的时间 (AccountID -> Unix ms)
+    session_token_usage: Arc<DashMap<String, SessionTokenUsage>>, // 各会话累计 token 用量 (SessionID -> Usage)
+    anomaly_detector: Arc<crate::proxy::anomaly::AnomalyDetector>, // 账号用量异常检测
+    error_budget: Arc<crate::proxy::error_budget::ErrorBudgetTracker>, // 账号错误预算
+    rate_limit_events: tokio::sync::broadcast::Sender<RateLimitEvent>, // 限流状态变更广播
+    token_usage_events: tokio::sync::broadcast::Sender<TokenUsageEvent>, // 账号 token 消耗广播
+    quota_protection_enabled: AtomicBool, // 配额保护开关 (随配置热更新)
+    app_handle: Arc<std::sync::RwLock<Option<Arc<tauri::AppHandle>>>>, // 推送账号状态事件 (启动反代服务时设置)
 }
 
 impl TokenManager {
     /// 创建新的 TokenManager
     pub fn new(data_dir: PathBuf) -> Self {
+        // 恢复上次停止时仍生效的限流记录，避免重启后立即重新试探已耗尽配额的账号
+        let rate_limit_path = data_dir.join(crate::proxy::rate_limit::RATE_LIMITS_FILE);
+        let rate_limit_tracker = if rate_limit_path.exists() {
+            crate::proxy::rate_limit::load_rate_limits(&rate_limit_path).unwrap_or_else(|e| {
+                tracing::warn!("恢复限流记录失败: {}", e);
+                RateLimitTracker::new()
+            })
+        } else {
+            RateLimitTracker::new()
+        };
+
         Self {
             tokens: Arc::new(DashMap::new()),
             current_index: Arc::new(AtomicUsize::new(0)),
             last_used_account: Arc::new(tokio::sync::Mutex::new(None)),
             data_dir,
-            rate_limit_tracker: Arc::new(RateLimitTracker::new()),
+            rate_limit_tracker: Arc::new(rate_limit_tracker),
             sticky_config: Arc::new(tokio::sync::RwLock::new(StickySessionConfig::default())),
             session_accounts: Arc::new(DashMap::new()),
+            health_probe_results: Arc::new(DashMap::new()),
+            daily_request_counts: Arc::new(DashMap::new()),
+            connection_pool: Arc::new(crate::proxy::connection_pool::ConnectionPoolManager::new()),
+            metrics: Arc::new(crate::proxy::metrics::ProxyMetrics::new()),
+            in_flight: Arc::new(DashMap::new()),
+            last_bind_time: Arc::new(DashMap::new()),
+            session_token_usage: Arc::new(DashMap::new()),
+            anomaly_detector: Arc::new(crate::proxy::anomaly::AnomalyDetector::new()),
+            error_budget: Arc::new(crate::proxy::error_budget::ErrorBudgetTracker::new()),
+            rate_limit_events: tokio::sync::broadcast::channel(RATE_LIMIT_EVENT_CAPACITY).0,
+            token_usage_events: tokio::sync::broadcast::channel(TOKEN_USAGE_EVENT_CAPACITY).0,
+            quota_protection_enabled: AtomicBool::new(false),
+            app_handle: Arc::new(std::sync::RwLock::new(None)),
+        }
+    }
+
+    /// 更新配额保护开关 (启动服务与配置热更新时调用，避免选号时读取磁盘配置)
+    pub fn set_quota_protection_enabled(&self, enabled: bool) {
+        self.quota_protection_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 设置用于推送账号状态事件的 AppHandle
+    pub fn set_app_handle(&self, app_handle: tauri::AppHandle) {
+        if let Ok(mut handle) = self.app_handle.write() {
+            *handle = Some(Arc::new(app_handle));
         }
     }
 
+    /// 向前端推送账号状态事件 (未设置 AppHandle 时忽略)
+    fn emit_account_event(&self, event: &str, account_id: &str, email: Option<&str>) {
+        use tauri::Emitter;
+
+        let Some(app) = self.app_handle.read().ok().and_then(|h| h.clone()) else {
+            return;
+        };
+        let email = email
+            .map(|e| e.to_string())
+            .or_else(|| self.email_for_account_id(account_id))
+            .unwrap_or_default();
+        let payload = AccountStateEvent {
+            account_id: account_id.to_string(),
+            email_masked: crate::utils::privacy::mask_email(&email),
+            timestamp: chrono::Utc::now().timestamp(),
+        };
+        let _ = app.emit(event, payload);
+    }
+
+    /// 按账号隔离的上游连接池
+    pub fn connection_pool(&self) -> Arc<crate::proxy::connection_pool::ConnectionPoolManager> {
+        self.connection_pool.clone()
+    }
+
+    /// 限流状态变更广播 (订阅者应先读取 rate_limit_statuses 快照)
+    pub fn rate_limit_events(&self) -> tokio::sync::broadcast::Sender<RateLimitEvent> {
+        self.rate_limit_events.clone()
+    }
+
+    /// 账号用量异常检测 (每次成功分配账号时计数)
+    pub fn anomaly_detector(&self) -> Arc<crate::proxy::anomaly::AnomalyDetector> {
+        self.anomaly_detector.clone()
+    }
+
+    /// 运行指标 (token 刷新计数在此记录，请求计数由指标中间件记录)
+    pub fn metrics(&self) -> Arc<crate::proxy::metrics::ProxyMetrics> {
+        self.metrics.clone()
+    }
+
+    /// 当前处于限流或熔断状态的账号数
+    pub fn rate_limited_count(&self) -> usize {
+        self.tokens
+            .iter()
+            .filter(|e| self.rate_limit_tracker.is_rate_limited(&e.value().account_id))
+            .count()
+    }
+
+    /// 通过 account_id 查找 email
+    pub fn email_for_account_id(&self, account_id: &str) -> Option<String> {
+        self.tokens.get(account_id).map(|t| t.email.clone())
+    }
+
+    /// 将账号锁定到指定的配额刷新时间 (ISO 8601)
+    pub fn lock_account_until_iso(&self, account_id: &str, reset_time: &str) -> bool {
+        self.rate_limit_tracker.set_lockout_until_iso(
+            account_id,
+            reset_time,
+            crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+            None,
+        )
+    }
+
     /// 启动限流记录自动清理后台任务（每60秒检查并清除过期记录）
     pub fn start_auto_cleanup(&self) {
         let tracker = self.rate_limit_tracker.clone();
@@ -59,11 +473,255 @@ impl TokenManager {
                 if cleaned > 0 {
                     tracing::info!("🧹 Auto-cleanup: Removed {} expired rate limit record(s)", cleaned);
                 }
+                // API Key 限流窗口
+                crate::proxy::middleware::key_rate_limit::KeyRateLimiter::global().reset_expired();
             }
         });
         tracing::info!("✅ Rate limit auto-cleanup task started (interval: 60s)");
     }
+
+    /// 启动会话回收后台任务（每 session_ttl_secs / 4 秒清除超过 TTL 的会话绑定，TTL 支持热更新）
+    /// 返回的句柄需在停止反代服务时 abort
+    pub fn start_session_gc_task(&self) -> tokio::task::JoinHandle<()> {
+        let sessions = self.session_accounts.clone();
+        let usage = self.session_token_usage.clone();
+        let sticky_config = self.sticky_config.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                let ttl = sticky_config.read().await.session_ttl_secs;
+                // TTL 为 0 时不回收，仍定期检查配置是否变更
+                let period = if ttl == 0 { 60 } else { (ttl / 4).max(1) };
+                tokio::time::sleep(std::time::Duration::from_secs(period)).await;
+
+                let ttl = sticky_config.read().await.session_ttl_secs;
+                let evicted = evict_expired_sessions(&sessions, ttl);
+                if evicted > 0 {
+                    tracing::info!("🧹 Session GC: Evicted {} expired session binding(s)", evicted);
+                }
+                // 会话绑定已回收且超过 TTL 的用量统计一并清理
+                if ttl > 0 {
+                    usage.retain(|sid, u| sessions.contains_key(sid) || u.started_at.elapsed().as_secs() <= ttl);
+                }
+            }
+        });
+        tracing::info!("✅ Session GC task started");
+        handle
+    }
+
+    /// 启动 token 用量统计任务 (订阅监控日志广播，仅在监控日志开启时有数据)
+    /// 广播为全局单例不会关闭，任务持有 TokenManager，返回的句柄需在停止反代服务时 abort
+    pub fn start_session_usage_task(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let manager = self.clone();
+        let mut rx = crate::proxy::monitor::LogBroadcaster::global().subscribe();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(log) => {
+                        if !(200..400).contains(&log.status) {
+                            continue;
+                        }
+                        manager.record_token_usage(
+                            log.account_email.as_deref(),
+                            log.conversation_id,
+                            log.input_tokens.unwrap_or(0) as u64,
+                            log.output_tokens.unwrap_or(0) as u64,
+                        );
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Session usage: Dropped {} request logs", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// 记录一次成功请求的 token 消耗：累加会话用量，并向订阅者广播账号消耗
+    pub fn record_token_usage(
+        &self,
+        email: Option<&str>,
+        session_id: Option<String>,
+        input_tokens: u64,
+        output_tokens: u64,
+    ) {
+        if let Some(session_id) = session_id {
+            record_session_tokens(&self.session_token_usage, session_id, input_tokens, output_tokens);
+        }
+        if input_tokens + output_tokens == 0 || self.token_usage_events.receiver_count() == 0 {
+            return;
+        }
+        if let Some(account_id) = email.and_then(|e| self.email_to_account_id(e)) {
+            let _ = self.token_usage_events.send(TokenUsageEvent { account_id, input_tokens, output_tokens });
+        }
+    }
+
+    /// 订阅账号 token 消耗事件
+    pub fn subscribe_token_usage(&self) -> tokio::sync::broadcast::Receiver<TokenUsageEvent> {
+        self.token_usage_events.subscribe()
+    }
+
+    /// 获取单个会话的累计 token 用量
+    pub fn session_token_usage(&self, session_id: &str) -> Option<SessionTokenUsageSnapshot> {
+        self.session_token_usage.get(session_id).map(|u| u.snapshot())
+    }
+
+    /// 获取所有会话的累计 token 用量 (按总 token 数由多到少)
+    pub fn list_session_token_usage(&self) -> Vec<SessionTokenUsageSnapshot> {
+        let mut usage: Vec<SessionTokenUsageSnapshot> =
+            self.session_token_usage.iter().map(|u| u.snapshot()).collect();
+        usage.sort_by_key(|u| std::cmp::Reverse(u.input_tokens + u.output_tokens));
+        usage
+    }
+
+    /// 当前会话绑定数量
+    pub fn session_count(&self) -> usize {
+        self.session_accounts.len()
+    }
+
+    /// 启动 Token 预刷新后台任务（每60秒扫描，提前10分钟刷新即将过期的 token）
+    /// 刷新期间请求仍使用旧 token（仍在有效期内），避免热路径阻塞等待刷新
+    /// 返回的句柄需在停止反代服务时 abort
+    pub fn start_token_prerefresh_task(&self) -> tokio::task::JoinHandle<()> {
+        let tokens = self.tokens.clone();
+        let tracker = self.rate_limit_tracker.clone();
+        let metrics = self.metrics.clone();
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                let now = chrono::Utc::now().timestamp();
+
+                // 先收集快照，避免在 await 期间持有 DashMap 锁
+                let expiring: Vec<(String, String, String, PathBuf)> = tokens
+                    .iter()
+                    .filter(|e| now >= e.value().timestamp - PREREFRESH_WINDOW_SECS)
+                    .filter(|e| !tracker.is_rate_limited(&e.value().account_id))
+                    .map(|e| {
+                        let t = e.value();
+                        (t.account_id.clone(), t.email.clone(), t.refresh_token.clone(), t.account_path.clone())
+                    })
+                    .collect();
+
+                for (account_id, email, refresh_token, account_path) in expiring {
+                    match crate::modules::oauth::refresh_access_token(&refresh_token).await {
+                        Ok(token_response) => {
+                            metrics.record_token_refresh(true);
+                            record_refresh_event(&email, None);
+                            let refreshed_at = chrono::Utc::now().timestamp();
+                            if let Some(mut entry) = tokens.get_mut(&account_id) {
+                                entry.access_token = token_response.access_token.clone();
+                                entry.expires_in = token_response.expires_in;
+                                entry.timestamp = refreshed_at + token_response.expires_in;
+                            }
+                            if let Err(e) = write_refreshed_token(&account_path, &token_response) {
+                                tracing::debug!("保存预刷新后的 token 失败 ({}): {}", email, e);
+                            }
+                            tracing::debug!("[Pre-refresh] Token refreshed for {}", email);
+                        }
+                        Err(e) => {
+                            metrics.record_token_refresh(false);
+                            record_refresh_event(&email, Some(&e));
+                            // 失败时交给热路径处理（包括 invalid_grant 禁用逻辑）
+                            tracing::warn!("[Pre-refresh] Token refresh failed for {}: {}", email, e);
+                        }
+                    }
+                }
+            }
+        });
+        tracing::info!("✅ Token pre-refresh task started (interval: 60s, window: {}s)", PREREFRESH_WINDOW_SECS);
+        handle
+    }
     
+    /// 启动账号健康探测后台任务
+    /// 定期对每个已加载账号调用 loadCodeAssist，提前发现失效账号（401 / invalid_grant）
+    /// 任务持有 TokenManager，返回的句柄需在停止反代服务时 abort
+    pub fn start_health_probe_task(self: &Arc<Self>, interval_secs: u64) -> Option<tokio::task::JoinHandle<()>> {
+        if interval_secs == 0 {
+            tracing::info!("Account health probe is disabled (interval: 0)");
+            return None;
+        }
+        let manager = self.clone();
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                manager.run_health_probe().await;
+            }
+        });
+        tracing::info!("✅ Account health probe task started (interval: {}s)", interval_secs);
+        Some(handle)
+    }
+
+    /// 执行一轮健康探测
+    async fn run_health_probe(&self) {
+        let now = chrono::Utc::now().timestamp();
+        let candidates: Vec<(String, String, String)> = self
+            .tokens
+            .iter()
+            // 已过期的 access_token 必然 401，交给刷新逻辑处理，避免误判
+            .filter(|e| now < e.value().timestamp)
+            .map(|e| {
+                let t = e.value();
+                (t.account_id.clone(), t.email.clone(), t.access_token.clone())
+            })
+            .collect();
+
+        for (account_id, email, access_token) in candidates {
+            let (status, message) = match crate::proxy::project_resolver::load_code_assist(&access_token).await {
+                Ok(_) => (ProbeStatus::Healthy, None),
+                Err(e) if e.is_auth_failure() => {
+                    tracing::error!("[Health Probe] Disabling account {}: {}", email, e);
+                    let _ = self
+                        .disable_account(&account_id, &format!("health_probe: {}", e))
+                        .await;
+                    (ProbeStatus::Disabled, Some(e.to_string()))
+                }
+                Err(e) => {
+                    tracing::warn!("[Health Probe] Account {} probe failed: {}", email, e);
+                    (ProbeStatus::Degraded, Some(e.to_string()))
+                }
+            };
+
+            self.health_probe_results.insert(
+                account_id.clone(),
+                ProbeResult {
+                    account_id,
+                    email,
+                    last_checked: chrono::Utc::now().timestamp(),
+                    status,
+                    message: message.map(|m| truncate_reason(&m, 300)),
+                },
+            );
+        }
+    }
+
+    /// 获取所有账号的最近一次健康探测结果
+    pub fn get_health_probe_results(&self) -> Vec<ProbeResult> {
+        self.health_probe_results.iter().map(|e| e.value().clone()).collect()
+    }
+
+    /// 启动每日请求计数重置任务（每天 UTC 零点清零）
+    /// 返回的句柄需在停止反代服务时 abort
+    pub fn start_daily_usage_reset_task(&self) -> tokio::task::JoinHandle<()> {
+        let counts = self.daily_request_counts.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                let now = chrono::Utc::now();
+                let next_midnight = (now + chrono::Duration::days(1))
+                    .date_naive()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap()
+                    .and_utc();
+                let wait = (next_midnight - now).to_std().unwrap_or(std::time::Duration::from_secs(60));
+                tokio::time::sleep(wait).await;
+                counts.clear();
+                tracing::info!("🔄 Daily request counters reset (UTC midnight)");
+            }
+        });
+        tracing::info!("✅ Daily request counter reset task started");
+        handle
+    }
+
     /// 从主应用账号目录加载所有账号
     pub async fn load_accounts(&self) -> Result<usize, String> {
         let accounts_dir = self.data_dir.join("accounts");
@@ -108,6 +766,13 @@ impl TokenManager {
                 }
             }
         }
+
+        // 回收已禁用/已删除账号的连接池
+        let active_ids: HashSet<String> = self.tokens.iter().map(|e| e.key().clone()).collect();
+        let evicted = self.connection_pool.retain_accounts(&active_ids);
+        if evicted > 0 {
+            tracing::debug!("Evicted {} HTTP client(s) for inactive accounts", evicted);
+        }
         
         Ok(count)
     }
@@ -129,15 +794,122 @@ impl TokenManager {
         }
     }
 
-    /// 重新加载所有账号
+    /// 重新加载所有账号 (同时重置用量异常检测基线)
     pub async fn reload_all_accounts(&self) -> Result<usize, String> {
+        self.anomaly_detector.reset();
         self.load_accounts().await
     }
+
+    /// 监听 accounts/ 目录，新增/删除/修改账号文件后无需手动重载即可同步账号池
+    /// 返回的句柄在停止反代服务时 abort (监听器随任务一起释放)
+    pub fn start_account_watcher(
+        self: &Arc<Self>,
+        app_handle: Option<tauri::AppHandle>,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        use notify::{RecursiveMode, Watcher};
+        use tauri::Emitter;
+
+        let accounts_dir = self.data_dir.join("accounts");
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<PathBuf>();
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove() {
+                    for path in event.paths {
+                        // 忽略保存时产生的临时文件
+                        if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                            let _ = tx.send(path);
+                        }
+                    }
+                }
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(w) => w,
+            Err(e) => {
+                tracing::warn!("[AccountWatcher] 创建文件监听失败: {}", e);
+                return None;
+            }
+        };
+        if let Err(e) = watcher.watch(&accounts_dir, RecursiveMode::NonRecursive) {
+            tracing::warn!("[AccountWatcher] 监听 {:?} 失败: {}", accounts_dir, e);
+            return None;
+        }
+
+        let manager = self.clone();
+        let handle = tokio::spawn(async move {
+            let _watcher = watcher;
+            while let Some(path) = rx.recv().await {
+                tokio::time::sleep(ACCOUNT_WATCH_DEBOUNCE).await;
+                let mut paths = HashSet::from([path]);
+                while let Ok(path) = rx.try_recv() {
+                    paths.insert(path);
+                }
+
+                let mut diff = AccountsDiff::default();
+                for path in &paths {
+                    manager.sync_account_file(path, &mut diff).await;
+                }
+                if diff.is_empty() {
+                    continue;
+                }
+                tracing::info!(
+                    "[AccountWatcher] Accounts changed: +{} -{} ~{}",
+                    diff.added.len(),
+                    diff.removed.len(),
+                    diff.updated.len()
+                );
+                if let Some(app) = &app_handle {
+                    let _ = app.emit("accounts://changed", &diff);
+                }
+            }
+        });
+        tracing::info!("✅ Account directory watcher started");
+        Some(handle)
+    }
+
+    /// 按账号文件的当前状态同步账号池 (以去抖后文件是否存在为准，而非单个事件类型)
+    async fn sync_account_file(&self, path: &PathBuf, diff: &mut AccountsDiff) {
+        let Some(account_id) = path.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string()) else {
+            return;
+        };
+
+        // 删除：清理账号池、限流记录与会话绑定
+        if !path.exists() {
+            if self.tokens.contains_key(&account_id) {
+                self.purge_account(&account_id);
+                diff.removed.push(account_id);
+            }
+            return;
+        }
+
+        // 修改：已禁用或文件无效时移出账号池
+        if self.tokens.contains_key(&account_id) {
+            match self.reload_account(&account_id).await {
+                Ok(()) => diff.updated.push(account_id),
+                Err(e) => {
+                    tracing::debug!("[AccountWatcher] Removing account {} from pool: {}", account_id, e);
+                    self.purge_account(&account_id);
+                    diff.removed.push(account_id);
+                }
+            }
+            return;
+        }
+
+        // 新增 (写入未完成导致的解析失败会在后续修改事件中重试)
+        match self.load_single_account(path).await {
+            Ok(Some(token)) => {
+                let account_id = token.account_id.clone();
+                self.tokens.insert(account_id.clone(), token);
+                diff.added.push(account_id);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::debug!("[AccountWatcher] 加载账号失败 {:?}: {}", path, e),
+        }
+    }
     
     /// 加载单个账号
-    async fn load_single_account(&self, path: &PathBuf) -> Result<Option<ProxyToken>, String> {
-        let content = std::fs::read_to_string(path)
-            .map_err(|e| format!("读取文件失败: {}", e))?;
+    pub(crate) async fn load_single_account(&self, path: &PathBuf) -> Result<Option<ProxyToken>, String> {
+        let content = crate::modules::account_crypto::read_account_file(path)?;
         
         let mut account: serde_json::Value = serde_json::from_str(&content)
             .map_err(|e| format!("解析 JSON 失败: {}", e))?;
@@ -183,6 +955,29 @@ impl TokenManager {
         let account_id = account["id"].as_str()
             .ok_or("缺少 id 字段")?
             .to_string();
+
+        // 设备指纹即将过期时自动重新生成 (proxy_disabled 账号已在上方跳过，避免无谓写入)
+        let device_expires_at = account
+            .get("device_profile")
+            .and_then(|p| p.get("expires_at"))
+            .and_then(|v| v.as_i64());
+        if crate::modules::account::device_profile_expiring(device_expires_at, chrono::Utc::now().timestamp())
+            && crate::modules::config::load_app_config()
+                .map(|c| c.device_profile_refresh_enabled)
+                .unwrap_or(false)
+        {
+            match crate::modules::account::refresh_expiring_device_profile(&account_id) {
+                Ok(Some(profile)) => {
+                    // 同步到内存中的账号数据，避免后续按旧数据写回时覆盖新指纹
+                    if let Ok(value) = serde_json::to_value(&profile) {
+                        account["device_profile"] = value;
+                    }
+                    tracing::info!("账号 {} 的设备指纹即将过期，已自动重新生成", account_id)
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("账号 {} 自动刷新设备指纹失败: {}", account_id, e),
+            }
+        }
         
         let email = account["email"].as_str()
             .ok_or("缺少 email 字段")?
@@ -221,6 +1016,11 @@ impl TokenManager {
         let remaining_quota = account.get("quota")
             .and_then(|q| self.calculate_quota_stats(q));
             // .filter(|&r| r > 0); // 移除 >0 过滤，因为 0% 也是有效数据，只是优先级低
+
+        // 记录配额快照 (用于趋势图)，按配额刷新时间去重
+        if let Some(quota) = account.get("quota") {
+            record_quota_snapshot(&account_id, quota);
+        }
         
         // 【新增 #621】提取受限模型列表
         let protected_models: HashSet<String> = account.get("protected_models")
@@ -233,6 +1033,42 @@ impl TokenManager {
             })
             .unwrap_or_default();
         
+        // 每日请求上限（可选）
+        let daily_request_limit = account.get("daily_request_limit")
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(u32::MAX as u64) as u32);
+        
+        let tags: Vec<String> = account.get("tags")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.to_lowercase())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // 最大并发请求数（可选）
+        let max_concurrent = account.get("max_concurrent")
+            .and_then(|v| v.as_u64())
+            .map(|v| v.min(u32::MAX as u64) as u32);
+
+        // 各模型剩余配额 (用于降级链可用性判断)
+        let model_quotas: HashMap<String, i32> = account.get("quota")
+            .and_then(|q| q.get("models"))
+            .and_then(|m| m.as_array())
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| {
+                        let name = m.get("name")?.as_str()?;
+                        let percentage = m.get("percentage")?.as_i64()?;
+                        Some((name.to_string(), percentage as i32))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Ok(Some(ProxyToken {
             account_id,
             access_token,
@@ -245,6 +1081,10 @@ impl TokenManager {
             subscription_tier,
             remaining_quota,
             protected_models,
+            daily_request_limit,
+            tags,
+            max_concurrent,
+            model_quotas,
         }))
     }
 
@@ -390,8 +1230,22 @@ impl TokenManager {
             );
             
             // 3. 写入磁盘
-            std::fs::write(account_path, serde_json::to_string_pretty(account_json).unwrap())
-                .map_err(|e| format!("写入文件失败: {}", e))?;
+            crate::modules::account_crypto::write_account_file(account_path, &serde_json::to_string_pretty(account_json).unwrap())?;
+
+            crate::modules::webhook::emit(
+                crate::modules::webhook::EVENT_ACCOUNT_QUOTA_LOW,
+                serde_json::json!({
+                    "account_id": account_id,
+                    "model": model_name,
+                    "percentage": current_val,
+                    "threshold": threshold,
+                }),
+            );
+            self.emit_account_event(
+                EVENT_ACCOUNT_QUOTA_PROTECTED,
+                account_id,
+                account_json.get("email").and_then(|v| v.as_str()),
+            );
             
             return Ok(true);
         }
@@ -435,7 +1289,7 @@ impl TokenManager {
         
         account_json["protected_models"] = serde_json::Value::Array(protected_list);
         
-        let _ = std::fs::write(account_path, serde_json::to_string_pretty(account_json).unwrap());
+        let _ = crate::modules::account_crypto::write_account_file(account_path, &serde_json::to_string_pretty(account_json).unwrap());
         
         false // 返回 false 表示现在已可以尝试加载该账号（模型级过滤会在 get_token 时发生）
     }
@@ -455,8 +1309,7 @@ impl TokenManager {
             
             if arr.len() < original_len {
                 tracing::info!("账号 {} 的模型 {} 配额已恢复，移出保护列表", account_id, model_name);
-                std::fs::write(account_path, serde_json::to_string_pretty(account_json).unwrap())
-                    .map_err(|e| format!("写入文件失败: {}", e))?;
+                crate::modules::account_crypto::write_account_file(account_path, &serde_json::to_string_pretty(account_json).unwrap())?;
                 return Ok(true);
             }
         }
@@ -476,7 +1329,7 @@ impl TokenManager {
         force_rotate: bool, 
         session_id: Option<&str>,
         target_model: &str,
-    ) -> Result<(String, String, String), String> {
+    ) -> Result<(String, String, String, InFlightGuard), String> {
         // 【优化 Issue #284】添加 5 秒超时，防止死锁
         let timeout_duration = std::time::Duration::from_secs(5);
         match tokio::time::timeout(timeout_duration, self.get_token_internal(quota_group, force_rotate, session_id, target_model)).await {
@@ -492,13 +1345,34 @@ impl TokenManager {
         force_rotate: bool, 
         session_id: Option<&str>,
         target_model: &str,
-    ) -> Result<(String, String, String), String> {
+    ) -> Result<(String, String, String, InFlightGuard), String> {
         let mut tokens_snapshot: Vec<ProxyToken> = self.tokens.iter().map(|e| e.value().clone()).collect();
-        let total = tokens_snapshot.len();
-        if total == 0 {
+        if tokens_snapshot.is_empty() {
             return Err("Token pool is empty".to_string());
         }
 
+        // 标签路由: 请求携带 X-Antigravity-Tag 时，仅在标签匹配的账号中调度
+        if let Some(tag) = crate::proxy::middleware::client_info::current().routing_tag {
+            let policy = self
+                .sticky_config
+                .read()
+                .await
+                .tag_routing
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(&tag))
+                .map(|(_, policy)| policy.clone())
+                .unwrap_or_default();
+            let mut desired: Vec<String> = policy.preferred_tags.iter().map(|t| t.to_lowercase()).collect();
+            if desired.is_empty() {
+                desired = tag.split(',').map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()).collect();
+            }
+            tokens_snapshot = filter_by_tags(tokens_snapshot, &desired, policy.fallback_to_untagged);
+            if tokens_snapshot.is_empty() {
+                return Err(format!("No accounts available for tag '{}'", tag));
+            }
+            tracing::debug!("Tag Routing: {} candidate account(s) for tag '{}'", tokens_snapshot.len(), tag);
+        }
+
         // ===== 【优化】根据订阅等级和剩余配额排序 =====
         // [FIX #563] 优先级: ULTRA > PRO > FREE, 同tier内优先高配额账号
         // 理由: ULTRA/PRO 重置快，优先消耗；FREE 重置慢，用于兜底
@@ -537,12 +1411,62 @@ impl TokenManager {
 
         // 0. 读取当前调度配置
         let scheduling = self.sticky_config.read().await.clone();
-        use crate::proxy::sticky_config::SchedulingMode;
+        use crate::proxy::sticky_config::{FreeAccountMode, SchedulingMode};
+
+        // FREE 账号禁用模式: 直接从候选池中移除
+        if scheduling.free_account_mode == FreeAccountMode::Disabled {
+            tokens_snapshot.retain(|t| !is_free_tier(t));
+            if tokens_snapshot.is_empty() {
+                return Err("No PRO/ULTRA accounts available (FREE accounts are disabled)".to_string());
+            }
+        }
+        let total = tokens_snapshot.len();
+
+        // 慢账号检测: P95 延迟超过阈值的账号不参与新分配 (全部为慢账号时不排除)
+        let mut slow_accounts: HashSet<String> = HashSet::new();
+        if scheduling.slow_account_threshold_ms > 0 {
+            for t in &tokens_snapshot {
+                if let Some(stats) = self.rate_limit_tracker.latency_stats(&t.account_id) {
+                    if stats.samples >= SLOW_ACCOUNT_MIN_SAMPLES && stats.p95_ms > scheduling.slow_account_threshold_ms {
+                        tracing::debug!(
+                            "Slow Account: {} P95 latency {}ms exceeds {}ms, skipping for new assignments",
+                            t.email, stats.p95_ms, scheduling.slow_account_threshold_ms
+                        );
+                        slow_accounts.insert(t.account_id.clone());
+                    }
+                }
+            }
+            if slow_accounts.len() == total {
+                slow_accounts.clear();
+            }
+        }
+
+        // 响应时间加权模式: 预先计算各账号延迟评分、池中位数与候选顺序
+        // 快照已按订阅等级排序，稳定排序保证同评分时按等级优先
+        let response_time_weighted = scheduling.mode == SchedulingMode::ResponseTimeWeighted;
+        let mut latency_scores: HashMap<String, u64> = HashMap::new();
+        let mut latency_order: Vec<usize> = Vec::new();
+        let mut latency_median = 0;
+        if response_time_weighted {
+            // 未采样账号取已采样账号的中位数，既不优先也不惩罚新账号
+            let sampled: HashMap<&str, u64> = tokens_snapshot
+                .iter()
+                .filter_map(|t| self.latency_score(&t.account_id).map(|ms| (t.account_id.as_str(), ms)))
+                .collect();
+            let mut sampled_values: Vec<u64> = sampled.values().copied().collect();
+            sampled_values.sort_unstable();
+            let neutral = sampled_values.get(sampled_values.len() / 2).copied().unwrap_or(0);
+            for t in &tokens_snapshot {
+                let score = sampled.get(t.account_id.as_str()).copied().unwrap_or(neutral);
+                latency_scores.insert(t.account_id.clone(), score);
+            }
+            latency_order = (0..total).collect();
+            latency_order.sort_by_key(|&i| latency_scores[&tokens_snapshot[i].account_id]);
+            latency_median = latency_scores[&tokens_snapshot[latency_order[total / 2]].account_id];
+        }
         
         // 【新增】检查配额保护是否启用（如果关闭，则忽略 protected_models 检查）
-        let quota_protection_enabled = crate::modules::config::load_app_config()
-            .map(|cfg| cfg.quota_protection.enabled)
-            .unwrap_or(false);
+        let quota_protection_enabled = self.quota_protection_enabled.load(Ordering::Relaxed);
 
         // 【优化 Issue #284】将锁操作移到循环外，避免重复获取锁
         // 预先获取 last_used_account 的快照，避免在循环中多次加锁
@@ -556,26 +1480,59 @@ impl TokenManager {
         let mut attempted: HashSet<String> = HashSet::new();
         let mut last_error: Option<String> = None;
         let mut need_update_last_used: Option<(String, std::time::Instant)> = None;
+        // 因超过最大会话时长而被解绑的账号，本次选择时尽量避开以实现轮换
+        let mut expired_session_account: Option<String> = None;
+        // 会话绑定的账号因限流被解绑，重新绑定时需错峰
+        let mut rebinding_after_limit = false;
 
         for attempt in 0..total {
             let rotate = force_rotate || attempt > 0;
 
             // ===== 【核心】粘性会话与智能调度逻辑 =====
             let mut target_token: Option<ProxyToken> = None;
+            // 绑定账号仅因并发已满被跳过时，兜底账号只服务本次请求，不改写会话绑定
+            let mut keep_session_binding = false;
             
             // 归一化目标模型名为标准 ID，用于配额保护检查
             let normalized_target = crate::proxy::common::model_mapping::normalize_to_standard_id(target_model)
                 .unwrap_or_else(|| target_model.to_string());
+
+            // FREE 仅兜底模式: 仍有可用的 PRO/ULTRA 账号时跳过 FREE 账号
+            let reserve_free = scheduling.free_account_mode == FreeAccountMode::FallbackOnly
+                && tokens_snapshot.iter().any(|t| {
+                    !is_free_tier(t)
+                        && !attempted.contains(&t.account_id)
+                        && !self.is_rate_limited_by_account_id(&t.account_id)
+                        && !(quota_protection_enabled && t.protected_models.contains(&normalized_target))
+                        && !self.is_daily_limit_reached(t)
+                        && !self.is_concurrency_limited(t)
+                });
             
             // 模式 A: 粘性会话处理 (CacheFirst 或 Balance 且有 session_id)
             if !rotate && session_id.is_some() && scheduling.mode != SchedulingMode::PerformanceFirst {
                 let sid = session_id.unwrap();
                 
                 // 1. 检查会话是否已绑定账号
-                if let Some(bound_id) = self.session_accounts.get(sid).map(|v| v.clone()) {
-                    // 【修复】先通过 account_id 找到对应的账号，获取其 email
-                    // 2. 转换 email -> account_id 检查绑定的账号是否限流
-                    if let Some(bound_token) = tokens_snapshot.iter().find(|t| t.account_id == bound_id) {
+                let bound = self
+                    .session_accounts
+                    .get(sid)
+                    .map(|meta| (meta.account_id.clone(), meta.bound_at.elapsed().as_secs()));
+                if let Some((bound_id, session_age)) = bound {
+                    if scheduling.session_ttl_secs > 0 && session_age > scheduling.session_ttl_secs {
+                        // 绑定已超过 TTL（后台回收尚未执行），直接回收并按新会话处理
+                        tracing::debug!("Sticky Session: Binding for session {} exceeded TTL ({}s), evicting.", sid, scheduling.session_ttl_secs);
+                        self.unbind_session(sid);
+                    } else if scheduling.max_session_age_secs > 0 && session_age > scheduling.max_session_age_secs {
+                        // 绑定已超过最大会话时长，解绑并轮换到新账号
+                        tracing::debug!(
+                            "Sticky Session: Binding for session {} exceeded max age ({}s), rotating.",
+                            sid, scheduling.max_session_age_secs
+                        );
+                        self.unbind_session(sid);
+                        expired_session_account = Some(bound_id);
+                    } else if let Some(bound_token) = tokens_snapshot.iter().find(|t| t.account_id == bound_id) {
+                        // 【修复】先通过 account_id 找到对应的账号，获取其 email
+                        // 2. 转换 email -> account_id 检查绑定的账号是否限流
                         let key = self.email_to_account_id(&bound_token.email).unwrap_or_else(|| bound_token.account_id.clone());
                         let reset_sec = self.rate_limit_tracker.get_remaining_wait(&key);
                         if reset_sec > 0 {
@@ -585,19 +1542,76 @@ impl TokenManager {
                                 "Sticky Session: Bound account {} is rate-limited ({}s), unbinding and switching.",
                                 bound_token.email, reset_sec
                             );
-                            self.session_accounts.remove(sid);
+                            self.unbind_session(sid);
+                            rebinding_after_limit = true;
+                        } else if self.is_daily_limit_reached(bound_token) {
+                            tracing::debug!("Sticky Session: Bound account {} reached daily request limit, unbinding and switching.", bound_token.email);
+                            self.unbind_session(sid);
+                        } else if self.is_concurrency_limited(bound_token) {
+                            // 并发已满是暂时状态，保留绑定，本次请求由其它账号处理
+                            tracing::debug!("Sticky Session: Bound account {} is at its concurrency limit, using another account for this request.", bound_token.email);
+                            keep_session_binding = true;
+                        } else if reserve_free && is_free_tier(bound_token) {
+                            tracing::debug!("Sticky Session: Bound account {} is FREE tier and PRO/ULTRA accounts are available, unbinding and switching.", bound_token.email);
+                            self.unbind_session(sid);
+                        } else if response_time_weighted && latency_scores[&bound_id] > latency_median * RESPONSE_TIME_REBIND_FACTOR {
+                            tracing::debug!(
+                                "Sticky Session: Bound account {} latency {}ms exceeds {}x pool median ({}ms), rebinding.",
+                                bound_token.email, latency_scores[&bound_id], RESPONSE_TIME_REBIND_FACTOR, latency_median
+                            );
+                            self.unbind_session(sid);
                         } else if !attempted.contains(&bound_id) && !(quota_protection_enabled && bound_token.protected_models.contains(&normalized_target)) {
                             // 3. 账号可用且未被标记为尝试失败，优先复用
                             tracing::debug!("Sticky Session: Successfully reusing bound account {} for session {}", bound_token.email, sid);
+                            if let Some(meta) = self.session_accounts.get(sid) {
+                                meta.request_count.fetch_add(1, Ordering::Relaxed);
+                            }
                             target_token = Some(bound_token.clone());
                         } else if quota_protection_enabled && bound_token.protected_models.contains(&normalized_target) {
                             tracing::debug!("Sticky Session: Bound account {} is quota-protected for model {} [{}], unbinding and switching.", bound_token.email, normalized_target, target_model);
-                            self.session_accounts.remove(sid);
+                            self.unbind_session(sid);
                         }
                     } else {
                         // 绑定的账号已不存在（可能被删除），解绑
                         tracing::debug!("Sticky Session: Bound account not found for session {}, unbinding", sid);
-                        self.session_accounts.remove(sid);
+                        self.unbind_session(sid);
+                    }
+                }
+            }
+
+            // 模型亲和规则: 优先尝试为目标模型指定的账号（适用于所有调度模式）
+            if target_token.is_none() {
+                let affinity = scheduling.model_account_affinity.get(&normalized_target)
+                    .or_else(|| scheduling.model_account_affinity.get(target_model));
+                if let Some(preferred_ids) = affinity {
+                    for preferred_id in preferred_ids {
+                        let Some(candidate) = tokens_snapshot.iter().find(|t| &t.account_id == preferred_id) else {
+                            continue;
+                        };
+                        if attempted.contains(&candidate.account_id)
+                            || expired_session_account.as_ref() == Some(&candidate.account_id)
+                            || self.is_rate_limited_by_account_id(&candidate.account_id)
+                            || (quota_protection_enabled && candidate.protected_models.contains(&normalized_target))
+                            || self.is_daily_limit_reached(candidate)
+                            || self.is_concurrency_limited(candidate)
+                            || slow_accounts.contains(&candidate.account_id)
+                            || (reserve_free && is_free_tier(candidate))
+                        {
+                            continue;
+                        }
+
+                        tracing::debug!("Model Affinity: Selected preferred account {} for model {}", candidate.email, normalized_target);
+                        target_token = Some(candidate.clone());
+                        if quota_group != "image_gen" {
+                            need_update_last_used = Some((candidate.account_id.clone(), std::time::Instant::now()));
+                        }
+                        if let Some(sid) = session_id {
+                            if scheduling.mode != SchedulingMode::PerformanceFirst && !keep_session_binding {
+                                self.session_accounts.insert(sid.to_string(), SessionMeta::new(candidate.account_id.clone()));
+                                self.record_bind_time(&candidate.account_id);
+                            }
+                        }
+                        break;
                     }
                 }
             }
@@ -605,18 +1619,37 @@ impl TokenManager {
             // 模式 B: 原子化 60s 全局锁定 (针对无 session_id 情况的默认保护)
             // 【修复】性能优先模式应跳过 60s 锁定；
             if target_token.is_none() && !rotate && quota_group != "image_gen" && scheduling.mode != SchedulingMode::PerformanceFirst {
+                // 限流解绑后的错峰重绑: 跳过冷却期内刚被其他会话绑定的账号，避免所有会话同时涌向同一账号
+                // 所有可用账号都在冷却期内时不跳过
+                let stagger_rebind = rebinding_after_limit
+                    && scheduling.rotation_cooldown_ms > 0
+                    && tokens_snapshot.iter().any(|t| {
+                        !attempted.contains(&t.account_id)
+                            && !self.is_rate_limited_by_account_id(&t.account_id)
+                            && !self.is_bind_cooling_down(&t.account_id, scheduling.rotation_cooldown_ms)
+                    });
+
                 // 【优化】使用预先获取的快照，不再在循环内加锁
                 if let Some((account_id, last_time)) = &last_used_account_id {
                     // [FIX #3] 60s 锁定逻辑应检查 `attempted` 集合，避免重复尝试失败的账号
-                    if last_time.elapsed().as_secs() < 60 && !attempted.contains(account_id) {
+                    // 响应时间加权模式始终按延迟选择，不复用上一个账号
+                    if !response_time_weighted && !stagger_rebind && last_time.elapsed().as_secs() < 60 && !attempted.contains(account_id) && expired_session_account.as_ref() != Some(account_id) {
                         if let Some(found) = tokens_snapshot.iter().find(|t| &t.account_id == account_id) {
                             // 【修复】检查限流状态和配额保护，避免复用已被锁定的账号
-                            if !self.is_rate_limited_by_account_id(&found.account_id) && !(quota_protection_enabled && found.protected_models.contains(&normalized_target)) {
+                            if !self.is_rate_limited_by_account_id(&found.account_id) && !(quota_protection_enabled && found.protected_models.contains(&normalized_target)) && !self.is_daily_limit_reached(found) && !self.is_concurrency_limited(found) && !slow_accounts.contains(&found.account_id) && !(reserve_free && is_free_tier(found)) {
                                 tracing::debug!("60s Window: Force reusing last account: {}", found.email);
                                 target_token = Some(found.clone());
                             } else {
                                 if self.is_rate_limited_by_account_id(&found.account_id) {
                                     tracing::debug!("60s Window: Last account {} is rate-limited, skipping", found.email);
+                                } else if self.is_daily_limit_reached(found) {
+                                    tracing::debug!("60s Window: Last account {} reached daily request limit, skipping", found.email);
+                                } else if self.is_concurrency_limited(found) {
+                                    tracing::debug!("60s Window: Last account {} is at its concurrency limit, skipping", found.email);
+                                } else if slow_accounts.contains(&found.account_id) {
+                                    tracing::debug!("60s Window: Last account {} is slow (P95 over threshold), skipping", found.email);
+                                } else if reserve_free && is_free_tier(found) {
+                                    tracing::debug!("60s Window: Last account {} is FREE tier (fallback only), skipping", found.email);
                                 } else {
                                     tracing::debug!("60s Window: Last account {} is quota-protected for model {} [{}], skipping", found.email, normalized_target, target_model);
                                 }
@@ -629,12 +1662,21 @@ impl TokenManager {
                 if target_token.is_none() {
                     let start_idx = self.current_index.fetch_add(1, Ordering::SeqCst) % total;
                     for offset in 0..total {
-                        let idx = (start_idx + offset) % total;
+                        let idx = if response_time_weighted { latency_order[offset] } else { (start_idx + offset) % total };
                         let candidate = &tokens_snapshot[idx];
                         if attempted.contains(&candidate.account_id) {
                             continue;
                         }
 
+                        // 会话到期轮换：避开刚解绑的账号（仅剩该账号时除外）
+                        if total > 1 && expired_session_account.as_ref() == Some(&candidate.account_id) {
+                            continue;
+                        }
+
+                        if reserve_free && is_free_tier(candidate) {
+                            continue;
+                        }
+
                         // 【新增 #621】模型级限流检查
                         if quota_protection_enabled && candidate.protected_models.contains(&normalized_target) {
                             tracing::debug!("Account {} is quota-protected for model {} [{}], skipping", candidate.email, normalized_target, target_model);
@@ -646,14 +1688,36 @@ impl TokenManager {
                             continue;
                         }
 
+                        // 每日请求上限检查
+                        if self.is_daily_limit_reached(candidate) {
+                            continue;
+                        }
+
+                        // 并发上限检查
+                        if self.is_concurrency_limited(candidate) {
+                            continue;
+                        }
+
+                        // 慢账号检查
+                        if slow_accounts.contains(&candidate.account_id) {
+                            continue;
+                        }
+
+                        // 轮换冷却检查
+                        if stagger_rebind && self.is_bind_cooling_down(&candidate.account_id, scheduling.rotation_cooldown_ms) {
+                            tracing::debug!("Rotation Cooldown: Account {} was bound within {}ms, skipping", candidate.email, scheduling.rotation_cooldown_ms);
+                            continue;
+                        }
+
                         target_token = Some(candidate.clone());
                         // 【优化】标记需要更新，稍后统一写回
                         need_update_last_used = Some((candidate.account_id.clone(), std::time::Instant::now()));
                         
                         // 如果是会话首次分配且需要粘性，在此建立绑定
                         if let Some(sid) = session_id {
-                            if scheduling.mode != SchedulingMode::PerformanceFirst {
-                                self.session_accounts.insert(sid.to_string(), candidate.account_id.clone());
+                            if scheduling.mode != SchedulingMode::PerformanceFirst && !keep_session_binding {
+                                self.session_accounts.insert(sid.to_string(), SessionMeta::new(candidate.account_id.clone()));
+                                self.record_bind_time(&candidate.account_id);
                                 tracing::debug!("Sticky Session: Bound new account {} to session {}", candidate.email, sid);
                             }
                         }
@@ -665,7 +1729,7 @@ impl TokenManager {
                 let start_idx = self.current_index.fetch_add(1, Ordering::SeqCst) % total;
                 tracing::info!("🔄 [Mode C] Round-robin from idx {}, total: {}", start_idx, total);
                 for offset in 0..total {
-                    let idx = (start_idx + offset) % total;
+                    let idx = if response_time_weighted { latency_order[offset] } else { (start_idx + offset) % total };
                     let candidate = &tokens_snapshot[idx];
                     
                     if attempted.contains(&candidate.account_id) {
@@ -673,6 +1737,11 @@ impl TokenManager {
                         continue;
                     }
 
+                    if reserve_free && is_free_tier(candidate) {
+                        tracing::debug!("  [{}] {} - SKIP: FREE tier reserved for fallback", idx, candidate.email);
+                        continue;
+                    }
+
                     // 【新增 #621】模型级限流检查
                     if quota_protection_enabled && candidate.protected_models.contains(&normalized_target) {
                         tracing::info!("  ⛔ {} - SKIP: quota-protected for {} [{}]", candidate.email, normalized_target, target_model);
@@ -685,6 +1754,24 @@ impl TokenManager {
                         continue;
                     }
 
+                    // 每日请求上限检查
+                    if self.is_daily_limit_reached(candidate) {
+                        tracing::info!("  📅 {} - SKIP: daily request limit reached", candidate.email);
+                        continue;
+                    }
+
+                    // 并发上限检查
+                    if self.is_concurrency_limited(candidate) {
+                        tracing::info!("  🚦 {} - SKIP: concurrency limit reached", candidate.email);
+                        continue;
+                    }
+
+                    // 慢账号检查
+                    if slow_accounts.contains(&candidate.account_id) {
+                        tracing::debug!("  🐢 {} - SKIP: P95 latency over threshold", candidate.email);
+                        continue;
+                    }
+
                     tracing::debug!("  [{}] {} - SELECTED", idx, candidate.email);
                     target_token = Some(candidate.clone());
                     
@@ -698,6 +1785,22 @@ impl TokenManager {
             let mut token = match target_token {
                 Some(t) => t,
                 None => {
+                    // 所有剩余账号均已达到每日请求上限，明确提示而非走乐观重置
+                    let mut remaining = tokens_snapshot.iter()
+                        .filter(|t| !attempted.contains(&t.account_id))
+                        .peekable();
+                    if remaining.peek().is_some() && remaining.all(|t| self.is_daily_limit_reached(t)) {
+                        return Err("All accounts have reached their daily request limit. Counters reset at 00:00 UTC.".to_string());
+                    }
+
+                    // 所有剩余可用账号的并发均已满，乐观重置无济于事
+                    let mut available = tokens_snapshot.iter()
+                        .filter(|t| !attempted.contains(&t.account_id) && !self.is_daily_limit_reached(t))
+                        .peekable();
+                    if available.peek().is_some() && available.all(|t| self.is_concurrency_limited(t)) {
+                        return Err("All accounts are at their concurrent request limit. Please retry shortly.".to_string());
+                    }
+
                     // 乐观重置策略: 双层防护机制
                     // 当所有账号都无法选择时,可能是时序竞争导致的状态不同步
                     
@@ -719,7 +1822,7 @@ impl TokenManager {
                             
                             // 重新尝试选择账号
                             let retry_token = tokens_snapshot.iter()
-                                .find(|t| !attempted.contains(&t.account_id) && !self.is_rate_limited_by_account_id(&t.account_id)); // Changed to account_id
+                                .find(|t| !attempted.contains(&t.account_id) && !self.is_rate_limited_by_account_id(&t.account_id) && !self.is_daily_limit_reached(t) && !self.is_concurrency_limited(t)); // Changed to account_id
                             
                             if let Some(t) = retry_token {
                                 tracing::info!("✅ Buffer delay successful! Found available account: {}", t.email);
@@ -733,10 +1836,11 @@ impl TokenManager {
                                 
                                 // 清除所有限流记录
                                 self.rate_limit_tracker.clear_all();
+                                let _ = self.rate_limit_events.send(RateLimitEvent::Cleared { account_id: None });
                                 
                                 // 再次尝试选择账号
                                 let final_token = tokens_snapshot.iter()
-                                    .find(|t| !attempted.contains(&t.account_id));
+                                    .find(|t| !attempted.contains(&t.account_id) && !self.is_daily_limit_reached(t) && !self.is_concurrency_limited(t));
                                 
                                 if let Some(t) = final_token {
                                     tracing::info!("✅ Optimistic reset successful! Using account: {}", t.email);
@@ -759,7 +1863,34 @@ impl TokenManager {
                 }
             };
 
-        
+            // 原子地占用并发名额 (筛选阶段的检查与此处之间可能有其他请求抢先)；之后任何失败分支 continue 时守卫自动释放
+            let Some(in_flight) = InFlightGuard::try_acquire(self.in_flight_counter(&token.account_id), token.max_concurrent) else {
+                tracing::debug!("Account {} reached its concurrency limit during selection, trying next account", token.email);
+                attempted.insert(token.account_id.clone());
+                last_error = Some("All accounts are at their concurrent request limit. Please retry shortly.".to_string());
+                continue;
+            };
+
+            // 同样原子地预占每日请求名额 (筛选阶段的 is_daily_limit_reached 只是预筛)
+            let Some(daily_reservation) = DailyRequestReservation::try_reserve(
+                &self.daily_request_counts,
+                &token.account_id,
+                token.daily_request_limit,
+            ) else {
+                tracing::debug!("Account {} reached its daily request limit during selection, trying next account", token.email);
+                attempted.insert(token.account_id.clone());
+                last_error = Some("All accounts have reached their daily request limit. Counters reset at 00:00 UTC.".to_string());
+                continue;
+            };
+
+            // 熔断器半开时只放行一个探测请求：选定账号后才占用名额，被其他请求抢先则换下一个账号
+            if !self.rate_limit_tracker.try_acquire_breaker(&token.account_id) {
+                tracing::debug!("Circuit breaker probe for {} already in flight, trying next account", token.email);
+                attempted.insert(token.account_id.clone());
+                last_error = Some("Circuit breaker probe already in flight".to_string());
+                continue;
+            }
+
             // 3. 检查 token 是否过期（提前5分钟刷新）
             let now = chrono::Utc::now().timestamp();
             if now >= token.timestamp - 300 {
@@ -769,6 +1900,8 @@ impl TokenManager {
                 match crate::modules::oauth::refresh_access_token(&token.refresh_token).await {
                     Ok(token_response) => {
                         tracing::debug!("Token 刷新成功！");
+                        self.metrics.record_token_refresh(true);
+                        record_refresh_event(&token.email, None);
 
                         // 更新本地内存对象供后续使用
                         token.access_token = token_response.access_token.clone();
@@ -788,6 +1921,8 @@ impl TokenManager {
                         }
                     }
                     Err(e) => {
+                        self.metrics.record_token_refresh(false);
+                        record_refresh_event(&token.email, Some(&e));
                         tracing::error!("Token 刷新失败 ({}): {}，尝试下一个账号", token.email, e);
                         if e.contains("\"invalid_grant\"") || e.contains("invalid_grant") {
                             tracing::error!(
@@ -856,23 +1991,85 @@ impl TokenManager {
                 }
             }
 
-            return Ok((token.access_token, project_id, token.email));
+            // 确认计入每日请求数
+            daily_reservation.commit();
+            self.anomaly_detector.record(&token.account_id);
+
+            return Ok((token.access_token, project_id, token.email, in_flight));
         }
 
         Err(last_error.unwrap_or_else(|| "All accounts failed".to_string()))
     }
 
+    /// 检查账号是否已达到每日请求上限
+    fn is_daily_limit_reached(&self, token: &ProxyToken) -> bool {
+        match token.daily_request_limit {
+            Some(limit) => self.get_daily_request_count(&token.account_id) >= limit,
+            None => false,
+        }
+    }
+
+    /// 检查账号进行中的请求数是否已达到并发上限
+    fn is_concurrency_limited(&self, token: &ProxyToken) -> bool {
+        match token.max_concurrent {
+            Some(limit) => self.get_in_flight_count(&token.account_id) >= limit,
+            None => false,
+        }
+    }
+
+    /// 获取账号当前进行中的请求数
+    pub fn get_in_flight_count(&self, account_id: &str) -> u32 {
+        self.in_flight
+            .get(account_id)
+            .map(|c| c.load(Ordering::SeqCst))
+            .unwrap_or(0)
+    }
+
+    fn in_flight_counter(&self, account_id: &str) -> Arc<AtomicU32> {
+        self.in_flight
+            .entry(account_id.to_string())
+            .or_insert_with(|| Arc::new(AtomicU32::new(0)))
+            .clone()
+    }
+
+    /// 获取账号当日已处理的请求数
+    pub fn get_daily_request_count(&self, account_id: &str) -> u32 {
+        self.daily_request_counts
+            .get(account_id)
+            .map(|c| c.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// 获取所有已加载账号的每日请求用量 (account_id, email, count, limit)
+    pub fn get_daily_usage(&self) -> Vec<(String, String, u32, Option<u32>)> {
+        self.tokens
+            .iter()
+            .map(|e| {
+                let t = e.value();
+                (
+                    t.account_id.clone(),
+                    t.email.clone(),
+                    self.get_daily_request_count(&t.account_id),
+                    t.daily_request_limit,
+                )
+            })
+            .collect()
+    }
+
     async fn disable_account(&self, account_id: &str, reason: &str) -> Result<(), String> {
-        let path = if let Some(entry) = self.tokens.get(account_id) {
-            entry.account_path.clone()
+        let (path, email) = if let Some(entry) = self.tokens.get(account_id) {
+            (entry.account_path.clone(), Some(entry.email.clone()))
         } else {
-            self.data_dir
-                .join("accounts")
-                .join(format!("{}.json", account_id))
+            (
+                self.data_dir
+                    .join("accounts")
+                    .join(format!("{}.json", account_id)),
+                None,
+            )
         };
 
         let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?,
+            &crate::modules::account_crypto::read_account_file(&path)?,
         )
         .map_err(|e| format!("解析 JSON 失败: {}", e))?;
 
@@ -881,13 +2078,21 @@ impl TokenManager {
         content["disabled_at"] = serde_json::Value::Number(now.into());
         content["disabled_reason"] = serde_json::Value::String(truncate_reason(reason, 800));
 
-        std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap())
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+        crate::modules::account_crypto::write_account_file(&path, &serde_json::to_string_pretty(&content).unwrap())?;
         
         // 【修复 Issue #3】从内存中移除禁用的账号，防止被60s锁定逻辑继续使用
         self.tokens.remove(account_id);
 
         tracing::warn!("Account disabled: {} ({:?})", account_id, path);
+        self.emit_account_event(EVENT_ACCOUNT_DISABLED, account_id, email.as_deref());
+        crate::modules::webhook::emit(
+            crate::modules::webhook::EVENT_ACCOUNT_DISABLED,
+            serde_json::json!({
+                "account_id": account_id,
+                "email": email,
+                "reason": truncate_reason(reason, 300),
+            }),
+        );
         Ok(())
     }
 
@@ -899,13 +2104,12 @@ impl TokenManager {
         let path = &entry.account_path;
         
         let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
+            &crate::modules::account_crypto::read_account_file(path)?
         ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
         
         content["token"]["project_id"] = serde_json::Value::String(project_id.to_string());
         
-        std::fs::write(path, serde_json::to_string_pretty(&content).unwrap())
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+        crate::modules::account_crypto::write_account_file(path, &serde_json::to_string_pretty(&content).unwrap())?;
         
         tracing::debug!("已保存 project_id 到账号 {}", account_id);
         Ok(())
@@ -913,23 +2117,12 @@ impl TokenManager {
     
     /// 保存刷新后的 token 到账号文件
     async fn save_refreshed_token(&self, account_id: &str, token_response: &crate::modules::oauth::TokenResponse) -> Result<(), String> {
-        let entry = self.tokens.get(account_id)
-            .ok_or("账号不存在")?;
-        
-        let path = &entry.account_path;
-        
-        let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
-        ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
+        let path = self.tokens.get(account_id)
+            .ok_or("账号不存在")?
+            .account_path
+            .clone();
         
-        let now = chrono::Utc::now().timestamp();
-        
-        content["token"]["access_token"] = serde_json::Value::String(token_response.access_token.clone());
-        content["token"]["expires_in"] = serde_json::Value::Number(token_response.expires_in.into());
-        content["token"]["expiry_timestamp"] = serde_json::Value::Number((now + token_response.expires_in).into());
-        
-        std::fs::write(path, serde_json::to_string_pretty(&content).unwrap())
-            .map_err(|e| format!("写入文件失败: {}", e))?;
+        write_refreshed_token(&path, token_response)?;
         
         tracing::debug!("已保存刷新后的 token 到账号 {}", account_id);
         Ok(())
@@ -939,6 +2132,48 @@ impl TokenManager {
         self.tokens.len()
     }
 
+    /// 获取任意一个未过期账号的 access_token（按 account_id 排序，结果稳定）
+    pub fn first_valid_access_token(&self) -> Option<String> {
+        let now = chrono::Utc::now().timestamp();
+        self.tokens
+            .iter()
+            .filter(|e| now < e.value().timestamp)
+            .min_by(|a, b| a.key().cmp(b.key()))
+            .map(|e| e.value().access_token.clone())
+    }
+
+    /// 获取账号池状态快照（邮箱已脱敏）
+    pub async fn snapshot(&self) -> Vec<AccountSnapshot> {
+        let sticky_account = self
+            .last_used_account
+            .lock()
+            .await
+            .as_ref()
+            .filter(|(_, t)| t.elapsed().as_secs() < 60)
+            .map(|(id, _)| id.clone());
+
+        let mut snapshot: Vec<AccountSnapshot> = self
+            .tokens
+            .iter()
+            .map(|e| {
+                let t = e.value();
+                let mut protected_models: Vec<String> = t.protected_models.iter().cloned().collect();
+                protected_models.sort();
+                AccountSnapshot {
+                    account_id: t.account_id.clone(),
+                    email: crate::utils::privacy::mask_email(&t.email),
+                    subscription_tier: t.subscription_tier.clone(),
+                    remaining_quota: t.remaining_quota,
+                    protected_models,
+                    rate_limit_remaining_secs: self.rate_limit_tracker.get_remaining_wait(&t.account_id),
+                    sticky_window_active: sticky_account.as_deref() == Some(t.account_id.as_str()),
+                }
+            })
+            .collect();
+        snapshot.sort_by(|a, b| a.account_id.cmp(&b.account_id));
+        snapshot
+    }
+
     /// 通过 email 获取指定账号的 Token（用于预热等需要指定账号的场景）
     /// 此方法会自动刷新过期的 token
     pub async fn get_token_by_email(&self, email: &str) -> Result<(String, String, String), String> {
@@ -989,6 +2224,8 @@ impl TokenManager {
         match crate::modules::oauth::refresh_access_token(&refresh_token).await {
             Ok(token_response) => {
                 tracing::info!("[Warmup] Token refresh successful for {}", email);
+                self.metrics.record_token_refresh(true);
+                record_refresh_event(email, None);
                 let new_now = chrono::Utc::now().timestamp();
                 
                 // 更新缓存
@@ -1003,7 +2240,11 @@ impl TokenManager {
 
                 Ok((token_response.access_token, project_id, email.to_string()))
             }
-            Err(e) => Err(format!("[Warmup] Token refresh failed for {}: {}", email, e)),
+            Err(e) => {
+                self.metrics.record_token_refresh(false);
+                record_refresh_event(email, Some(&e));
+                Err(format!("[Warmup] Token refresh failed for {}: {}", email, e))
+            }
         }
     }
     
@@ -1020,6 +2261,7 @@ impl TokenManager {
     ) {
         // 【替代方案】转换 email -> account_id
         let key = self.email_to_account_id(email).unwrap_or_else(|| email.to_string());
+        emit_rate_limit_event(&key, status, None);
         self.rate_limit_tracker.parse_from_error(
             &key,
             status,
@@ -1027,6 +2269,7 @@ impl TokenManager {
             error_body,
             None,
         );
+        self.notify_rate_limit_updated(&key);
     }
     
 
@@ -1034,6 +2277,7 @@ impl TokenManager {
     pub fn is_rate_limited_by_account_id(&self, account_id: &str) -> bool {
         self.rate_limit_tracker.is_rate_limited(account_id)
     }
+
     
     /// 获取距离限流重置还有多少秒
     #[allow(dead_code)]
@@ -1049,7 +2293,7 @@ impl TokenManager {
     
     /// 【替代方案】通过 email 查找对应的 account_id
     /// 用于将 handlers 传入的 email 转换为 tracker 使用的 account_id
-    fn email_to_account_id(&self, email: &str) -> Option<String> {
+    pub fn email_to_account_id(&self, email: &str) -> Option<String> {
         self.tokens.iter()
             .find(|entry| entry.value().email == email)
             .map(|entry| entry.value().account_id.clone())
@@ -1058,7 +2302,14 @@ impl TokenManager {
     /// 清除指定账号的限流记录
     #[allow(dead_code)]
     pub fn clear_rate_limit(&self, account_id: &str) -> bool {
-        self.rate_limit_tracker.clear(account_id)
+        let cleared = self.rate_limit_tracker.clear(account_id);
+        if cleared {
+            let _ = self.rate_limit_events.send(RateLimitEvent::Cleared {
+                account_id: Some(account_id.to_string()),
+            });
+            self.emit_account_event(EVENT_ACCOUNT_RATE_LIMIT_CLEARED, account_id, None);
+        }
+        cleared
     }
     
     /// 标记账号请求成功，重置连续失败计数
@@ -1066,7 +2317,126 @@ impl TokenManager {
     /// 在请求成功完成后调用，将该账号的失败计数归零，
     /// 下次失败时从最短的锁定时间开始（智能限流）。
     pub fn mark_account_success(&self, account_id: &str) {
-        self.rate_limit_tracker.mark_success(account_id);
+        // 调用方可能传入 email，统一转换为 tracker 使用的 account_id
+        let key = self.email_to_account_id(account_id).unwrap_or_else(|| account_id.to_string());
+        self.rate_limit_tracker.mark_success(&key);
+    }
+
+    /// 记录账号被会话绑定的时间 (用于轮换冷却)
+    fn record_bind_time(&self, account_id: &str) {
+        let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        self.last_bind_time
+            .entry(account_id.to_string())
+            .or_insert_with(|| AtomicU64::new(0))
+            .store(now_ms, Ordering::Relaxed);
+    }
+
+    /// 账号是否在 `cooldown_ms` 内刚被会话绑定
+    fn is_bind_cooling_down(&self, account_id: &str, cooldown_ms: u64) -> bool {
+        let Some(last) = self.last_bind_time.get(account_id) else {
+            return false;
+        };
+        let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        now_ms.saturating_sub(last.load(Ordering::Relaxed)) < cooldown_ms
+    }
+
+    /// 响应时间加权模式下的账号评分 (最近平均延迟，样本不足时返回 None)
+    fn latency_score(&self, account_id: &str) -> Option<u64> {
+        match self.rate_limit_tracker.average_latency(account_id) {
+            Some((samples, avg_ms)) if samples >= RESPONSE_TIME_MIN_SAMPLES => Some(avg_ms),
+            _ => None,
+        }
+    }
+
+    /// 记录账号一次上游响应结果 (调用方可能传入 email)
+    /// `status` 为 None 表示传输层错误 (连接失败、超时等)
+    /// 只有 429/5xx 与传输错误计为失败；其余 4xx 是请求本身的问题，不计入错误预算
+    /// 5 分钟错误率超过 50% 时计入熔断器失败，持续失败会打开熔断
+    /// 2xx 视为探测成功并关闭熔断器 (所有协议处理器都会调用此方法)
+    pub fn record_upstream_outcome(&self, account_id: &str, status: Option<u16>) {
+        let is_success = match status {
+            Some(code) if (200..300).contains(&code) => true,
+            Some(code) if code == 429 || code >= 500 => false,
+            Some(_) => return,
+            None => false,
+        };
+        let key = self.email_to_account_id(account_id).unwrap_or_else(|| account_id.to_string());
+        let exhausted = self.error_budget.record(&key, is_success);
+        if is_success {
+            self.rate_limit_tracker.mark_success(&key);
+            return;
+        }
+        // 429/500/503/529 已由 parse_from_error 计入熔断器，这里不再重复计数
+        let recorded_by_tracker = matches!(status, Some(429 | 500 | 503 | 529));
+        if exhausted && !recorded_by_tracker {
+            tracing::warn!("账号 {} 错误预算耗尽 (5 分钟错误率 > 50%)", key);
+            self.rate_limit_tracker.record_breaker_failure(&key);
+        }
+    }
+
+    /// 各账号最近 1 小时 / 5 分钟的上游错误率
+    pub fn error_budget_snapshot(&self) -> Vec<crate::proxy::error_budget::ErrorBudgetSnapshot> {
+        self.error_budget.snapshot()
+    }
+
+    /// 记录账号一次成功请求的上游耗时 (调用方可能传入 email)
+    pub fn record_latency(&self, account_id: &str, duration_ms: u64) {
+        let key = self.email_to_account_id(account_id).unwrap_or_else(|| account_id.to_string());
+        self.rate_limit_tracker.record_latency(&key, duration_ms);
+    }
+
+    /// 获取各账号最近请求的延迟分位数 (邮箱已脱敏，无样本的账号不返回)
+    pub fn account_latency_stats(&self) -> Vec<AccountLatencyStats> {
+        let mut stats: Vec<AccountLatencyStats> = self
+            .tokens
+            .iter()
+            .filter_map(|e| {
+                let t = e.value();
+                self.rate_limit_tracker.latency_stats(&t.account_id).map(|latency| AccountLatencyStats {
+                    account_id: t.account_id.clone(),
+                    email: crate::utils::privacy::mask_email(&t.email),
+                    latency,
+                })
+            })
+            .collect();
+        stats.sort_by(|a, b| b.latency.p95_ms.cmp(&a.latency.p95_ms));
+        stats
+    }
+
+    /// 获取各账号限流与熔断状态
+    pub fn rate_limit_statuses(&self) -> Vec<AccountRateLimitStatus> {
+        let mut statuses: Vec<AccountRateLimitStatus> = self
+            .tokens
+            .iter()
+            .map(|e| self.build_rate_limit_status(e.value()))
+            .collect();
+        statuses.sort_by(|a, b| a.account_id.cmp(&b.account_id));
+        statuses
+    }
+
+    fn build_rate_limit_status(&self, t: &ProxyToken) -> AccountRateLimitStatus {
+        AccountRateLimitStatus {
+            account_id: t.account_id.clone(),
+            email: crate::utils::privacy::mask_email(&t.email),
+            rate_limit_remaining_secs: self.rate_limit_tracker.get_remaining_wait(&t.account_id),
+            reason: self
+                .rate_limit_tracker
+                .get(&t.account_id)
+                .filter(|info| info.reset_time > std::time::SystemTime::now())
+                .map(|info| format!("{:?}", info.reason)),
+            breaker: self.rate_limit_tracker.breaker_snapshot(&t.account_id),
+        }
+    }
+
+    /// 推送单个账号的最新限流状态 (无订阅者时忽略)
+    fn notify_rate_limit_updated(&self, account_id: &str) {
+        if self.rate_limit_events.receiver_count() == 0 {
+            return;
+        }
+        if let Some(token) = self.tokens.get(account_id) {
+            let status = self.build_rate_limit_status(token.value());
+            let _ = self.rate_limit_events.send(RateLimitEvent::Updated { status });
+        }
     }
     
     /// 检查是否有可用的 Google 账号
@@ -1090,10 +2460,8 @@ impl TokenManager {
     /// }
     /// ```
     pub async fn has_available_account(&self, _quota_group: &str, target_model: &str) -> bool {
-        // 检查配额保护是否启用
-        let quota_protection_enabled = crate::modules::config::load_app_config()
-            .map(|cfg| cfg.quota_protection.enabled)
-            .unwrap_or(false);
+        // 检查配额保护是否启用 (内存状态，每个候选模型都会调用，不能读取磁盘)
+        let quota_protection_enabled = self.quota_protection_enabled.load(Ordering::Relaxed);
         
         // 遍历所有账号,检查是否有可用的
         for entry in self.tokens.iter() {
@@ -1135,6 +2503,36 @@ impl TokenManager {
         false
     }
     
+    /// 检查是否至少有一个账号能处理 `model` 且该模型剩余配额不低于 `min_percent`
+    ///
+    /// 账号需未限流、未达每日上限、未被该模型的配额保护；
+    /// 账号文件中没有该模型的配额数据时不按百分比过滤 (避免配额未刷新的账号被误判为不可用)
+    pub fn is_model_available_with_min_percent(&self, model: &str, min_percent: i32) -> bool {
+        let quota_protection_enabled = self.quota_protection_enabled.load(Ordering::Relaxed);
+        let normalized = crate::proxy::common::model_mapping::normalize_to_standard_id(model)
+            .unwrap_or_else(|| model.to_string());
+
+        let available = self.tokens.iter().any(|entry| {
+            let token = entry.value();
+            if self.is_rate_limited_by_account_id(&token.account_id) || self.is_daily_limit_reached(token) {
+                return false;
+            }
+            if quota_protection_enabled && token.protected_models.contains(&normalized) {
+                return false;
+            }
+            token
+                .model_quotas
+                .get(model)
+                .or_else(|| token.model_quotas.get(&normalized))
+                .map_or(true, |&percent| percent >= min_percent)
+        });
+
+        if !available {
+            tracing::debug!("[Fallback Check] No account can serve {} with >= {}% quota", model, min_percent);
+        }
+        available
+    }
+
     /// 从账号文件获取配额刷新时间
     /// 
     /// 返回该账号最近的配额刷新时间字符串（ISO 8601 格式）
@@ -1145,7 +2543,7 @@ impl TokenManager {
         // 遍历账号文件查找对应的 email
         if let Ok(entries) = std::fs::read_dir(&accounts_dir) {
             for entry in entries.flatten() {
-                if let Ok(content) = std::fs::read_to_string(entry.path()) {
+                if let Ok(content) = crate::modules::account_crypto::read_account_file(&entry.path()) {
                     if let Ok(account) = serde_json::from_str::<serde_json::Value>(&content) {
                         // 检查 email 是否匹配
                         if account.get("email").and_then(|e| e.as_str()) == Some(email) {
@@ -1280,6 +2678,24 @@ impl TokenManager {
         error_body: &str,
         model: Option<&str>,  // 🆕 新增模型参数
     ) {
+        self.apply_rate_limit_async(account_id, status, retry_after_header, error_body, model).await;
+        let key = self.email_to_account_id(account_id).unwrap_or_else(|| account_id.to_string());
+        self.notify_rate_limit_updated(&key);
+        if self.rate_limit_tracker.get_remaining_wait(&key) > 0 {
+            self.emit_account_event(EVENT_ACCOUNT_RATE_LIMITED, &key, None);
+        }
+    }
+
+    async fn apply_rate_limit_async(
+        &self,
+        account_id: &str,
+        status: u16,
+        retry_after_header: Option<&str>,
+        error_body: &str,
+        model: Option<&str>,
+    ) {
+        emit_rate_limit_event(account_id, status, model);
+
         // 检查 API 是否返回了精确的重试时间
         let has_explicit_retry_time = retry_after_header.is_some() || 
             error_body.contains("quotaResetDelay");
@@ -1356,6 +2772,11 @@ impl TokenManager {
     /// 清除特定会话的粘性映射
     #[allow(dead_code)]
     pub fn clear_session_binding(&self, session_id: &str) {
+        self.unbind_session(session_id);
+    }
+
+    /// 解除会话绑定
+    fn unbind_session(&self, session_id: &str) {
         self.session_accounts.remove(session_id);
     }
 
@@ -1363,9 +2784,206 @@ impl TokenManager {
     pub fn clear_all_sessions(&self) {
         self.session_accounts.clear();
     }
+
+    /// 保存未过期的限流记录到磁盘 (反代停止时调用)
+    pub fn save_rate_limits(&self) -> Result<(), String> {
+        crate::proxy::rate_limit::save_rate_limits(
+            &self.data_dir.join(crate::proxy::rate_limit::RATE_LIMITS_FILE),
+            &self.rate_limit_tracker,
+        )
+    }
+
+    /// 清空内存中的全部限流记录
+    pub fn clear_all_rate_limits(&self) {
+        let limited: Vec<String> = self
+            .tokens
+            .iter()
+            .filter(|e| self.rate_limit_tracker.get(e.key()).is_some())
+            .map(|e| e.key().clone())
+            .collect();
+        self.rate_limit_tracker.clear_all();
+        let _ = self.rate_limit_events.send(RateLimitEvent::Cleared { account_id: None });
+        for account_id in &limited {
+            self.emit_account_event(EVENT_ACCOUNT_RATE_LIMIT_CLEARED, account_id, None);
+        }
+    }
+
+    /// 会话持久化文件路径
+    fn sessions_path(&self) -> PathBuf {
+        self.data_dir.join("sticky_sessions.json")
+    }
+
+    /// 保存会话绑定到磁盘 (反代停止时调用)
+    pub fn save_sessions(&self) -> Result<(), String> {
+        crate::proxy::session_manager::save_sessions(&self.sessions_path(), &self.session_accounts)
+    }
+
+    /// 从磁盘恢复会话绑定：丢弃超过最大会话时长或绑定账号已不在池中的会话
+    /// 需在 load_accounts 与 update_sticky_config 之后调用
+    pub async fn restore_sessions(&self) -> Result<usize, String> {
+        let (max_age, ttl) = {
+            let config = self.sticky_config.read().await;
+            (config.max_session_age_secs, config.session_ttl_secs)
+        };
+        let loaded = crate::proxy::session_manager::load_sessions(&self.sessions_path())?;
+
+        let mut restored = 0;
+        for (sid, meta) in loaded {
+            let age = meta.bound_at.elapsed().as_secs();
+            if (max_age > 0 && age > max_age) || (ttl > 0 && age > ttl) {
+                continue;
+            }
+            if !self.tokens.contains_key(&meta.account_id) {
+                continue;
+            }
+            self.session_accounts.insert(sid, meta);
+            restored += 1;
+        }
+        tracing::info!("Restored {} sticky session bindings", restored);
+        Ok(restored)
+    }
+
+    /// 获取所有活跃会话快照（按绑定时间由新到旧）
+    pub fn list_sessions(&self) -> Vec<SessionSnapshot> {
+        snapshot_sessions(&self.session_accounts)
+    }
+
+    /// 移除账号的全部运行时数据 (账号池、会话绑定及其用量、限流记录)，返回移除的会话数
+    pub fn purge_account(&self, account_id: &str) -> usize {
+        self.tokens.remove(account_id);
+        if self.rate_limit_tracker.clear(account_id) {
+            let _ = self.rate_limit_events.send(RateLimitEvent::Cleared {
+                account_id: Some(account_id.to_string()),
+            });
+        }
+        let session_ids: Vec<String> = self
+            .session_accounts
+            .iter()
+            .filter(|e| e.value().account_id == account_id)
+            .map(|e| e.key().clone())
+            .collect();
+        for sid in &session_ids {
+            self.session_accounts.remove(sid);
+            self.session_token_usage.remove(sid);
+        }
+        session_ids.len()
+    }
+}
+
+/// 清除超过 TTL 的会话绑定，返回清除数量（ttl 为 0 时不清除）
+fn evict_expired_sessions(sessions: &DashMap<String, SessionMeta>, ttl_secs: u64) -> usize {
+    if ttl_secs == 0 {
+        return 0;
+    }
+    let before = sessions.len();
+    sessions.retain(|_, meta| meta.bound_at.elapsed().as_secs() <= ttl_secs);
+    before.saturating_sub(sessions.len())
+}
+
+/// 累加会话一次请求的 token 用量
+fn record_session_tokens(
+    usage: &DashMap<String, SessionTokenUsage>,
+    session_id: String,
+    input_tokens: u64,
+    output_tokens: u64,
+) {
+    let entry = usage
+        .entry(session_id.clone())
+        .or_insert_with(|| SessionTokenUsage::new(session_id));
+    entry.input_tokens.fetch_add(input_tokens, Ordering::Relaxed);
+    entry.output_tokens.fetch_add(output_tokens, Ordering::Relaxed);
+    entry.request_count.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 将刷新后的 token 写入账号文件
+fn write_refreshed_token(path: &PathBuf, token_response: &crate::modules::oauth::TokenResponse) -> Result<(), String> {
+    let mut content: serde_json::Value = serde_json::from_str(
+        &crate::modules::account_crypto::read_account_file(path)?
+    ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
+    
+    let now = chrono::Utc::now().timestamp();
+    
+    content["token"]["access_token"] = serde_json::Value::String(token_response.access_token.clone());
+    content["token"]["expires_in"] = serde_json::Value::Number(token_response.expires_in.into());
+    content["token"]["expiry_timestamp"] = serde_json::Value::Number((now + token_response.expires_in).into());
+    
+    crate::modules::account_crypto::write_account_file(path, &serde_json::to_string_pretty(&content).unwrap())
+}
+
+/// 推送限流 Webhook 事件
+fn emit_rate_limit_event(account_id: &str, status: u16, model: Option<&str>) {
+    crate::modules::webhook::emit(
+        crate::modules::webhook::EVENT_RATE_LIMIT_TRIGGERED,
+        serde_json::json!({
+            "account_id": account_id,
+            "status": status,
+            "model": model,
+        }),
+    );
+}
+
+/// 记录配额快照，写库放到阻塞线程池执行
+/// 快照按 last_updated 去重，缺少该字段时跳过 (回退到当前时间会在每次加载账号时重复写入)
+fn record_quota_snapshot(account_id: &str, quota: &serde_json::Value) {
+    let Some(recorded_at) = quota
+        .get("last_updated")
+        .and_then(|v| v.as_i64())
+        .map(|secs| secs * 1000)
+    else {
+        return;
+    };
+    let models: Vec<(String, i32)> = quota
+        .get("models")
+        .and_then(|m| m.as_array())
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|m| {
+                    let name = m.get("name")?.as_str()?;
+                    let percentage = m.get("percentage")?.as_i64()?;
+                    Some((name.to_string(), percentage as i32))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if models.is_empty() {
+        return;
+    }
+    let account_id = account_id.to_string();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = crate::modules::proxy_db::record_quota_snapshot(&account_id, &models, recorded_at) {
+            tracing::debug!("记录配额快照失败 ({}): {}", account_id, e);
+        }
+    });
+}
+
+fn is_free_tier(token: &ProxyToken) -> bool {
+    token.subscription_tier.as_deref() == Some("FREE")
+}
+
+/// 按标签筛选候选账号；无匹配账号且允许回退时改用未打标签的账号
+fn filter_by_tags(tokens: Vec<ProxyToken>, desired: &[String], fallback_to_untagged: bool) -> Vec<ProxyToken> {
+    let (matched, rest): (Vec<ProxyToken>, Vec<ProxyToken>) = tokens
+        .into_iter()
+        .partition(|t| t.tags.iter().any(|tag| desired.contains(tag)));
+    if !matched.is_empty() || !fallback_to_untagged {
+        return matched;
+    }
+    rest.into_iter().filter(|t| t.tags.is_empty()).collect()
+}
+
+/// 记录账号级 token 刷新结果 (用于账号健康度评分)，写库放到阻塞线程池执行
+fn record_refresh_event(email: &str, error: Option<&str>) {
+    let email = email.to_string();
+    let error = error.map(|e| truncate_reason(e, 300));
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = crate::modules::proxy_db::record_token_refresh(&email, error.is_none(), error.as_deref()) {
+            tracing::debug!("记录 token 刷新事件失败 ({}): {}", email, e);
+        }
+    });
 }
 
-fn truncate_reason(reason: &str, max_len: usize) -> String {
+pub(crate) fn truncate_reason(reason: &str, max_len: usize) -> String {
     if reason.chars().count() <= max_len {
         return reason.to_string();
     }
@@ -1373,3 +2991,442 @@ fn truncate_reason(reason: &str, max_len: usize) -> String {
     s.push('…');
     s
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_daily_reservation_enforces_limit_and_rolls_back() {
+        let counts = Arc::new(DashMap::new());
+        let first = DailyRequestReservation::try_reserve(&counts, "acc", Some(2)).unwrap();
+        let second = DailyRequestReservation::try_reserve(&counts, "acc", Some(2)).unwrap();
+        assert!(DailyRequestReservation::try_reserve(&counts, "acc", Some(2)).is_none());
+
+        // 未确认的预占归还名额
+        drop(second);
+        assert_eq!(counts.get("acc").unwrap().load(Ordering::SeqCst), 1);
+        first.commit();
+        DailyRequestReservation::try_reserve(&counts, "acc", Some(2)).unwrap().commit();
+        assert_eq!(counts.get("acc").unwrap().load(Ordering::SeqCst), 2);
+        assert!(DailyRequestReservation::try_reserve(&counts, "acc", None).is_some());
+    }
+
+    fn session(account_id: &str, age_secs: u64) -> SessionMeta {
+        SessionMeta {
+            account_id: account_id.to_string(),
+            bound_at: Instant::now() - Duration::from_secs(age_secs),
+            client_ip: None,
+            user_agent: None,
+            request_count: AtomicU32::new(1),
+        }
+    }
+
+    fn tagged_token(account_id: &str, tags: &[&str]) -> ProxyToken {
+        ProxyToken {
+            account_id: account_id.to_string(),
+            access_token: String::new(),
+            refresh_token: String::new(),
+            expires_in: 0,
+            timestamp: 0,
+            email: format!("{}@example.com", account_id),
+            account_path: PathBuf::new(),
+            project_id: None,
+            subscription_tier: None,
+            remaining_quota: None,
+            protected_models: HashSet::new(),
+            daily_request_limit: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            max_concurrent: None,
+            model_quotas: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_filter_by_tags() {
+        let pool = || {
+            vec![
+                tagged_token("team-a", &["team-a"]),
+                tagged_token("shared", &["team-a", "team-b"]),
+                tagged_token("untagged", &[]),
+            ]
+        };
+        let ids = |tokens: Vec<ProxyToken>| tokens.into_iter().map(|t| t.account_id).collect::<Vec<_>>();
+
+        assert_eq!(ids(filter_by_tags(pool(), &["team-b".to_string()], false)), vec!["shared"]);
+        assert!(filter_by_tags(pool(), &["team-c".to_string()], false).is_empty());
+        assert_eq!(ids(filter_by_tags(pool(), &["team-c".to_string()], true)), vec!["untagged"]);
+    }
+
+    fn tier_token(account_id: &str, tier: &str) -> ProxyToken {
+        let mut token = tagged_token(account_id, &[]);
+        token.subscription_tier = Some(tier.to_string());
+        token.timestamp = chrono::Utc::now().timestamp() + 3600;
+        token.project_id = Some("project".to_string());
+        token
+    }
+
+    #[tokio::test]
+    async fn test_free_account_fallback_only() {
+        use crate::proxy::sticky_config::{FreeAccountMode, SchedulingMode};
+
+        let data_dir = std::env::temp_dir().join(format!("tm-free-{}", uuid::Uuid::new_v4()));
+        let manager = TokenManager::new(data_dir);
+        for token in [tier_token("pro", "PRO"), tier_token("free", "FREE")] {
+            manager.tokens.insert(token.account_id.clone(), token);
+        }
+        {
+            let mut config = manager.sticky_config.write().await;
+            config.mode = SchedulingMode::PerformanceFirst;
+            config.free_account_mode = FreeAccountMode::FallbackOnly;
+        }
+
+        for _ in 0..4 {
+            let (_, _, email, _) = manager.get_token_internal("claude", false, None, "claude-sonnet-4-5").await.unwrap();
+            assert_eq!(email, "pro@example.com");
+        }
+
+        // PRO 账号限流后回退到 FREE
+        manager.rate_limit_tracker.set_lockout_until(
+            "pro",
+            std::time::SystemTime::now() + Duration::from_secs(600),
+            crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+            None,
+        );
+        let (_, _, email, _) = manager.get_token_internal("claude", false, None, "claude-sonnet-4-5").await.unwrap();
+        assert_eq!(email, "free@example.com");
+
+        manager.sticky_config.write().await.free_account_mode = FreeAccountMode::Disabled;
+        assert!(manager.get_token_internal("claude", false, None, "claude-sonnet-4-5").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_skips_saturated_account() {
+        use crate::proxy::sticky_config::SchedulingMode;
+
+        let data_dir = std::env::temp_dir().join(format!("tm-conc-{}", uuid::Uuid::new_v4()));
+        let manager = TokenManager::new(data_dir);
+        let mut limited = tier_token("limited", "ULTRA");
+        limited.max_concurrent = Some(1);
+        for token in [limited, tier_token("other", "PRO")] {
+            manager.tokens.insert(token.account_id.clone(), token);
+        }
+        manager.sticky_config.write().await.mode = SchedulingMode::PerformanceFirst;
+
+        let (_, _, first, guard) = manager.get_token("claude", false, None, "claude-sonnet-4-5").await.unwrap();
+        assert_eq!(first, "limited@example.com");
+        assert_eq!(manager.get_in_flight_count("limited"), 1);
+
+        // 并发已满时跳过该账号
+        for _ in 0..3 {
+            let (_, _, email, _guard) = manager.get_token("claude", false, None, "claude-sonnet-4-5").await.unwrap();
+            assert_eq!(email, "other@example.com");
+        }
+
+        drop(guard);
+        assert_eq!(manager.get_in_flight_count("limited"), 0);
+        assert_eq!(manager.get_in_flight_count("other"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limited_session_keeps_binding() {
+        let data_dir = std::env::temp_dir().join(format!("tm-conc-sticky-{}", uuid::Uuid::new_v4()));
+        let manager = TokenManager::new(data_dir);
+        let mut limited = tier_token("limited", "ULTRA");
+        limited.max_concurrent = Some(1);
+        for token in [limited, tier_token("other", "PRO")] {
+            manager.tokens.insert(token.account_id.clone(), token);
+        }
+        manager.session_accounts.insert("s1".to_string(), SessionMeta::new("limited".to_string()));
+
+        // 绑定账号并发已满：本次请求由其它账号处理，会话仍绑定原账号
+        let busy = InFlightGuard::try_acquire(manager.in_flight_counter("limited"), Some(1)).unwrap();
+        let (_, _, email, _) = manager.get_token_internal("claude", false, Some("s1"), "claude-sonnet-4-5").await.unwrap();
+        assert_eq!(email, "other@example.com");
+        assert_eq!(manager.session_accounts.get("s1").unwrap().account_id, "limited");
+
+        drop(busy);
+        let (_, _, email, _) = manager.get_token_internal("claude", false, Some("s1"), "claude-sonnet-4-5").await.unwrap();
+        assert_eq!(email, "limited@example.com");
+    }
+
+    #[test]
+    fn test_model_available_with_min_percent() {
+        let manager = TokenManager::new(std::env::temp_dir().join(format!("tm-min-pct-{}", uuid::Uuid::new_v4())));
+        let mut low = tier_token("low", "PRO");
+        low.model_quotas.insert("gemini-3-pro-high".to_string(), 5);
+        manager.tokens.insert(low.account_id.clone(), low);
+
+        assert!(manager.is_model_available_with_min_percent("gemini-3-pro-high", 5));
+        assert!(!manager.is_model_available_with_min_percent("gemini-3-pro-high", 20));
+        // 没有配额数据的模型不按百分比过滤
+        assert!(manager.is_model_available_with_min_percent("gemini-3-flash", 20));
+
+        manager.rate_limit_tracker.set_lockout_until(
+            "low",
+            std::time::SystemTime::now() + Duration::from_secs(600),
+            crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+            None,
+        );
+        assert!(!manager.is_model_available_with_min_percent("gemini-3-flash", 0));
+    }
+
+    #[test]
+    fn test_in_flight_guard_reserves_atomically() {
+        let counter = Arc::new(AtomicU32::new(0));
+        let first = InFlightGuard::try_acquire(counter.clone(), Some(1)).unwrap();
+        assert!(InFlightGuard::try_acquire(counter.clone(), Some(1)).is_none());
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+
+        drop(first);
+        let _second = InFlightGuard::try_acquire(counter.clone(), Some(1)).unwrap();
+        let _unlimited = InFlightGuard::try_acquire(counter.clone(), None).unwrap();
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_response_time_weighted_selection() {
+        use crate::proxy::sticky_config::SchedulingMode;
+
+        let data_dir = std::env::temp_dir().join(format!("tm-rtw-{}", uuid::Uuid::new_v4()));
+        let manager = TokenManager::new(data_dir);
+        for token in [
+            tier_token("slow", "ULTRA"),
+            tier_token("mid", "ULTRA"),
+            tier_token("fast", "PRO"),
+            tier_token("new", "PRO"),
+        ] {
+            manager.tokens.insert(token.account_id.clone(), token);
+        }
+        manager.sticky_config.write().await.mode = SchedulingMode::ResponseTimeWeighted;
+        for _ in 0..5 {
+            manager.record_latency("slow", 20_000);
+            manager.record_latency("mid", 2_000);
+            manager.record_latency("fast", 500);
+        }
+        // 新账号取已采样账号的中位数，而不是固定的惩罚值
+        assert_eq!(manager.latency_score("new"), None);
+
+        for _ in 0..3 {
+            let (_, _, email, _) = manager.get_token_internal("claude", false, None, "claude-sonnet-4-5").await.unwrap();
+            assert_eq!(email, "fast@example.com");
+        }
+
+        // 绑定账号延迟超过池中位数 (2000ms) 的 3 倍时重新绑定
+        manager.session_accounts.insert("s1".to_string(), SessionMeta::new("slow".to_string()));
+        let (_, _, email, _) = manager.get_token_internal("claude", false, Some("s1"), "claude-sonnet-4-5").await.unwrap();
+        assert_eq!(email, "fast@example.com");
+        assert_eq!(manager.session_accounts.get("s1").unwrap().account_id, "fast");
+    }
+
+    #[tokio::test]
+    async fn test_rotation_cooldown_staggers_rebinding() {
+        let data_dir = std::env::temp_dir().join(format!("tm-cooldown-{}", uuid::Uuid::new_v4()));
+        let manager = TokenManager::new(data_dir);
+        for token in [tier_token("limited", "ULTRA"), tier_token("b", "PRO"), tier_token("c", "PRO")] {
+            manager.tokens.insert(token.account_id.clone(), token);
+        }
+        for sid in ["s1", "s2"] {
+            manager.session_accounts.insert(sid.to_string(), SessionMeta::new("limited".to_string()));
+        }
+        manager.rate_limit_tracker.set_lockout_until(
+            "limited",
+            std::time::SystemTime::now() + Duration::from_secs(600),
+            crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+            None,
+        );
+
+        // 两个会话同时因限流解绑，应分散到不同账号
+        let (_, _, first, _) = manager.get_token_internal("claude", false, Some("s1"), "claude-sonnet-4-5").await.unwrap();
+        let (_, _, second, _) = manager.get_token_internal("claude", false, Some("s2"), "claude-sonnet-4-5").await.unwrap();
+        assert_ne!(first, "limited@example.com");
+        assert_ne!(second, "limited@example.com");
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_session_token_usage_accumulates() {
+        let manager = TokenManager::new(std::env::temp_dir().join(format!("tm-usage-{}", uuid::Uuid::new_v4())));
+        record_session_tokens(&manager.session_token_usage, "s1".to_string(), 100, 20);
+        record_session_tokens(&manager.session_token_usage, "s1".to_string(), 50, 5);
+        record_session_tokens(&manager.session_token_usage, "s2".to_string(), 1000, 300);
+
+        let s1 = manager.session_token_usage("s1").unwrap();
+        assert_eq!((s1.input_tokens, s1.output_tokens, s1.request_count), (150, 25, 2));
+        assert!(manager.session_token_usage("missing").is_none());
+
+        let all = manager.list_session_token_usage();
+        assert_eq!(all.iter().map(|u| u.session_id.as_str()).collect::<Vec<_>>(), vec!["s2", "s1"]);
+    }
+
+    #[test]
+    fn test_record_token_usage_broadcasts_account_usage() {
+        let manager = TokenManager::new(std::env::temp_dir().join(format!("tm-usage-ev-{}", uuid::Uuid::new_v4())));
+        let token = tier_token("acc", "PRO");
+        let email = token.email.clone();
+        manager.tokens.insert("acc".to_string(), token);
+        let mut rx = manager.subscribe_token_usage();
+
+        manager.record_token_usage(Some(&email), Some("s1".to_string()), 100, 20);
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            TokenUsageEvent { account_id: "acc".to_string(), input_tokens: 100, output_tokens: 20 }
+        );
+        assert_eq!(manager.session_token_usage("s1").unwrap().request_count, 1);
+
+        // 未知账号只记录会话用量
+        manager.record_token_usage(Some("unknown@example.com"), None, 10, 0);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_purge_account_removes_runtime_data() {
+        let manager = TokenManager::new(std::env::temp_dir().join(format!("tm-purge-{}", uuid::Uuid::new_v4())));
+        for token in [tier_token("gone", "PRO"), tier_token("kept", "PRO")] {
+            manager.tokens.insert(token.account_id.clone(), token);
+        }
+        manager.session_accounts.insert("s1".to_string(), session("gone", 10));
+        manager.session_accounts.insert("s2".to_string(), session("kept", 10));
+        record_session_tokens(&manager.session_token_usage, "s1".to_string(), 100, 20);
+        manager.rate_limit_tracker.set_lockout_until(
+            "gone",
+            std::time::SystemTime::now() + Duration::from_secs(600),
+            crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+            None,
+        );
+
+        assert_eq!(manager.purge_account("gone"), 1);
+        assert!(!manager.tokens.contains_key("gone"));
+        assert!(!manager.is_rate_limited_by_account_id("gone"));
+        assert!(manager.session_token_usage("s1").is_none());
+        assert_eq!(manager.list_sessions().len(), 1);
+        assert_eq!(manager.list_sessions()[0].account_id, "kept");
+    }
+
+    #[test]
+    fn test_rate_limit_changes_are_broadcast() {
+        let manager = TokenManager::new(std::env::temp_dir().join(format!("tm-rl-events-{}", uuid::Uuid::new_v4())));
+        let token = tier_token("limited", "PRO");
+        manager.tokens.insert(token.account_id.clone(), token);
+        let mut rx = manager.rate_limit_events().subscribe();
+
+        manager.mark_rate_limited("limited@example.com", 429, Some("30"), "");
+        match rx.try_recv().unwrap() {
+            RateLimitEvent::Updated { status } => {
+                assert_eq!(status.account_id, "limited");
+                assert!(status.rate_limit_remaining_secs > 0);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        manager.clear_all_rate_limits();
+        assert!(matches!(rx.try_recv().unwrap(), RateLimitEvent::Cleared { account_id: None }));
+    }
+
+    #[test]
+    fn test_upstream_outcome_classification() {
+        let manager = TokenManager::new(std::env::temp_dir().join(format!("tm-outcome-{}", uuid::Uuid::new_v4())));
+        // 客户端错误不计入错误预算
+        manager.record_upstream_outcome("acc", Some(400));
+        assert_eq!(manager.error_budget.error_rate("acc"), 0.0);
+
+        manager.record_upstream_outcome("acc", Some(200));
+        manager.record_upstream_outcome("acc", None);
+        assert_eq!(manager.error_budget.error_rate("acc"), 0.5);
+    }
+
+    #[test]
+    fn test_upstream_success_closes_half_open_breaker() {
+        let manager = TokenManager::new(std::env::temp_dir().join(format!("tm-breaker-{}", uuid::Uuid::new_v4())));
+        manager.rate_limit_tracker.force_breaker_half_open("acc");
+        assert_eq!(manager.rate_limit_tracker.breaker_snapshot("acc").state, crate::proxy::rate_limit::BreakerState::HalfOpen);
+
+        // Gemini / OpenAI 处理器只上报结果，不单独调用 mark_account_success
+        manager.record_upstream_outcome("acc", Some(200));
+        let snapshot = manager.rate_limit_tracker.breaker_snapshot("acc");
+        assert_eq!(snapshot.state, crate::proxy::rate_limit::BreakerState::Closed);
+        assert_eq!(snapshot.failure_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sync_account_file() {
+        let data_dir = std::env::temp_dir().join(format!("tm-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(data_dir.join("accounts")).unwrap();
+        let manager = TokenManager::new(data_dir.clone());
+        let path = data_dir.join("accounts").join("acc-1.json");
+        let account = |access_token: &str| {
+            serde_json::json!({
+                "id": "acc-1",
+                "email": "a@example.com",
+                "token": {
+                    "access_token": access_token,
+                    "refresh_token": "refresh",
+                    "expires_in": 3600,
+                    "expiry_timestamp": 4102444800i64
+                }
+            })
+            .to_string()
+        };
+
+        std::fs::write(&path, account("first")).unwrap();
+        let mut diff = AccountsDiff::default();
+        manager.sync_account_file(&path, &mut diff).await;
+        assert_eq!(diff.added, vec!["acc-1"]);
+
+        std::fs::write(&path, account("second")).unwrap();
+        let mut diff = AccountsDiff::default();
+        manager.sync_account_file(&path, &mut diff).await;
+        assert_eq!(diff.updated, vec!["acc-1"]);
+        assert_eq!(manager.tokens.get("acc-1").unwrap().access_token, "second");
+
+        std::fs::remove_file(&path).unwrap();
+        let mut diff = AccountsDiff::default();
+        manager.sync_account_file(&path, &mut diff).await;
+        assert_eq!(diff.removed, vec!["acc-1"]);
+        assert!(manager.tokens.is_empty());
+
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_sync_account_file_purges_on_reload_failure() {
+        let data_dir = std::env::temp_dir().join(format!("tm-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(data_dir.join("accounts")).unwrap();
+        let manager = TokenManager::new(data_dir.clone());
+        let path = data_dir.join("accounts").join("broken.json");
+        manager.tokens.insert("broken".to_string(), tier_token("broken", "PRO"));
+        manager.session_accounts.insert("s1".to_string(), session("broken", 10));
+        manager.rate_limit_tracker.set_lockout_until(
+            "broken",
+            std::time::SystemTime::now() + Duration::from_secs(600),
+            crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+            None,
+        );
+
+        // 文件损坏导致重新加载失败时，会话绑定与限流记录也应一并清理
+        std::fs::write(&path, "{not json").unwrap();
+        let mut diff = AccountsDiff::default();
+        manager.sync_account_file(&path, &mut diff).await;
+        assert_eq!(diff.removed, vec!["broken"]);
+        assert!(manager.tokens.is_empty());
+        assert!(manager.list_sessions().is_empty());
+        assert!(!manager.is_rate_limited_by_account_id("broken"));
+
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn test_evict_expired_sessions() {
+        let sessions = DashMap::new();
+        sessions.insert("fresh".to_string(), session("acc-1", 10));
+        sessions.insert("stale".to_string(), session("acc-2", 7200));
+
+        assert_eq!(evict_expired_sessions(&sessions, 0), 0);
+        assert_eq!(sessions.len(), 2);
+
+        assert_eq!(evict_expired_sessions(&sessions, 3600), 1);
+        assert!(sessions.contains_key("fresh"));
+        assert!(!sessions.contains_key("stale"));
+    }
+}
@@ -3,8 +3,13 @@
 
 use reqwest::{header, Client, Response, StatusCode};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use tokio::time::Duration;
 
+use crate::proxy::upstream_proxy::PooledClient;
+
 // Cloud Code v1internal endpoints (fallback order: prod → daily)
 // 优先使用稳定的 prod 端点，避免影响缓存命中率
 const V1_INTERNAL_BASE_URL_PROD: &str = "https://cloudcode-pa.googleapis.com/v1internal";
@@ -15,11 +20,31 @@ const V1_INTERNAL_BASE_URL_FALLBACKS: [&str; 2] = [
 ];
 
 pub struct UpstreamClient {
-    http_client: Client,
+    http_client: std::sync::RwLock<Arc<PooledClient>>, // 所用代理进入冷却后重新构建
+    proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>,
+    mtls_config: Option<crate::proxy::config::MtlsConfig>,
+    model_timeouts: Arc<RwLock<HashMap<String, u64>>>, // 按模型覆盖的请求超时(秒)
 }
 
 impl UpstreamClient {
-    pub fn new(proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>) -> Self {
+    pub fn new(
+        proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>,
+        mtls_config: Option<&crate::proxy::config::MtlsConfig>,
+    ) -> Self {
+        let http_client = Self::build_client(proxy_config.as_ref(), mtls_config);
+        Self {
+            http_client: std::sync::RwLock::new(Arc::new(http_client)),
+            proxy_config,
+            mtls_config: mtls_config.cloned(),
+            model_timeouts: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// 按代理池选择代理并构建 Client
+    fn build_client(
+        proxy_config: Option<&crate::proxy::config::UpstreamProxyConfig>,
+        mtls_config: Option<&crate::proxy::config::MtlsConfig>,
+    ) -> PooledClient {
         let mut builder = Client::builder()
             // Connection settings (优化连接复用，减少建立开销)
             .connect_timeout(Duration::from_secs(20))
@@ -29,18 +54,71 @@ impl UpstreamClient {
             .timeout(Duration::from_secs(600))
             .user_agent("antigravity/1.11.9 windows/amd64");
 
+        let mut proxy_url = None;
         if let Some(config) = proxy_config {
-            if config.enabled && !config.url.is_empty() {
-                if let Ok(proxy) = reqwest::Proxy::all(&config.url) {
+            if let Some(url) = crate::proxy::upstream_proxy::UpstreamProxyPool::global().next_url(config) {
+                if let Ok(proxy) = reqwest::Proxy::all(&url) {
                     builder = builder.proxy(proxy);
-                    tracing::info!("UpstreamClient enabled proxy: {}", config.url);
+                    tracing::info!("UpstreamClient enabled proxy: {}", url);
+                    proxy_url = Some(url);
                 }
             }
         }
 
-        let http_client = builder.build().expect("Failed to create HTTP client");
+        if let Some(mtls) = mtls_config {
+            // 配置已在启动反代服务时校验，此处失败仅记录日志
+            match mtls.load() {
+                Ok((identity, ca)) => {
+                    builder = builder.identity(identity);
+                    if let Some(ca) = ca {
+                        builder = builder.add_root_certificate(ca);
+                    }
+                    tracing::info!("UpstreamClient enabled mTLS client certificate");
+                }
+                Err(e) => tracing::error!("UpstreamClient mTLS setup failed: {}", e),
+            }
+        }
 
-        Self { http_client }
+        let client = builder.build().expect("Failed to create HTTP client");
+        PooledClient { client, proxy_url }
+    }
+
+    /// 当前共享 Client；所用代理连续失败进入冷却时切换到代理池中的下一个代理
+    fn client(&self) -> Arc<PooledClient> {
+        let current = match self.http_client.read() {
+            Ok(client) => client.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        };
+        if !current.needs_reselect() {
+            return current;
+        }
+
+        let Ok(mut slot) = self.http_client.write() else {
+            return current;
+        };
+        // 其他请求可能已完成切换
+        if Arc::ptr_eq(&slot, &current) {
+            tracing::warn!(
+                "UpstreamClient proxy {} is cooling down, re-selecting upstream proxy",
+                current.proxy_url.as_deref().unwrap_or_default()
+            );
+            *slot = Arc::new(Self::build_client(self.proxy_config.as_ref(), self.mtls_config.as_ref()));
+        }
+        slot.clone()
+    }
+
+    /// 共享按模型的超时配置 (随模型映射热更新)
+    pub fn with_model_timeouts(mut self, model_timeouts: Arc<RwLock<HashMap<String, u64>>>) -> Self {
+        self.model_timeouts = model_timeouts;
+        self
+    }
+
+    /// 请求体中模型的超时覆盖 (未配置时使用 Client 默认超时)
+    /// 仅限制等待响应头的时间，不限制流式响应体的传输时长
+    async fn model_timeout(&self, body: &Value) -> Option<Duration> {
+        let model = body.get("model").and_then(|v| v.as_str())?;
+        let secs = self.model_timeouts.read().await.get(model).copied()?;
+        Some(Duration::from_secs(secs.max(5)))
     }
 
     /// 构建 v1internal URL
@@ -77,6 +155,21 @@ impl UpstreamClient {
         access_token: &str,
         body: Value,
         query_string: Option<&str>,
+    ) -> Result<Response, String> {
+        let client = self.client();
+        self.call_v1_internal_with_client(&client, method, access_token, body, query_string)
+            .await
+    }
+
+    /// 调用 v1internal API（使用指定的 Client，如账号专属连接池）
+    /// 发送结果回报给上游代理池，用于代理健康度统计与切换
+    pub async fn call_v1_internal_with_client(
+        &self,
+        http_client: &PooledClient,
+        method: &str,
+        access_token: &str,
+        body: Value,
+        query_string: Option<&str>,
     ) -> Result<Response, String> {
         // 构建 Headers (所有端点复用)
         let mut headers = header::HeaderMap::new();
@@ -93,7 +186,10 @@ impl UpstreamClient {
             header::USER_AGENT,
             header::HeaderValue::from_static("antigravity/1.11.9 windows/amd64"),
         );
+        crate::proxy::otel::inject_trace_headers(&mut headers);
+        crate::proxy::middleware::request_id::inject_header(&mut headers);
 
+        let timeout = self.model_timeout(&body).await;
         let mut last_err: Option<String> = None;
 
         // 遍历所有端点，失败时自动切换
@@ -101,13 +197,30 @@ impl UpstreamClient {
             let url = Self::build_url(base_url, method, query_string);
             let has_next = idx + 1 < V1_INTERNAL_BASE_URL_FALLBACKS.len();
 
-            let response = self
-                .http_client
-                .post(&url)
-                .headers(headers.clone())
-                .json(&body)
-                .send()
-                .await;
+            let request = http_client.client.post(&url).headers(headers.clone()).json(&body);
+            let response = match timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, request.send()).await {
+                    Ok(response) => response,
+                    Err(_) => {
+                        if let Some(proxy_url) = http_client.proxy_url.as_deref() {
+                            crate::proxy::upstream_proxy::UpstreamProxyPool::global().record_failure(proxy_url);
+                        }
+                        let msg = format!(
+                            "HTTP request at {} timed out after {}s waiting for response headers",
+                            base_url,
+                            timeout.as_secs()
+                        );
+                        tracing::debug!("{}", msg);
+                        last_err = Some(msg);
+                        if !has_next {
+                            break;
+                        }
+                        continue;
+                    }
+                },
+                None => request.send().await,
+            };
+            http_client.report(&response);
 
             match response {
                 Ok(resp) => {
@@ -197,20 +310,23 @@ impl UpstreamClient {
             header::USER_AGENT,
             header::HeaderValue::from_static("antigravity/1.11.9 windows/amd64"),
         );
+        crate::proxy::otel::inject_trace_headers(&mut headers);
 
         let mut last_err: Option<String> = None;
+        let http_client = self.client();
 
         // 遍历所有端点，失败时自动切换
         for (idx, base_url) in V1_INTERNAL_BASE_URL_FALLBACKS.iter().enumerate() {
             let url = Self::build_url(base_url, "fetchAvailableModels", None);
 
-            let response = self
-                .http_client
+            let response = http_client
+                .client
                 .post(&url)
                 .headers(headers.clone())
                 .json(&serde_json::json!({}))
                 .send()
                 .await;
+            http_client.report(&response);
 
             match response {
                 Ok(resp) => {
@@ -286,4 +402,21 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_model_timeout_override() {
+        let timeouts = Arc::new(RwLock::new(HashMap::from([("gemini-3-pro-high".to_string(), 600)])));
+        let client = UpstreamClient::new(None, None).with_model_timeouts(timeouts.clone());
+
+        let body = serde_json::json!({ "model": "gemini-3-pro-high" });
+        assert_eq!(client.model_timeout(&body).await, Some(Duration::from_secs(600)));
+        assert_eq!(client.model_timeout(&serde_json::json!({ "model": "gemini-3-flash" })).await, None);
+
+        // 热更新后立即生效
+        timeouts.write().await.insert("gemini-3-flash".to_string(), 30);
+        assert_eq!(
+            client.model_timeout(&serde_json::json!({ "model": "gemini-3-flash" })).await,
+            Some(Duration::from_secs(30))
+        );
+    }
+
 }
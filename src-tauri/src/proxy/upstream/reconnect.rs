@@ -0,0 +1,255 @@
+// 上游流式响应断线续传
+// 上游 SSE 在生成中途断开 (reqwest body / decode 错误) 时，等待 reconnect_delay_ms 后重新请求，
+// 并把已转发给客户端的文本作为 model 轮次附加到 contents 末尾，让模型从断点继续生成
+// (v1internal 不支持 Range 续传)。新流拼接在已完整转发的行之后，断开时残留的半行被丢弃
+// 已转发函数调用等非文本内容时无法安全续写，直接返回原错误
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::proxy::config::RetryConfig;
+use crate::proxy::upstream::client::UpstreamClient;
+
+pub type UpstreamByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>;
+
+/// 记录已转发给客户端的内容，只转发完整的行
+#[derive(Default)]
+struct DeliveryTracker {
+    pending: BytesMut,
+    delivered_bytes: u64,
+    text: String,
+    /// 已转发函数调用、内联数据等无法作为文本续写的内容时为 false
+    resumable: bool,
+}
+
+impl DeliveryTracker {
+    fn new() -> Self {
+        Self {
+            resumable: true,
+            ..Default::default()
+        }
+    }
+
+    /// 追加数据块，返回可以转发的完整行
+    fn push(&mut self, chunk: &[u8]) -> Option<Bytes> {
+        self.pending.extend_from_slice(chunk);
+        let end = self.pending.iter().rposition(|&b| b == b'\n')? + 1;
+        let complete = self.pending.split_to(end).freeze();
+        self.record(&complete);
+        Some(complete)
+    }
+
+    /// 流正常结束时转发剩余数据
+    fn flush(&mut self) -> Option<Bytes> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let rest = self.pending.split().freeze();
+        self.record(&rest);
+        Some(rest)
+    }
+
+    /// 断线时丢弃未转发的半行
+    fn discard_partial(&mut self) -> usize {
+        let len = self.pending.len();
+        self.pending.clear();
+        len
+    }
+
+    fn record(&mut self, data: &[u8]) {
+        self.delivered_bytes += data.len() as u64;
+        for line in String::from_utf8_lossy(data).lines() {
+            let Some(payload) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let Ok(event) = serde_json::from_str::<Value>(payload.trim()) else {
+                continue;
+            };
+            // v1internal 响应包裹在 response 字段中
+            let event = event.get("response").unwrap_or(&event);
+            let parts = event
+                .pointer("/candidates/0/content/parts")
+                .and_then(|p| p.as_array())
+                .cloned()
+                .unwrap_or_default();
+            for part in parts {
+                if part.get("thought").and_then(|t| t.as_bool()).unwrap_or(false) {
+                    continue;
+                }
+                match part.get("text").and_then(|t| t.as_str()) {
+                    Some(text) => self.text.push_str(text),
+                    None => self.resumable = false,
+                }
+            }
+        }
+    }
+}
+
+/// 续写请求：已生成的文本作为 model 轮次附加在末尾
+fn continuation_body(body: &Value, delivered_text: &str) -> Value {
+    let mut body = body.clone();
+    if delivered_text.is_empty() {
+        return body;
+    }
+    if let Some(contents) = body.pointer_mut("/request/contents").and_then(|c| c.as_array_mut()) {
+        contents.push(json!({ "role": "model", "parts": [{ "text": delivered_text }] }));
+    }
+    body
+}
+
+pub struct StreamReconnector {
+    upstream: Arc<UpstreamClient>,
+    client: Option<Arc<crate::proxy::upstream_proxy::PooledClient>>,
+    access_token: String,
+    /// 原始 v1internal 请求体
+    body: Value,
+    max_attempts: u32,
+    delay: Duration,
+    trace_id: String,
+}
+
+impl StreamReconnector {
+    pub fn new(
+        upstream: Arc<UpstreamClient>,
+        client: Option<Arc<crate::proxy::upstream_proxy::PooledClient>>,
+        access_token: String,
+        body: Value,
+        retry: &RetryConfig,
+        trace_id: String,
+    ) -> Self {
+        Self {
+            upstream,
+            client,
+            access_token,
+            body,
+            max_attempts: retry.max_reconnect_attempts,
+            delay: Duration::from_millis(retry.reconnect_delay_ms),
+            trace_id,
+        }
+    }
+
+    /// 包装上游 SSE 字节流，断线时自动续传；重连次数用尽后返回原始错误
+    pub fn wrap(self, stream: UpstreamByteStream) -> UpstreamByteStream {
+        if self.max_attempts == 0 {
+            return stream;
+        }
+        Box::pin(async_stream::stream! {
+            let mut current = stream;
+            let mut tracker = DeliveryTracker::new();
+            let mut attempts = 0;
+            loop {
+                match current.next().await {
+                    Some(Ok(chunk)) => {
+                        if let Some(complete) = tracker.push(&chunk) {
+                            yield Ok(complete);
+                        }
+                    }
+                    None => {
+                        if let Some(rest) = tracker.flush() {
+                            yield Ok(rest);
+                        }
+                        break;
+                    }
+                    Some(Err(e)) => {
+                        if !(e.is_body() || e.is_decode()) || !tracker.resumable {
+                            yield Err(e);
+                            break;
+                        }
+                        let discarded = tracker.discard_partial();
+                        let mut reconnected = None;
+                        while attempts < self.max_attempts {
+                            attempts += 1;
+                            tracing::warn!(
+                                "[{}] Upstream stream dropped after {} bytes ({}), reconnecting {}/{} (discarded {} partial bytes)",
+                                self.trace_id, tracker.delivered_bytes, e, attempts, self.max_attempts, discarded
+                            );
+                            tokio::time::sleep(self.delay).await;
+                            match self.reconnect(&tracker.text).await {
+                                Ok(stream) => {
+                                    reconnected = Some(stream);
+                                    break;
+                                }
+                                Err(err) => tracing::warn!("[{}] Stream reconnect failed: {}", self.trace_id, err),
+                            }
+                        }
+                        match reconnected {
+                            Some(stream) => {
+                                tracing::info!("[{}] Stream resumed after {} bytes", self.trace_id, tracker.delivered_bytes);
+                                current = stream;
+                            }
+                            None => {
+                                yield Err(e);
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    async fn reconnect(&self, delivered_text: &str) -> Result<UpstreamByteStream, String> {
+        let body = continuation_body(&self.body, delivered_text);
+        let response = match &self.client {
+            Some(client) => {
+                self.upstream
+                    .call_v1_internal_with_client(client, "streamGenerateContent", &self.access_token, body, Some("alt=sse"))
+                    .await?
+            }
+            None => {
+                self.upstream
+                    .call_v1_internal("streamGenerateContent", &self.access_token, body, Some("alt=sse"))
+                    .await?
+            }
+        };
+        if !response.status().is_success() {
+            return Err(format!("upstream returned {}", response.status()));
+        }
+        Ok(Box::pin(response.bytes_stream()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_forwards_complete_lines_only() {
+        let mut tracker = DeliveryTracker::new();
+        let first = r#"data: {"response":{"candidates":[{"content":{"parts":[{"text":"Hel"}]}}]}}"#;
+        assert!(tracker.push(first.as_bytes()).is_none());
+        let forwarded = tracker.push(b"\n\ndata: {\"respo").unwrap();
+        assert_eq!(forwarded.len(), first.len() + 2);
+        assert_eq!(tracker.text, "Hel");
+        assert_eq!(tracker.discard_partial(), "data: {\"respo".len());
+        assert_eq!(tracker.delivered_bytes, forwarded.len() as u64);
+        assert!(tracker.resumable);
+    }
+
+    #[test]
+    fn test_tracker_skips_thoughts_and_blocks_function_calls() {
+        let mut tracker = DeliveryTracker::new();
+        tracker.push(br#"data: {"candidates":[{"content":{"parts":[{"text":"hmm","thought":true},{"text":"ok"}]}}]}
+"#);
+        assert_eq!(tracker.text, "ok");
+        assert!(tracker.resumable);
+
+        tracker.push(br#"data: {"candidates":[{"content":{"parts":[{"functionCall":{"name":"f","args":{}}}]}}]}
+"#);
+        assert!(!tracker.resumable);
+    }
+
+    #[test]
+    fn test_continuation_body_appends_model_turn() {
+        let body = json!({ "request": { "contents": [{ "role": "user", "parts": [{ "text": "hi" }] }] } });
+        let resumed = continuation_body(&body, "Hello wor");
+        let contents = resumed["request"]["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(contents[1]["parts"][0]["text"], "Hello wor");
+        assert_eq!(continuation_body(&body, ""), body);
+    }
+}
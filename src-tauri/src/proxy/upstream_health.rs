@@ -0,0 +1,158 @@
+// 上游健康检查
+// 每 30 秒向 Gemini API 发送 HEAD 请求，记录可达性与延迟；连续 3 次不可达时推送 Tauri 事件
+// (has_available_account 只反映内存中的账号状态，无法发现上游本身不可达)
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio::sync::RwLock;
+
+const HEALTH_CHECK_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
+const CHECK_INTERVAL_SECS: u64 = 30;
+const CHECK_TIMEOUT_SECS: u64 = 10;
+/// 连续失败达到该次数时推送 proxy://upstream_unreachable
+const UNREACHABLE_THRESHOLD: u32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct UpstreamHealth {
+    pub reachable: bool,
+    pub latency_ms: u64,
+    pub last_checked: Instant,
+    pub consecutive_failures: u32,
+}
+
+impl Default for UpstreamHealth {
+    fn default() -> Self {
+        Self {
+            reachable: true,
+            latency_ms: 0,
+            last_checked: Instant::now(),
+            consecutive_failures: 0,
+        }
+    }
+}
+
+impl UpstreamHealth {
+    /// 记录一次检查结果 (latency 为 None 表示不可达)，返回是否刚好达到不可达阈值
+    fn record(&mut self, latency: Option<Duration>) -> bool {
+        self.last_checked = Instant::now();
+        match latency {
+            Some(latency) => {
+                self.reachable = true;
+                self.latency_ms = latency.as_millis() as u64;
+                self.consecutive_failures = 0;
+                false
+            }
+            None => {
+                self.reachable = false;
+                self.consecutive_failures += 1;
+                self.consecutive_failures == UNREACHABLE_THRESHOLD
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> UpstreamHealthSnapshot {
+        UpstreamHealthSnapshot {
+            reachable: self.reachable,
+            latency_ms: self.latency_ms,
+            last_checked_secs_ago: self.last_checked.elapsed().as_secs(),
+            consecutive_failures: self.consecutive_failures,
+        }
+    }
+}
+
+/// 上游健康状态 (用于前端展示)
+#[derive(Debug, Clone, Serialize)]
+pub struct UpstreamHealthSnapshot {
+    pub reachable: bool,
+    pub latency_ms: u64,
+    pub last_checked_secs_ago: u64,
+    pub consecutive_failures: u32,
+}
+
+pub struct UpstreamHealthChecker {
+    health: Arc<RwLock<UpstreamHealth>>,
+    app_handle: Option<tauri::AppHandle>,
+    client: reqwest::Client,
+}
+
+impl UpstreamHealthChecker {
+    pub fn new(health: Arc<RwLock<UpstreamHealth>>, app_handle: Option<tauri::AppHandle>) -> Self {
+        Self {
+            health,
+            app_handle,
+            client: crate::utils::http::create_client(CHECK_TIMEOUT_SECS),
+        }
+    }
+
+    /// 启动后台检查任务，返回的句柄在停止服务时 abort
+    pub fn start(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                self.check().await;
+            }
+        })
+    }
+
+    /// 任何非 5xx 响应都视为可达 (未携带 key 时上游会返回 403)
+    async fn probe(&self) -> Option<Duration> {
+        let start = Instant::now();
+        match self.client.head(HEALTH_CHECK_URL).send().await {
+            Ok(resp) if !resp.status().is_server_error() => Some(start.elapsed()),
+            Ok(resp) => {
+                tracing::warn!("[UpstreamHealth] Upstream returned {}", resp.status());
+                None
+            }
+            Err(e) => {
+                tracing::warn!("[UpstreamHealth] Upstream unreachable: {}", e);
+                None
+            }
+        }
+    }
+
+    async fn check(&self) {
+        let latency = self.probe().await;
+        let (became_unreachable, snapshot) = {
+            let mut health = self.health.write().await;
+            let became_unreachable = health.record(latency);
+            (became_unreachable, health.snapshot())
+        };
+        if !became_unreachable {
+            return;
+        }
+
+        tracing::error!(
+            "[UpstreamHealth] Upstream unreachable for {} consecutive checks",
+            snapshot.consecutive_failures
+        );
+        if let Some(app) = &self.app_handle {
+            let _ = app.emit("proxy://upstream_unreachable", &snapshot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_signals_threshold_once() {
+        let mut health = UpstreamHealth::default();
+        assert!(!health.record(Some(Duration::from_millis(120))));
+        assert_eq!(health.latency_ms, 120);
+
+        assert!(!health.record(None));
+        assert!(!health.record(None));
+        assert!(health.record(None));
+        assert!(!health.reachable);
+        // 持续不可达时不重复推送
+        assert!(!health.record(None));
+        assert_eq!(health.consecutive_failures, 4);
+
+        assert!(!health.record(Some(Duration::from_millis(80))));
+        assert!(health.reachable);
+        assert_eq!(health.consecutive_failures, 0);
+    }
+}
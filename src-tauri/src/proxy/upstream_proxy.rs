@@ -0,0 +1,276 @@
+// 上游代理池
+// 配置多个上游代理时按轮询选择，连续失败 3 次的代理跳过 5 分钟
+// 全部代理都在冷却中时选择最早恢复的一个，不会因代理池耗尽而直接失败
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::proxy::config::UpstreamProxyConfig;
+
+/// 连续失败达到该次数后进入冷却
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// 冷却时长
+const COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+static GLOBAL_POOL: Lazy<UpstreamProxyPool> = Lazy::new(UpstreamProxyPool::default);
+
+#[derive(Debug, Clone)]
+struct ProxyEntry {
+    url: String,
+    consecutive_failures: u32,
+    total_failures: u64,
+    skip_until: Option<Instant>,
+}
+
+impl ProxyEntry {
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            consecutive_failures: 0,
+            total_failures: 0,
+            skip_until: None,
+        }
+    }
+
+    fn is_available(&self, now: Instant) -> bool {
+        self.skip_until.map(|until| now >= until).unwrap_or(true)
+    }
+}
+
+/// 单个上游代理的健康状态 (get_upstream_proxy_status)
+#[derive(Debug, Clone, Serialize)]
+pub struct UpstreamProxyStatus {
+    pub url: String,
+    pub healthy: bool,
+    pub consecutive_failures: u32,
+    pub total_failures: u64,
+    pub cooldown_remaining_secs: u64,
+}
+
+#[derive(Default)]
+pub struct UpstreamProxyPool {
+    entries: Mutex<Vec<ProxyEntry>>,
+    cursor: AtomicUsize,
+}
+
+impl UpstreamProxyPool {
+    pub fn global() -> &'static UpstreamProxyPool {
+        &GLOBAL_POOL
+    }
+
+    /// 按配置同步代理列表：保留仍在配置中的代理的健康状态
+    fn sync(entries: &mut Vec<ProxyEntry>, urls: &[String]) {
+        if entries.len() == urls.len() && entries.iter().zip(urls).all(|(e, u)| &e.url == u) {
+            return;
+        }
+        let mut previous = std::mem::take(entries);
+        *entries = urls
+            .iter()
+            .map(|url| match previous.iter().position(|e| &e.url == url) {
+                Some(idx) => previous.swap_remove(idx),
+                None => ProxyEntry::new(url.clone()),
+            })
+            .collect();
+    }
+
+    /// 选择下一个代理地址；未启用代理时返回 None
+    pub fn next_url(&self, config: &UpstreamProxyConfig) -> Option<String> {
+        self.next_url_at(config, Instant::now())
+    }
+
+    fn next_url_at(&self, config: &UpstreamProxyConfig, now: Instant) -> Option<String> {
+        let urls = config.proxy_urls();
+        if urls.is_empty() {
+            return None;
+        }
+        let mut entries = self.entries.lock().ok()?;
+        Self::sync(&mut entries, &urls);
+
+        let start = self.cursor.fetch_add(1, Ordering::Relaxed);
+        let len = entries.len();
+        (0..len)
+            .map(|offset| &entries[(start + offset) % len])
+            .find(|e| e.is_available(now))
+            .or_else(|| entries.iter().min_by_key(|e| e.skip_until))
+            .map(|e| e.url.clone())
+    }
+
+    /// 代理当前是否可用 (不在冷却中)；长期复用的 Client 据此判断是否需要重新选择代理
+    pub fn is_available(&self, url: &str) -> bool {
+        let now = Instant::now();
+        self.entries
+            .lock()
+            .map(|entries| entries.iter().find(|e| e.url == url).map(|e| e.is_available(now)).unwrap_or(true))
+            .unwrap_or(true)
+    }
+
+    pub fn record_success(&self, url: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if let Some(entry) = entries.iter_mut().find(|e| e.url == url) {
+                entry.consecutive_failures = 0;
+                entry.skip_until = None;
+            }
+        }
+    }
+
+    pub fn record_failure(&self, url: &str) {
+        self.record_failure_at(url, Instant::now());
+    }
+
+    fn record_failure_at(&self, url: &str, now: Instant) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if let Some(entry) = entries.iter_mut().find(|e| e.url == url) {
+                entry.consecutive_failures += 1;
+                entry.total_failures += 1;
+                if entry.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                    entry.skip_until = Some(now + COOLDOWN);
+                    entry.consecutive_failures = 0;
+                    tracing::warn!(
+                        "[UpstreamProxy] {} failed {} times in a row, skipping for {}s",
+                        url,
+                        MAX_CONSECUTIVE_FAILURES,
+                        COOLDOWN.as_secs()
+                    );
+                }
+            }
+        }
+    }
+
+    /// 按请求结果更新代理健康度：连接失败与超时计为代理故障，其余结果视为代理可用
+    pub fn report<T>(&self, url: Option<&str>, result: &Result<T, reqwest::Error>) {
+        let Some(url) = url else { return };
+        match result {
+            Err(e) if e.is_connect() || e.is_timeout() => self.record_failure(url),
+            _ => self.record_success(url),
+        }
+    }
+
+    pub fn status(&self, config: &UpstreamProxyConfig) -> Vec<UpstreamProxyStatus> {
+        let now = Instant::now();
+        let urls = config.proxy_urls();
+        let Ok(mut entries) = self.entries.lock() else {
+            return Vec::new();
+        };
+        Self::sync(&mut entries, &urls);
+        entries
+            .iter()
+            .map(|e| UpstreamProxyStatus {
+                url: e.url.clone(),
+                healthy: e.is_available(now),
+                consecutive_failures: e.consecutive_failures,
+                total_failures: e.total_failures,
+                cooldown_remaining_secs: e
+                    .skip_until
+                    .map(|until| until.saturating_duration_since(now).as_secs())
+                    .unwrap_or(0),
+            })
+            .collect()
+    }
+}
+
+/// 使用代理池构建的 Client 及其代理地址
+pub struct PooledClient {
+    pub client: reqwest::Client,
+    pub proxy_url: Option<String>,
+}
+
+impl PooledClient {
+    /// 为 Client 配置代理池中的下一个代理
+    pub fn build(builder: reqwest::ClientBuilder, config: &UpstreamProxyConfig) -> Result<Self, String> {
+        let proxy_url = UpstreamProxyPool::global().next_url(config);
+        let builder = match &proxy_url {
+            Some(url) => builder.proxy(
+                reqwest::Proxy::all(url).map_err(|e| format!("Invalid upstream proxy url: {}", e))?,
+            ),
+            None => builder,
+        };
+        let client = builder
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        Ok(Self { client, proxy_url })
+    }
+
+    pub fn report<T>(&self, result: &Result<T, reqwest::Error>) {
+        UpstreamProxyPool::global().report(self.proxy_url.as_deref(), result);
+    }
+
+    /// 所用代理已进入冷却 (连续失败) 时返回 true，长期复用的 Client 应重新构建以切换代理
+    pub fn needs_reselect(&self) -> bool {
+        self.proxy_url
+            .as_deref()
+            .map(|url| !UpstreamProxyPool::global().is_available(url))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(urls: &[&str]) -> UpstreamProxyConfig {
+        UpstreamProxyConfig {
+            enabled: true,
+            url: String::new(),
+            urls: urls.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_round_robin_and_cooldown() {
+        let pool = UpstreamProxyPool::default();
+        let config = config(&["http://a:1", "http://b:1"]);
+        let now = Instant::now();
+
+        let first = pool.next_url_at(&config, now).unwrap();
+        let second = pool.next_url_at(&config, now).unwrap();
+        assert_ne!(first, second);
+
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            pool.record_failure_at("http://a:1", now);
+        }
+        for _ in 0..4 {
+            assert_eq!(pool.next_url_at(&config, now).as_deref(), Some("http://b:1"));
+        }
+        // 冷却结束后重新参与轮询
+        let later = now + COOLDOWN;
+        let picked: Vec<_> = (0..2).filter_map(|_| pool.next_url_at(&config, later)).collect();
+        assert!(picked.contains(&"http://a:1".to_string()));
+    }
+
+    #[test]
+    fn test_all_in_cooldown_falls_back_to_earliest() {
+        let pool = UpstreamProxyPool::default();
+        let config = config(&["http://a:1", "http://b:1"]);
+        let now = Instant::now();
+        pool.next_url_at(&config, now);
+        for _ in 0..MAX_CONSECUTIVE_FAILURES {
+            pool.record_failure_at("http://b:1", now);
+            pool.record_failure_at("http://a:1", now + Duration::from_secs(1));
+        }
+        assert_eq!(pool.next_url_at(&config, now).as_deref(), Some("http://b:1"));
+    }
+
+    #[test]
+    fn test_success_resets_failures_and_disabled_returns_none() {
+        let pool = UpstreamProxyPool::default();
+        let mut config = config(&["http://a:1"]);
+        pool.next_url(&config);
+        pool.record_failure("http://a:1");
+        pool.record_failure("http://a:1");
+        pool.record_success("http://a:1");
+        pool.record_failure("http://a:1");
+        let status = pool.status(&config);
+        assert!(status[0].healthy);
+        assert_eq!((status[0].consecutive_failures, status[0].total_failures), (1, 3));
+        assert!(pool.is_available("http://a:1"));
+        pool.record_failure("http://a:1");
+        pool.record_failure("http://a:1");
+        assert!(!pool.is_available("http://a:1"));
+
+        config.enabled = false;
+        assert_eq!(pool.next_url(&config), None);
+    }
+}
@@ -3,21 +3,16 @@ use serde_json::{json, Value};
 use tokio::time::Duration;
 
 use crate::proxy::config::UpstreamProxyConfig;
+use crate::proxy::upstream_proxy::PooledClient;
 use crate::proxy::ZaiConfig;
 
 const ZAI_PAAZ_CHAT_COMPLETIONS_URL: &str = "https://api.z.ai/api/paas/v4/chat/completions";
 
-fn build_client(upstream_proxy: UpstreamProxyConfig, timeout_secs: u64) -> Result<reqwest::Client, String> {
-    let mut builder = reqwest::Client::builder()
+fn build_client(upstream_proxy: UpstreamProxyConfig, timeout_secs: u64) -> Result<PooledClient, String> {
+    let builder = reqwest::Client::builder()
         .timeout(Duration::from_secs(timeout_secs.max(5)));
 
-    if upstream_proxy.enabled && !upstream_proxy.url.is_empty() {
-        let proxy = reqwest::Proxy::all(&upstream_proxy.url)
-            .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
-        builder = builder.proxy(proxy);
-    }
-
-    builder.build().map_err(|e| format!("Failed to build HTTP client: {}", e))
+    PooledClient::build(builder, &upstream_proxy)
 }
 
 fn is_http_url(value: &str) -> bool {
@@ -116,7 +111,7 @@ fn user_message_with_content(mut content: Vec<Value>, prompt: &str) -> Value {
 }
 
 async fn vision_chat_completion(
-    client: &reqwest::Client,
+    client: &PooledClient,
     api_key: &str,
     system_prompt: &str,
     user_content: Vec<Value>,
@@ -135,15 +130,17 @@ async fn vision_chat_completion(
         "max_tokens": 32768
     });
 
-    let resp = client
+    let result = client
+        .client
         .post(ZAI_PAAZ_CHAT_COMPLETIONS_URL)
         .bearer_auth(api_key)
         .header("X-Title", "Vision MCP Local")
         .header("Accept-Language", "en-US,en")
         .json(&body)
         .send()
-        .await
-        .map_err(|e| format!("Upstream request failed: {}", e))?;
+        .await;
+    client.report(&result);
+    let resp = result.map_err(|e| format!("Upstream request failed: {}", e))?;
 
     if !resp.status().is_success() {
         let status = resp.status().as_u16();
@@ -0,0 +1,503 @@
+// z.ai Web Reader 结果缓存
+// 工具循环中同一页面可能被反复读取，按规范化后的 URL + 其余工具参数缓存 tools/call 的 JSON-RPC result，
+// TTL 内命中时直接用缓存结果应答 (替换为本次请求的 id)，不再请求上游
+// 工具参数 no_cache: true 时跳过缓存并使已有条目失效；过期条目由后台任务定期清理
+//
+// webSearchPrime 结果去重
+// 同一 MCP 会话 (Mcp-Session-Id) 内多次搜索时，过滤掉此前已返回过的 URL；会话空闲超过 SEARCH_SESSION_IDLE_TTL 后清理
+//
+// 按域名超时 (ZaiConfig.domain_timeouts)
+// Web Reader 取 url 参数的域名，webSearchPrime 取 search_domain_filter，未配置时使用默认超时
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+/// 过期缓存条目的清理间隔
+const CACHE_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 搜索会话的空闲过期时间 (与粘性会话 TTL 无关，MCP 会话标识不是粘性会话标识)
+const SEARCH_SESSION_IDLE_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// 各 MCP 会话已返回过的搜索结果 URL (Mcp-Session-Id -> (最近活动时间, URL 集合))
+#[derive(Default)]
+pub struct SearchSeenUrls {
+    sessions: DashMap<String, (Instant, HashSet<String>)>,
+}
+
+/// webSearchPrime 单条搜索结果 (未识别字段原样保留)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchResult {
+    #[serde(alias = "url")]
+    pub link: String,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
+
+/// 缓存命中统计
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CacheStatsSnapshot {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    pub fn record_hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CacheStatsSnapshot {
+        CacheStatsSnapshot {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Web Reader tools/call 请求中与缓存相关的参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebReaderCall {
+    pub id: Value,
+    pub url: String,
+    /// 缓存键：规范化 URL + 工具名 + 除 url/no_cache 外其余参数的规范化 JSON
+    pub cache_key: String,
+    pub no_cache: bool,
+}
+
+/// 缓存键 -> (过期时间, result JSON)
+#[derive(Default)]
+pub struct WebReaderCache {
+    entries: DashMap<String, (Instant, String)>,
+}
+
+/// 规范化 URL：scheme/host 小写、去掉 fragment；无法解析时仅去除首尾空白
+pub fn normalize_url(url: &str) -> String {
+    match reqwest::Url::parse(url.trim()) {
+        Ok(mut parsed) => {
+            parsed.set_fragment(None);
+            parsed.to_string()
+        }
+        Err(_) => url.trim().to_string(),
+    }
+}
+
+/// 解析 JSON-RPC 请求体，仅识别带 url 参数的 tools/call
+pub fn parse_web_reader_call(body: &[u8]) -> Option<WebReaderCall> {
+    let request: Value = serde_json::from_slice(body).ok()?;
+    if request.get("method").and_then(|m| m.as_str()) != Some("tools/call") {
+        return None;
+    }
+    let params = request.get("params")?;
+    let arguments = params.get("arguments")?;
+    let url = normalize_url(arguments.get("url")?.as_str()?);
+
+    // return_format 等参数会改变返回内容，需要纳入缓存键
+    let mut rest = arguments.as_object()?.clone();
+    rest.remove("url");
+    rest.remove("no_cache");
+    let mut cache_key = url.clone();
+    cache_key.push('\n');
+    cache_key.push_str(params.get("name").and_then(|n| n.as_str()).unwrap_or_default());
+    cache_key.push('\n');
+    crate::proxy::dedup::write_canonical(&Value::Object(rest), &mut cache_key);
+
+    Some(WebReaderCall {
+        id: request.get("id").cloned().unwrap_or(Value::Null),
+        url,
+        cache_key,
+        no_cache: arguments.get("no_cache").and_then(|v| v.as_bool()).unwrap_or(false),
+    })
+}
+
+/// 从上游响应 (JSON 或 SSE) 中提取成功的 JSON-RPC result；错误结果不缓存
+pub fn extract_result(body: &[u8]) -> Option<Value> {
+    let text = std::str::from_utf8(body).ok()?;
+    let candidates = std::iter::once(text.trim()).chain(
+        text.lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(str::trim),
+    );
+    candidates
+        .filter_map(|candidate| serde_json::from_str::<Value>(candidate).ok())
+        .find_map(|message| message.get("result").cloned())
+        .filter(|result| !result.get("isError").and_then(|v| v.as_bool()).unwrap_or(false))
+}
+
+/// 域名规则是否为合法主机名 (可带 `*.` 前缀，标签为字母数字和连字符)
+pub fn is_valid_domain_pattern(pattern: &str) -> bool {
+    let host = pattern.strip_prefix("*.").unwrap_or(pattern);
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// tools/call 请求的目标域名：Web Reader 的 url 参数，或 webSearchPrime 的 search_domain_filter
+pub fn request_domain(body: &[u8]) -> Option<String> {
+    let request: Value = serde_json::from_slice(body).ok()?;
+    if request.get("method").and_then(|m| m.as_str()) != Some("tools/call") {
+        return None;
+    }
+    let arguments = request.get("params")?.get("arguments")?;
+    if let Some(url) = arguments.get("url").and_then(|u| u.as_str()) {
+        return reqwest::Url::parse(url.trim())
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_ascii_lowercase()));
+    }
+    arguments
+        .get("search_domain_filter")
+        .and_then(|d| d.as_str())
+        .map(|d| d.trim().trim_start_matches("*.").to_ascii_lowercase())
+        .filter(|d| !d.is_empty())
+}
+
+/// 查找域名对应的超时 (秒)：精确匹配优先，其次是最长的父域名规则
+pub fn domain_timeout(domain_timeouts: &HashMap<String, u64>, domain: &str) -> Option<u64> {
+    domain_timeouts
+        .iter()
+        .filter_map(|(pattern, secs)| {
+            let pattern = pattern.trim().trim_start_matches("*.").to_ascii_lowercase();
+            let matches = domain == pattern
+                || domain
+                    .strip_suffix(pattern.as_str())
+                    .map(|prefix| prefix.ends_with('.'))
+                    .unwrap_or(false);
+            matches.then_some((pattern.len(), *secs))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, secs)| secs)
+}
+
+/// 本次 MCP 请求应使用的超时 (秒)
+pub fn timeout_for_request(domain_timeouts: &HashMap<String, u64>, body: &[u8], default_secs: u64) -> u64 {
+    if domain_timeouts.is_empty() {
+        return default_secs;
+    }
+    request_domain(body)
+        .and_then(|domain| domain_timeout(domain_timeouts, &domain))
+        .unwrap_or(default_secs)
+}
+
+/// 过滤已见过的 URL (同一批结果内的重复 URL 也只保留第一条)
+pub fn deduplicate_search_results(existing_urls: &HashSet<String>, new_results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut batch = HashSet::new();
+    new_results
+        .into_iter()
+        .filter(|r| {
+            let url = normalize_url(&r.link);
+            !existing_urls.contains(&url) && batch.insert(url)
+        })
+        .collect()
+}
+
+/// 对 tools/call result 中的搜索结果 (content[].text 为结果数组的 JSON) 去重，并记录新 URL
+fn dedup_result_content(result: &mut Value, seen: &mut HashSet<String>) -> bool {
+    let Some(content) = result.get_mut("content").and_then(|c| c.as_array_mut()) else {
+        return false;
+    };
+    let mut changed = false;
+    for item in content {
+        let Some(text) = item.get("text").and_then(|t| t.as_str()) else {
+            continue;
+        };
+        let Ok(results) = serde_json::from_str::<Vec<SearchResult>>(text) else {
+            continue;
+        };
+        let before = results.len();
+        let unique = deduplicate_search_results(seen, results);
+        seen.extend(unique.iter().map(|r| normalize_url(&r.link)));
+        if unique.len() != before {
+            if let Ok(text) = serde_json::to_string(&unique) {
+                item["text"] = Value::String(text);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// 对上游 webSearchPrime 响应 (JSON 或 SSE) 去重；无需改写时返回 None
+pub fn dedup_search_response(body: &[u8], seen: &mut HashSet<String>) -> Option<Vec<u8>> {
+    let text = std::str::from_utf8(body).ok()?;
+    if let Ok(mut message) = serde_json::from_str::<Value>(text.trim()) {
+        let changed = message
+            .get_mut("result")
+            .map(|result| dedup_result_content(result, seen))
+            .unwrap_or(false);
+        return if changed { serde_json::to_vec(&message).ok() } else { None };
+    }
+
+    let mut changed = false;
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            let Some(data) = line.strip_prefix("data:") else {
+                return line.to_string();
+            };
+            let Ok(mut message) = serde_json::from_str::<Value>(data.trim()) else {
+                return line.to_string();
+            };
+            match message.get_mut("result") {
+                Some(result) if dedup_result_content(result, seen) => {
+                    changed = true;
+                    format!("data: {}", message)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect();
+    changed.then(|| lines.join("\n").into_bytes())
+}
+
+impl SearchSeenUrls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按会话过滤已返回过的搜索结果并刷新会话活动时间；无需改写时返回 None
+    pub fn dedup_response(&self, session_id: String, body: &[u8]) -> Option<Vec<u8>> {
+        let mut entry = self
+            .sessions
+            .entry(session_id)
+            .or_insert_with(|| (Instant::now(), HashSet::new()));
+        entry.0 = Instant::now();
+        dedup_search_response(body, &mut entry.1)
+    }
+
+    pub fn clear(&self) {
+        self.sessions.clear();
+    }
+
+    /// 删除空闲超过 SEARCH_SESSION_IDLE_TTL 的会话，返回删除数量
+    pub fn sweep_idle(&self) -> usize {
+        self.sweep_idle_at(Instant::now())
+    }
+
+    fn sweep_idle_at(&self, now: Instant) -> usize {
+        let before = self.sessions.len();
+        self.sessions
+            .retain(|_, (last_active, _)| now.saturating_duration_since(*last_active) < SEARCH_SESSION_IDLE_TTL);
+        before.saturating_sub(self.sessions.len())
+    }
+
+    /// 启动空闲会话清理任务 (持有弱引用，服务器停止后自动退出)
+    pub fn start_sweep_task(self: &Arc<Self>) {
+        let seen: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CACHE_SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(seen) = seen.upgrade() else {
+                    break;
+                };
+                let removed = seen.sweep_idle();
+                if removed > 0 {
+                    tracing::debug!("[MCP] Removed {} idle webSearchPrime sessions", removed);
+                }
+            }
+        });
+    }
+}
+
+impl WebReaderCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 读取未过期的缓存结果
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.get_at(key, Instant::now())
+    }
+
+    fn get_at(&self, key: &str, now: Instant) -> Option<String> {
+        let entry = self.entries.get(key)?;
+        if now < entry.0 {
+            return Some(entry.1.clone());
+        }
+        drop(entry);
+        self.entries.remove(key);
+        None
+    }
+
+    pub fn insert(&self, key: String, result: String, ttl: Duration) {
+        self.entries.insert(key, (Instant::now() + ttl, result));
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        self.entries.remove(key);
+    }
+
+    /// 删除所有已过期条目，返回删除数量
+    pub fn sweep_expired(&self) -> usize {
+        self.sweep_expired_at(Instant::now())
+    }
+
+    fn sweep_expired_at(&self, now: Instant) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, (expires_at, _)| now < *expires_at);
+        before.saturating_sub(self.entries.len())
+    }
+
+    /// 启动后台清理任务 (缓存被释放后自动退出)
+    pub fn start_sweep_task(self: &Arc<Self>) {
+        let cache: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CACHE_SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(cache) = cache.upgrade() else {
+                    break;
+                };
+                let removed = cache.sweep_expired();
+                if removed > 0 {
+                    tracing::debug!("[MCP] Removed {} expired Web Reader cache entries", removed);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_call_and_extract_result() {
+        let body = br#"{"jsonrpc":"2.0","id":7,"method":"tools/call","params":{"name":"webReader","arguments":{"url":"HTTPS://Example.com/a#top","no_cache":true}}}"#;
+        let call = parse_web_reader_call(body).unwrap();
+        assert_eq!(call.id, Value::from(7));
+        assert_eq!(call.url, "https://example.com/a");
+        assert!(call.no_cache);
+
+        // no_cache 不影响缓存键，其余参数会区分缓存条目
+        let cached = br#"{"jsonrpc":"2.0","id":8,"method":"tools/call","params":{"name":"webReader","arguments":{"url":"https://example.com/a"}}}"#;
+        assert_eq!(parse_web_reader_call(cached).unwrap().cache_key, call.cache_key);
+        let text = br#"{"jsonrpc":"2.0","id":9,"method":"tools/call","params":{"name":"webReader","arguments":{"url":"https://example.com/a","return_format":"text"}}}"#;
+        assert_ne!(parse_web_reader_call(text).unwrap().cache_key, call.cache_key);
+        assert!(parse_web_reader_call(br#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#).is_none());
+
+        let sse = b"event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":7,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"page\"}]}}\n\n";
+        assert_eq!(extract_result(sse).unwrap()["content"][0]["text"], "page");
+        let json = br#"{"jsonrpc":"2.0","id":7,"result":{"content":[],"isError":true}}"#;
+        assert!(extract_result(json).is_none());
+    }
+
+    #[test]
+    fn test_search_results_deduplicated_across_calls() {
+        let response = |urls: &[&str]| {
+            let results: Vec<Value> = urls
+                .iter()
+                .map(|u| serde_json::json!({ "title": "t", "link": u, "content": "c" }))
+                .collect();
+            let message = serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "content": [{ "type": "text", "text": serde_json::to_string(&results).unwrap() }] }
+            });
+            format!("event: message\ndata: {}\n\n", message).into_bytes()
+        };
+        let links = |body: &[u8]| -> Vec<String> {
+            let line = std::str::from_utf8(body).unwrap().lines().find(|l| l.starts_with("data:")).unwrap();
+            let message: Value = serde_json::from_str(line.trim_start_matches("data:").trim()).unwrap();
+            let text = message["result"]["content"][0]["text"].as_str().unwrap();
+            serde_json::from_str::<Vec<SearchResult>>(text).unwrap().into_iter().map(|r| r.link).collect()
+        };
+
+        let mut seen = HashSet::new();
+        // 首次搜索没有重复，无需改写
+        assert!(dedup_search_response(&response(&["https://a.com/", "https://b.com/x"]), &mut seen).is_none());
+        assert_eq!(seen.len(), 2);
+
+        let rewritten = dedup_search_response(
+            &response(&["https://B.com/x#frag", "https://c.com/", "https://c.com/"]),
+            &mut seen,
+        )
+        .unwrap();
+        assert_eq!(links(&rewritten), vec!["https://c.com/"]);
+        assert_eq!(seen.len(), 3);
+
+        // 未识别字段原样保留
+        let result: SearchResult = serde_json::from_str(r#"{"url":"https://d.com/","media":"m"}"#).unwrap();
+        assert_eq!(result.link, "https://d.com/");
+        assert_eq!(result.extra["media"], "m");
+    }
+
+    #[test]
+    fn test_search_sessions_swept_when_idle() {
+        let seen = SearchSeenUrls::new();
+        let body = br#"{"jsonrpc":"2.0","id":1,"result":{"content":[{"type":"text","text":"[{\"link\":\"https://a.com/\"}]"}]}}"#;
+        assert!(seen.dedup_response("s1".to_string(), body).is_none());
+        assert!(seen.dedup_response("s1".to_string(), body).is_some());
+
+        let now = Instant::now();
+        assert_eq!(seen.sweep_idle_at(now), 0);
+        assert_eq!(seen.sweep_idle_at(now + SEARCH_SESSION_IDLE_TTL), 1);
+        assert!(seen.dedup_response("s1".to_string(), body).is_none());
+    }
+
+    #[test]
+    fn test_domain_timeouts() {
+        let timeouts: HashMap<String, u64> = [
+            ("intranet.corp".to_string(), 120),
+            ("wiki.intranet.corp".to_string(), 300),
+            ("api.example.com".to_string(), 5),
+        ]
+        .into_iter()
+        .collect();
+
+        let reader = br#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"arguments":{"url":"https://Docs.Intranet.corp/page"}}}"#;
+        assert_eq!(timeout_for_request(&timeouts, reader, 60), 120);
+        let nested = br#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"arguments":{"url":"https://wiki.intranet.corp/"}}}"#;
+        assert_eq!(timeout_for_request(&timeouts, nested, 60), 300);
+        let search = br#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"arguments":{"search_query":"q","search_domain_filter":"api.example.com"}}}"#;
+        assert_eq!(timeout_for_request(&timeouts, search, 60), 5);
+        let unknown = br#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"arguments":{"search_query":"q"}}}"#;
+        assert_eq!(timeout_for_request(&timeouts, unknown, 60), 60);
+        // 不能只按后缀字符串匹配
+        assert_eq!(domain_timeout(&timeouts, "notintranet.corp"), None);
+
+        assert!(is_valid_domain_pattern("*.example.com"));
+        assert!(is_valid_domain_pattern("localhost"));
+        assert!(!is_valid_domain_pattern("https://example.com"));
+        assert!(!is_valid_domain_pattern("-bad.example.com"));
+        assert!(!is_valid_domain_pattern("a..b"));
+    }
+
+    #[test]
+    fn test_cache_ttl_and_invalidate() {
+        let cache = WebReaderCache::new();
+        let ttl = Duration::from_secs(300);
+        cache.insert("https://example.com/".to_string(), "{}".to_string(), ttl);
+
+        let now = Instant::now();
+        assert_eq!(cache.get_at("https://example.com/", now).as_deref(), Some("{}"));
+        assert!(cache.get_at("https://example.com/", now + ttl).is_none());
+
+        cache.insert("https://example.com/".to_string(), "{}".to_string(), ttl);
+        cache.invalidate("https://example.com/");
+        assert!(cache.get("https://example.com/").is_none());
+
+        cache.insert("https://example.com/a".to_string(), "{}".to_string(), ttl);
+        cache.insert("https://example.com/b".to_string(), "{}".to_string(), ttl * 2);
+        assert_eq!(cache.sweep_expired_at(Instant::now() + ttl), 1);
+        assert!(cache.get("https://example.com/b").is_some());
+    }
+}
@@ -1,14 +1,11 @@
 use reqwest::{Client, Proxy};
 use crate::modules::config::load_app_config;
+use crate::proxy::upstream_proxy::PooledClient;
 
 /// 创建统一配置的 HTTP 客户端
 /// 自动加载全局配置并应用代理
 pub fn create_client(timeout_secs: u64) -> Client {
-    if let Ok(config) = load_app_config() {
-        create_client_with_proxy(timeout_secs, Some(config.proxy.upstream_proxy))
-    } else {
-        create_client_with_proxy(timeout_secs, None)
-    }
+    create_pooled_client(timeout_secs).client
 }
 
 /// 创建带指定代理配置的 HTTP 客户端
@@ -16,22 +13,57 @@ pub fn create_client_with_proxy(
     timeout_secs: u64, 
     proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>
 ) -> Client {
+    create_pooled_client_with_proxy(timeout_secs, proxy_config, None).client
+}
+
+/// 同 `create_client`，同时返回所用的上游代理
+/// 调用方应通过 `PooledClient::report` 回报请求结果；失败后重新创建即可切换到代理池中的下一个代理
+pub fn create_pooled_client(timeout_secs: u64) -> PooledClient {
+    let proxy = load_app_config().ok().map(|config| config.proxy);
+    create_pooled_client_with_proxy(
+        timeout_secs,
+        proxy.as_ref().map(|p| p.upstream_proxy.clone()),
+        proxy.as_ref().and_then(|p| p.upstream_mtls.as_ref()),
+    )
+}
+
+/// 按指定代理与 mTLS 配置创建客户端；mTLS 证书加载失败时仅记录日志，不使用客户端证书
+pub fn create_pooled_client_with_proxy(
+    timeout_secs: u64,
+    proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>,
+    mtls_config: Option<&crate::proxy::config::MtlsConfig>,
+) -> PooledClient {
     let mut builder = Client::builder()
         .timeout(std::time::Duration::from_secs(timeout_secs));
 
+    let mut proxy_url = None;
     if let Some(config) = proxy_config {
-        if config.enabled && !config.url.is_empty() {
-            match Proxy::all(&config.url) {
+        if let Some(url) = crate::proxy::upstream_proxy::UpstreamProxyPool::global().next_url(&config) {
+            match Proxy::all(&url) {
                 Ok(proxy) => {
                     builder = builder.proxy(proxy);
-                    tracing::info!("HTTP 客户端已启用上游代理: {}", config.url);
+                    tracing::info!("HTTP 客户端已启用上游代理: {}", url);
+                    proxy_url = Some(url);
                 }
                 Err(e) => {
-                    tracing::error!("无效的代理地址: {}, 错误: {}", config.url, e);
+                    tracing::error!("无效的代理地址: {}, 错误: {}", url, e);
+                }
+            }
+        }
+    }
+
+    if let Some(mtls) = mtls_config {
+        match mtls.load() {
+            Ok((identity, ca)) => {
+                builder = builder.identity(identity);
+                if let Some(ca) = ca {
+                    builder = builder.add_root_certificate(ca);
                 }
             }
+            Err(e) => tracing::error!("HTTP 客户端配置 mTLS 失败: {}", e),
         }
     }
 
-    builder.build().unwrap_or_else(|_| Client::new())
+    let client = builder.build().unwrap_or_else(|_| Client::new());
+    PooledClient { client, proxy_url }
 }
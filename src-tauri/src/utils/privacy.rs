@@ -0,0 +1,49 @@
+/// 邮箱脱敏：保留本地部分首尾字符与域名，例如 `alice@example.com` -> `a***e@example.com`
+pub fn mask_email(email: &str) -> String {
+    let (local, domain) = match email.split_once('@') {
+        Some(parts) => parts,
+        None => return "***".to_string(),
+    };
+
+    let chars: Vec<char> = local.chars().collect();
+    let masked_local = match chars.len() {
+        0 => "***".to_string(),
+        1 | 2 => format!("{}***", chars[0]),
+        n => format!("{}***{}", chars[0], chars[n - 1]),
+    };
+
+    format!("{}@{}", masked_local, domain)
+}
+
+/// IP 脱敏：IPv4 隐藏最后一段，IPv6 仅保留前三段，例如 `192.168.1.23` -> `192.168.1.*`
+pub fn mask_ip(ip: std::net::IpAddr) -> String {
+    match ip {
+        std::net::IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{}.{}.{}.*", a, b, c)
+        }
+        std::net::IpAddr::V6(v6) => {
+            let s = v6.segments();
+            format!("{:x}:{:x}:{:x}:*", s[0], s[1], s[2])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_email() {
+        assert_eq!(mask_email("alice@example.com"), "a***e@example.com");
+        assert_eq!(mask_email("ab@example.com"), "a***@example.com");
+        assert_eq!(mask_email("@example.com"), "***@example.com");
+        assert_eq!(mask_email("not-an-email"), "***");
+    }
+
+    #[test]
+    fn test_mask_ip() {
+        assert_eq!(mask_ip("192.168.1.23".parse().unwrap()), "192.168.1.*");
+        assert_eq!(mask_ip("2001:db8:85a3::8a2e:370:7334".parse().unwrap()), "2001:db8:85a3:*");
+    }
+}
@@ -82,6 +82,24 @@ function App() {
       })
     );
 
+    // 监听账号目录变更 (反代运行时由文件监听推送)
+    unlistenPromises.push(
+      listen('accounts://changed', () => {
+        console.log('[App] Account files changed, refreshing...');
+        fetchAccounts();
+      })
+    );
+
+    // 监听反代推送的账号状态变更 (禁用 / 配额保护)
+    for (const event of ['accounts://disabled', 'accounts://quota_protected']) {
+      unlistenPromises.push(
+        listen(event, () => {
+          console.log(`[App] Received ${event}, refreshing...`);
+          fetchAccounts();
+        })
+      );
+    }
+
     // Cleanup
     return () => {
       Promise.all(unlistenPromises).then(unlisteners => {
@@ -1,7 +1,11 @@
-import { X, Clock, AlertCircle } from 'lucide-react';
+import { X, Clock, AlertCircle, Download, Trash2 } from 'lucide-react';
 import { createPortal } from 'react-dom';
+import { ask, save } from '@tauri-apps/plugin-dialog';
 import { Account, ModelQuota } from '../../types/account';
 import { formatDate } from '../../utils/format';
+import { request as invoke } from '../../utils/request';
+import { showToast } from '../common/ToastContainer';
+import { useAccountStore } from '../../stores/useAccountStore';
 import { useTranslation } from 'react-i18next';
 
 interface AccountDetailsDialogProps {
@@ -11,8 +15,40 @@ interface AccountDetailsDialogProps {
 
 export default function AccountDetailsDialog({ account, onClose }: AccountDetailsDialogProps) {
     const { t } = useTranslation();
+    const fetchAccounts = useAccountStore(state => state.fetchAccounts);
     if (!account) return null;
 
+    const handleExportData = async () => {
+        try {
+            const path = await save({
+                filters: [{ name: 'ZIP', extensions: ['zip'] }],
+                defaultPath: `account_data_${account.email}.zip`
+            });
+            if (!path) return;
+            await invoke('export_account_data', { email: account.email, destPath: path });
+            showToast(t('accounts.details.export_data_success'), 'success');
+        } catch (error) {
+            showToast(`${t('accounts.details.export_data_error')}: ${error}`, 'error');
+        }
+    };
+
+    // 删除不可恢复，使用系统对话框二次确认
+    const handleDeleteData = async () => {
+        const confirmed = await ask(t('accounts.details.delete_data_confirm', { email: account.email }), {
+            title: t('accounts.details.delete_data'),
+            kind: 'warning'
+        });
+        if (!confirmed) return;
+        try {
+            await invoke('delete_account_data', { email: account.email });
+            showToast(t('accounts.details.delete_data_success'), 'success');
+            onClose();
+            await fetchAccounts();
+        } catch (error) {
+            showToast(`${t('accounts.details.delete_data_error')}: ${error}`, 'error');
+        }
+    };
+
     return createPortal(
         <div className="modal modal-open z-[100]">
             {/* Draggable Top Region */}
@@ -76,6 +112,18 @@ export default function AccountDetailsDialog({ account, onClose }: AccountDetail
                             </div>
                         )}
                 </div>
+
+                {/* Footer: 账号数据导出 / 删除 */}
+                <div className="px-6 py-4 border-t border-gray-100 dark:border-base-200 flex justify-end gap-2">
+                    <button className="btn btn-sm btn-ghost gap-1.5" onClick={handleExportData}>
+                        <Download size={14} />
+                        {t('accounts.details.export_data')}
+                    </button>
+                    <button className="btn btn-sm btn-ghost gap-1.5 text-red-500 hover:bg-red-50 dark:hover:bg-red-900/20" onClick={handleDeleteData}>
+                        <Trash2 size={14} />
+                        {t('accounts.details.delete_data')}
+                    </button>
+                </div>
             </div>
             <div className="modal-backdrop bg-black/40 backdrop-blur-sm" onClick={onClose}></div>
         </div>,
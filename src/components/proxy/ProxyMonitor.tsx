@@ -24,12 +24,17 @@ interface ProxyRequestLog {
     input_tokens?: number;
     output_tokens?: number;
     account_email?: string;
+    conversation_id?: string;
+    estimated_cost_usd?: number;
+    request_id?: string;
 }
 
 interface ProxyStats {
     total_requests: number;
     success_count: number;
     error_count: number;
+    dedup_hits?: number;
+    scrub_stats?: { patterns_matched: Record<string, number> };
 }
 
 interface ProxyMonitorProps {
@@ -831,7 +831,7 @@ print(response.text)`;
                                                 </span>
                                             </label>
                                             <select
-                                                value={appConfig.proxy.auth_mode || 'off'}
+                                                value={typeof appConfig.proxy.auth_mode === 'object' ? 'jwt' : (appConfig.proxy.auth_mode || 'off')}
                                                 onChange={(e) =>
                                                     updateProxyConfig({
                                                         auth_mode: e.target.value as ProxyConfig['auth_mode'],
@@ -843,6 +843,9 @@ print(response.text)`;
                                                 <option value="strict">{t('proxy.config.auth.modes.strict')}</option>
                                                 <option value="all_except_health">{t('proxy.config.auth.modes.all_except_health')}</option>
                                                 <option value="auto">{t('proxy.config.auth.modes.auto')}</option>
+                                                {typeof appConfig.proxy.auth_mode === 'object' && (
+                                                    <option value="jwt" disabled>JWT</option>
+                                                )}
                                             </select>
                                             <p className="mt-0.5 text-[10px] text-gray-500 dark:text-gray-400">
                                                 {t('proxy.config.auth.hint')}
@@ -1193,7 +1196,7 @@ print(response.text)`;
                                                 </button>
                                             </div>
                                             <div className="grid grid-cols-1 gap-2">
-                                                {(['CacheFirst', 'Balance', 'PerformanceFirst'] as const).map(mode => (
+                                                {(['CacheFirst', 'Balance', 'PerformanceFirst', 'ResponseTimeWeighted'] as const).map(mode => (
                                                     <label
                                                         key={mode}
                                                         className={`flex items-start gap-3 p-3 rounded-xl border cursor-pointer transition-all duration-200 ${(appConfig.proxy.scheduling?.mode || 'Balance') === mode
@@ -65,6 +65,8 @@ function Settings() {
     const [httpApiSettings, setHttpApiSettings] = useState<{
         enabled: boolean;
         port: number;
+        auth_enabled?: boolean;
+        token?: string;
     }>({ enabled: true, port: 19527 });
     const [httpApiPortInput, setHttpApiPortInput] = useState('19527');
     const [httpApiSettingsChanged, setHttpApiSettingsChanged] = useState(false);
@@ -96,7 +98,7 @@ function Settings() {
             .catch(err => console.error('Failed to get auto launch status:', err));
 
         // 加载 HTTP API 设置
-        invoke<{ enabled: boolean; port: number }>('get_http_api_settings')
+        invoke<{ enabled: boolean; port: number; auth_enabled?: boolean; token?: string }>('get_http_api_settings')
             .then(settings => {
                 setHttpApiSettings(settings);
                 setHttpApiPortInput(String(settings.port));
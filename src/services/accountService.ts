@@ -1,6 +1,6 @@
 import i18n from '../i18n';
 import { request as invoke } from '../utils/request';
-import { Account, QuotaData, DeviceProfile, DeviceProfileVersion } from '../types/account';
+import { Account, QuotaData, DeviceProfile, DeviceProfileVersion, ImportReport, BatchResult, AccountHealth, AccountComparison, QuotaSnapshot } from '../types/account';
 
 // 检查 Tauri 环境
 function ensureTauriEnvironment() {
@@ -103,6 +103,10 @@ export async function importFromCustomDb(path: string): Promise<Account> {
     return await invoke('import_custom_db', { path });
 }
 
+export async function importAccountsFromFile(path: string, format?: 'csv' | 'json'): Promise<ImportReport> {
+    return await invoke('import_accounts', { path, format });
+}
+
 export async function syncAccountFromDb(): Promise<Account | null> {
     return await invoke('sync_account_from_db');
 }
@@ -111,6 +115,14 @@ export async function toggleProxyStatus(accountId: string, enable: boolean, reas
     return await invoke('toggle_proxy_status', { accountId, enable, reason });
 }
 
+export async function batchDisableAccounts(accountIds: string[], reason?: string): Promise<BatchResult> {
+    return await invoke('batch_disable_accounts', { accountIds, reason });
+}
+
+export async function batchEnableAccounts(accountIds: string[]): Promise<BatchResult> {
+    return await invoke('batch_enable_accounts', { accountIds });
+}
+
 /**
  * 重新排序账号列表
  * @param accountIds 按新顺序排列的账号ID数组
@@ -171,3 +183,30 @@ export async function warmUpAccount(accountId: string): Promise<string> {
     return await invoke('warm_up_account', { accountId });
 }
 
+export async function getAllAccountHealth(windowHours?: number): Promise<AccountHealth[]> {
+    return await invoke('get_all_account_health', { windowHours });
+}
+
+export async function getAccountHealth(accountId: string, windowHours?: number): Promise<AccountHealth> {
+    return await invoke('get_account_health', { accountId, windowHours });
+}
+
+export async function getAccountComparisonReport(): Promise<AccountComparison[]> {
+    return await invoke('get_account_comparison_report');
+}
+
+export async function getQuotaHistory(accountId: string, model?: string, days?: number): Promise<QuotaSnapshot[]> {
+    return await invoke('get_quota_history', { accountId, model, days });
+}
+
+export async function tagAccount(accountId: string, tag: string): Promise<string[]> {
+    return await invoke('tag_account', { accountId, tag });
+}
+
+export async function untagAccount(accountId: string, tag: string): Promise<string[]> {
+    return await invoke('untag_account', { accountId, tag });
+}
+
+export async function listAccountsByTag(tag: string): Promise<Account[]> {
+    return await invoke('list_accounts_by_tag', { tag });
+}
@@ -12,6 +12,7 @@ export interface Account {
     proxy_disabled?: boolean;
     proxy_disabled_reason?: string;
     proxy_disabled_at?: number;
+    tags?: string[];
     created_at: number;
     last_used: number;
 }
@@ -43,6 +44,7 @@ export interface DeviceProfile {
     mac_machine_id: string;
     dev_device_id: string;
     sqm_id: string;
+    expires_at?: number;
 }
 
 export interface DeviceProfileVersion {
@@ -53,3 +55,54 @@ export interface DeviceProfileVersion {
     is_current?: boolean;
 }
 
+
+export interface ImportReport {
+    imported: number;
+    skipped: number;
+    /** [行号 (从 1 开始), 错误信息] */
+    errors: [number, string][];
+}
+
+export interface BatchResult {
+    succeeded: string[];
+    /** [账号 ID, 错误信息] */
+    failed: [string, string][];
+}
+
+export interface AccountHealth {
+    account_id: string;
+    email: string;
+    window_hours: number;
+    total_requests: number;
+    success_rate: number;
+    avg_latency_ms: number | null;
+    rate_limit_events: number;
+    quota_remaining: number | null;
+    token_refresh_failures: number;
+    disabled: boolean;
+    proxy_disabled: boolean;
+    health_score: number; // 0.0 - 1.0
+}
+
+/** 账号横向对比 (最近 24 小时) */
+export interface AccountComparison {
+    account_id: string;
+    email: string;
+    total_requests: number;
+    error_count: number;
+    avg_latency_ms: number | null;
+    p95_latency_ms: number | null;
+    input_tokens_used: number;
+    output_tokens_used: number;
+    estimated_cost: number;
+    is_rate_limited: boolean;
+    quota_percentage: number | null;
+    protected_models_count: number;
+}
+
+export interface QuotaSnapshot {
+    account_id: string;
+    model: string;
+    percentage: number;
+    recorded_at: number; // unix ms
+}
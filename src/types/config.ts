@@ -1,29 +1,113 @@
 export interface UpstreamProxyConfig {
     enabled: boolean;
+    /** @deprecated 使用 urls */
     url: string;
+    urls?: string[];
+}
+
+export interface MtlsConfig {
+    cert_path: string;
+    key_path: string;
+    ca_path?: string | null;
+}
+
+export interface ResponseSigningConfig {
+    private_key_path: string;
+    algorithm?: 'Ed25519';
+}
+
+export interface UpstreamProxyStatus {
+    url: string;
+    healthy: boolean;
+    consecutive_failures: number;
+    total_failures: number;
+    cooldown_remaining_secs: number;
 }
 
 export interface ProxyConfig {
     enabled: boolean;
     allow_lan_access?: boolean;
-    auth_mode?: 'off' | 'strict' | 'all_except_health' | 'auto';
+    auth_mode?: 'off' | 'strict' | 'all_except_health' | 'auto' | { jwt: { jwks_url: string; audience: string } };
+    ip_allowlist?: string[]; // CIDR entries; empty = no restriction
+    trusted_proxies?: string[]; // CIDR entries; X-Forwarded-For is only honoured from these peers
+    bypass_paths?: string[]; // always unauthenticated; exact path or `prefix*`
+    cors_allowed_origins?: string[]; // e.g. https://app.example.com or *.example.com; empty = any origin
+    api_keys?: ApiKeyEntry[];
     port: number;
     api_key: string;
     auto_start: boolean;
     custom_mapping?: Record<string, string>;
+    regex_mapping?: RegexMappingRule[];
+    fallback_chains?: Record<string, string[]>; // original model -> ordered fallback models
     request_timeout: number;
     enable_logging: boolean;
+    max_db_size_mb?: number; // trim the oldest 10% of logs when the log database exceeds this size
+    max_request_body_mb?: number; // request body size limit, default 10
+    stream_heartbeat_interval_secs?: number; // SSE keep-alive interval, default 30, 0 = off
+    response_compression?: boolean; // gzip non-streaming responses, default true
+    response_headers?: Record<string, string>; // injected into every proxy response
+    model_timeouts?: Record<string, number>; // model -> upstream time-to-headers timeout (seconds)
+    grpc_port?: number; // gRPC inference service port (requires a build with the grpc feature)
     upstream_proxy: UpstreamProxyConfig;
+    upstream_mtls?: MtlsConfig | null;
+    response_signing?: ResponseSigningConfig | null;
     zai?: ZaiConfig;
     scheduling?: StickySessionConfig;
     experimental?: ExperimentalConfig;
+    retry?: RetryConfig;
+    enable_otel?: boolean; // requires a build with the `otel` feature; applied on restart
+    otel_endpoint?: string;
 }
 
-export type SchedulingMode = 'CacheFirst' | 'Balance' | 'PerformanceFirst';
+export interface RegexMappingRule {
+    pattern: string;
+    target: string;
+    priority?: number; // higher matches first
+}
+
+export interface RetryConfig {
+    max_attempts: number; // including the first attempt
+    retry_statuses: number[];
+    initial_delay_ms: number;
+    backoff_multiplier: number;
+    jitter_ratio: number; // 0.2 = ±20%
+    max_reconnect_attempts?: number; // mid-stream reconnects, 0 disables
+    reconnect_delay_ms?: number;
+}
+
+export interface KeyPermissions {
+    can_write: boolean;
+    can_read_logs: boolean;
+    can_manage_accounts: boolean;
+}
+
+export interface ApiKeyEntry {
+    key_hash: string; // SHA-256 hex; plaintext is only returned once by generate_api_key
+    label: string;
+    permissions: KeyPermissions;
+    expires_at?: number | null; // unix seconds
+    requests_per_minute?: number | null; // null = unlimited
+}
+
+export type SchedulingMode = 'CacheFirst' | 'Balance' | 'PerformanceFirst' | 'ResponseTimeWeighted';
+
+export type FreeAccountMode = 'Normal' | 'FallbackOnly' | 'Disabled';
+
+export interface TagRoutingPolicy {
+    preferred_tags: string[]; // empty = use the X-Antigravity-Tag header value itself
+    fallback_to_untagged: boolean;
+}
 
 export interface StickySessionConfig {
     mode: SchedulingMode;
     max_wait_seconds: number;
+    max_session_age_secs?: number; // 0 = unlimited
+    session_ttl_secs?: number; // idle bindings are garbage-collected after this; 0 = never
+    model_account_affinity?: Record<string, string[]>; // model id -> preferred account ids
+    tag_routing?: Record<string, TagRoutingPolicy>; // X-Antigravity-Tag value -> policy
+    free_account_mode?: FreeAccountMode; // FallbackOnly = use FREE accounts only when PRO/ULTRA are unavailable
+    slow_account_threshold_ms?: number; // skip accounts whose P95 latency exceeds this; 0 = disabled, default 30000
+    rotation_cooldown_ms?: number; // stagger session rebinding after a rate limit; 0 = disabled, default 500
 }
 
 export type ZaiDispatchMode = 'off' | 'exclusive' | 'pooled' | 'fallback';
@@ -49,6 +133,8 @@ export interface ZaiConfig {
     model_mapping?: Record<string, string>;
     models: ZaiModelDefaults;
     mcp: ZaiMcpConfig;
+    cache_ttl_secs?: number; // Web Reader 结果缓存时间 (秒)，默认 300，0 表示不缓存
+    domain_timeouts?: Record<string, number>; // 按域名覆盖 Web 工具超时 (秒)，同时匹配子域名
 }
 
 export interface ScheduledWarmupConfig {
@@ -62,8 +148,65 @@ export interface QuotaProtectionConfig {
     monitored_models: string[];
 }
 
+export interface ToolCompressorConfig {
+    drop_keys: string[]; // JSON keys stripped from large tool results
+}
+
+export interface AlertConfig {
+    error_rate_threshold: number; // 0.0-1.0, default 0.2
+    window_secs: number;
+    cooldown_secs: number;
+}
+
+export interface LogRetentionPolicy {
+    success_days: number; // 0 = 不按时间清理
+    error_days: number;
+    large_body_days: number; // 请求体 + 响应体 > 1MB
+}
+
+export interface HealthWeights {
+    success_rate: number;
+    latency: number;
+    rate_limit: number;
+    quota: number;
+    token_refresh: number;
+}
+
+export interface ModelPricing {
+    input_cost_per_1k_tokens: number;
+    output_cost_per_1k_tokens: number;
+    currency: string;
+}
+
+export interface QuotaGroup {
+    name: string;
+    account_ids: string[];
+    shared_budget_tokens?: number; // shared token budget per quota window; unset = track only
+}
+
+export interface PluginsConfig {
+    enabled: string[];
+    system_prompt: string; // used by the built-in system-prompt-injector
+}
+
+export interface PluginInfo {
+    name: string;
+    description: string;
+    priority: number;
+    builtin: boolean;
+    enabled: boolean;
+    error?: string;
+}
+
 export interface ExperimentalConfig {
     enable_usage_scaling: boolean;
+    enable_request_dedup?: boolean; // coalesce identical concurrent non-streaming requests (off by default)
+}
+
+export interface ConfigError {
+    path: string; // e.g. `proxy.port`
+    message: string;
+    suggestion?: string;
 }
 
 export interface AppConfig {
@@ -82,6 +225,20 @@ export interface AppConfig {
     accounts_page_size?: number; // 账号列表每页显示数量,默认 0 表示自动计算
     scheduled_warmup: ScheduledWarmupConfig;
     quota_protection: QuotaProtectionConfig; // [NEW] 配额保护配置
+    tool_compressor?: ToolCompressorConfig;
+    alert?: AlertConfig;
+    health_weights?: HealthWeights;
+    quota_groups?: QuotaGroup[];
+    model_pricing?: Record<string, ModelPricing>;
+    scrub_pii?: boolean; // 日志写入前脱敏 PII
+    pii_patterns?: string[];
+    plugins?: PluginsConfig; // 请求转换插件
+    encrypt_accounts?: boolean; // 账号文件加密落盘 (通过迁移命令切换)
+    anomaly_detection_enabled?: boolean; // 账号用量异常检测
+    auto_archive_days?: number; // 反代日志超过 N 天后归档为 gzip JSONL 并从数据库删除
+    log_retention?: LogRetentionPolicy; // 按类别的反代日志保留天数 (默认均为 30)
+    anomaly_z_score_threshold?: number; // 默认 3.0
+    device_profile_refresh_enabled?: boolean; // 设备指纹过期前 48 小时内自动重新生成
     proxy: ProxyConfig;
 }
 